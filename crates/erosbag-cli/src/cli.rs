@@ -1,8 +1,19 @@
+use crate::util::deserialize_optional_duration;
+use crate::util::deserialize_optional_timestamp;
 use crate::util::parse_duration;
+use crate::util::parse_mcap_compression;
+use crate::util::parse_storage_format;
+use crate::util::parse_table_format;
 use crate::util::parse_timestamp;
+use crate::util::parse_trajectory_format;
 use chrono::{DateTime, Utc};
-use clap::{Parser, Subcommand, ValueHint};
+use clap::{Args, Parser, Subcommand, ValueHint};
 use ecoord::FrameId;
+use erosbag::foxglove::DEFAULT_PORT;
+use erosbag::storage::{DEFAULT_MCAP_CHUNK_SIZE, McapCompression, StorageFormat};
+use erosbag::table::TableFormat;
+use erosbag::transform::TrajectoryFormat;
+use serde::Deserialize;
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -12,14 +23,45 @@ pub struct Cli {
     pub command: Commands,
 }
 
+// the command is parsed once per run
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 pub enum Commands {
+    /// Run a job specification on all bags found recursively below a directory
+    Batch {
+        /// Path to the directory, which is searched recursively for bags
+        #[clap(long, value_hint = ValueHint::DirPath)]
+        input_directory_path: PathBuf,
+
+        /// Path to the YAML job specification
+        #[clap(long, value_hint = ValueHint::FilePath)]
+        job_file_path: PathBuf,
+
+        /// Path to the output directory, to which the output templates of the tasks are relative
+        #[clap(long, value_hint = ValueHint::DirPath)]
+        output_directory_path: PathBuf,
+
+        /// Number of bags processed in parallel
+        #[clap(long, default_value_t = 4)]
+        threads: usize,
+
+        /// Rerun tasks, which were completed by a previous run
+        #[clap(long, default_value_t = false)]
+        force: bool,
+    },
+
     /// Extract the transforms
     ExtractTransforms {
         /// Path to the ROS2 bag
         #[clap(long, value_hint = ValueHint::DirPath)]
         rosbag_directory_path: PathBuf,
 
+        #[clap(flatten)]
+        time_window: TimeWindowArgs,
+
+        #[clap(flatten)]
+        channel_selection: ChannelSelectionArgs,
+
         /// Path to the output ecoord file
         #[clap(long, value_hint = ValueHint::FilePath)]
         output_ecoord_path: PathBuf,
@@ -35,39 +77,256 @@ pub enum Commands {
         #[clap(long, value_hint = ValueHint::FilePath)]
         ecoord_file_path: Option<PathBuf>,
 
-        /// The start time of the import in UTC.
-        /// Example: 2020-04-12 22:10:57.123456789 +00:00
-        /// If not provided, the import starts from the beginning.
-        #[clap(long, value_parser = parse_timestamp)]
-        start_date_time: Option<DateTime<Utc>>,
-
-        /// The end time of the import in UTC.
-        /// Example: 2020-04-12 22:10:57.123456789 +00:00
-        /// If not provided, the import runs until the end of the available data.
-        #[clap(long, value_parser = parse_timestamp)]
-        end_date_time: Option<DateTime<Utc>>,
-
-        /// The time offset applied to the rosbag import.
-        /// Example: "5s" (5 seconds), "2m" (2 minutes).
-        /// If not provided, no offset is applied.
-        #[clap(long, value_parser = parse_duration)]
-        start_time_offset: Option<chrono::Duration>,
+        #[clap(flatten)]
+        time_window: TimeWindowArgs,
+
+        // all PointCloud2, LaserScan and MultiEchoLaserScan channels or all tf channels are
+        // used, if none of them is selected
+        #[clap(flatten)]
+        channel_selection: ChannelSelectionArgs,
+
+        /// Target frame id of extracted point cloud
+        #[clap(long)]
+        target_frame_id: Option<FrameId>,
+
+        #[clap(flatten)]
+        filter: PointFilterArgs,
+
+        #[clap(flatten)]
+        grouping: PointCloudGroupingArgs,
+
+        /// Add columns with the channel topic, file name, chunk id, message id and log time of
+        /// the source message of each point
+        #[clap(long, default_value_t = false)]
+        source_columns: bool,
+
+        /// Extension defining the format of the written point clouds, if they are split.
+        #[clap(long, default_value_t = String::from("xyz"))]
+        output_extension: String,
 
-        /// The total duration of the rosbag import.
-        /// Example: "30s" (30 seconds), "1h" (1 hour).
-        /// If not provided, the import runs until the end time or the end of the data.
+        /// Path to the output epoint file containing the extracted point clouds, or to the output
+        /// directory with one file per point cloud and an index.csv, if they are split
+        #[clap(long, value_hint = ValueHint::AnyPath)]
+        output_path: PathBuf,
+    },
+
+    /// Check the data quality of the channels for drops, gaps, jitter and latency and fail on issues
+    Check {
+        /// Path to the ROS2 bag
+        #[clap(long, value_hint = ValueHint::DirPath)]
+        rosbag_directory_path: PathBuf,
+
+        #[clap(flatten)]
+        time_window: TimeWindowArgs,
+
+        #[clap(flatten)]
+        channel_selection: ChannelSelectionArgs,
+
+        /// Maximum duration between two consecutive log times of a channel.
+        /// Example: "500ms", "1s".
+        #[clap(long, value_parser = parse_duration, default_value = "1s")]
+        max_gap_duration: chrono::Duration,
+
+        /// Path to the output JSON file containing the full report.
+        /// If not provided, the report is printed to stdout.
+        #[clap(long, value_hint = ValueHint::FilePath)]
+        output_path: Option<PathBuf>,
+    },
+
+    /// Check the transforms for consistency and fail on issues
+    CheckTf {
+        /// Path to the ROS2 bag
+        #[clap(long, value_hint = ValueHint::DirPath)]
+        rosbag_directory_path: PathBuf,
+
+        #[clap(flatten)]
+        time_window: TimeWindowArgs,
+
+        #[clap(flatten)]
+        channel_selection: ChannelSelectionArgs,
+
+        /// Frame id from which all point cloud and image frames must be reachable.
+        /// If not provided, the root of the largest subtree is used.
+        #[clap(long)]
+        target_frame_id: Option<FrameId>,
+
+        /// Maximum duration between two consecutive stamps of a dynamic transform.
+        /// Example: "500ms", "1s".
+        #[clap(long, value_parser = parse_duration, default_value = "1s")]
+        max_gap_duration: chrono::Duration,
+    },
+
+    /// Export the messages of each channel as a table
+    ExportTable {
+        /// Path to the ROS2 bag
+        #[clap(long, value_hint = ValueHint::DirPath)]
+        rosbag_directory_path: PathBuf,
+
+        #[clap(flatten)]
+        time_window: TimeWindowArgs,
+
+        #[clap(flatten)]
+        channel_selection: ChannelSelectionArgs,
+
+        /// Format of the written tables: parquet, csv or ipc.
+        /// CSV files do not contain list and binary columns.
+        #[clap(long, value_parser = parse_table_format, default_value = "parquet")]
+        format: TableFormat,
+
+        /// Path to the output directory containing one table per channel
+        #[clap(long, value_hint = ValueHint::DirPath)]
+        output_directory_path: PathBuf,
+    },
+
+    /// Query the messages of the channels with an SQL statement
+    Query {
+        /// Path to the ROS2 bag
+        #[clap(long, value_hint = ValueHint::DirPath)]
+        rosbag_directory_path: PathBuf,
+
+        #[clap(flatten)]
+        time_window: TimeWindowArgs,
+
+        #[clap(flatten)]
+        channel_selection: ChannelSelectionArgs,
+
+        /// SQL statement evaluated on the table `messages` of each channel.
+        /// Example: 'SELECT * FROM messages WHERE abs("linear_acceleration.z") > 15'.
+        /// Without selected channels, channels lacking a referenced column are skipped.
+        #[clap(long)]
+        sql: Option<String>,
+
+        /// Format of the written tables: parquet, csv or ipc.
+        #[clap(long, value_parser = parse_table_format, default_value = "parquet")]
+        format: TableFormat,
+
+        /// Path to the output directory containing one table per channel.
+        /// If not provided, the results are printed.
+        #[clap(long, value_hint = ValueHint::DirPath)]
+        output_directory_path: Option<PathBuf>,
+    },
+
+    /// Export the trajectory of a frame
+    ExportTrajectory {
+        /// Path to the ROS2 bag
+        #[clap(long, value_hint = ValueHint::DirPath)]
+        rosbag_directory_path: PathBuf,
+
+        #[clap(flatten)]
+        time_window: TimeWindowArgs,
+
+        #[clap(flatten)]
+        channel_selection: ChannelSelectionArgs,
+
+        /// Name of a PoseStamped, PoseWithCovarianceStamped, Odometry or Path channel providing
+        /// the poses instead of the transforms
+        #[clap(long, conflicts_with = "channel_names")]
+        pose_channel_name: Option<String>,
+
+        /// Frame id in which the poses are expressed
+        #[clap(long)]
+        parent_frame_id: FrameId,
+
+        /// Frame id of which the poses are exported
+        #[clap(long)]
+        child_frame_id: FrameId,
+
+        /// Interval for sampling the poses at a fixed rate.
+        /// Example: "100ms".
+        /// If not provided, the poses are sampled at the native stamps.
         #[clap(long, value_parser = parse_duration)]
-        total_duration: Option<chrono::Duration>,
+        sampling_interval: Option<chrono::Duration>,
 
-        /// Name of the channel providing the transform messages
-        #[clap(long, default_value_t = String::from("/tf_static"))]
-        transform_channel_name: String,
+        /// Express the poses relative to the first pose
+        #[clap(long, default_value_t = false)]
+        relative_to_first: bool,
 
-        /// Target frame id of extracted point cloud
+        /// Format of the written trajectory: tum, kitti or euroc
+        #[clap(long, value_parser = parse_trajectory_format, default_value = "tum")]
+        format: TrajectoryFormat,
+
+        /// Path to the output trajectory file
+        #[clap(long, value_hint = ValueHint::FilePath)]
+        output_path: PathBuf,
+    },
+
+    /// Export a dataset in the KITTI raw format
+    ExportKitti {
+        /// Path to the ROS2 bag
+        #[clap(long, value_hint = ValueHint::DirPath)]
+        rosbag_directory_path: PathBuf,
+
+        #[clap(flatten)]
+        time_window: TimeWindowArgs,
+
+        /// Name of the PointCloud2 channel of the lidar defining the frames
+        #[clap(long)]
+        lidar_channel_name: String,
+
+        /// Names of the image channels, where the order defines the image_XX directories
+        #[clap(long, num_args = 1..)]
+        image_channel_names: Vec<String>,
+
+        /// Names of the camera info channels in the same order as the image channels
+        #[clap(long, num_args = 1..)]
+        camera_info_channel_names: Vec<String>,
+
+        /// Name of the NavSatFix channel for the oxts files
+        #[clap(long)]
+        nav_sat_fix_channel_name: Option<String>,
+
+        /// Name of the Imu channel for the oxts files
+        #[clap(long)]
+        imu_channel_name: Option<String>,
+
+        #[clap(flatten)]
+        channel_selection: ChannelSelectionArgs,
+
+        /// Maximum time difference between the lidar scan and the matched messages.
+        /// Example: "50ms".
+        #[clap(long, value_parser = parse_duration, default_value = "50ms")]
+        max_time_difference: chrono::Duration,
+
+        /// Path to the output directory
+        #[clap(long, value_hint = ValueHint::DirPath)]
+        output_directory_path: PathBuf,
+    },
+
+    /// Colorize the point clouds with the temporally closest images of calibrated cameras
+    Colorize {
+        /// Path to the ROS2 bag
+        #[clap(long, value_hint = ValueHint::DirPath)]
+        rosbag_directory_path: PathBuf,
+
+        #[clap(flatten)]
+        time_window: TimeWindowArgs,
+
+        /// Names of the image channels, where the order defines the camera ids starting at 1
+        #[clap(long, num_args = 1..)]
+        image_channel_names: Vec<String>,
+
+        /// Names of the camera info channels in the same order as the image channels
+        #[clap(long, num_args = 1..)]
+        camera_info_channel_names: Vec<String>,
+
+        #[clap(flatten)]
+        channel_selection: ChannelSelectionArgs,
+
+        /// Target frame id of the colorized point cloud
         #[clap(long)]
         target_frame_id: Option<FrameId>,
 
-        /// Path to the output epoint file containing the extracted point clouds
+        /// Maximum time difference between the point cloud and the matched image.
+        /// Example: "50ms".
+        #[clap(long, value_parser = parse_duration, default_value = "50ms")]
+        max_time_difference: chrono::Duration,
+
+        /// Depth in meters, by which a point may lie behind the closest point of its pixel and
+        /// still be colored
+        #[clap(long, default_value_t = 0.1)]
+        occlusion_tolerance: f64,
+
+        /// Path to the output epoint file containing the colorized point cloud
         #[clap(long, value_hint = ValueHint::FilePath)]
         output_path: PathBuf,
     },
@@ -78,11 +337,82 @@ pub enum Commands {
         #[clap(long, value_hint = ValueHint::DirPath)]
         rosbag_directory_path: PathBuf,
 
+        #[clap(flatten)]
+        time_window: TimeWindowArgs,
+
+        #[clap(flatten)]
+        channel_selection: ChannelSelectionArgs,
+
         /// Path to output eimage file containing the extracted images
         #[clap(long, value_hint = ValueHint::FilePath)]
         output_eimage_path: PathBuf,
     },
 
+    /// Generate Rust structs of the messages defined in .msg/.idl files or MCAP schemas
+    GenerateMessages {
+        /// Paths to directories, which are searched recursively for .msg and .idl files, or to
+        /// MCAP files, whose schemas are used
+        #[clap(long, num_args = 1.., required = true, value_hint = ValueHint::AnyPath)]
+        input_paths: Vec<PathBuf>,
+
+        /// Path to the generated Rust source file
+        #[clap(long, value_hint = ValueHint::FilePath)]
+        output_path: PathBuf,
+
+        /// Path of the erosbag crate used in the generated code
+        #[clap(long, default_value = "erosbag")]
+        crate_path: String,
+    },
+
+    /// Convert a ROS1 bag to a ROS2 bag in the MCAP format or a ROS2 bag between the MCAP and
+    /// SQLite3 storage formats
+    Convert {
+        /// Path to the ROS1 bag file or the directory of the ROS2 bag
+        #[clap(long, value_hint = ValueHint::AnyPath)]
+        input_path: PathBuf,
+
+        /// Path to the directory of the converted ROS2 bag
+        #[clap(long, value_hint = ValueHint::DirPath)]
+        output_directory_path: PathBuf,
+
+        /// Storage format of the converted ROS2 bag: mcap or sqlite3.
+        /// ROS1 bags are always converted to mcap.
+        #[clap(long, value_parser = parse_storage_format, default_value = "mcap")]
+        storage_format: StorageFormat,
+
+        /// Compression of the MCAP chunks: none, lz4 or zstd
+        #[clap(long, value_parser = parse_mcap_compression, default_value = "zstd")]
+        compression: McapCompression,
+
+        /// Size of the MCAP chunks in bytes
+        #[clap(long, default_value_t = DEFAULT_MCAP_CHUNK_SIZE)]
+        chunk_size: u64,
+    },
+
+    /// Play back a ROS2 bag to Foxglove over the Foxglove WebSocket protocol
+    Serve {
+        /// Path to the ROS2 bag
+        #[clap(long, value_hint = ValueHint::DirPath)]
+        rosbag_directory_path: PathBuf,
+
+        /// Host address the server listens on
+        #[clap(long, default_value = "127.0.0.1")]
+        host: String,
+
+        /// Port the server listens on
+        #[clap(long, default_value_t = DEFAULT_PORT)]
+        port: u16,
+
+        /// Factor of the playback rate relative to real time.
+        /// Example: 2.0 for twice as fast.
+        #[clap(long, default_value_t = 1.0)]
+        rate: f64,
+
+        /// Start the playback paused until the client plays it
+        #[clap(long)]
+        paused: bool,
+    },
+
     /// Append the transform tree to a ROS bag
     CreateFromEcoord {
         /// Path to the directory containing transform tree
@@ -101,3 +431,162 @@ pub enum Commands {
         rosbag_directory_path: PathBuf,
     },
 }
+
+/// Selection of the time window shared by all commands reading messages, where the window is
+/// clamped to the time span of the bag.
+#[derive(Args, Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeWindowArgs {
+    /// The start time in UTC.
+    /// Example: 2020-04-12T22:10:57.123456789+00:00
+    /// If not provided, the selection starts from the beginning of the bag.
+    #[clap(long, value_parser = parse_timestamp, conflicts_with = "start_seconds")]
+    #[serde(deserialize_with = "deserialize_optional_timestamp")]
+    pub start_date_time: Option<DateTime<Utc>>,
+
+    /// The end time in UTC (exclusive).
+    /// Example: 2020-04-12T22:10:57.123456789+00:00
+    /// If not provided, the selection runs until the end of the bag.
+    #[clap(long, value_parser = parse_timestamp, conflicts_with_all = ["end_seconds", "total_duration"])]
+    #[serde(deserialize_with = "deserialize_optional_timestamp")]
+    pub end_date_time: Option<DateTime<Utc>>,
+
+    /// The start in seconds relative to the beginning of the bag.
+    /// Example: 12.5
+    #[clap(long)]
+    pub start_seconds: Option<f64>,
+
+    /// The end in seconds relative to the beginning of the bag (exclusive).
+    /// Example: 42.0
+    #[clap(long, conflicts_with = "total_duration")]
+    pub end_seconds: Option<f64>,
+
+    /// The time offset added to the start.
+    /// Example: "5s" (5 seconds), "2m" (2 minutes).
+    /// If not provided, no offset is applied.
+    #[clap(long, value_parser = parse_duration)]
+    #[serde(deserialize_with = "deserialize_optional_duration")]
+    pub start_time_offset: Option<chrono::Duration>,
+
+    /// The total duration of the selection from the start.
+    /// Example: "30s" (30 seconds), "1h" (1 hour).
+    /// If not provided, the selection runs until the end time or the end of the bag.
+    #[clap(long, value_parser = parse_duration)]
+    #[serde(deserialize_with = "deserialize_optional_duration")]
+    pub total_duration: Option<chrono::Duration>,
+}
+
+/// Selection of the channels shared by the commands reading messages of arbitrary channels.
+#[derive(Args, Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChannelSelectionArgs {
+    /// Names or patterns of the channels, where `*` matches any sequence of characters.
+    /// Example: "/camera/*/image_raw".
+    /// If not provided, all channels with suitable message types are used.
+    #[clap(long, num_args = 1..)]
+    pub channel_names: Option<Vec<String>>,
+}
+
+/// Filters applied to the point cloud of each message.
+#[derive(Args, Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PointFilterArgs {
+    /// Keep only every n-th message of each point cloud channel
+    #[clap(long)]
+    pub keep_every_nth_message: Option<usize>,
+
+    /// Remove points with a NaN or infinite coordinate
+    #[clap(long, default_value_t = false)]
+    pub remove_non_finite: bool,
+
+    /// Minimum distance of the points to the sensor origin in meters
+    #[clap(long, default_value_t = 0.0)]
+    pub min_beam_length: f64,
+
+    /// Maximum distance of the points to the sensor origin in meters
+    #[clap(long, default_value_t = 30.0)]
+    pub max_beam_length: f64,
+
+    /// Minimum intensity of the points
+    #[clap(long)]
+    pub min_intensity: Option<f32>,
+
+    /// Maximum intensity of the points
+    #[clap(long)]
+    pub max_intensity: Option<f32>,
+
+    /// Keep only points inside the box given as min_x min_y min_z max_x max_y max_z in the
+    /// bounding box frame
+    #[clap(
+        long,
+        num_args = 6,
+        allow_negative_numbers = true,
+        requires = "bounding_box_frame_id"
+    )]
+    pub bounding_box: Option<Vec<f64>>,
+
+    /// Frame id of the bounding box
+    #[clap(long)]
+    pub bounding_box_frame_id: Option<String>,
+
+    /// Remove the points of the ego vehicle inside the box given as min_x min_y min_z max_x
+    /// max_y max_z in the ego box frame
+    #[clap(long, num_args = 6, allow_negative_numbers = true)]
+    pub ego_box: Option<Vec<f64>>,
+
+    /// Frame id of the ego box
+    #[clap(long, default_value = "base_link")]
+    pub ego_box_frame_id: String,
+
+    /// Keep one point per voxel with this edge length in meters, where the voxels are formed in
+    /// the target frame after merging the point clouds of the messages
+    #[clap(long)]
+    pub voxel_size: Option<f64>,
+}
+
+impl Default for PointFilterArgs {
+    fn default() -> Self {
+        Self {
+            keep_every_nth_message: None,
+            remove_non_finite: false,
+            min_beam_length: 0.0,
+            max_beam_length: 30.0,
+            min_intensity: None,
+            max_intensity: None,
+            bounding_box: None,
+            bounding_box_frame_id: None,
+            ego_box: None,
+            ego_box_frame_id: String::from("base_link"),
+            voxel_size: None,
+        }
+    }
+}
+
+/// Division of the point cloud messages into separately written point clouds, where all messages
+/// are merged into one point cloud, if none is provided.
+#[derive(Args, Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PointCloudGroupingArgs {
+    /// Write one point cloud per number of consecutive messages of a channel.
+    /// Example: 1 for one file per scan.
+    #[clap(long, conflicts_with_all = ["time_slice_duration", "sliding_window_duration"])]
+    pub messages_per_file: Option<usize>,
+
+    /// Write one point cloud per time slice of a channel.
+    /// Example: "1s".
+    #[clap(long, value_parser = parse_duration, conflicts_with = "sliding_window_duration")]
+    #[serde(deserialize_with = "deserialize_optional_duration")]
+    pub time_slice_duration: Option<chrono::Duration>,
+
+    /// Write one point cloud per sliding window of a channel, for example to build submaps.
+    /// Example: "5s".
+    #[clap(long, value_parser = parse_duration)]
+    #[serde(deserialize_with = "deserialize_optional_duration")]
+    pub sliding_window_duration: Option<chrono::Duration>,
+
+    /// Step between the starts of the sliding windows.
+    /// If not provided, the windows do not overlap.
+    #[clap(long, value_parser = parse_duration, requires = "sliding_window_duration")]
+    #[serde(deserialize_with = "deserialize_optional_duration")]
+    pub sliding_window_step: Option<chrono::Duration>,
+}
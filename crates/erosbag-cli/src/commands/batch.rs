@@ -0,0 +1,601 @@
+use crate::cli::{ChannelSelectionArgs, PointCloudGroupingArgs, PointFilterArgs, TimeWindowArgs};
+use crate::commands;
+use crate::error::Error;
+use crate::util::parse_table_format;
+use erosbag::ros1::Ros1Bag;
+use erosbag::storage::{StorageConversionOptions, StorageFormat};
+use erosbag::{MCAP_EXTENSION, ROS1_BAG_EXTENSION, SQLITE3_EXTENSION, ZSTD_EXTENSION};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use tracing::{error, info, warn};
+use walkdir::WalkDir;
+
+/// Name of the directory within the output directory holding one report per bag.
+const REPORTS_DIRECTORY_NAME: &str = "reports";
+const REPORT_EXTENSION: &str = "yaml";
+/// Name of the directory within the output directory holding the bags converted to MCAP.
+const CONVERTED_DIRECTORY_NAME: &str = "converted";
+
+const PLACEHOLDER_BAG_NAME: &str = "{bag_name}";
+const PLACEHOLDER_BAG_PATH: &str = "{bag_path}";
+
+/// Job specification listing the tasks run on each bag, such as:
+///
+/// ```yaml
+/// tasks:
+///   - type: extract_transforms
+///     output: "{bag_path}/transforms.ecoord"
+///   - type: extract_point_clouds
+///     output: "{bag_path}/point_clouds"
+///     channel_selection: { channel_names: ["/lidar/points", "/tf_static"] }
+///     time_window: { start_time_offset: 10s, total_duration: 1m }
+///     filter: { min_beam_length: 1.0, voxel_size: 0.05 }
+///     grouping: { time_slice_duration: 1s }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobSpec {
+    pub tasks: Vec<Task>,
+}
+
+impl JobSpec {
+    /// Reads the job specification and the hash of each task specification, so that outputs of
+    /// a changed task are not reused.
+    pub fn from_file(file_path: impl AsRef<Path>) -> Result<(Self, Vec<String>), Error> {
+        let value: serde_yaml::Value = serde_yaml::from_reader(fs::File::open(file_path)?)?;
+        let task_hashes = value
+            .get("tasks")
+            .and_then(|x| x.as_sequence())
+            .map(|x| x.iter().map(hash_task_spec).collect())
+            .transpose()?
+            .unwrap_or_default();
+        let job_spec: JobSpec = serde_yaml::from_value(value)?;
+        Ok((job_spec, task_hashes))
+    }
+}
+
+/// Returns the hex-encoded SHA-256 digest of the normalized task specification.
+fn hash_task_spec(task_spec: &serde_yaml::Value) -> Result<String, Error> {
+    let normalized = serde_yaml::to_string(task_spec)?;
+    let digest = Sha256::digest(normalized.as_bytes());
+    Ok(digest.iter().map(|x| format!("{x:02x}")).collect())
+}
+
+/// Task of a job specification, where the output is a path template relative to the output
+/// directory, which may contain the `{bag_name}` and `{bag_path}` placeholders.
+// the tasks are deserialized once per job
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Task {
+    ExtractTransforms {
+        output: String,
+        #[serde(default)]
+        time_window: TimeWindowArgs,
+        #[serde(default)]
+        channel_selection: ChannelSelectionArgs,
+    },
+    ExtractImages {
+        output: String,
+        #[serde(default)]
+        time_window: TimeWindowArgs,
+        #[serde(default)]
+        channel_selection: ChannelSelectionArgs,
+    },
+    ExportTable {
+        output: String,
+        #[serde(default)]
+        time_window: TimeWindowArgs,
+        #[serde(default)]
+        channel_selection: ChannelSelectionArgs,
+        #[serde(default = "default_table_format")]
+        format: String,
+    },
+    ExtractPointClouds {
+        output: String,
+        #[serde(default)]
+        time_window: TimeWindowArgs,
+        #[serde(default)]
+        channel_selection: ChannelSelectionArgs,
+        #[serde(default)]
+        target_frame_id: Option<String>,
+        #[serde(default)]
+        filter: PointFilterArgs,
+        #[serde(default)]
+        grouping: PointCloudGroupingArgs,
+        #[serde(default)]
+        source_columns: bool,
+        #[serde(default = "default_output_extension")]
+        output_extension: String,
+    },
+}
+
+fn default_table_format() -> String {
+    String::from("parquet")
+}
+
+fn default_output_extension() -> String {
+    String::from("xyz")
+}
+
+impl Task {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Task::ExtractTransforms { .. } => "extract_transforms",
+            Task::ExtractImages { .. } => "extract_images",
+            Task::ExportTable { .. } => "export_table",
+            Task::ExtractPointClouds { .. } => "extract_point_clouds",
+        }
+    }
+
+    fn output(&self) -> &str {
+        match self {
+            Task::ExtractTransforms { output, .. }
+            | Task::ExtractImages { output, .. }
+            | Task::ExportTable { output, .. }
+            | Task::ExtractPointClouds { output, .. } => output,
+        }
+    }
+
+    fn run(&self, rosbag_directory_path: &Path, output_path: &Path) -> Result<(), Error> {
+        match self {
+            Task::ExtractTransforms {
+                time_window,
+                channel_selection,
+                ..
+            } => commands::extract_transforms::run(
+                rosbag_directory_path,
+                time_window,
+                channel_selection,
+                output_path,
+            ),
+            Task::ExtractImages {
+                time_window,
+                channel_selection,
+                ..
+            } => commands::extract_images::run(
+                rosbag_directory_path,
+                time_window,
+                channel_selection,
+                output_path,
+            ),
+            Task::ExportTable {
+                time_window,
+                channel_selection,
+                format,
+                ..
+            } => commands::export_table::run(
+                rosbag_directory_path,
+                time_window,
+                channel_selection,
+                parse_table_format(format)?,
+                output_path,
+            ),
+            Task::ExtractPointClouds {
+                time_window,
+                channel_selection,
+                target_frame_id,
+                filter,
+                grouping,
+                source_columns,
+                output_extension,
+                ..
+            } => commands::extract_point_clouds::run(
+                rosbag_directory_path,
+                None::<PathBuf>,
+                time_window,
+                channel_selection,
+                target_frame_id.clone().map(|x| x.into()),
+                filter.filters(),
+                filter.voxel_size,
+                grouping.grouping(),
+                *source_columns,
+                output_extension,
+                output_path,
+            ),
+        }
+    }
+}
+
+/// Bag found below the input directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum RosbagKind {
+    /// ROS2 bag directory with MCAP files, which may be compressed as a whole.
+    Mcap,
+    /// ROS2 bag directory with SQLite3 files, which is converted to MCAP before processing.
+    Sqlite3,
+    /// ROS1 bag file, which is converted to MCAP before processing.
+    Ros1,
+}
+
+/// Report of all tasks run on a bag, which is rewritten after each task.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BagReport {
+    pub rosbag_directory_path: PathBuf,
+    pub tasks: Vec<TaskReport>,
+    /// Error, which aborted the processing of the bag before or between its tasks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskReport {
+    /// Index of the task within the job specification.
+    pub index: usize,
+    pub task_type: String,
+    /// SHA-256 digest of the task specification, by which a completed task is matched.
+    #[serde(default)]
+    pub spec_hash: String,
+    pub output_path: PathBuf,
+    pub status: TaskStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_seconds: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Succeeded,
+    Failed,
+    /// Completed by a previous run.
+    Skipped,
+}
+
+impl TaskReport {
+    fn is_complete(&self) -> bool {
+        self.status != TaskStatus::Failed && self.output_path.exists()
+    }
+}
+
+/// Runs the tasks of the job specification on each bag found below the input directory.
+///
+/// Bags are ROS2 directories with MCAP or SQLite3 files, which may be compressed as a whole, and
+/// ROS1 bag files. SQLite3 and ROS1 bags are converted to MCAP within the `converted` directory
+/// of the output directory first.
+///
+/// Tasks reported as completed by a previous run are skipped, if their specification is
+/// unchanged, their output still exists and force is not set. The reports are written to the
+/// `reports` directory within the output directory, mirroring the relative paths of the bags.
+/// A bag failing or panicking does not abort the processing of the other bags.
+pub fn run(
+    input_directory_path: impl AsRef<Path>,
+    job_file_path: impl AsRef<Path>,
+    output_directory_path: impl AsRef<Path>,
+    threads: usize,
+    force: bool,
+) -> Result<(), Error> {
+    let input_directory_path = input_directory_path.as_ref();
+    info!("Start batch processing");
+    info!("Input directory path: {}", input_directory_path.display());
+    info!("Job file path: {}", job_file_path.as_ref().display());
+
+    let (job_spec, task_hashes) = JobSpec::from_file(job_file_path)?;
+    let rosbags = find_rosbags(input_directory_path)?;
+    info!(
+        "Found {} bags for {} tasks",
+        rosbags.len(),
+        job_spec.tasks.len()
+    );
+
+    let output_directory_path = output_directory_path.as_ref();
+    let reports_directory_path = output_directory_path.join(REPORTS_DIRECTORY_NAME);
+    fs::create_dir_all(&reports_directory_path)?;
+
+    let thread_pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()?;
+    let processed_count = AtomicUsize::new(0);
+    let reports: Vec<BagReport> = thread_pool.install(|| {
+        rosbags
+            .par_iter()
+            .map(|(rosbag_path, rosbag_kind)| {
+                let relative_path = rosbag_path
+                    .strip_prefix(input_directory_path)
+                    .unwrap_or(rosbag_path);
+                let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                    process_rosbag(
+                        rosbag_path,
+                        *rosbag_kind,
+                        relative_path,
+                        &job_spec,
+                        &task_hashes,
+                        output_directory_path,
+                        &reports_directory_path,
+                        force,
+                    )
+                }))
+                .unwrap_or_else(|panic| Err(panic_message(panic)));
+                let report = result.unwrap_or_else(|(mut report, message)| {
+                    error!("Processing {} failed: {message}", rosbag_path.display());
+                    report.rosbag_directory_path = rosbag_path.to_path_buf();
+                    report.error = Some(message);
+                    let report_path = get_report_path(&reports_directory_path, relative_path);
+                    if let Err(e) = write_report(&report_path, &report) {
+                        error!("Writing report {} failed: {e}", report_path.display());
+                    }
+                    report
+                });
+
+                let processed = processed_count.fetch_add(1, Ordering::SeqCst) + 1;
+                match &report.error {
+                    Some(error) => info!(
+                        "[{processed}/{}] Processing {} failed: {error}",
+                        rosbags.len(),
+                        rosbag_path.display()
+                    ),
+                    None => {
+                        let failed = report
+                            .tasks
+                            .iter()
+                            .filter(|x| x.status == TaskStatus::Failed)
+                            .count();
+                        info!(
+                            "[{processed}/{}] Processed {} with {failed} failed tasks",
+                            rosbags.len(),
+                            rosbag_path.display()
+                        );
+                    }
+                }
+                report
+            })
+            .collect()
+    });
+
+    let task_reports: Vec<&TaskReport> = reports.iter().flat_map(|x| &x.tasks).collect();
+    let failed_count = task_reports
+        .iter()
+        .filter(|x| x.status == TaskStatus::Failed)
+        .count();
+    let skipped_count = task_reports
+        .iter()
+        .filter(|x| x.status == TaskStatus::Skipped)
+        .count();
+    let failed_rosbag_count = reports.iter().filter(|x| x.error.is_some()).count();
+    info!(
+        "Completed {} tasks, of which {skipped_count} were skipped and {failed_count} failed.",
+        task_reports.len()
+    );
+    if failed_rosbag_count > 0 {
+        return Err(Error::FailedBatchRosbags(
+            failed_rosbag_count,
+            reports.len(),
+        ));
+    }
+    if failed_count > 0 {
+        return Err(Error::FailedBatchTasks(failed_count, task_reports.len()));
+    }
+
+    Ok(())
+}
+
+/// Returns the bags below the path, which are the directories containing at least one MCAP or
+/// SQLite3 file, possibly compressed with zstd, and the ROS1 bag files.
+///
+/// A directory containing both MCAP and SQLite3 files is read as MCAP bag.
+fn find_rosbags(directory_path: &Path) -> Result<Vec<(PathBuf, RosbagKind)>, Error> {
+    let mut rosbags: BTreeMap<PathBuf, RosbagKind> = BTreeMap::new();
+    for entry in WalkDir::new(directory_path) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let mut file_path = entry.path().to_path_buf();
+        if file_path.extension() == Some(std::ffi::OsStr::new(ZSTD_EXTENSION)) {
+            file_path = file_path.with_extension("");
+        }
+        let (rosbag_path, rosbag_kind) = match file_path.extension().and_then(|x| x.to_str()) {
+            Some(MCAP_EXTENSION) => (entry.path().parent(), RosbagKind::Mcap),
+            Some(SQLITE3_EXTENSION) => (entry.path().parent(), RosbagKind::Sqlite3),
+            Some(ROS1_BAG_EXTENSION) if file_path == entry.path() => {
+                (Some(entry.path()), RosbagKind::Ros1)
+            }
+            _ => continue,
+        };
+        if let Some(rosbag_path) = rosbag_path {
+            let rosbag_kind = rosbags
+                .get(rosbag_path)
+                .map_or(rosbag_kind, |x| rosbag_kind.min(*x));
+            rosbags.insert(rosbag_path.to_path_buf(), rosbag_kind);
+        }
+    }
+    Ok(rosbags.into_iter().collect())
+}
+
+/// Returns the path of the report of a bag, which mirrors its relative path, so that the
+/// reports of distinct bags never collide.
+fn get_report_path(reports_directory_path: &Path, relative_path: &Path) -> PathBuf {
+    let mut report_path = reports_directory_path.join(relative_path).into_os_string();
+    report_path.push(format!(".{REPORT_EXTENSION}"));
+    report_path.into()
+}
+
+fn write_report(report_path: &Path, report: &BagReport) -> Result<(), Error> {
+    if let Some(parent) = report_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    serde_yaml::to_writer(fs::File::create(report_path)?, report)?;
+    Ok(())
+}
+
+fn panic_message(panic: Box<dyn std::any::Any + Send>) -> (BagReport, String) {
+    let message = panic
+        .downcast_ref::<&str>()
+        .map(|x| x.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    (BagReport::default(), format!("panicked: {message}"))
+}
+
+/// Converts a SQLite3 or ROS1 bag to an MCAP bag in the directory, which is replaced.
+fn convert_to_mcap(
+    rosbag_path: &Path,
+    rosbag_kind: RosbagKind,
+    converted_directory_path: &Path,
+) -> Result<(), Error> {
+    info!(
+        "Converting {} to {}",
+        rosbag_path.display(),
+        converted_directory_path.display()
+    );
+    if converted_directory_path.exists() {
+        fs::remove_dir_all(converted_directory_path)?;
+    }
+    match rosbag_kind {
+        RosbagKind::Mcap => {}
+        RosbagKind::Sqlite3 => {
+            erosbag::storage::convert_storage(
+                rosbag_path,
+                converted_directory_path,
+                &StorageConversionOptions::new(StorageFormat::Mcap),
+            )?;
+        }
+        RosbagKind::Ros1 => {
+            fs::create_dir_all(converted_directory_path)?;
+            let file_stem = rosbag_path.file_stem().unwrap_or_default();
+            let mcap_file_path = converted_directory_path
+                .join(file_stem)
+                .with_extension(MCAP_EXTENSION);
+            Ros1Bag::new(rosbag_path)?.convert(mcap_file_path)?;
+        }
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn process_rosbag(
+    rosbag_path: &Path,
+    rosbag_kind: RosbagKind,
+    relative_path: &Path,
+    job_spec: &JobSpec,
+    task_hashes: &[String],
+    output_directory_path: &Path,
+    reports_directory_path: &Path,
+    force: bool,
+) -> Result<BagReport, (BagReport, String)> {
+    let mut report = BagReport {
+        rosbag_directory_path: rosbag_path.to_path_buf(),
+        tasks: Vec::new(),
+        error: None,
+    };
+    let fail = |report: &BagReport, error: Error| (report.clone(), error.to_string());
+
+    let bag_name = match rosbag_kind {
+        RosbagKind::Ros1 => rosbag_path.file_stem(),
+        RosbagKind::Mcap | RosbagKind::Sqlite3 => rosbag_path.file_name(),
+    }
+    .map(|x| x.to_string_lossy().to_string())
+    .unwrap_or_default();
+    let relative_path = if relative_path.as_os_str().is_empty() {
+        Path::new(&bag_name)
+    } else {
+        relative_path
+    };
+    let bag_path = relative_path.to_string_lossy().replace('\\', "/");
+    let report_path = get_report_path(reports_directory_path, relative_path);
+
+    let previous_report: Option<BagReport> = if !force && report_path.exists() {
+        let previous_report = fs::File::open(&report_path)
+            .map_err(Error::from)
+            .and_then(|x| serde_yaml::from_reader(x).map_err(Error::from));
+        match previous_report {
+            Ok(report) => Some(report),
+            Err(e) => {
+                warn!("Ignoring unreadable report {}: {e}", report_path.display());
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    // converted lazily, since all tasks may have been completed by a previous run
+    let mut mcap_directory_path: Option<PathBuf> = match rosbag_kind {
+        RosbagKind::Mcap => Some(rosbag_path.to_path_buf()),
+        RosbagKind::Sqlite3 | RosbagKind::Ros1 => None,
+    };
+    for (index, task) in job_spec.tasks.iter().enumerate() {
+        let spec_hash = task_hashes.get(index).cloned().unwrap_or_default();
+        let output_path = output_directory_path.join(
+            task.output()
+                .replace(PLACEHOLDER_BAG_NAME, &bag_name)
+                .replace(PLACEHOLDER_BAG_PATH, &bag_path),
+        );
+
+        let is_complete = previous_report.as_ref().is_some_and(|previous_report| {
+            previous_report.tasks.iter().any(|x| {
+                x.index == index
+                    && x.spec_hash == spec_hash
+                    && x.output_path == output_path
+                    && x.is_complete()
+            })
+        });
+        if is_complete {
+            info!(
+                "Skipping completed task {} for {}",
+                task.name(),
+                rosbag_path.display()
+            );
+            report.tasks.push(TaskReport {
+                index,
+                task_type: task.name().to_string(),
+                spec_hash,
+                output_path,
+                status: TaskStatus::Skipped,
+                error: None,
+                duration_seconds: 0.0,
+            });
+            continue;
+        }
+
+        let mcap_directory_path = match &mcap_directory_path {
+            Some(x) => x.clone(),
+            None => {
+                let converted_directory_path = output_directory_path
+                    .join(CONVERTED_DIRECTORY_NAME)
+                    .join(relative_path);
+                convert_to_mcap(rosbag_path, rosbag_kind, &converted_directory_path)
+                    .map_err(|e| fail(&report, e))?;
+                mcap_directory_path.insert(converted_directory_path).clone()
+            }
+        };
+
+        let start = Instant::now();
+        let result = output_path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .map_err(Error::from)
+            .and_then(|_| task.run(&mcap_directory_path, &output_path));
+        if let Err(e) = &result {
+            error!(
+                "Task {} failed for {}: {e}",
+                task.name(),
+                rosbag_path.display()
+            );
+        }
+        report.tasks.push(TaskReport {
+            index,
+            task_type: task.name().to_string(),
+            spec_hash,
+            output_path,
+            status: if result.is_ok() {
+                TaskStatus::Succeeded
+            } else {
+                TaskStatus::Failed
+            },
+            error: result.err().map(|x| x.to_string()),
+            duration_seconds: start.elapsed().as_secs_f64(),
+        });
+        write_report(&report_path, &report).map_err(|e| fail(&report, e))?;
+    }
+    write_report(&report_path, &report).map_err(|e| fail(&report, e))?;
+
+    Ok(report)
+}
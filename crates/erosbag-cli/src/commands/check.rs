@@ -0,0 +1,79 @@
+use crate::cli::{ChannelSelectionArgs, TimeWindowArgs};
+use crate::error::Error;
+use chrono::Duration;
+use erosbag::Rosbag;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use tracing::{info, warn};
+
+pub fn run(
+    rosbag_directory_path: impl AsRef<Path>,
+    time_window: &TimeWindowArgs,
+    channel_selection: &ChannelSelectionArgs,
+    max_gap_duration: Duration,
+    output_path: Option<impl AsRef<Path>>,
+) -> Result<(), Error> {
+    info!("Start checking data quality");
+    info!("Rosbag path: {}", rosbag_directory_path.as_ref().display());
+
+    let rosbag = Rosbag::new(rosbag_directory_path.as_ref())?;
+    let (start_date_time, end_date_time) = time_window.resolve(&rosbag)?;
+    let channel_topics = channel_selection.resolve(&rosbag)?;
+    let report = rosbag.quality_report(
+        &start_date_time,
+        &end_date_time,
+        &channel_topics,
+        max_gap_duration,
+    )?;
+
+    for current_channel in &report.channels {
+        let format_rate =
+            |rate: Option<f64>| rate.map_or("-".to_string(), |x| format!("{x:.2} Hz"));
+        info!(
+            "{}: {} messages, rate {} (expected {}), ~{} dropped",
+            current_channel.channel_topic,
+            current_channel.message_count,
+            format_rate(current_channel.actual_rate),
+            format_rate(current_channel.expected_rate),
+            current_channel.estimated_dropped_messages
+        );
+        if let Some(interval_statistics) = &current_channel.interval_statistics {
+            info!(
+                "  interval: median {:.4}s, max {:.4}s, jitter {:.4}s",
+                interval_statistics.median,
+                interval_statistics.max,
+                interval_statistics.standard_deviation
+            );
+        }
+        if let Some(latency_statistics) = &current_channel.latency_statistics {
+            info!(
+                "  latency: median {:.4}s, p95 {:.4}s, max {:.4}s",
+                latency_statistics.median, latency_statistics.percentile_95, latency_statistics.max
+            );
+        }
+    }
+    for current_issue in &report.issues {
+        warn!("{current_issue}");
+    }
+
+    if report.is_consistent() {
+        info!("No issues found.");
+    } else {
+        info!("Found {} issues.", report.issues.len());
+    }
+
+    match output_path {
+        Some(output_path) => {
+            info!("Writing report to {}", output_path.as_ref().display());
+            let writer = BufWriter::new(File::create(output_path)?);
+            serde_json::to_writer_pretty(writer, &report)?;
+        }
+        None => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+
+    if !report.is_consistent() {
+        return Err(Error::QualityIssuesFound(report.issues.len()));
+    }
+    Ok(())
+}
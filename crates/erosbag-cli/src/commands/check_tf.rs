@@ -0,0 +1,52 @@
+use crate::cli::{ChannelSelectionArgs, TimeWindowArgs};
+use crate::error::Error;
+use chrono::Duration;
+use erosbag::Rosbag;
+use std::path::Path;
+use tracing::{info, warn};
+
+pub fn run(
+    rosbag_directory_path: impl AsRef<Path>,
+    time_window: &TimeWindowArgs,
+    channel_selection: &ChannelSelectionArgs,
+    target_frame_id: Option<ecoord::FrameId>,
+    max_gap_duration: Duration,
+) -> Result<(), Error> {
+    info!("Start checking transforms");
+    info!("Rosbag path: {}", rosbag_directory_path.as_ref().display());
+
+    let rosbag = Rosbag::new(rosbag_directory_path.as_ref())?;
+    let (start_date_time, end_date_time) = time_window.resolve(&rosbag)?;
+    let channel_topics = channel_selection.resolve(&rosbag)?;
+    let report = rosbag.check_transforms(
+        &start_date_time,
+        &end_date_time,
+        &channel_topics,
+        target_frame_id,
+        max_gap_duration,
+    )?;
+
+    info!(
+        "Checked {} frames: {}",
+        report.frame_ids.len(),
+        report
+            .frame_ids
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    if let Some(target_frame_id) = &report.target_frame_id {
+        info!("Checked the reachability of the sensor frames from `{target_frame_id}`");
+    }
+    for current_issue in &report.issues {
+        warn!("{current_issue}");
+    }
+
+    if !report.is_consistent() {
+        return Err(Error::TransformIssuesFound(report.issues.len()));
+    }
+    info!("No issues found.");
+
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use crate::cli::{ChannelSelectionArgs, TimeWindowArgs};
+use crate::error::Error;
+use epoint::io::AutoWriter;
+use erosbag::ros_messages::RosMessageType;
+use erosbag::transform::CameraChannels;
+use erosbag::{ChannelTopic, Rosbag};
+use std::path::Path;
+use tracing::info;
+
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    rosbag_directory_path: impl AsRef<Path>,
+    time_window: &TimeWindowArgs,
+    image_channel_topics: Vec<ChannelTopic>,
+    camera_info_channel_topics: Vec<ChannelTopic>,
+    channel_selection: &ChannelSelectionArgs,
+    target_frame_id: Option<ecoord::FrameId>,
+    max_time_difference: chrono::Duration,
+    occlusion_tolerance: f64,
+    output_path: impl AsRef<Path>,
+) -> Result<(), Error> {
+    info!("Start colorizing point clouds");
+    info!("Rosbag path: {}", rosbag_directory_path.as_ref().display());
+    if image_channel_topics.len() != camera_info_channel_topics.len() {
+        return Err(Error::MismatchedCameraChannelCount(
+            image_channel_topics.len(),
+            camera_info_channel_topics.len(),
+        ));
+    }
+
+    let rosbag = Rosbag::new(rosbag_directory_path.as_ref())?;
+    let (start_date_time, end_date_time) = time_window.resolve(&rosbag)?;
+    let cameras: Vec<CameraChannels> = image_channel_topics
+        .into_iter()
+        .zip(camera_info_channel_topics)
+        .map(|(image, camera_info)| CameraChannels::new(image, camera_info))
+        .collect();
+
+    let point_cloud_channel_topics = channel_selection
+        .resolve_of_types(&rosbag, &[RosMessageType::SensorMessagesPointCloud2])?;
+    let transform_channel_topics =
+        channel_selection.resolve_of_types(&rosbag, &[RosMessageType::Tf2MessagesTFMessage])?;
+    let transform_tree =
+        rosbag.get_transforms(&start_date_time, &end_date_time, &transform_channel_topics)?;
+
+    let mut point_cloud = erosbag::transform::colorize_point_clouds(
+        &rosbag,
+        &start_date_time,
+        &end_date_time,
+        &point_cloud_channel_topics,
+        &cameras,
+        &transform_tree,
+        max_time_difference,
+        occlusion_tolerance,
+    )?;
+    if let Some(target_frame_id) = target_frame_id {
+        point_cloud.resolve_to_frame(target_frame_id)?;
+    }
+    info!("Colorized {} points", point_cloud.size());
+
+    info!("Start writing to: {}", output_path.as_ref().display());
+    AutoWriter::from_path(output_path)?.finish(point_cloud)?;
+
+    Ok(())
+}
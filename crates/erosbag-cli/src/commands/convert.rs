@@ -0,0 +1,83 @@
+use crate::error::Error;
+use erosbag::ros1::Ros1Bag;
+use erosbag::storage::StorageConversionOptions;
+use erosbag::{MCAP_EXTENSION, Rosbag};
+use std::path::Path;
+use tracing::info;
+
+pub fn run(
+    input_path: impl AsRef<Path>,
+    output_directory_path: impl AsRef<Path>,
+    options: &StorageConversionOptions,
+) -> Result<(), Error> {
+    info!("Start converting");
+    info!("Input path: {}", input_path.as_ref().display());
+    info!(
+        "Output directory path: {}",
+        output_directory_path.as_ref().display()
+    );
+
+    if input_path.as_ref().is_dir() {
+        convert_storage(input_path, output_directory_path, options)
+    } else {
+        convert_ros1_bag(input_path, output_directory_path)
+    }
+}
+
+fn convert_storage(
+    input_directory_path: impl AsRef<Path>,
+    output_directory_path: impl AsRef<Path>,
+    options: &StorageConversionOptions,
+) -> Result<(), Error> {
+    info!(
+        "Storage format: {} (compression: {}, chunk size: {} bytes)",
+        options.storage_format, options.compression, options.chunk_size
+    );
+
+    let metadata =
+        erosbag::storage::convert_storage(input_directory_path, output_directory_path, options)?;
+    let information = metadata.rosbag2_bagfile_information;
+    info!(
+        "Converted {} files with {} topics and {} messages",
+        information.relative_file_paths.len(),
+        information.topics_with_message_count.len(),
+        information.message_count
+    );
+
+    Ok(())
+}
+
+fn convert_ros1_bag(
+    input_path: impl AsRef<Path>,
+    output_directory_path: impl AsRef<Path>,
+) -> Result<(), Error> {
+    let ros1_bag = Ros1Bag::new(input_path.as_ref())?;
+    info!(
+        "ROS1 bag with {} connections and {} messages",
+        ros1_bag.connections().len(),
+        ros1_bag.get_message_count()
+    );
+    for current_connection in ros1_bag.connections().values() {
+        info!(
+            "Connection {}: {} ({})",
+            current_connection.id, current_connection.topic, current_connection.message_type
+        );
+    }
+
+    std::fs::create_dir_all(output_directory_path.as_ref())?;
+    let file_stem = input_path.as_ref().file_stem().unwrap_or_default();
+    let mcap_file_path = output_directory_path
+        .as_ref()
+        .join(file_stem)
+        .with_extension(MCAP_EXTENSION);
+    ros1_bag.convert(mcap_file_path)?;
+
+    let rosbag = Rosbag::new(output_directory_path)?;
+    let overview = rosbag.get_overview()?;
+    info!(
+        "Converted to {} channels",
+        overview.files.values().map(|x| x.len()).sum::<usize>()
+    );
+
+    Ok(())
+}
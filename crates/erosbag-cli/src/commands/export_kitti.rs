@@ -0,0 +1,59 @@
+use crate::cli::{ChannelSelectionArgs, TimeWindowArgs};
+use crate::error::Error;
+use erosbag::transform::{CameraChannels, KittiChannels};
+use erosbag::{ChannelTopic, Rosbag};
+use std::path::Path;
+use tracing::info;
+
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    rosbag_directory_path: impl AsRef<Path>,
+    time_window: &TimeWindowArgs,
+    lidar_channel_topic: ChannelTopic,
+    image_channel_topics: Vec<ChannelTopic>,
+    camera_info_channel_topics: Vec<ChannelTopic>,
+    nav_sat_fix_channel_topic: Option<ChannelTopic>,
+    imu_channel_topic: Option<ChannelTopic>,
+    channel_selection: &ChannelSelectionArgs,
+    max_time_difference: chrono::Duration,
+    output_directory_path: impl AsRef<Path>,
+) -> Result<(), Error> {
+    info!("Start exporting KITTI raw dataset");
+    info!("Rosbag path: {}", rosbag_directory_path.as_ref().display());
+    info!(
+        "Output directory path: {}",
+        output_directory_path.as_ref().display()
+    );
+    if image_channel_topics.len() != camera_info_channel_topics.len() {
+        return Err(Error::MismatchedCameraChannelCount(
+            image_channel_topics.len(),
+            camera_info_channel_topics.len(),
+        ));
+    }
+
+    let rosbag = Rosbag::new(rosbag_directory_path.as_ref())?;
+    let (start_date_time, end_date_time) = time_window.resolve(&rosbag)?;
+    let channels = KittiChannels {
+        lidar_channel_topic,
+        cameras: image_channel_topics
+            .into_iter()
+            .zip(camera_info_channel_topics)
+            .map(|(image, camera_info)| CameraChannels::new(image, camera_info))
+            .collect(),
+        nav_sat_fix_channel_topic,
+        imu_channel_topic,
+        transform_channel_topics: channel_selection.resolve(&rosbag)?,
+    };
+
+    let frame_count = erosbag::transform::export_kitti_raw(
+        &rosbag,
+        &start_date_time,
+        &end_date_time,
+        &channels,
+        max_time_difference,
+        output_directory_path,
+    )?;
+    info!("Exported {frame_count} frames");
+
+    Ok(())
+}
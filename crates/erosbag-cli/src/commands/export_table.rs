@@ -0,0 +1,54 @@
+use crate::cli::{ChannelSelectionArgs, TimeWindowArgs};
+use crate::error::Error;
+use erosbag::table::TableFormat;
+use erosbag::{ChannelTopic, Rosbag};
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+pub fn run(
+    rosbag_directory_path: impl AsRef<Path>,
+    time_window: &TimeWindowArgs,
+    channel_selection: &ChannelSelectionArgs,
+    table_format: TableFormat,
+    output_directory_path: impl AsRef<Path>,
+) -> Result<(), Error> {
+    info!("Start exporting tables");
+    info!("Rosbag path: {}", rosbag_directory_path.as_ref().display());
+    info!(
+        "Output directory path: {}",
+        output_directory_path.as_ref().display()
+    );
+
+    let rosbag = Rosbag::new(rosbag_directory_path.as_ref())?;
+    let (start_date_time, end_date_time) = time_window.resolve(&rosbag)?;
+    let channel_topics = channel_selection.resolve(&rosbag)?;
+    let mut data_frames =
+        rosbag.get_data_frames(&start_date_time, &end_date_time, &channel_topics)?;
+
+    fs::create_dir_all(output_directory_path.as_ref())?;
+    let mut exported_channel_topics: Vec<ChannelTopic> = data_frames.keys().cloned().collect();
+    exported_channel_topics.sort();
+    for channel_topic in exported_channel_topics {
+        let data_frame = data_frames.get_mut(&channel_topic).expect("should exist");
+        let file_name = format!(
+            "{}.{}",
+            channel_topic
+                .to_string()
+                .trim_start_matches('/')
+                .replace('/', "_"),
+            table_format.extension()
+        );
+        let output_path = output_directory_path.as_ref().join(file_name);
+
+        info!(
+            "Writing {} messages of {} to {}",
+            data_frame.height(),
+            channel_topic,
+            output_path.display()
+        );
+        table_format.write(data_frame, output_path)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use crate::cli::{ChannelSelectionArgs, TimeWindowArgs};
+use crate::error::Error;
+use ecoord::FrameId;
+use erosbag::transform::{TrajectoryFormat, TrajectorySource};
+use erosbag::{ChannelTopic, Rosbag};
+use std::fs;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use tracing::info;
+
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    rosbag_directory_path: impl AsRef<Path>,
+    time_window: &TimeWindowArgs,
+    channel_selection: &ChannelSelectionArgs,
+    pose_channel_topic: Option<ChannelTopic>,
+    parent_frame_id: FrameId,
+    child_frame_id: FrameId,
+    sampling_interval: Option<chrono::Duration>,
+    relative_to_first: bool,
+    trajectory_format: TrajectoryFormat,
+    output_path: impl AsRef<Path>,
+) -> Result<(), Error> {
+    info!("Start exporting trajectory");
+    info!("Rosbag path: {}", rosbag_directory_path.as_ref().display());
+    info!("Output path: {}", output_path.as_ref().display());
+
+    let rosbag = Rosbag::new(rosbag_directory_path.as_ref())?;
+    let (start_date_time, end_date_time) = time_window.resolve(&rosbag)?;
+    let source = match pose_channel_topic {
+        Some(pose_channel_topic) => TrajectorySource::Poses(pose_channel_topic),
+        None => TrajectorySource::Transforms(channel_selection.resolve(&rosbag)?),
+    };
+    let mut trajectory = erosbag::transform::get_trajectory(
+        &rosbag,
+        &start_date_time,
+        &end_date_time,
+        &source,
+        parent_frame_id,
+        child_frame_id,
+        sampling_interval,
+    )?;
+    if relative_to_first {
+        trajectory = trajectory.relative_to_first();
+    }
+
+    info!(
+        "Writing {} poses of {} in {} as {}",
+        trajectory.poses.len(),
+        trajectory.child_frame_id,
+        trajectory.parent_frame_id,
+        trajectory_format
+    );
+    if let Some(parent_directory_path) = output_path.as_ref().parent() {
+        fs::create_dir_all(parent_directory_path)?;
+    }
+    let writer = BufWriter::new(File::create(output_path)?);
+    trajectory.write(trajectory_format, writer)?;
+
+    Ok(())
+}
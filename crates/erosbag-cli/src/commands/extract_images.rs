@@ -1,13 +1,15 @@
-use chrono::{DateTime, Duration, Utc};
 use erosbag::Rosbag;
 use std::fs;
 
+use crate::cli::{ChannelSelectionArgs, TimeWindowArgs};
 use crate::error::Error;
 use std::path::Path;
 use tracing::info;
 
 pub fn run(
     rosbag_directory_path: impl AsRef<Path>,
+    time_window: &TimeWindowArgs,
+    channel_selection: &ChannelSelectionArgs,
     output_eimage_path: impl AsRef<Path>,
 ) -> Result<(), Error> {
     info!("Start extracting images");
@@ -19,12 +21,10 @@ pub fn run(
 
     let rosbag = Rosbag::new(rosbag_directory_path.as_ref())?;
 
-    let start_date_time: DateTime<Utc> =
-        rosbag.get_start_date_time()?.unwrap() + Duration::seconds(5);
-    let end_date_time: DateTime<Utc> = start_date_time + Duration::seconds(1);
+    let (start_date_time, end_date_time) = time_window.resolve(&rosbag)?;
+    let channel_topics = channel_selection.resolve(&rosbag)?;
 
-    let image_collection =
-        rosbag.get_images(&Some(start_date_time), &Some(end_date_time), &None)?;
+    let image_collection = rosbag.get_images(&start_date_time, &end_date_time, &channel_topics)?;
 
     fs::create_dir_all(output_eimage_path.as_ref().parent().expect("should exist"))?;
     info!("Extracted {} images.", image_collection.total_image_count());
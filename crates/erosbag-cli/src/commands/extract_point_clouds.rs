@@ -1,104 +1,124 @@
-use chrono::{DateTime, Duration, Utc};
 use ecoord::merge;
+use epoint::PointCloud;
 use epoint::io::AutoWriter;
-use erosbag::{ChannelTopic, Rosbag};
-use std::collections::HashSet;
+use erosbag::Rosbag;
+use erosbag::dto::{PointCloudGrouping, get_point_cloud_group_index};
+use erosbag::filter::PointFilter;
+use erosbag::ros_messages::RosMessageType;
+use erosbag::table::TableFormat;
 
+use crate::cli::{ChannelSelectionArgs, TimeWindowArgs};
 use crate::error::Error;
+use std::fs;
 use std::path::Path;
 use tracing::{info, warn};
 
+/// Name of the sidecar index listing the messages of each written point cloud.
+const INDEX_FILE_NAME: &str = "index.csv";
+
+/// Extracts the point clouds, which are written to output_path for the combined grouping and
+/// otherwise as one file per group into the output_path directory.
+///
+/// The point cloud and tf channels are taken from the channel selection, where all channels of
+/// a kind are used, if none of them is selected.
+#[allow(clippy::too_many_arguments)]
 pub fn run(
     rosbag_directory_path: impl AsRef<Path>,
     ecoord_file_path: Option<impl AsRef<Path>>,
-    start_date_time: Option<DateTime<Utc>>,
-    end_date_time: Option<DateTime<Utc>>,
-    start_time_offset: Option<Duration>,
-    total_duration: Option<Duration>,
-    transform_channel_id: ChannelTopic,
+    time_window: &TimeWindowArgs,
+    channel_selection: &ChannelSelectionArgs,
     target_frame_id: Option<ecoord::FrameId>,
+    filters: Vec<PointFilter>,
+    voxel_size: Option<f64>,
+    grouping: PointCloudGrouping,
+    with_source_columns: bool,
+    output_extension: &str,
     output_path: impl AsRef<Path>,
 ) -> Result<(), Error> {
     let rosbag = Rosbag::new(rosbag_directory_path.as_ref())?;
-    let rosbag_start_date_time = match rosbag.get_start_date_time()? {
-        Some(date_time) => date_time,
-        None => {
-            panic!("Not able to retrieve start date time from Rosbag.")
+    let (start_date_time, end_date_time) = time_window.resolve(&rosbag)?;
+
+    let point_cloud_channel_topics = channel_selection.resolve_of_types(
+        &rosbag,
+        &[
+            RosMessageType::SensorMessagesPointCloud2,
+            RosMessageType::SensorMessagesLaserScan,
+            RosMessageType::SensorMessagesMultiEchoLaserScan,
+        ],
+    )?;
+    let transform_channel_topics =
+        channel_selection.resolve_of_types(&rosbag, &[RosMessageType::Tf2MessagesTFMessage])?;
+
+    let transform_tree =
+        rosbag.get_transforms(&start_date_time, &end_date_time, &transform_channel_topics)?;
+    let additional_transform_tree = match ecoord_file_path {
+        Some(ecoord_file_path) => {
+            Some(ecoord::io::EcoordReader::from_path(ecoord_file_path)?.finish()?)
         }
+        None => None,
     };
-    let rosbag_end_date_time = match rosbag.get_end_date_time()? {
-        Some(date_time) => date_time,
-        None => {
-            panic!("Not able to retrieve end date time from Rosbag.")
+
+    let point_cloud_groups = rosbag.get_point_cloud_groups(
+        &start_date_time,
+        &end_date_time,
+        &point_cloud_channel_topics,
+        &filters,
+        &grouping,
+        with_source_columns,
+    )?;
+    info!("Read {} point cloud groups", point_cloud_groups.len());
+
+    let process = |mut point_cloud: PointCloud| -> Result<PointCloud, Error> {
+        point_cloud.transform_tree = transform_tree.clone();
+        if let Some(additional_transform_tree) = &additional_transform_tree {
+            let original_transform_tree = point_cloud.transform_tree().clone();
+            let merged_transform_tree =
+                merge(&[original_transform_tree, additional_transform_tree.clone()])?;
+            point_cloud.set_transform_tree(merged_transform_tree);
         }
-    };
-    info!(
-        "Rosbag times: {rosbag_start_date_time} - {rosbag_end_date_time} with a duration of {}",
-        rosbag_end_date_time - rosbag_start_date_time
-    );
 
-    let start_date_time: DateTime<Utc> =
-        start_date_time.unwrap_or(rosbag_start_date_time) + start_time_offset.unwrap_or_default();
-    let end_date_time: DateTime<Utc> = match (total_duration, end_date_time) {
-        (Some(_total_duration), Some(end_date_time)) => {
-            warn!("Both end_date_time and total_duration defined. Using end_date_time");
-            end_date_time
+        // point_cloud.point_data.add_sequential_id()?;
+        // point_cloud.derive_spherical_points()?;
+        if let Some(target_frame_id) = &target_frame_id {
+            point_cloud.resolve_to_frame(target_frame_id.clone())?;
+        }
+        if let Some(voxel_size) = voxel_size {
+            erosbag::filter::voxel_downsample(&mut point_cloud, voxel_size)?;
         }
-        (Some(total_duration), None) => start_date_time + total_duration,
-        (None, Some(end_date_time)) => end_date_time,
-        _ => rosbag_end_date_time,
-    };
 
-    let start_date_time = if rosbag_start_date_time <= start_date_time {
-        start_date_time
-    } else {
-        warn!(
-            "Defined start_date_time ({}) is before rosbag's start date time ({})",
-            start_date_time, rosbag_start_date_time
-        );
-        rosbag_start_date_time
-    };
-    let end_date_time = if end_date_time <= rosbag_end_date_time {
-        end_date_time
-    } else {
-        warn!(
-            "Defined end_date_time ({}) is after rosbag's end date time ({})",
-            end_date_time, rosbag_end_date_time
-        );
-        rosbag_end_date_time
+        Ok(point_cloud)
     };
 
-    let mut point_cloud =
-        rosbag.get_point_clouds(&Some(start_date_time), &Some(end_date_time), &None)?;
-    info!("Read {} points", point_cloud.size());
-    if rosbag.contains_channel(&transform_channel_id)? {
-        let channel_ids = HashSet::from([transform_channel_id]);
-        point_cloud.transform_tree = rosbag.get_transforms(&None, &None, &Some(channel_ids))?;
-    }
-
-    if let Some(ecoord_file_path) = ecoord_file_path {
-        let additional_transform_tree =
-            ecoord::io::EcoordReader::from_path(ecoord_file_path)?.finish()?;
-
-        let original_transform_tree = point_cloud.transform_tree().clone();
-        let merged_transform_tree = merge(&[original_transform_tree, additional_transform_tree])?;
+    if grouping == PointCloudGrouping::Combined {
+        let Some(point_cloud_group) = point_cloud_groups.into_iter().next() else {
+            warn!("No point cloud messages found");
+            return Ok(());
+        };
+        let point_cloud = process(point_cloud_group.point_cloud)?;
+        info!("Extracted {} points", point_cloud.size());
 
-        point_cloud.set_transform_tree(merged_transform_tree);
-    }
+        info!("Start writing to: {}", output_path.as_ref().display());
+        AutoWriter::from_path(output_path)?.finish(point_cloud)?;
+    } else {
+        let output_directory_path = output_path.as_ref();
+        info!(
+            "Start writing {} point clouds to: {}",
+            point_cloud_groups.len(),
+            output_directory_path.display()
+        );
+        fs::create_dir_all(output_directory_path)?;
+        let mut index = get_point_cloud_group_index(&point_cloud_groups, output_extension)?;
+        TableFormat::Csv.write(&mut index, output_directory_path.join(INDEX_FILE_NAME))?;
 
-    // point_cloud.point_data.add_sequential_id()?;
-    // point_cloud.derive_spherical_points()?;
-    if let Some(target_frame_id) = target_frame_id {
-        point_cloud.resolve_to_frame(target_frame_id.clone())?;
-        info!("Resolved to frame_id: {}", target_frame_id);
+        for point_cloud_group in point_cloud_groups {
+            let file_path = output_directory_path
+                .join(point_cloud_group.file_stem())
+                .with_extension(output_extension);
+            let point_cloud = process(point_cloud_group.point_cloud)?;
+            AutoWriter::from_path(file_path)?.finish(point_cloud)?;
+        }
     }
 
-    point_cloud = point_cloud.filter_by_beam_length(0.0, 30.0)?.unwrap();
-    info!("Filtered to {} points", point_cloud.size());
-
-    info!("Start writing to: {}", output_path.as_ref().display());
-    AutoWriter::from_path(output_path)?.finish(point_cloud)?;
-
     info!("Completed.");
 
     Ok(())
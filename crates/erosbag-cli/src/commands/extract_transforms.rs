@@ -1,3 +1,4 @@
+use crate::cli::{ChannelSelectionArgs, TimeWindowArgs};
 use crate::error::Error;
 use ecoord::io::EcoordWriter;
 use erosbag::Rosbag;
@@ -6,6 +7,8 @@ use tracing::info;
 
 pub fn run(
     rosbag_directory_path: impl AsRef<Path>,
+    time_window: &TimeWindowArgs,
+    channel_selection: &ChannelSelectionArgs,
     output_ecoord_path: impl AsRef<Path>,
 ) -> Result<(), Error> {
     info!("Start extracting transforms");
@@ -16,9 +19,11 @@ pub fn run(
     );
 
     let rosbag = Rosbag::new(rosbag_directory_path.as_ref())?;
-    let _o = rosbag.get_overview()?;
+    let (start_date_time, end_date_time) = time_window.resolve(&rosbag)?;
+    let channel_topics = channel_selection.resolve(&rosbag)?;
 
-    let transform_tree = rosbag.get_transforms(&None, &None, &None)?;
+    let transform_tree =
+        rosbag.get_transforms(&start_date_time, &end_date_time, &channel_topics)?;
 
     info!(
         "Start writing to: {}",
@@ -0,0 +1,30 @@
+use crate::error::Error;
+use erosbag_codegen::MessageGenerator;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+pub fn run(
+    input_paths: &[PathBuf],
+    output_path: impl AsRef<Path>,
+    crate_path: &str,
+) -> Result<(), Error> {
+    info!("Start generating messages");
+
+    let mut generator = MessageGenerator::new().with_crate_path(crate_path);
+    for current_path in input_paths {
+        info!("Input path: {}", current_path.display());
+        generator.add_path(current_path)?;
+    }
+    let source = generator.generate()?;
+
+    if let Some(parent_path) = output_path.as_ref().parent()
+        && !parent_path.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent_path)?;
+    }
+    fs::write(output_path.as_ref(), source)?;
+    info!("Generated messages to {}", output_path.as_ref().display());
+
+    Ok(())
+}
@@ -1,4 +1,15 @@
+pub mod batch;
+pub mod check;
+pub mod check_tf;
+pub mod colorize;
+pub mod convert;
+pub mod export_kitti;
+pub mod export_table;
+pub mod export_trajectory;
 pub mod extract_images;
 pub mod extract_point_clouds;
 pub mod extract_transforms;
+pub mod generate_messages;
+pub mod query;
+pub mod serve;
 pub mod test;
@@ -0,0 +1,73 @@
+use crate::cli::{ChannelSelectionArgs, TimeWindowArgs};
+use crate::error::Error;
+use erosbag::table::{MessageQuery, TableFormat};
+use erosbag::{ChannelTopic, Rosbag};
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    rosbag_directory_path: impl AsRef<Path>,
+    time_window: &TimeWindowArgs,
+    channel_selection: &ChannelSelectionArgs,
+    sql: Option<String>,
+    table_format: TableFormat,
+    output_directory_path: Option<impl AsRef<Path>>,
+) -> Result<(), Error> {
+    info!("Start querying messages");
+    info!("Rosbag path: {}", rosbag_directory_path.as_ref().display());
+
+    let rosbag = Rosbag::new(rosbag_directory_path.as_ref())?;
+    let (start_date_time, end_date_time) = time_window.resolve(&rosbag)?;
+    let mut query = MessageQuery::new().with_time_window(start_date_time, end_date_time);
+    for current_pattern in channel_selection.channel_names.iter().flatten() {
+        query = query.with_channel_topic_pattern(current_pattern.as_str());
+    }
+    if let Some(sql) = sql {
+        query = query.with_sql(sql);
+    }
+    let mut results = rosbag.query(&query)?;
+
+    let mut result_channel_topics: Vec<ChannelTopic> = results.keys().cloned().collect();
+    result_channel_topics.sort();
+    info!("Found results in {} channels", result_channel_topics.len());
+
+    if let Some(output_directory_path) = &output_directory_path {
+        fs::create_dir_all(output_directory_path.as_ref())?;
+    }
+    for channel_topic in result_channel_topics {
+        let data_frame = results.get_mut(&channel_topic).expect("should exist");
+
+        match &output_directory_path {
+            Some(output_directory_path) => {
+                let file_name = format!(
+                    "{}.{}",
+                    channel_topic
+                        .to_string()
+                        .trim_start_matches('/')
+                        .replace('/', "_"),
+                    table_format.extension()
+                );
+                let output_path = output_directory_path.as_ref().join(file_name);
+                info!(
+                    "Writing {} rows of {} to {}",
+                    data_frame.height(),
+                    channel_topic,
+                    output_path.display()
+                );
+                table_format.write(data_frame, output_path)?;
+            }
+            None => {
+                info!(
+                    "{} rows of {}:\n{}",
+                    data_frame.height(),
+                    channel_topic,
+                    data_frame
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use crate::error::Error;
+use erosbag::Rosbag;
+use erosbag::foxglove::{FoxgloveServer, FoxgloveServerOptions};
+use std::net::TcpListener;
+use std::path::Path;
+use tracing::info;
+
+pub fn run(
+    rosbag_directory_path: impl AsRef<Path>,
+    host: &str,
+    port: u16,
+    options: FoxgloveServerOptions,
+) -> Result<(), Error> {
+    info!("Start serving");
+    info!(
+        "Rosbag directory path: {}",
+        rosbag_directory_path.as_ref().display()
+    );
+    info!(
+        "Playback rate: {} (paused: {})",
+        options.rate, options.paused
+    );
+
+    let rosbag = Rosbag::new(rosbag_directory_path)?;
+    let server = FoxgloveServer::new(&rosbag, options)?;
+    for current_channel in server.channels() {
+        info!(
+            "Channel {}: {} ({})",
+            current_channel.id, current_channel.topic, current_channel.schema_name
+        );
+    }
+
+    let listener = TcpListener::bind((host, port))?;
+    info!("Listening on ws://{}", listener.local_addr()?);
+    server.serve(&listener)?;
+
+    Ok(())
+}
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,6 +7,8 @@ pub enum Error {
     ErosbagError(#[from] erosbag::Error),
     #[error(transparent)]
     ErosbagTransformError(#[from] erosbag::transform::Error),
+    #[error(transparent)]
+    ErosbagCodegenError(#[from] erosbag_codegen::Error),
 
     #[error(transparent)]
     EcoordError(#[from] ecoord::Error),
@@ -22,4 +25,29 @@ pub enum Error {
 
     #[error(transparent)]
     StdIoError(#[from] std::io::Error),
+    #[error(transparent)]
+    SerdeYamlError(#[from] serde_yaml::Error),
+    #[error(transparent)]
+    SerdeJsonError(#[from] serde_json::Error),
+    #[error(transparent)]
+    WalkdirError(#[from] walkdir::Error),
+    #[error(transparent)]
+    ThreadPoolBuildError(#[from] rayon::ThreadPoolBuildError),
+    #[error(transparent)]
+    TableFormatParseError(#[from] crate::util::TableFormatParseError),
+
+    #[error("time window arguments `{0}` and `{1}` conflict")]
+    ConflictingTimeWindowArgs(&'static str, &'static str),
+    #[error("selected time window from {0} to {1} is empty")]
+    EmptyTimeWindow(DateTime<Utc>, DateTime<Utc>),
+    #[error("number of image channels ({0}) and camera info channels ({1}) differ")]
+    MismatchedCameraChannelCount(usize, usize),
+    #[error("{0} of {1} batch tasks failed")]
+    FailedBatchTasks(usize, usize),
+    #[error("processing {0} of {1} bags failed")]
+    FailedBatchRosbags(usize, usize),
+    #[error("found {0} data quality issues")]
+    QualityIssuesFound(usize),
+    #[error("found {0} transform issues")]
+    TransformIssuesFound(usize),
 }
@@ -1,62 +1,266 @@
 mod cli;
 mod commands;
 mod error;
+mod selection;
 mod util;
 
 use crate::cli::{Cli, Commands};
 use clap::Parser;
 
 use anyhow::Result;
-use erosbag::ChannelTopic;
+use erosbag::foxglove::FoxgloveServerOptions;
+use erosbag::storage::StorageConversionOptions;
 
 fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
     let cli = Cli::parse();
 
     match &cli.command {
+        Commands::Batch {
+            input_directory_path,
+            job_file_path,
+            output_directory_path,
+            threads,
+            force,
+        } => {
+            commands::batch::run(
+                input_directory_path.canonicalize()?,
+                job_file_path,
+                output_directory_path,
+                *threads,
+                *force,
+            )?;
+        }
         Commands::ExtractTransforms {
             rosbag_directory_path,
+            time_window,
+            channel_selection,
             output_ecoord_path,
         } => {
             commands::extract_transforms::run(
                 rosbag_directory_path.canonicalize()?,
+                time_window,
+                channel_selection,
                 output_ecoord_path,
             )?;
         }
         Commands::ExtractPointClouds {
             rosbag_directory_path,
             ecoord_file_path,
-            start_date_time,
-            end_date_time,
-            start_time_offset,
-            total_duration,
-            transform_channel_name,
+            time_window,
+            channel_selection,
             target_frame_id,
+            filter,
+            grouping,
+            source_columns,
+            output_extension,
             output_path,
         } => {
-            let transform_channel_id: ChannelTopic = transform_channel_name.as_str().into();
-
             commands::extract_point_clouds::run(
                 rosbag_directory_path.canonicalize()?,
                 ecoord_file_path.as_ref().map(|x| x.canonicalize().unwrap()),
-                *start_date_time,
-                *end_date_time,
-                *start_time_offset,
-                *total_duration,
-                transform_channel_id,
+                time_window,
+                channel_selection,
+                target_frame_id.clone(),
+                filter.filters(),
+                filter.voxel_size,
+                grouping.grouping(),
+                *source_columns,
+                output_extension,
+                output_path,
+            )?;
+        }
+        Commands::Check {
+            rosbag_directory_path,
+            time_window,
+            channel_selection,
+            max_gap_duration,
+            output_path,
+        } => {
+            commands::check::run(
+                rosbag_directory_path.canonicalize()?,
+                time_window,
+                channel_selection,
+                *max_gap_duration,
+                output_path.as_ref(),
+            )?;
+        }
+        Commands::CheckTf {
+            rosbag_directory_path,
+            time_window,
+            channel_selection,
+            target_frame_id,
+            max_gap_duration,
+        } => {
+            commands::check_tf::run(
+                rosbag_directory_path.canonicalize()?,
+                time_window,
+                channel_selection,
+                target_frame_id.clone(),
+                *max_gap_duration,
+            )?;
+        }
+        Commands::ExportTable {
+            rosbag_directory_path,
+            time_window,
+            channel_selection,
+            format,
+            output_directory_path,
+        } => {
+            commands::export_table::run(
+                rosbag_directory_path.canonicalize()?,
+                time_window,
+                channel_selection,
+                *format,
+                output_directory_path,
+            )?;
+        }
+        Commands::Query {
+            rosbag_directory_path,
+            time_window,
+            channel_selection,
+            sql,
+            format,
+            output_directory_path,
+        } => {
+            commands::query::run(
+                rosbag_directory_path.canonicalize()?,
+                time_window,
+                channel_selection,
+                sql.clone(),
+                *format,
+                output_directory_path.as_ref(),
+            )?;
+        }
+        Commands::ExportTrajectory {
+            rosbag_directory_path,
+            time_window,
+            channel_selection,
+            pose_channel_name,
+            parent_frame_id,
+            child_frame_id,
+            sampling_interval,
+            relative_to_first,
+            format,
+            output_path,
+        } => {
+            commands::export_trajectory::run(
+                rosbag_directory_path.canonicalize()?,
+                time_window,
+                channel_selection,
+                pose_channel_name.as_ref().map(|x| x.as_str().into()),
+                parent_frame_id.clone(),
+                child_frame_id.clone(),
+                *sampling_interval,
+                *relative_to_first,
+                *format,
+                output_path,
+            )?;
+        }
+        Commands::ExportKitti {
+            rosbag_directory_path,
+            time_window,
+            lidar_channel_name,
+            image_channel_names,
+            camera_info_channel_names,
+            nav_sat_fix_channel_name,
+            imu_channel_name,
+            channel_selection,
+            max_time_difference,
+            output_directory_path,
+        } => {
+            commands::export_kitti::run(
+                rosbag_directory_path.canonicalize()?,
+                time_window,
+                lidar_channel_name.as_str().into(),
+                image_channel_names
+                    .iter()
+                    .map(|x| x.as_str().into())
+                    .collect(),
+                camera_info_channel_names
+                    .iter()
+                    .map(|x| x.as_str().into())
+                    .collect(),
+                nav_sat_fix_channel_name.as_ref().map(|x| x.as_str().into()),
+                imu_channel_name.as_ref().map(|x| x.as_str().into()),
+                channel_selection,
+                *max_time_difference,
+                output_directory_path,
+            )?;
+        }
+        Commands::Colorize {
+            rosbag_directory_path,
+            time_window,
+            image_channel_names,
+            camera_info_channel_names,
+            channel_selection,
+            target_frame_id,
+            max_time_difference,
+            occlusion_tolerance,
+            output_path,
+        } => {
+            commands::colorize::run(
+                rosbag_directory_path.canonicalize()?,
+                time_window,
+                image_channel_names
+                    .iter()
+                    .map(|x| x.as_str().into())
+                    .collect(),
+                camera_info_channel_names
+                    .iter()
+                    .map(|x| x.as_str().into())
+                    .collect(),
+                channel_selection,
                 target_frame_id.clone(),
+                *max_time_difference,
+                *occlusion_tolerance,
                 output_path,
             )?;
         }
         Commands::ExtractImages {
             rosbag_directory_path,
+            time_window,
+            channel_selection,
             output_eimage_path,
         } => {
             commands::extract_images::run(
                 rosbag_directory_path.canonicalize()?,
+                time_window,
+                channel_selection,
                 output_eimage_path,
             )?;
         }
+        Commands::GenerateMessages {
+            input_paths,
+            output_path,
+            crate_path,
+        } => {
+            commands::generate_messages::run(input_paths, output_path, crate_path)?;
+        }
+        Commands::Convert {
+            input_path,
+            output_directory_path,
+            storage_format,
+            compression,
+            chunk_size,
+        } => {
+            let options = StorageConversionOptions::new(*storage_format)
+                .with_compression(*compression)
+                .with_chunk_size(*chunk_size);
+            commands::convert::run(input_path, output_directory_path, &options)?;
+        }
+        Commands::Serve {
+            rosbag_directory_path,
+            host,
+            port,
+            rate,
+            paused,
+        } => {
+            let options = FoxgloveServerOptions::new()
+                .with_rate(*rate)
+                .with_paused(*paused);
+            commands::serve::run(rosbag_directory_path.canonicalize()?, host, *port, options)?;
+        }
         Commands::CreateFromEcoord {
             transform_tree_directory_path: _,
             rosbag_directory_path: _,
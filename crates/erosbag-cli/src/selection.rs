@@ -0,0 +1,252 @@
+use crate::cli::{ChannelSelectionArgs, PointCloudGroupingArgs, PointFilterArgs, TimeWindowArgs};
+use crate::error::Error;
+use chrono::{DateTime, Utc};
+use erosbag::dto::PointCloudGrouping;
+use erosbag::filter::PointFilter;
+use erosbag::ros_messages::RosMessageType;
+use erosbag::{ChannelTopic, Rosbag};
+use nalgebra::Point3;
+use std::collections::HashSet;
+use tracing::{info, warn};
+
+/// Start (inclusive) and end (exclusive) of a time window, which is unbounded on a side if
+/// `None`.
+type TimeWindow = (Option<DateTime<Utc>>, Option<DateTime<Utc>>);
+
+impl TimeWindowArgs {
+    /// Returns the start (inclusive) and end (exclusive) of the selected time window, where
+    /// `None` means that the window is not bounded on this side within the bag.
+    ///
+    /// Fails, if conflicting arguments are provided, as by a job specification bypassing the
+    /// argument parser, or if the window does not overlap with the bag.
+    pub fn resolve(&self, rosbag: &Rosbag) -> Result<TimeWindow, Error> {
+        self.validate()?;
+        let (Some(rosbag_start_date_time), Some(rosbag_end_date_time)) =
+            (rosbag.get_start_date_time()?, rosbag.get_end_date_time()?)
+        else {
+            warn!("Not able to retrieve the start and end date time of the rosbag");
+            return Ok((self.start_date_time, self.end_date_time));
+        };
+        info!(
+            "Rosbag times: {rosbag_start_date_time} - {rosbag_end_date_time} with a duration of {}",
+            rosbag_end_date_time - rosbag_start_date_time
+        );
+
+        let mut start_date_time: Option<DateTime<Utc>> =
+            match (self.start_date_time, self.start_seconds) {
+                (Some(start_date_time), _) => Some(start_date_time),
+                (None, Some(seconds)) => {
+                    Some(rosbag_start_date_time + seconds_to_duration(seconds))
+                }
+                (None, None) => None,
+            };
+        if let Some(start_time_offset) = self.start_time_offset {
+            start_date_time =
+                Some(start_date_time.unwrap_or(rosbag_start_date_time) + start_time_offset);
+        }
+
+        let mut end_date_time: Option<DateTime<Utc>> =
+            match (self.end_date_time, self.end_seconds, self.total_duration) {
+                (Some(end_date_time), _, _) => Some(end_date_time),
+                (None, Some(seconds), _) => {
+                    Some(rosbag_start_date_time + seconds_to_duration(seconds))
+                }
+                (None, None, Some(total_duration)) => {
+                    Some(start_date_time.unwrap_or(rosbag_start_date_time) + total_duration)
+                }
+                (None, None, None) => None,
+            };
+
+        if let Some(current_start_date_time) = start_date_time
+            && current_start_date_time < rosbag_start_date_time
+        {
+            warn!(
+                "Defined start date time ({current_start_date_time}) is before rosbag's start date time ({rosbag_start_date_time})"
+            );
+            start_date_time = None;
+        }
+        if let Some(current_end_date_time) = end_date_time
+            && rosbag_end_date_time < current_end_date_time
+        {
+            warn!(
+                "Defined end date time ({current_end_date_time}) is after rosbag's end date time ({rosbag_end_date_time})"
+            );
+            end_date_time = None;
+        }
+        if let Some(current_start_date_time) = start_date_time
+            && rosbag_end_date_time < current_start_date_time
+        {
+            return Err(Error::EmptyTimeWindow(
+                current_start_date_time,
+                rosbag_end_date_time,
+            ));
+        }
+        if let Some(current_end_date_time) = end_date_time
+            && current_end_date_time <= start_date_time.unwrap_or(rosbag_start_date_time)
+        {
+            return Err(Error::EmptyTimeWindow(
+                start_date_time.unwrap_or(rosbag_start_date_time),
+                current_end_date_time,
+            ));
+        }
+
+        info!(
+            "Selected time window: {} - {}",
+            start_date_time.unwrap_or(rosbag_start_date_time),
+            end_date_time.unwrap_or(rosbag_end_date_time)
+        );
+        Ok((start_date_time, end_date_time))
+    }
+
+    /// Fails on the combinations of arguments, which the argument parser rejects as conflicting.
+    fn validate(&self) -> Result<(), Error> {
+        let conflicts = [
+            (
+                "start_date_time",
+                self.start_date_time.is_some(),
+                "start_seconds",
+                self.start_seconds.is_some(),
+            ),
+            (
+                "end_date_time",
+                self.end_date_time.is_some(),
+                "end_seconds",
+                self.end_seconds.is_some(),
+            ),
+            (
+                "end_date_time",
+                self.end_date_time.is_some(),
+                "total_duration",
+                self.total_duration.is_some(),
+            ),
+            (
+                "end_seconds",
+                self.end_seconds.is_some(),
+                "total_duration",
+                self.total_duration.is_some(),
+            ),
+        ];
+        for (first_name, is_first_set, second_name, is_second_set) in conflicts {
+            if is_first_set && is_second_set {
+                return Err(Error::ConflictingTimeWindowArgs(first_name, second_name));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ChannelSelectionArgs {
+    /// Returns the topics of the channels matching the names or patterns, or `None`, if no
+    /// channels are selected.
+    pub fn resolve(&self, rosbag: &Rosbag) -> Result<Option<HashSet<ChannelTopic>>, Error> {
+        let Some(channel_names) = &self.channel_names else {
+            return Ok(None);
+        };
+
+        let overview = rosbag.get_overview()?;
+        let available_channel_topics: HashSet<ChannelTopic> = overview
+            .files
+            .values()
+            .flat_map(|x| x.channels.values().map(|channel| channel.topic.clone()))
+            .collect();
+
+        let mut channel_topics: HashSet<ChannelTopic> = HashSet::new();
+        for current_name in channel_names {
+            let matching_channel_topics: Vec<&ChannelTopic> = available_channel_topics
+                .iter()
+                .filter(|x| x.matches_pattern(current_name))
+                .collect();
+            if matching_channel_topics.is_empty() {
+                warn!("No channel matches `{current_name}`");
+            }
+            channel_topics.extend(matching_channel_topics.into_iter().cloned());
+        }
+
+        Ok(Some(channel_topics))
+    }
+
+    /// Returns the selected channels holding one of the message types, or `None`, if no channel
+    /// of these types is selected, so that all channels of these types are used.
+    pub fn resolve_of_types(
+        &self,
+        rosbag: &Rosbag,
+        message_types: &[RosMessageType],
+    ) -> Result<Option<HashSet<ChannelTopic>>, Error> {
+        let Some(channel_topics) = self.resolve(rosbag)? else {
+            return Ok(None);
+        };
+
+        let overview = rosbag.get_overview()?;
+        let channel_topics_of_types: HashSet<ChannelTopic> = message_types
+            .iter()
+            .flat_map(|x| overview.get_channel_topics_of_message_type(*x))
+            .filter(|x| channel_topics.contains(x))
+            .collect();
+        Ok(Some(channel_topics_of_types).filter(|x| !x.is_empty()))
+    }
+}
+
+impl PointFilterArgs {
+    /// Returns the filter pipeline applied to each message in the order of application, which
+    /// excludes the voxel downsampling of the merged point cloud.
+    pub fn filters(&self) -> Vec<PointFilter> {
+        let mut filters: Vec<PointFilter> = Vec::new();
+        if let Some(keep_every) = self.keep_every_nth_message {
+            filters.push(PointFilter::TemporalDecimation { keep_every });
+        }
+        if self.remove_non_finite {
+            filters.push(PointFilter::RemoveNonFinite);
+        }
+        filters.push(PointFilter::BeamLength {
+            min: self.min_beam_length,
+            max: self.max_beam_length,
+        });
+        if self.min_intensity.is_some() || self.max_intensity.is_some() {
+            filters.push(PointFilter::Intensity {
+                min: self.min_intensity.unwrap_or(f32::NEG_INFINITY),
+                max: self.max_intensity.unwrap_or(f32::INFINITY),
+            });
+        }
+        if let (Some(bounding_box), Some(frame_id)) =
+            (&self.bounding_box, &self.bounding_box_frame_id)
+        {
+            filters.push(PointFilter::BoundingBox {
+                frame_id: frame_id.clone().into(),
+                min: Point3::new(bounding_box[0], bounding_box[1], bounding_box[2]),
+                max: Point3::new(bounding_box[3], bounding_box[4], bounding_box[5]),
+            });
+        }
+        if let Some(ego_box) = &self.ego_box {
+            filters.push(PointFilter::ExcludeBoundingBox {
+                frame_id: self.ego_box_frame_id.clone().into(),
+                min: Point3::new(ego_box[0], ego_box[1], ego_box[2]),
+                max: Point3::new(ego_box[3], ego_box[4], ego_box[5]),
+            });
+        }
+        filters
+    }
+}
+
+impl PointCloudGroupingArgs {
+    /// Returns the selected grouping, where all messages are combined by default.
+    pub fn grouping(&self) -> PointCloudGrouping {
+        match (
+            self.messages_per_file,
+            self.time_slice_duration,
+            self.sliding_window_duration,
+        ) {
+            (Some(count), _, _) => PointCloudGrouping::MessageCount(count),
+            (_, Some(duration), _) => PointCloudGrouping::TimeSlice(duration),
+            (_, _, Some(window)) => PointCloudGrouping::SlidingWindow {
+                window,
+                step: self.sliding_window_step.unwrap_or(window),
+            },
+            (None, None, None) => PointCloudGrouping::Combined,
+        }
+    }
+}
+
+fn seconds_to_duration(seconds: f64) -> chrono::Duration {
+    chrono::Duration::nanoseconds((seconds * 1e9) as i64)
+}
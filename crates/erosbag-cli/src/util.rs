@@ -1,4 +1,9 @@
 use chrono::Utc;
+use erosbag::storage::{McapCompression, StorageFormat};
+use erosbag::table::TableFormat;
+use erosbag::transform::TrajectoryFormat;
+use serde::{Deserialize, Deserializer};
+use std::str::FromStr;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -16,6 +21,16 @@ pub fn parse_duration(arg: &str) -> Result<chrono::Duration, DurationParseError>
     Ok(chrono_duration)
 }
 
+/// Deserializes an optional human-readable duration, such as `1m 30s`, of a job specification.
+pub fn deserialize_optional_duration<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<chrono::Duration>, D::Error> {
+    let value: Option<String> = Option::deserialize(deserializer)?;
+    value
+        .map(|x| parse_duration(&x).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
 #[derive(Debug, Error)]
 pub enum TimestampParseError {
     #[error("failed to convert to chrono::DateTime<Utc>: {0}")]
@@ -27,3 +42,56 @@ pub fn parse_timestamp(arg: &str) -> Result<chrono::DateTime<Utc>, TimestampPars
         chrono::DateTime::parse_from_rfc3339(arg)?.with_timezone(&Utc);
     Ok(chrono_datetime)
 }
+
+/// Deserializes an optional RFC 3339 timestamp of a job specification.
+pub fn deserialize_optional_timestamp<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<chrono::DateTime<Utc>>, D::Error> {
+    let value: Option<String> = Option::deserialize(deserializer)?;
+    value
+        .map(|x| parse_timestamp(&x).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+#[derive(Debug, Error)]
+pub enum TableFormatParseError {
+    #[error("unknown table format `{0}`, expected one of: parquet, csv, ipc")]
+    UnknownFormat(String),
+}
+
+pub fn parse_table_format(arg: &str) -> Result<TableFormat, TableFormatParseError> {
+    TableFormat::from_str(arg).map_err(|_| TableFormatParseError::UnknownFormat(arg.to_string()))
+}
+
+#[derive(Debug, Error)]
+pub enum TrajectoryFormatParseError {
+    #[error("unknown trajectory format `{0}`, expected one of: tum, kitti, euroc")]
+    UnknownFormat(String),
+}
+
+pub fn parse_trajectory_format(arg: &str) -> Result<TrajectoryFormat, TrajectoryFormatParseError> {
+    TrajectoryFormat::from_str(arg)
+        .map_err(|_| TrajectoryFormatParseError::UnknownFormat(arg.to_string()))
+}
+
+#[derive(Debug, Error)]
+pub enum StorageFormatParseError {
+    #[error("unknown storage format `{0}`, expected one of: mcap, sqlite3")]
+    UnknownFormat(String),
+}
+
+pub fn parse_storage_format(arg: &str) -> Result<StorageFormat, StorageFormatParseError> {
+    StorageFormat::from_str(arg)
+        .map_err(|_| StorageFormatParseError::UnknownFormat(arg.to_string()))
+}
+
+#[derive(Debug, Error)]
+pub enum McapCompressionParseError {
+    #[error("unknown MCAP compression `{0}`, expected one of: none, lz4, zstd")]
+    UnknownCompression(String),
+}
+
+pub fn parse_mcap_compression(arg: &str) -> Result<McapCompression, McapCompressionParseError> {
+    McapCompression::from_str(arg)
+        .map_err(|_| McapCompressionParseError::UnknownCompression(arg.to_string()))
+}
@@ -0,0 +1,99 @@
+//! Runs the batch command on a tree of synthetic bags, some of which cannot be read.
+
+mod common;
+
+use common::{TestDirectory, write_imu_bag};
+use std::path::Path;
+use std::process::Command;
+
+fn write_file(file_path: &Path, content: &str) {
+    std::fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+    std::fs::write(file_path, content).unwrap();
+}
+
+fn read_file(file_path: &Path) -> String {
+    std::fs::read_to_string(file_path)
+        .unwrap_or_else(|e| panic!("reading {} should work: {e}", file_path.display()))
+}
+
+/// Runs the batch command and returns whether it succeeded.
+fn run_batch(
+    input_directory_path: &Path,
+    job_file_path: &Path,
+    output_directory_path: &Path,
+) -> bool {
+    Command::new(env!("CARGO_BIN_EXE_erosbag"))
+        .arg("batch")
+        .arg("--input-directory-path")
+        .arg(input_directory_path)
+        .arg("--job-file-path")
+        .arg(job_file_path)
+        .arg("--output-directory-path")
+        .arg(output_directory_path)
+        .arg("--threads")
+        .arg("2")
+        .status()
+        .expect("running the command should work")
+        .success()
+}
+
+#[test]
+fn batch_reports_failures_per_bag_and_resumes() {
+    let test_directory = TestDirectory::new("batch");
+    let input_directory_path = test_directory.directory_path.join("input");
+    let output_directory_path = test_directory.directory_path.join("output");
+    let job_file_path = test_directory.directory_path.join("job.yaml");
+
+    // the relative paths of these bags are equal once the separators are replaced
+    write_imu_bag(&input_directory_path.join("a/b_c"), [0, 100, 200]);
+    write_imu_bag(&input_directory_path.join("a_b/c"), [0, 100, 200]);
+    write_file(
+        &input_directory_path.join("legacy/rosbag2_0.db3"),
+        "not a database",
+    );
+    write_file(&input_directory_path.join("drive.bag"), "not a bag");
+    write_file(&input_directory_path.join("notes/readme.txt"), "no bag");
+    write_file(
+        &job_file_path,
+        "tasks:\n  - type: export_table\n    output: \"{bag_path}/tables\"\n    format: csv\n",
+    );
+
+    assert!(!run_batch(
+        &input_directory_path,
+        &job_file_path,
+        &output_directory_path
+    ));
+    let reports_directory_path = output_directory_path.join("reports");
+    for bag_path in ["a/b_c", "a_b/c"] {
+        let report = read_file(&reports_directory_path.join(format!("{bag_path}.yaml")));
+        assert!(report.contains("status: succeeded"), "{report}");
+        assert!(output_directory_path.join(bag_path).join("tables").is_dir());
+    }
+    for bag_path in ["legacy", "drive.bag"] {
+        let report = read_file(&reports_directory_path.join(format!("{bag_path}.yaml")));
+        assert!(report.contains("error:"), "{report}");
+    }
+    assert!(!reports_directory_path.join("notes.yaml").exists());
+
+    // completed tasks with an unchanged specification are skipped
+    assert!(!run_batch(
+        &input_directory_path,
+        &job_file_path,
+        &output_directory_path
+    ));
+    let report = read_file(&reports_directory_path.join("a/b_c.yaml"));
+    assert!(report.contains("status: skipped"), "{report}");
+
+    // a changed specification reruns the task, although its output exists
+    write_file(
+        &job_file_path,
+        "tasks:\n  - type: export_table\n    output: \"{bag_path}/tables\"\n    format: parquet\n",
+    );
+    assert!(!run_batch(
+        &input_directory_path,
+        &job_file_path,
+        &output_directory_path
+    ));
+    let report = read_file(&reports_directory_path.join("a/b_c.yaml"));
+    assert!(report.contains("status: succeeded"), "{report}");
+}
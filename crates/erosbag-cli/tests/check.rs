@@ -0,0 +1,38 @@
+//! Runs the check command on synthetic bags with and without gaps.
+
+mod common;
+
+use common::{TestDirectory, write_imu_bag};
+use std::path::Path;
+use std::process::Command;
+
+/// Runs the check command and returns whether it succeeded.
+fn run_check(rosbag_directory_path: &Path, max_gap_duration: &str) -> bool {
+    Command::new(env!("CARGO_BIN_EXE_erosbag"))
+        .arg("check")
+        .arg("--rosbag-directory-path")
+        .arg(rosbag_directory_path)
+        .arg("--max-gap-duration")
+        .arg(max_gap_duration)
+        .status()
+        .expect("running the check command should work")
+        .success()
+}
+
+#[test]
+fn consistent_bag_passes() {
+    let test_directory = TestDirectory::new("check-consistent");
+    let rosbag_directory_path = test_directory.directory_path.join("bag");
+    write_imu_bag(&rosbag_directory_path, [0, 100, 200, 300]);
+
+    assert!(run_check(&rosbag_directory_path, "1s"));
+}
+
+#[test]
+fn bag_with_gap_fails() {
+    let test_directory = TestDirectory::new("check-gap");
+    let rosbag_directory_path = test_directory.directory_path.join("bag");
+    write_imu_bag(&rosbag_directory_path, [0, 100, 200, 1500, 1600]);
+
+    assert!(!run_check(&rosbag_directory_path, "1s"));
+}
@@ -0,0 +1,38 @@
+//! Runs the check-tf command on synthetic bags with and without gaps between the transforms.
+
+mod common;
+
+use common::{TestDirectory, write_transform_bag};
+use std::path::Path;
+use std::process::Command;
+
+/// Runs the check-tf command and returns whether it succeeded.
+fn run_check_tf(rosbag_directory_path: &Path, max_gap_duration: &str) -> bool {
+    Command::new(env!("CARGO_BIN_EXE_erosbag"))
+        .arg("check-tf")
+        .arg("--rosbag-directory-path")
+        .arg(rosbag_directory_path)
+        .arg("--max-gap-duration")
+        .arg(max_gap_duration)
+        .status()
+        .expect("running the check-tf command should work")
+        .success()
+}
+
+#[test]
+fn consistent_transforms_pass() {
+    let test_directory = TestDirectory::new("check-tf-consistent");
+    let rosbag_directory_path = test_directory.directory_path.join("bag");
+    write_transform_bag(&rosbag_directory_path, [0, 100, 200, 300]);
+
+    assert!(run_check_tf(&rosbag_directory_path, "1s"));
+}
+
+#[test]
+fn transforms_with_gap_fail() {
+    let test_directory = TestDirectory::new("check-tf-gap");
+    let rosbag_directory_path = test_directory.directory_path.join("bag");
+    write_transform_bag(&rosbag_directory_path, [0, 100, 200, 1500, 1600]);
+
+    assert!(!run_check_tf(&rosbag_directory_path, "1s"));
+}
@@ -0,0 +1,140 @@
+//! Writes synthetic bags to temporary directories for running the commands.
+#![allow(dead_code)]
+
+use chrono::{DateTime, TimeZone, Utc};
+use erosbag::ros_messages::{
+    RosMessage, builtin_msgs, geometry_msgs, sensor_msgs, std_msgs, tf2_msgs,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+pub fn imu(date_time: DateTime<Utc>) -> sensor_msgs::Imu {
+    let vector = geometry_msgs::Vector3 {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    sensor_msgs::Imu {
+        header: header("imu_link", date_time),
+        orientation: geometry_msgs::Quaternion {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            w: 1.0,
+        },
+        orientation_covariance: [0.0; 9],
+        angular_velocity: vector,
+        angular_velocity_covariance: [0.0; 9],
+        linear_acceleration: vector,
+        linear_acceleration_covariance: [0.0; 9],
+    }
+}
+
+/// Writes an MCAP file with IMU messages logged at the offsets in milliseconds.
+pub fn write_imu_bag(directory_path: &Path, offset_milliseconds: impl IntoIterator<Item = i64>) {
+    write_bag(directory_path, "/imu", offset_milliseconds, imu);
+}
+
+/// Writes an MCAP file with transforms from `map` to `base_link` logged at the offsets in
+/// milliseconds.
+pub fn write_transform_bag(
+    directory_path: &Path,
+    offset_milliseconds: impl IntoIterator<Item = i64>,
+) {
+    write_bag(directory_path, "/tf", offset_milliseconds, transform);
+}
+
+pub fn transform(date_time: DateTime<Utc>) -> tf2_msgs::TFMessage {
+    tf2_msgs::TFMessage {
+        transforms: vec![geometry_msgs::TransformStamped {
+            header: header("map", date_time),
+            child_frame_id: "base_link".to_string(),
+            transform: geometry_msgs::Transform {
+                translation: geometry_msgs::Vector3 {
+                    x: 1.0,
+                    y: 0.0,
+                    z: 0.0,
+                },
+                rotation: geometry_msgs::Quaternion {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                    w: 1.0,
+                },
+            },
+        }],
+    }
+}
+
+fn header(frame_id: &str, date_time: DateTime<Utc>) -> std_msgs::Header {
+    std_msgs::Header {
+        stamp: builtin_msgs::Time {
+            sec: date_time.timestamp() as i32,
+            nanosec: date_time.timestamp_subsec_nanos(),
+        },
+        frame_id: frame_id.to_string(),
+    }
+}
+
+/// Writes an MCAP file with a single channel, whose messages are created for the date times of
+/// the offsets in milliseconds.
+fn write_bag<T: RosMessage + Serialize>(
+    directory_path: &Path,
+    topic: &str,
+    offset_milliseconds: impl IntoIterator<Item = i64>,
+    message: impl Fn(DateTime<Utc>) -> T,
+) {
+    std::fs::create_dir_all(directory_path).unwrap();
+    let file = BufWriter::new(File::create(directory_path.join("rosbag2_0.mcap")).unwrap());
+    let mut writer = mcap::Writer::new(file).unwrap();
+    let schema_id = writer
+        .add_schema(T::SCHEMA_NAME, "ros2msg", T::DEFINITION.as_bytes())
+        .unwrap();
+    let channel_id = writer
+        .add_channel(schema_id, topic, "cdr", &BTreeMap::new())
+        .unwrap();
+    for (i, offset_milliseconds) in offset_milliseconds.into_iter().enumerate() {
+        let date_time = Utc.timestamp_opt(1700000000, 0).unwrap()
+            + chrono::Duration::milliseconds(offset_milliseconds);
+        let data = cdr::serialize::<_, _, cdr::CdrLe>(&message(date_time), cdr::Infinite).unwrap();
+        let log_time = date_time.timestamp_nanos_opt().unwrap() as u64;
+        writer
+            .write_to_known_channel(
+                &mcap::records::MessageHeader {
+                    channel_id,
+                    sequence: i as u32,
+                    log_time,
+                    publish_time: log_time,
+                },
+                &data,
+            )
+            .unwrap();
+    }
+    writer.finish().unwrap();
+}
+
+pub struct TestDirectory {
+    pub directory_path: PathBuf,
+}
+
+impl TestDirectory {
+    pub fn new(name: &str) -> Self {
+        let directory_path = std::env::temp_dir()
+            .join("erosbag-cli-tests")
+            .join(format!("{name}-{}", std::process::id()));
+        if directory_path.exists() {
+            std::fs::remove_dir_all(&directory_path).unwrap();
+        }
+        std::fs::create_dir_all(&directory_path).unwrap();
+        Self { directory_path }
+    }
+}
+
+impl Drop for TestDirectory {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.directory_path);
+    }
+}
@@ -0,0 +1,75 @@
+//! Runs commands with time windows, which are empty or given by conflicting arguments.
+
+mod common;
+
+use common::{TestDirectory, write_imu_bag};
+use std::path::Path;
+use std::process::Command;
+
+/// Runs the extract transforms command with the time window arguments and returns whether it
+/// succeeded.
+fn run_extract_transforms(
+    rosbag_directory_path: &Path,
+    output_ecoord_path: &Path,
+    time_window_args: &[&str],
+) -> bool {
+    Command::new(env!("CARGO_BIN_EXE_erosbag"))
+        .arg("extract-transforms")
+        .arg("--rosbag-directory-path")
+        .arg(rosbag_directory_path)
+        .args(time_window_args)
+        .arg("--output-ecoord-path")
+        .arg(output_ecoord_path)
+        .status()
+        .expect("running the command should work")
+        .success()
+}
+
+#[test]
+fn empty_time_window_fails() {
+    let test_directory = TestDirectory::new("time_window_empty");
+    let rosbag_directory_path = test_directory.directory_path.join("bag");
+    let output_ecoord_path = test_directory.directory_path.join("transforms.ecoord");
+    write_imu_bag(&rosbag_directory_path, [0, 100, 200]);
+
+    assert!(!run_extract_transforms(
+        &rosbag_directory_path,
+        &output_ecoord_path,
+        &["--start-seconds", "10"]
+    ));
+    assert!(!run_extract_transforms(
+        &rosbag_directory_path,
+        &output_ecoord_path,
+        &["--start-seconds", "0.15", "--end-seconds", "0.1"]
+    ));
+    assert!(!output_ecoord_path.exists());
+}
+
+#[test]
+fn conflicting_time_window_of_batch_task_fails() {
+    let test_directory = TestDirectory::new("time_window_conflicting");
+    let input_directory_path = test_directory.directory_path.join("input");
+    let output_directory_path = test_directory.directory_path.join("output");
+    let job_file_path = test_directory.directory_path.join("job.yaml");
+    write_imu_bag(&input_directory_path.join("bag"), [0, 100, 200]);
+    std::fs::write(
+        &job_file_path,
+        "tasks:\n  - type: extract_transforms\n    output: \"{bag_path}/transforms.ecoord\"\n    time_window: { end_seconds: 0.1, total_duration: 1s }\n",
+    )
+    .unwrap();
+
+    let is_successful = Command::new(env!("CARGO_BIN_EXE_erosbag"))
+        .arg("batch")
+        .arg("--input-directory-path")
+        .arg(&input_directory_path)
+        .arg("--job-file-path")
+        .arg(&job_file_path)
+        .arg("--output-directory-path")
+        .arg(&output_directory_path)
+        .status()
+        .expect("running the command should work")
+        .success();
+    assert!(!is_successful);
+    let report = std::fs::read_to_string(output_directory_path.join("reports/bag.yaml")).unwrap();
+    assert!(report.contains("conflict"), "{report}");
+}
@@ -0,0 +1,265 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Name of a message consisting of its package and type name, such as `sensor_msgs/Imu`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MessageName {
+    pub package: String,
+    pub name: String,
+}
+
+impl MessageName {
+    pub fn new(package: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            package: package.into(),
+            name: name.into(),
+        }
+    }
+
+    /// Parses `pkg/Type` or `pkg/msg/Type`, where a type without package belongs to the current
+    /// package.
+    pub fn parse(value: &str, current_package: &str) -> Self {
+        let segments: Vec<&str> = value.split('/').collect();
+        match segments.as_slice() {
+            [name] => Self::new(current_package, *name),
+            [package, name] | [package, _, name] => Self::new(*package, *name),
+            _ => Self::new(current_package, value),
+        }
+    }
+
+    /// Returns the schema name as written to MCAP files, such as `sensor_msgs/msg/Imu`.
+    pub fn schema_name(&self) -> String {
+        format!("{}/msg/{}", self.package, self.name)
+    }
+}
+
+impl fmt::Display for MessageName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.package, self.name)
+    }
+}
+
+/// Primitive types of the ROS2 interface definition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PrimitiveType {
+    Bool,
+    Byte,
+    Char,
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Int64,
+    UInt64,
+    Float32,
+    Float64,
+    String,
+    WString,
+}
+
+impl FromStr for PrimitiveType {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<PrimitiveType, Self::Err> {
+        match input {
+            "bool" => Ok(PrimitiveType::Bool),
+            "byte" => Ok(PrimitiveType::Byte),
+            "char" => Ok(PrimitiveType::Char),
+            "int8" => Ok(PrimitiveType::Int8),
+            "uint8" => Ok(PrimitiveType::UInt8),
+            "int16" => Ok(PrimitiveType::Int16),
+            "uint16" => Ok(PrimitiveType::UInt16),
+            "int32" => Ok(PrimitiveType::Int32),
+            "uint32" => Ok(PrimitiveType::UInt32),
+            "int64" => Ok(PrimitiveType::Int64),
+            "uint64" => Ok(PrimitiveType::UInt64),
+            "float32" => Ok(PrimitiveType::Float32),
+            "float64" => Ok(PrimitiveType::Float64),
+            "string" => Ok(PrimitiveType::String),
+            "wstring" => Ok(PrimitiveType::WString),
+            _ => Err(()),
+        }
+    }
+}
+
+impl PrimitiveType {
+    /// Returns the name in the ros2msg format.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PrimitiveType::Bool => "bool",
+            PrimitiveType::Byte => "byte",
+            PrimitiveType::Char => "char",
+            PrimitiveType::Int8 => "int8",
+            PrimitiveType::UInt8 => "uint8",
+            PrimitiveType::Int16 => "int16",
+            PrimitiveType::UInt16 => "uint16",
+            PrimitiveType::Int32 => "int32",
+            PrimitiveType::UInt32 => "uint32",
+            PrimitiveType::Int64 => "int64",
+            PrimitiveType::UInt64 => "uint64",
+            PrimitiveType::Float32 => "float32",
+            PrimitiveType::Float64 => "float64",
+            PrimitiveType::String => "string",
+            PrimitiveType::WString => "wstring",
+        }
+    }
+
+    /// Returns the Rust type, where byte and char map to `u8` as in the ROS2 CDR encoding and
+    /// wstring maps to `WString` of the `ros_messages` module of erosbag.
+    pub fn rust_type(&self) -> &'static str {
+        match self {
+            PrimitiveType::Bool => "bool",
+            PrimitiveType::Byte | PrimitiveType::Char | PrimitiveType::UInt8 => "u8",
+            PrimitiveType::Int8 => "i8",
+            PrimitiveType::Int16 => "i16",
+            PrimitiveType::UInt16 => "u16",
+            PrimitiveType::Int32 => "i32",
+            PrimitiveType::UInt32 => "u32",
+            PrimitiveType::Int64 => "i64",
+            PrimitiveType::UInt64 => "u64",
+            PrimitiveType::Float32 => "f32",
+            PrimitiveType::Float64 => "f64",
+            PrimitiveType::String => "String",
+            PrimitiveType::WString => "WString",
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            PrimitiveType::Byte
+                | PrimitiveType::Char
+                | PrimitiveType::Int8
+                | PrimitiveType::UInt8
+                | PrimitiveType::Int16
+                | PrimitiveType::UInt16
+                | PrimitiveType::Int32
+                | PrimitiveType::UInt32
+                | PrimitiveType::Int64
+                | PrimitiveType::UInt64
+        )
+    }
+}
+
+impl fmt::Display for PrimitiveType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BaseType {
+    Primitive(PrimitiveType),
+    Message(MessageName),
+}
+
+/// Array of a field, where bounded and unbounded sequences are encoded identically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArrayKind {
+    Fixed(usize),
+    Bounded(usize),
+    Unbounded,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FieldType {
+    pub base_type: BaseType,
+    pub array: Option<ArrayKind>,
+    /// Maximum length of a string, which does not affect the encoding.
+    pub string_bound: Option<usize>,
+}
+
+impl FieldType {
+    pub fn new(base_type: BaseType, array: Option<ArrayKind>) -> Self {
+        Self {
+            base_type,
+            array,
+            string_bound: None,
+        }
+    }
+
+    pub fn with_string_bound(mut self, string_bound: Option<usize>) -> Self {
+        self.string_bound = string_bound;
+        self
+    }
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.base_type {
+            BaseType::Primitive(primitive_type) => write!(f, "{primitive_type}")?,
+            BaseType::Message(message_name) => write!(f, "{message_name}")?,
+        }
+        if let Some(string_bound) = self.string_bound {
+            write!(f, "<={string_bound}")?;
+        }
+        match self.array {
+            Some(ArrayKind::Fixed(size)) => write!(f, "[{size}]"),
+            Some(ArrayKind::Bounded(size)) => write!(f, "[<={size}]"),
+            Some(ArrayKind::Unbounded) => write!(f, "[]"),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Field {
+    pub name: String,
+    pub field_type: FieldType,
+}
+
+/// Constant of a message with the value as written in the definition.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Constant {
+    pub name: String,
+    pub primitive_type: PrimitiveType,
+    pub value: String,
+}
+
+/// Parsed definition of a message.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MessageDefinition {
+    pub name: MessageName,
+    pub fields: Vec<Field>,
+    pub constants: Vec<Constant>,
+}
+
+impl MessageDefinition {
+    pub fn new(name: MessageName, fields: Vec<Field>, constants: Vec<Constant>) -> Self {
+        Self {
+            name,
+            fields,
+            constants,
+        }
+    }
+
+    /// Returns the names of the directly referenced messages in the order of the fields.
+    pub fn dependencies(&self) -> Vec<&MessageName> {
+        let mut dependencies: Vec<&MessageName> = Vec::new();
+        for current_field in &self.fields {
+            if let BaseType::Message(message_name) = &current_field.field_type.base_type
+                && !dependencies.contains(&message_name)
+            {
+                dependencies.push(message_name);
+            }
+        }
+        dependencies
+    }
+
+    /// Returns the definition of this message without its dependencies in the ros2msg format.
+    pub fn to_msg(&self) -> String {
+        let mut lines: Vec<String> = self
+            .constants
+            .iter()
+            .map(|x| format!("{} {}={}", x.primitive_type, x.name, x.value))
+            .collect();
+        lines.extend(
+            self.fields
+                .iter()
+                .map(|x| format!("{} {}", x.field_type, x.name)),
+        );
+        lines.iter().map(|x| format!("{x}\n")).collect()
+    }
+}
@@ -0,0 +1,24 @@
+use crate::MessageName;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    McapError(#[from] mcap::McapError),
+    #[error(transparent)]
+    WalkdirError(#[from] walkdir::Error),
+
+    #[error(transparent)]
+    StdIoError(#[from] std::io::Error),
+
+    #[error("invalid definition of `{0}` in line {1}: {2}")]
+    InvalidMsgDefinition(MessageName, usize, String),
+    #[error("invalid IDL definition: {0}")]
+    InvalidIdlDefinition(String),
+    #[error("invalid schema: {0}")]
+    InvalidSchema(String),
+    #[error("unsupported schema encoding `{0}`")]
+    UnsupportedSchemaEncoding(String),
+    #[error("message `{0}` references the unknown message type `{1}`")]
+    UnresolvedMessageType(MessageName, MessageName),
+}
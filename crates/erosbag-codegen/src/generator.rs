@@ -0,0 +1,676 @@
+use crate::Error;
+use crate::definition::{
+    ArrayKind, BaseType, Constant, Field, FieldType, MessageDefinition, MessageName, PrimitiveType,
+};
+use crate::idl::parse_idl;
+use crate::msg::{parse_idl_schema, parse_msg, parse_msg_schema};
+use itertools::Itertools;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::path::Path;
+use tracing::warn;
+use walkdir::WalkDir;
+
+const MSG_EXTENSION: &str = "msg";
+const IDL_EXTENSION: &str = "idl";
+const MSG_DIRECTORY_NAME: &str = "msg";
+const MCAP_EXTENSION: &str = "mcap";
+
+const SCHEMA_ENCODING_ROS2MSG: &str = "ros2msg";
+const SCHEMA_ENCODING_ROS2IDL: &str = "ros2idl";
+
+const DEFAULT_CRATE_PATH: &str = "erosbag";
+
+/// Maximum length of arrays, for which serde and `Default` are implemented by the standard library.
+const MAX_STD_ARRAY_LENGTH: usize = 32;
+
+/// Name of the member added to empty messages, which is serialized as `uint8` by rosidl.
+const EMPTY_MESSAGE_MEMBER_NAME: &str = "structure_needs_at_least_one_member";
+
+/// Messages, which are not generated but taken from the `ros_messages` module of erosbag, so that
+/// the generated messages interoperate with the traits of erosbag.
+const EXTERNAL_MESSAGES: [(&str, &str, &str, &str); 3] = [
+    (
+        "builtin_interfaces",
+        "Time",
+        "ros_messages::builtin_msgs::Time",
+        "int32 sec\nuint32 nanosec\n",
+    ),
+    (
+        "builtin_interfaces",
+        "Duration",
+        "ros_messages::builtin_msgs::Duration",
+        "int32 sec\nuint32 nanosec\n",
+    ),
+    (
+        "std_msgs",
+        "Header",
+        "ros_messages::std_msgs::Header",
+        "builtin_interfaces/Time stamp\nstring frame_id\n",
+    ),
+];
+
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract",
+    "as",
+    "async",
+    "await",
+    "become",
+    "box",
+    "break",
+    "const",
+    "continue",
+    "do",
+    "dyn",
+    "else",
+    "enum",
+    "extern",
+    "false",
+    "final",
+    "fn",
+    "for",
+    "gen",
+    "if",
+    "impl",
+    "in",
+    "let",
+    "loop",
+    "macro",
+    "match",
+    "mod",
+    "move",
+    "mut",
+    "override",
+    "priv",
+    "pub",
+    "ref",
+    "return",
+    "static",
+    "struct",
+    "trait",
+    "true",
+    "try",
+    "type",
+    "typeof",
+    "unsafe",
+    "unsized",
+    "use",
+    "virtual",
+    "where",
+    "while",
+    "yield",
+    "union",
+    "macro_rules",
+];
+
+/// Generates Rust structs from ROS2 message definitions.
+///
+/// The generated structs derive `serde::Deserialize` and `serde::Serialize` in the field order of
+/// the definitions and implement `erosbag::ros_messages::RosMessage`, so that they can be read
+/// with `Rosbag::messages`. Each package is generated as a module.
+#[derive(Debug, Clone)]
+pub struct MessageGenerator {
+    definitions: BTreeMap<MessageName, MessageDefinition>,
+    crate_path: String,
+}
+
+impl Default for MessageGenerator {
+    fn default() -> Self {
+        let mut definitions: BTreeMap<MessageName, MessageDefinition> = BTreeMap::new();
+        for (package, name, _, msg) in EXTERNAL_MESSAGES {
+            let name = MessageName::new(package, name);
+            let definition = parse_msg(name.clone(), msg).expect("should be a valid definition");
+            definitions.insert(name, definition);
+        }
+
+        Self {
+            definitions,
+            crate_path: DEFAULT_CRATE_PATH.to_string(),
+        }
+    }
+}
+
+impl MessageGenerator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the path of the erosbag crate used in the generated code, which is `erosbag` by
+    /// default.
+    pub fn with_crate_path(mut self, crate_path: impl Into<String>) -> Self {
+        self.crate_path = crate_path.into();
+        self
+    }
+
+    /// Returns the added definitions including the definitions of the messages provided by erosbag.
+    pub fn definitions(&self) -> impl Iterator<Item = &MessageDefinition> {
+        self.definitions.values()
+    }
+
+    /// Adds a message definition, which replaces a previously added definition of the same name.
+    ///
+    /// Definitions of the messages provided by erosbag are ignored.
+    pub fn add_definition(&mut self, definition: MessageDefinition) {
+        if is_external(&definition.name) {
+            return;
+        }
+        self.definitions.insert(definition.name.clone(), definition);
+    }
+
+    /// Adds all `.msg` and `.idl` files found recursively below a directory.
+    ///
+    /// The package of a `.msg` file is the name of the directory containing the `msg` directory,
+    /// such as `my_msgs` for `my_msgs/msg/Status.msg`. If a message is defined by both formats,
+    /// the `.msg` file takes precedence.
+    pub fn add_directory(&mut self, directory_path: impl AsRef<Path>) -> Result<(), Error> {
+        let mut idl_file_paths = Vec::new();
+        let mut msg_file_paths = Vec::new();
+        for entry in WalkDir::new(directory_path).sort_by_file_name() {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            match entry.path().extension().and_then(|x| x.to_str()) {
+                Some(IDL_EXTENSION) => idl_file_paths.push(entry.into_path()),
+                Some(MSG_EXTENSION) => msg_file_paths.push(entry.into_path()),
+                _ => {}
+            }
+        }
+
+        for current_path in idl_file_paths {
+            let text = std::fs::read_to_string(&current_path)?;
+            for current_definition in parse_idl(&text)? {
+                self.add_definition(current_definition);
+            }
+        }
+        for current_path in msg_file_paths {
+            let text = std::fs::read_to_string(&current_path)?;
+            let name = MessageName::new(
+                derive_package(&current_path),
+                current_path
+                    .file_stem()
+                    .and_then(|x| x.to_str())
+                    .unwrap_or_default(),
+            );
+            self.add_definition(parse_msg(name, &text)?);
+        }
+
+        Ok(())
+    }
+
+    /// Adds the definitions of the schemas of an MCAP file, which are read from the summary or
+    /// from the channels of the messages, if the file has no summary.
+    ///
+    /// Schemas with an encoding other than `ros2msg` and `ros2idl` are skipped.
+    pub fn add_mcap_schemas(&mut self, file_path: impl AsRef<Path>) -> Result<(), Error> {
+        let buffer = std::fs::read(file_path)?;
+
+        let mut schemas: BTreeMap<u16, (String, String, Vec<u8>)> = BTreeMap::new();
+        match mcap::Summary::read(&buffer)? {
+            Some(summary) => {
+                for current_schema in summary.schemas.values() {
+                    schemas.insert(
+                        current_schema.id,
+                        (
+                            current_schema.name.clone(),
+                            current_schema.encoding.clone(),
+                            current_schema.data.to_vec(),
+                        ),
+                    );
+                }
+            }
+            None => {
+                for current_message in mcap::MessageStream::new(&buffer)? {
+                    if let Some(current_schema) = &current_message?.channel.schema {
+                        schemas.entry(current_schema.id).or_insert_with(|| {
+                            (
+                                current_schema.name.clone(),
+                                current_schema.encoding.clone(),
+                                current_schema.data.to_vec(),
+                            )
+                        });
+                    }
+                }
+            }
+        }
+
+        for (name, encoding, data) in schemas.values() {
+            match parse_schema(name, encoding, data) {
+                Ok(definitions) => definitions.into_iter().for_each(|x| self.add_definition(x)),
+                Err(Error::UnsupportedSchemaEncoding(encoding)) => {
+                    warn!("Skip schema {name} with unsupported encoding {encoding}");
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds the definitions of MCAP files and directories of `.msg` and `.idl` files.
+    pub fn add_path(&mut self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        if path.is_file() && path.extension().and_then(|x| x.to_str()) == Some(MCAP_EXTENSION) {
+            self.add_mcap_schemas(path)
+        } else {
+            self.add_directory(path)
+        }
+    }
+
+    /// Generates the Rust source of all added messages.
+    pub fn generate(&self) -> Result<String, Error> {
+        for current_definition in self.definitions.values() {
+            for current_dependency in current_definition.dependencies() {
+                if !self.definitions.contains_key(current_dependency) {
+                    return Err(Error::UnresolvedMessageType(
+                        current_definition.name.clone(),
+                        current_dependency.clone(),
+                    ));
+                }
+            }
+        }
+
+        let mut defaults: HashMap<&MessageName, bool> = HashMap::new();
+        for current_name in self.definitions.keys() {
+            self.derives_default(current_name, &mut defaults);
+        }
+
+        let mut source = String::new();
+        writeln!(
+            source,
+            "// Generated by erosbag-codegen. Do not edit manually."
+        )
+        .unwrap();
+        let packages = self
+            .definitions
+            .values()
+            .filter(|x| !is_external(&x.name))
+            .chunk_by(|x| x.name.package.clone());
+        for (package, definitions) in &packages {
+            writeln!(source).unwrap();
+            writeln!(source, "pub mod {package} {{").unwrap();
+            for current_definition in definitions {
+                self.generate_message(&mut source, current_definition, &defaults);
+            }
+            writeln!(source, "}}").unwrap();
+        }
+
+        Ok(source)
+    }
+
+    fn generate_message(
+        &self,
+        source: &mut String,
+        definition: &MessageDefinition,
+        defaults: &HashMap<&MessageName, bool>,
+    ) {
+        let crate_path = &self.crate_path;
+        let name = &definition.name.name;
+        let schema_name = definition.name.schema_name();
+
+        let mut derives = vec!["Debug", "Clone", "PartialEq"];
+        if defaults[&definition.name] {
+            derives.push("Default");
+        }
+        derives.extend(["serde::Deserialize", "serde::Serialize"]);
+
+        writeln!(source).unwrap();
+        writeln!(
+            source,
+            "    /// Generated from the `{schema_name}` definition."
+        )
+        .unwrap();
+        writeln!(source, "    #[derive({})]", derives.join(", ")).unwrap();
+        writeln!(source, "    pub struct {name} {{").unwrap();
+        for current_field in &definition.fields {
+            if let Some(ArrayKind::Fixed(size)) = current_field.field_type.array
+                && size > MAX_STD_ARRAY_LENGTH
+            {
+                writeln!(
+                    source,
+                    "        #[serde(with = \"{crate_path}::serde_big_array::BigArray\")]"
+                )
+                .unwrap();
+            }
+            writeln!(
+                source,
+                "        pub {}: {},",
+                field_identifier(&current_field.name),
+                self.rust_type(&definition.name.package, &current_field.field_type)
+            )
+            .unwrap();
+        }
+        if definition.fields.is_empty() {
+            writeln!(source, "        pub {EMPTY_MESSAGE_MEMBER_NAME}: u8,").unwrap();
+        }
+        writeln!(source, "    }}").unwrap();
+
+        if !definition.constants.is_empty() {
+            writeln!(source).unwrap();
+            writeln!(source, "    impl {name} {{").unwrap();
+            for current_constant in &definition.constants {
+                writeln!(
+                    source,
+                    "        pub const {}: {} = {};",
+                    current_constant.name,
+                    constant_type(current_constant.primitive_type),
+                    constant_value(current_constant)
+                )
+                .unwrap();
+            }
+            writeln!(source, "    }}").unwrap();
+        }
+
+        writeln!(source).unwrap();
+        writeln!(
+            source,
+            "    impl {crate_path}::ros_messages::RosMessage for {name} {{"
+        )
+        .unwrap();
+        writeln!(
+            source,
+            "        const SCHEMA_NAME: &'static str = {schema_name:?};"
+        )
+        .unwrap();
+        writeln!(
+            source,
+            "        const DEFINITION: &'static str = {:?};",
+            self.render_definition(definition)
+        )
+        .unwrap();
+        writeln!(source, "    }}").unwrap();
+
+        let has_header = definition.fields.iter().any(|x| {
+            x.name == "header"
+                && x.field_type
+                    == FieldType::new(
+                        BaseType::Message(MessageName::new("std_msgs", "Header")),
+                        None,
+                    )
+        });
+        if has_header {
+            writeln!(source).unwrap();
+            writeln!(
+                source,
+                "    impl {crate_path}::ros_messages::Header for {name} {{"
+            )
+            .unwrap();
+            writeln!(
+                source,
+                "        fn header(&self) -> &{crate_path}::ros_messages::std_msgs::Header {{"
+            )
+            .unwrap();
+            writeln!(source, "            &self.header").unwrap();
+            writeln!(source, "        }}").unwrap();
+            writeln!(source, "    }}").unwrap();
+        }
+
+        for current_field in &definition.fields {
+            if let Some(enumeration) = derive_enumeration(definition, current_field) {
+                generate_enumeration(source, &enumeration);
+            }
+        }
+    }
+
+    fn rust_type(&self, current_package: &str, field_type: &FieldType) -> String {
+        let base_type = match &field_type.base_type {
+            BaseType::Primitive(PrimitiveType::WString) => {
+                format!("{}::ros_messages::WString", self.crate_path)
+            }
+            BaseType::Primitive(primitive_type) => primitive_type.rust_type().to_string(),
+            BaseType::Message(message_name) => {
+                match EXTERNAL_MESSAGES
+                    .iter()
+                    .find(|(package, name, _, _)| {
+                        message_name.package == *package && message_name.name == *name
+                    })
+                    .map(|(_, _, path, _)| path)
+                {
+                    Some(path) => format!("{}::{path}", self.crate_path),
+                    None if message_name.package == current_package => message_name.name.clone(),
+                    None => format!("super::{}::{}", message_name.package, message_name.name),
+                }
+            }
+        };
+
+        match field_type.array {
+            Some(ArrayKind::Fixed(size)) => format!("[{base_type}; {size}]"),
+            Some(ArrayKind::Bounded(_)) | Some(ArrayKind::Unbounded) => format!("Vec<{base_type}>"),
+            None => base_type,
+        }
+    }
+
+    /// Renders the definition followed by the definitions of all nested messages as written by
+    /// rosbag2 to the schemas of MCAP files.
+    fn render_definition(&self, definition: &MessageDefinition) -> String {
+        let mut dependencies: Vec<&MessageName> = Vec::new();
+        let mut stack: Vec<&MessageName> = definition.dependencies().into_iter().rev().collect();
+        while let Some(current_name) = stack.pop() {
+            if dependencies.contains(&current_name) {
+                continue;
+            }
+            dependencies.push(current_name);
+            stack.extend(
+                self.definitions[current_name]
+                    .dependencies()
+                    .into_iter()
+                    .rev(),
+            );
+        }
+
+        let mut text = definition.to_msg();
+        for current_name in dependencies {
+            text.push_str(&"=".repeat(80));
+            text.push_str(&format!("\nMSG: {current_name}\n"));
+            text.push_str(&self.definitions[current_name].to_msg());
+        }
+        text
+    }
+
+    fn derives_default<'a>(
+        &'a self,
+        name: &'a MessageName,
+        defaults: &mut HashMap<&'a MessageName, bool>,
+    ) -> bool {
+        if let Some(derives_default) = defaults.get(name) {
+            return *derives_default;
+        }
+        // guard against recursive definitions
+        defaults.insert(name, false);
+
+        let derives_default = self.definitions[name].fields.iter().all(|x| {
+            let is_std_array = match x.field_type.array {
+                Some(ArrayKind::Fixed(size)) => size <= MAX_STD_ARRAY_LENGTH,
+                _ => true,
+            };
+            let is_base_default = match &x.field_type.base_type {
+                BaseType::Primitive(_) => true,
+                BaseType::Message(message_name) => self.derives_default(message_name, defaults),
+            };
+            is_std_array && is_base_default
+        });
+        defaults.insert(name, derives_default);
+        derives_default
+    }
+}
+
+fn parse_schema(name: &str, encoding: &str, data: &[u8]) -> Result<Vec<MessageDefinition>, Error> {
+    let text = String::from_utf8_lossy(data);
+    match encoding {
+        SCHEMA_ENCODING_ROS2MSG => parse_msg_schema(MessageName::parse(name, ""), &text),
+        SCHEMA_ENCODING_ROS2IDL => parse_idl_schema(&text),
+        _ => Err(Error::UnsupportedSchemaEncoding(encoding.to_string())),
+    }
+}
+
+fn is_external(name: &MessageName) -> bool {
+    EXTERNAL_MESSAGES
+        .iter()
+        .any(|(package, x, _, _)| name.package == *package && name.name == *x)
+}
+
+fn derive_package(file_path: &Path) -> String {
+    let parent_path = file_path.parent();
+    let package_path = match parent_path.and_then(|x| x.file_name()) {
+        Some(x) if x == MSG_DIRECTORY_NAME => parent_path.and_then(|x| x.parent()),
+        _ => parent_path,
+    };
+    package_path
+        .and_then(|x| x.file_name())
+        .and_then(|x| x.to_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn field_identifier(name: &str) -> String {
+    if RUST_KEYWORDS.contains(&name) {
+        format!("r#{name}")
+    } else {
+        name.to_string()
+    }
+}
+
+fn constant_type(primitive_type: PrimitiveType) -> &'static str {
+    match primitive_type {
+        PrimitiveType::String | PrimitiveType::WString => "&'static str",
+        _ => primitive_type.rust_type(),
+    }
+}
+
+fn constant_value(constant: &Constant) -> String {
+    let value = constant.value.trim();
+    match constant.primitive_type {
+        PrimitiveType::Bool => (value.eq_ignore_ascii_case("true") || value == "1").to_string(),
+        PrimitiveType::Float32 | PrimitiveType::Float64
+            if !value.contains(['.', 'e', 'E', 'n', 'N']) =>
+        {
+            format!("{value}.0")
+        }
+        PrimitiveType::String | PrimitiveType::WString => {
+            let unquoted = value
+                .strip_prefix('"')
+                .and_then(|x| x.strip_suffix('"'))
+                .or_else(|| value.strip_prefix('\'').and_then(|x| x.strip_suffix('\'')))
+                .unwrap_or(value);
+            format!("{unquoted:?}")
+        }
+        _ => value.to_string(),
+    }
+}
+
+/// Enumeration derived from the constants sharing the name of an integer field as prefix, such as
+/// `STATUS_FIX` for the field `status`.
+struct Enumeration {
+    name: String,
+    primitive_type: PrimitiveType,
+    variants: Vec<(String, String)>,
+}
+
+fn derive_enumeration(definition: &MessageDefinition, field: &Field) -> Option<Enumeration> {
+    let BaseType::Primitive(primitive_type) = field.field_type.base_type else {
+        return None;
+    };
+    if field.field_type.array.is_some() || !primitive_type.is_integer() {
+        return None;
+    }
+
+    // try the longest prefix first, such as `POSITION_COVARIANCE_TYPE_` before `COVARIANCE_TYPE_`
+    let segments: Vec<String> = field.name.split('_').map(|x| x.to_uppercase()).collect();
+    for start_index in 0..segments.len() {
+        let prefix = format!("{}_", segments[start_index..].join("_"));
+        let constants: Vec<&Constant> = definition
+            .constants
+            .iter()
+            .filter(|x| x.primitive_type == primitive_type && x.name.starts_with(&prefix))
+            .collect();
+        if constants.len() < 2 {
+            continue;
+        }
+
+        let values: Vec<i128> = constants
+            .iter()
+            .filter_map(|x| x.value.trim().parse::<i128>().ok())
+            .collect();
+        let variant_names: Vec<String> = constants
+            .iter()
+            .map(|x| to_camel_case(&x.name[prefix.len()..]))
+            .collect();
+        let is_valid = values.len() == constants.len()
+            && values.iter().all_unique()
+            && variant_names
+                .iter()
+                .all(|x| x.starts_with(|c: char| c.is_ascii_alphabetic()));
+        if !is_valid {
+            return None;
+        }
+
+        return Some(Enumeration {
+            name: format!("{}{}", definition.name.name, to_camel_case(&field.name)),
+            primitive_type,
+            variants: variant_names
+                .into_iter()
+                .zip(values.iter().map(|x| x.to_string()))
+                .collect(),
+        });
+    }
+
+    None
+}
+
+fn generate_enumeration(source: &mut String, enumeration: &Enumeration) {
+    let name = &enumeration.name;
+    let rust_type = enumeration.primitive_type.rust_type();
+
+    writeln!(source).unwrap();
+    writeln!(
+        source,
+        "    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]"
+    )
+    .unwrap();
+    writeln!(source, "    #[repr({rust_type})]").unwrap();
+    writeln!(source, "    pub enum {name} {{").unwrap();
+    for (variant, value) in &enumeration.variants {
+        writeln!(source, "        {variant} = {value},").unwrap();
+    }
+    writeln!(source, "    }}").unwrap();
+
+    writeln!(source).unwrap();
+    writeln!(source, "    impl TryFrom<{rust_type}> for {name} {{").unwrap();
+    writeln!(source, "        type Error = {rust_type};").unwrap();
+    writeln!(source).unwrap();
+    writeln!(
+        source,
+        "        fn try_from(value: {rust_type}) -> Result<Self, {rust_type}> {{"
+    )
+    .unwrap();
+    writeln!(source, "            match value {{").unwrap();
+    for (variant, value) in &enumeration.variants {
+        writeln!(source, "                {value} => Ok(Self::{variant}),").unwrap();
+    }
+    writeln!(source, "                _ => Err(value),").unwrap();
+    writeln!(source, "            }}").unwrap();
+    writeln!(source, "        }}").unwrap();
+    writeln!(source, "    }}").unwrap();
+
+    writeln!(source).unwrap();
+    writeln!(source, "    impl From<{name}> for {rust_type} {{").unwrap();
+    writeln!(source, "        fn from(item: {name}) -> Self {{").unwrap();
+    writeln!(source, "            item as {rust_type}").unwrap();
+    writeln!(source, "        }}").unwrap();
+    writeln!(source, "    }}").unwrap();
+}
+
+fn to_camel_case(value: &str) -> String {
+    value
+        .split('_')
+        .filter(|x| !x.is_empty())
+        .map(|x| {
+            let lowercase = x.to_lowercase();
+            let mut chars = lowercase.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,415 @@
+//! Parser of the IDL subset generated by rosidl for ROS2 messages.
+use crate::Error;
+use crate::definition::{
+    ArrayKind, BaseType, Constant, Field, FieldType, MessageDefinition, MessageName, PrimitiveType,
+};
+use std::collections::HashMap;
+
+/// Suffix of the modules holding the constants of the message with the module's prefix.
+const CONSTANTS_MODULE_SUFFIX: &str = "_Constants";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Identifier(String),
+    Literal(String),
+    Symbol(char),
+}
+
+/// Parses the messages defined in IDL text, such as
+/// `module pkg { module msg { struct Type { double x; }; }; };`.
+///
+/// Annotations, preprocessor directives and default values are ignored, and fixed arrays
+/// declared via typedefs are resolved.
+pub fn parse_idl(text: &str) -> Result<Vec<MessageDefinition>, Error> {
+    let tokens = tokenize(text)?;
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        typedefs: HashMap::new(),
+        constants: HashMap::new(),
+        definitions: Vec::new(),
+    };
+    parser.parse_definitions(&mut Vec::new())?;
+
+    let mut definitions = parser.definitions;
+    for current_definition in &mut definitions {
+        if let Some(constants) = parser.constants.remove(&current_definition.name) {
+            current_definition.constants = constants;
+        }
+    }
+    Ok(definitions)
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, Error> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut index = 0;
+
+    while index < chars.len() {
+        let current = chars[index];
+        let next = chars.get(index + 1).copied();
+
+        if current.is_whitespace() {
+            index += 1;
+        } else if current == '/' && next == Some('/') || current == '#' {
+            while index < chars.len() && chars[index] != '\n' {
+                index += 1;
+            }
+        } else if current == '/' && next == Some('*') {
+            index += 2;
+            while index + 1 < chars.len() && !(chars[index] == '*' && chars[index + 1] == '/') {
+                index += 1;
+            }
+            index += 2;
+        } else if current == '"' || current == '\'' {
+            let start = index;
+            index += 1;
+            while index < chars.len() && chars[index] != current {
+                if chars[index] == '\\' {
+                    index += 1;
+                }
+                index += 1;
+            }
+            if index >= chars.len() {
+                return Err(Error::InvalidIdlDefinition("unterminated literal".into()));
+            }
+            index += 1;
+            tokens.push(Token::Literal(chars[start..index].iter().collect()));
+        } else if current.is_alphabetic() || current == '_' {
+            let start = index;
+            while index < chars.len()
+                && (chars[index].is_alphanumeric()
+                    || chars[index] == '_'
+                    || chars[index] == ':' && chars.get(index + 1) == Some(&':'))
+            {
+                index += if chars[index] == ':' { 2 } else { 1 };
+            }
+            tokens.push(Token::Identifier(chars[start..index].iter().collect()));
+        } else if current.is_ascii_digit() || current == '.' {
+            let start = index;
+            while index < chars.len()
+                && (chars[index].is_alphanumeric()
+                    || chars[index] == '.'
+                    || (chars[index] == '-' || chars[index] == '+')
+                        && matches!(chars[index - 1], 'e' | 'E'))
+            {
+                index += 1;
+            }
+            tokens.push(Token::Literal(chars[start..index].iter().collect()));
+        } else {
+            tokens.push(Token::Symbol(current));
+            index += 1;
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    typedefs: HashMap<String, FieldType>,
+    constants: HashMap<MessageName, Vec<Constant>>,
+    definitions: Vec<MessageDefinition>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Result<Token, Error> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or(Error::InvalidIdlDefinition("unexpected end".into()))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn expect_symbol(&mut self, symbol: char) -> Result<(), Error> {
+        match self.next()? {
+            Token::Symbol(x) if x == symbol => Ok(()),
+            other => Err(Error::InvalidIdlDefinition(format!(
+                "expected `{symbol}`, found {other:?}"
+            ))),
+        }
+    }
+
+    fn expect_identifier(&mut self) -> Result<String, Error> {
+        match self.next()? {
+            Token::Identifier(x) => Ok(x),
+            other => Err(Error::InvalidIdlDefinition(format!(
+                "expected identifier, found {other:?}"
+            ))),
+        }
+    }
+
+    fn is_symbol(&self, symbol: char) -> bool {
+        self.peek() == Some(&Token::Symbol(symbol))
+    }
+
+    fn is_identifier(&self, identifier: &str) -> bool {
+        matches!(self.peek(), Some(Token::Identifier(x)) if x == identifier)
+    }
+
+    /// Skips annotations such as `@verbatim (language="comment", text="...")`.
+    fn skip_annotations(&mut self) -> Result<(), Error> {
+        while self.is_symbol('@') {
+            self.position += 1;
+            self.expect_identifier()?;
+            if self.is_symbol('(') {
+                let mut depth = 0;
+                loop {
+                    match self.next()? {
+                        Token::Symbol('(') => depth += 1,
+                        Token::Symbol(')') => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_definitions(&mut self, modules: &mut Vec<String>) -> Result<(), Error> {
+        loop {
+            self.skip_annotations()?;
+            match self.peek() {
+                None | Some(Token::Symbol('}')) => return Ok(()),
+                Some(Token::Identifier(x)) if x == "module" => {
+                    self.position += 1;
+                    let name = self.expect_identifier()?;
+                    self.expect_symbol('{')?;
+                    modules.push(name);
+                    self.parse_definitions(modules)?;
+                    modules.pop();
+                    self.expect_symbol('}')?;
+                    self.expect_symbol(';')?;
+                }
+                Some(Token::Identifier(x)) if x == "struct" => {
+                    self.position += 1;
+                    let definition = self.parse_struct(modules)?;
+                    self.definitions.push(definition);
+                }
+                Some(Token::Identifier(x)) if x == "typedef" => {
+                    self.position += 1;
+                    let field_type = self.parse_type(modules)?;
+                    let name = self.expect_identifier()?;
+                    let field_type = self.parse_array_suffix(field_type)?;
+                    self.expect_symbol(';')?;
+                    self.typedefs.insert(name, field_type);
+                }
+                Some(Token::Identifier(x)) if x == "const" => {
+                    self.position += 1;
+                    self.parse_constant(modules)?;
+                }
+                other => {
+                    return Err(Error::InvalidIdlDefinition(format!("unexpected {other:?}")));
+                }
+            }
+        }
+    }
+
+    fn parse_struct(&mut self, modules: &[String]) -> Result<MessageDefinition, Error> {
+        let name = self.expect_identifier()?;
+        let package = modules.first().cloned().unwrap_or_default();
+        self.expect_symbol('{')?;
+
+        let mut fields: Vec<Field> = Vec::new();
+        loop {
+            self.skip_annotations()?;
+            if self.is_symbol('}') {
+                break;
+            }
+            let field_type = self.parse_type(modules)?;
+            loop {
+                let field_name = self.expect_identifier()?;
+                let field_type = self.parse_array_suffix(field_type.clone())?;
+                fields.push(Field {
+                    name: field_name,
+                    field_type,
+                });
+                if !self.is_symbol(',') {
+                    break;
+                }
+                self.position += 1;
+            }
+            self.expect_symbol(';')?;
+        }
+        self.expect_symbol('}')?;
+        self.expect_symbol(';')?;
+
+        Ok(MessageDefinition::new(
+            MessageName::new(package, name),
+            fields,
+            Vec::new(),
+        ))
+    }
+
+    fn parse_constant(&mut self, modules: &[String]) -> Result<(), Error> {
+        let field_type = self.parse_type(modules)?;
+        let BaseType::Primitive(primitive_type) = field_type.base_type else {
+            return Err(Error::InvalidIdlDefinition(
+                "constants must have a primitive type".into(),
+            ));
+        };
+        let name = self.expect_identifier()?;
+        self.expect_symbol('=')?;
+        let mut value = String::new();
+        while !self.is_symbol(';') {
+            match self.next()? {
+                Token::Identifier(x) | Token::Literal(x) => value.push_str(&x),
+                Token::Symbol(x) => value.push(x),
+            }
+        }
+        self.expect_symbol(';')?;
+
+        let message_module = modules
+            .last()
+            .and_then(|x| x.strip_suffix(CONSTANTS_MODULE_SUFFIX))
+            .ok_or(Error::InvalidIdlDefinition(format!(
+                "constant `{name}` outside of a constants module"
+            )))?;
+        let message_name =
+            MessageName::new(modules.first().cloned().unwrap_or_default(), message_module);
+        self.constants
+            .entry(message_name)
+            .or_default()
+            .push(Constant {
+                name,
+                primitive_type,
+                value,
+            });
+        Ok(())
+    }
+
+    fn parse_array_suffix(&mut self, field_type: FieldType) -> Result<FieldType, Error> {
+        if !self.is_symbol('[') {
+            return Ok(field_type);
+        }
+        self.position += 1;
+        let size = match self.next()? {
+            Token::Literal(x) => x.parse::<usize>().ok(),
+            _ => None,
+        }
+        .ok_or(Error::InvalidIdlDefinition("invalid array size".into()))?;
+        self.expect_symbol(']')?;
+        if field_type.array.is_some() {
+            return Err(Error::InvalidIdlDefinition(
+                "nested arrays are not supported".into(),
+            ));
+        }
+        Ok(FieldType {
+            array: Some(ArrayKind::Fixed(size)),
+            ..field_type
+        })
+    }
+
+    fn parse_type(&mut self, modules: &[String]) -> Result<FieldType, Error> {
+        let identifier = self.expect_identifier()?;
+        let primitive_type = match identifier.as_str() {
+            "sequence" => {
+                self.expect_symbol('<')?;
+                let element_type = self.parse_type(modules)?;
+                let array = if self.is_symbol(',') {
+                    self.position += 1;
+                    let bound = match self.next()? {
+                        Token::Literal(x) => x.parse::<usize>().ok(),
+                        _ => None,
+                    }
+                    .ok_or(Error::InvalidIdlDefinition("invalid sequence bound".into()))?;
+                    ArrayKind::Bounded(bound)
+                } else {
+                    ArrayKind::Unbounded
+                };
+                self.expect_symbol('>')?;
+                if element_type.array.is_some() {
+                    return Err(Error::InvalidIdlDefinition(
+                        "nested sequences are not supported".into(),
+                    ));
+                }
+                return Ok(FieldType {
+                    array: Some(array),
+                    ..element_type
+                });
+            }
+            "string" | "wstring" => {
+                let string_bound = if self.is_symbol('<') {
+                    self.position += 1;
+                    let bound = match self.next()? {
+                        Token::Literal(x) => x.parse::<usize>().ok(),
+                        _ => None,
+                    }
+                    .ok_or(Error::InvalidIdlDefinition("invalid string bound".into()))?;
+                    self.expect_symbol('>')?;
+                    Some(bound)
+                } else {
+                    None
+                };
+                let primitive_type = if identifier == "string" {
+                    PrimitiveType::String
+                } else {
+                    PrimitiveType::WString
+                };
+                return Ok(FieldType::new(BaseType::Primitive(primitive_type), None)
+                    .with_string_bound(string_bound));
+            }
+            "unsigned" => {
+                let next = self.expect_identifier()?;
+                match next.as_str() {
+                    "short" => PrimitiveType::UInt16,
+                    "long" if self.is_identifier("long") => {
+                        self.position += 1;
+                        PrimitiveType::UInt64
+                    }
+                    "long" => PrimitiveType::UInt32,
+                    _ => {
+                        return Err(Error::InvalidIdlDefinition(format!(
+                            "unsupported type `unsigned {next}`"
+                        )));
+                    }
+                }
+            }
+            "long" if self.is_identifier("long") => {
+                self.position += 1;
+                PrimitiveType::Int64
+            }
+            "long" => PrimitiveType::Int32,
+            "short" => PrimitiveType::Int16,
+            "boolean" => PrimitiveType::Bool,
+            "octet" => PrimitiveType::Byte,
+            "char" => PrimitiveType::Char,
+            "float" => PrimitiveType::Float32,
+            "double" => PrimitiveType::Float64,
+            "int8" => PrimitiveType::Int8,
+            "uint8" => PrimitiveType::UInt8,
+            "int16" => PrimitiveType::Int16,
+            "uint16" => PrimitiveType::UInt16,
+            "int32" => PrimitiveType::Int32,
+            "uint32" => PrimitiveType::UInt32,
+            "int64" => PrimitiveType::Int64,
+            "uint64" => PrimitiveType::UInt64,
+            _ => {
+                if let Some(field_type) = self.typedefs.get(&identifier) {
+                    return Ok(field_type.clone());
+                }
+                let segments: Vec<&str> = identifier.split("::").collect();
+                let message_name = match segments.as_slice() {
+                    [name] => MessageName::new(modules.first().cloned().unwrap_or_default(), *name),
+                    [package, .., name] => MessageName::new(*package, *name),
+                    [] => unreachable!("split returns at least one segment"),
+                };
+                return Ok(FieldType::new(BaseType::Message(message_name), None));
+            }
+        };
+
+        Ok(FieldType::new(BaseType::Primitive(primitive_type), None))
+    }
+}
@@ -0,0 +1,47 @@
+mod definition;
+mod error;
+mod generator;
+mod idl;
+mod msg;
+
+#[doc(inline)]
+pub use error::Error;
+
+#[doc(inline)]
+pub use definition::ArrayKind;
+
+#[doc(inline)]
+pub use definition::BaseType;
+
+#[doc(inline)]
+pub use definition::Constant;
+
+#[doc(inline)]
+pub use definition::Field;
+
+#[doc(inline)]
+pub use definition::FieldType;
+
+#[doc(inline)]
+pub use definition::MessageDefinition;
+
+#[doc(inline)]
+pub use definition::MessageName;
+
+#[doc(inline)]
+pub use definition::PrimitiveType;
+
+#[doc(inline)]
+pub use generator::MessageGenerator;
+
+#[doc(inline)]
+pub use idl::parse_idl;
+
+#[doc(inline)]
+pub use msg::parse_msg;
+
+#[doc(inline)]
+pub use msg::parse_idl_schema;
+
+#[doc(inline)]
+pub use msg::parse_msg_schema;
@@ -0,0 +1,183 @@
+//! Parser of the ros2msg format of `.msg` files and MCAP schemas.
+use crate::Error;
+use crate::definition::{
+    ArrayKind, BaseType, Constant, Field, FieldType, MessageDefinition, MessageName, PrimitiveType,
+};
+use std::str::FromStr;
+
+const DEPENDENCY_PREFIX_MSG: &str = "MSG:";
+const DEPENDENCY_PREFIX_IDL: &str = "IDL:";
+
+/// Parses the definition of a single message in the ros2msg format.
+pub fn parse_msg(name: MessageName, text: &str) -> Result<MessageDefinition, Error> {
+    let mut fields: Vec<Field> = Vec::new();
+    let mut constants: Vec<Constant> = Vec::new();
+
+    for (line_index, current_line) in text.lines().enumerate() {
+        let invalid =
+            |reason: &str| Error::InvalidMsgDefinition(name.clone(), line_index + 1, reason.into());
+        let current_line = current_line.trim();
+        if current_line.is_empty() || current_line.starts_with('#') {
+            continue;
+        }
+
+        let (type_str, rest) = current_line
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| invalid("missing field name"))?;
+        let field_type = parse_field_type(type_str, &name.package).map_err(|e| invalid(&e))?;
+        let rest = rest.trim();
+
+        // constants are given as `NAME=value`, while fields may have a default value
+        let is_constant = rest
+            .split_once('=')
+            .is_some_and(|(x, _)| !x.trim().is_empty() && !x.trim().contains(char::is_whitespace));
+        if is_constant {
+            let BaseType::Primitive(primitive_type) = field_type.base_type else {
+                return Err(invalid("constants must have a primitive type"));
+            };
+            let (constant_name, value) = rest.split_once('=').expect("should contain =");
+            let value = if matches!(
+                primitive_type,
+                PrimitiveType::String | PrimitiveType::WString
+            ) {
+                value.trim()
+            } else {
+                strip_comment(value)
+            };
+            constants.push(Constant {
+                name: constant_name.trim().to_string(),
+                primitive_type,
+                value: value.to_string(),
+            });
+        } else {
+            let field_name = strip_comment(rest)
+                .split_whitespace()
+                .next()
+                .ok_or_else(|| invalid("missing field name"))?;
+            fields.push(Field {
+                name: field_name.to_string(),
+                field_type,
+            });
+        }
+    }
+
+    Ok(MessageDefinition::new(name, fields, constants))
+}
+
+/// Parses a schema consisting of the definition of the named message followed by the
+/// definitions of its dependencies, which are separated by `=` lines and introduced by
+/// `MSG: pkg/Type`.
+pub fn parse_msg_schema(name: MessageName, text: &str) -> Result<Vec<MessageDefinition>, Error> {
+    parse_sections(Some(name), false, text)
+}
+
+/// Parses a schema in the IDL format, where the dependencies are introduced by `IDL: pkg/Type`.
+pub fn parse_idl_schema(text: &str) -> Result<Vec<MessageDefinition>, Error> {
+    parse_sections(None, true, text)
+}
+
+fn parse_sections(
+    name: Option<MessageName>,
+    is_idl: bool,
+    text: &str,
+) -> Result<Vec<MessageDefinition>, Error> {
+    let mut definitions: Vec<MessageDefinition> = Vec::new();
+    let mut current_name = name;
+    let mut current_is_idl = is_idl;
+    let mut current_text = String::new();
+    let mut lines = text.lines().peekable();
+
+    while let Some(current_line) = lines.next() {
+        let is_separator = current_line.len() >= 3 && current_line.chars().all(|x| x == '=');
+        if !is_separator {
+            current_text.push_str(current_line);
+            current_text.push('\n');
+            continue;
+        }
+
+        definitions.extend(parse_section(
+            current_name.take(),
+            current_is_idl,
+            &current_text,
+        )?);
+        current_text.clear();
+        if let Some(header) = lines.next_if(|x| {
+            x.starts_with(DEPENDENCY_PREFIX_MSG) || x.starts_with(DEPENDENCY_PREFIX_IDL)
+        }) {
+            current_is_idl = header.starts_with(DEPENDENCY_PREFIX_IDL);
+            let dependency_name = header[DEPENDENCY_PREFIX_MSG.len()..].trim();
+            current_name = Some(MessageName::parse(dependency_name, ""));
+        }
+    }
+    definitions.extend(parse_section(
+        current_name.take(),
+        current_is_idl,
+        &current_text,
+    )?);
+
+    Ok(definitions)
+}
+
+fn parse_section(
+    name: Option<MessageName>,
+    is_idl: bool,
+    text: &str,
+) -> Result<Vec<MessageDefinition>, Error> {
+    if is_idl {
+        return crate::idl::parse_idl(text);
+    }
+    match name {
+        Some(name) => Ok(vec![parse_msg(name, text)?]),
+        None if text.trim().is_empty() => Ok(Vec::new()),
+        None => Err(Error::InvalidSchema(
+            "definition without `MSG:` line".into(),
+        )),
+    }
+}
+
+/// Parses types such as `float64[9]`, `string<=10`, `geometry_msgs/Point[]` or `Header[<=3]`.
+fn parse_field_type(type_str: &str, current_package: &str) -> Result<FieldType, String> {
+    let (base_str, array) = match type_str.split_once('[') {
+        Some((base_str, array_str)) => {
+            let array_str = array_str
+                .strip_suffix(']')
+                .ok_or(format!("invalid array type `{type_str}`"))?;
+            let array = if array_str.is_empty() {
+                ArrayKind::Unbounded
+            } else if let Some(bound) = array_str.strip_prefix("<=") {
+                ArrayKind::Bounded(parse_size(bound)?)
+            } else {
+                ArrayKind::Fixed(parse_size(array_str)?)
+            };
+            (base_str, Some(array))
+        }
+        None => (type_str, None),
+    };
+
+    let (base_str, string_bound) = match base_str.split_once("<=") {
+        Some((base_str, bound)) => (base_str, Some(parse_size(bound)?)),
+        None => (base_str, None),
+    };
+    let base_type = match PrimitiveType::from_str(base_str) {
+        Ok(primitive_type) => BaseType::Primitive(primitive_type),
+        Err(_) => BaseType::Message(match base_str {
+            "Header" => MessageName::new("std_msgs", "Header"),
+            "time" => MessageName::new("builtin_interfaces", "Time"),
+            "duration" => MessageName::new("builtin_interfaces", "Duration"),
+            _ => MessageName::parse(base_str, current_package),
+        }),
+    };
+
+    Ok(FieldType::new(base_type, array).with_string_bound(string_bound))
+}
+
+fn parse_size(value: &str) -> Result<usize, String> {
+    value
+        .trim()
+        .parse::<usize>()
+        .map_err(|_| format!("invalid array size `{value}`"))
+}
+
+fn strip_comment(value: &str) -> &str {
+    value.split('#').next().unwrap_or_default().trim()
+}
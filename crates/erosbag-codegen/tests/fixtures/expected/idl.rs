@@ -0,0 +1,72 @@
+// Generated by erosbag-codegen. Do not edit manually.
+
+pub mod idl_msgs {
+
+    /// Generated from the `idl_msgs/msg/Item` definition.
+    #[derive(Debug, Clone, PartialEq, Default, serde::Deserialize, serde::Serialize)]
+    pub struct Item {
+        pub id: u32,
+        pub offset: i64,
+        pub valid: bool,
+    }
+
+    impl erosbag::ros_messages::RosMessage for Item {
+        const SCHEMA_NAME: &'static str = "idl_msgs/msg/Item";
+        const DEFINITION: &'static str = "uint32 id\nint64 offset\nbool valid\n";
+    }
+
+    /// Generated from the `idl_msgs/msg/Sample` definition.
+    #[derive(Debug, Clone, PartialEq, Default, serde::Deserialize, serde::Serialize)]
+    pub struct Sample {
+        pub header: erosbag::ros_messages::std_msgs::Header,
+        pub mode: u8,
+        pub name: String,
+        pub label: erosbag::ros_messages::WString,
+        pub covariance: [f64; 9],
+        pub values: [f32; 4],
+        pub bounded_values: Vec<f32>,
+        pub items: Vec<Item>,
+    }
+
+    impl Sample {
+        pub const MODE_IDLE: u8 = 0;
+        pub const MODE_ACTIVE: u8 = 1;
+        pub const DEFAULT_NAME: &'static str = "sample";
+    }
+
+    impl erosbag::ros_messages::RosMessage for Sample {
+        const SCHEMA_NAME: &'static str = "idl_msgs/msg/Sample";
+        const DEFINITION: &'static str = "uint8 MODE_IDLE=0\nuint8 MODE_ACTIVE=1\nstring DEFAULT_NAME=\"sample\"\nstd_msgs/Header header\nuint8 mode\nstring<=32 name\nwstring<=8 label\nfloat64[9] covariance\nfloat32[4] values\nfloat32[<=10] bounded_values\nidl_msgs/Item[] items\n================================================================================\nMSG: std_msgs/Header\nbuiltin_interfaces/Time stamp\nstring frame_id\n================================================================================\nMSG: builtin_interfaces/Time\nint32 sec\nuint32 nanosec\n================================================================================\nMSG: idl_msgs/Item\nuint32 id\nint64 offset\nbool valid\n";
+    }
+
+    impl erosbag::ros_messages::Header for Sample {
+        fn header(&self) -> &erosbag::ros_messages::std_msgs::Header {
+            &self.header
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[repr(u8)]
+    pub enum SampleMode {
+        Idle = 0,
+        Active = 1,
+    }
+
+    impl TryFrom<u8> for SampleMode {
+        type Error = u8;
+
+        fn try_from(value: u8) -> Result<Self, u8> {
+            match value {
+                0 => Ok(Self::Idle),
+                1 => Ok(Self::Active),
+                _ => Err(value),
+            }
+        }
+    }
+
+    impl From<SampleMode> for u8 {
+        fn from(item: SampleMode) -> Self {
+            item as u8
+        }
+    }
+}
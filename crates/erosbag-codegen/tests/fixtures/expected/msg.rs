@@ -0,0 +1,108 @@
+// Generated by erosbag-codegen. Do not edit manually.
+
+pub mod my_msgs {
+
+    /// Generated from the `my_msgs/msg/Reading` definition.
+    #[derive(Debug, Clone, PartialEq, Default, serde::Deserialize, serde::Serialize)]
+    pub struct Reading {
+        pub stamp: erosbag::ros_messages::builtin_msgs::Time,
+        pub value: f32,
+    }
+
+    impl erosbag::ros_messages::RosMessage for Reading {
+        const SCHEMA_NAME: &'static str = "my_msgs/msg/Reading";
+        const DEFINITION: &'static str = "builtin_interfaces/Time stamp\nfloat32 value\n================================================================================\nMSG: builtin_interfaces/Time\nint32 sec\nuint32 nanosec\n";
+    }
+
+    /// Generated from the `my_msgs/msg/Status` definition.
+    #[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+    pub struct Status {
+        pub header: erosbag::ros_messages::std_msgs::Header,
+        pub level: u8,
+        pub name: String,
+        pub label: erosbag::ros_messages::WString,
+        pub position: [f64; 3],
+        #[serde(with = "erosbag::serde_big_array::BigArray")]
+        pub covariance: [f64; 36],
+        pub readings: Vec<i32>,
+        pub flags: Vec<u16>,
+        pub tags: Vec<String>,
+        pub last_reading: Reading,
+        pub previous_readings: [Reading; 2],
+        pub point: super::other_msgs::Point,
+        pub path: Vec<super::other_msgs::Point>,
+    }
+
+    impl Status {
+        pub const LEVEL_OK: u8 = 0;
+        pub const LEVEL_WARN: u8 = 1;
+        pub const LEVEL_ERROR: u8 = 2;
+        pub const NAME_PREFIX: &'static str = "device_";
+        pub const MAX_RANGE: f64 = 100.0;
+        pub const ENABLED: bool = true;
+    }
+
+    impl erosbag::ros_messages::RosMessage for Status {
+        const SCHEMA_NAME: &'static str = "my_msgs/msg/Status";
+        const DEFINITION: &'static str = "uint8 LEVEL_OK=0\nuint8 LEVEL_WARN=1\nuint8 LEVEL_ERROR=2\nstring NAME_PREFIX=\"device_\"\nfloat64 MAX_RANGE=100\nbool ENABLED=true\nstd_msgs/Header header\nuint8 level\nstring<=16 name\nwstring label\nfloat64[3] position\nfloat64[36] covariance\nint32[] readings\nuint16[<=8] flags\nstring<=8[<=4] tags\nmy_msgs/Reading last_reading\nmy_msgs/Reading[2] previous_readings\nother_msgs/Point point\nother_msgs/Point[] path\n================================================================================\nMSG: std_msgs/Header\nbuiltin_interfaces/Time stamp\nstring frame_id\n================================================================================\nMSG: builtin_interfaces/Time\nint32 sec\nuint32 nanosec\n================================================================================\nMSG: my_msgs/Reading\nbuiltin_interfaces/Time stamp\nfloat32 value\n================================================================================\nMSG: other_msgs/Point\nfloat64 x\nfloat64 y\n";
+    }
+
+    impl erosbag::ros_messages::Header for Status {
+        fn header(&self) -> &erosbag::ros_messages::std_msgs::Header {
+            &self.header
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[repr(u8)]
+    pub enum StatusLevel {
+        Ok = 0,
+        Warn = 1,
+        Error = 2,
+    }
+
+    impl TryFrom<u8> for StatusLevel {
+        type Error = u8;
+
+        fn try_from(value: u8) -> Result<Self, u8> {
+            match value {
+                0 => Ok(Self::Ok),
+                1 => Ok(Self::Warn),
+                2 => Ok(Self::Error),
+                _ => Err(value),
+            }
+        }
+    }
+
+    impl From<StatusLevel> for u8 {
+        fn from(item: StatusLevel) -> Self {
+            item as u8
+        }
+    }
+}
+
+pub mod other_msgs {
+
+    /// Generated from the `other_msgs/msg/Empty` definition.
+    #[derive(Debug, Clone, PartialEq, Default, serde::Deserialize, serde::Serialize)]
+    pub struct Empty {
+        pub structure_needs_at_least_one_member: u8,
+    }
+
+    impl erosbag::ros_messages::RosMessage for Empty {
+        const SCHEMA_NAME: &'static str = "other_msgs/msg/Empty";
+        const DEFINITION: &'static str = "";
+    }
+
+    /// Generated from the `other_msgs/msg/Point` definition.
+    #[derive(Debug, Clone, PartialEq, Default, serde::Deserialize, serde::Serialize)]
+    pub struct Point {
+        pub x: f64,
+        pub y: f64,
+    }
+
+    impl erosbag::ros_messages::RosMessage for Point {
+        const SCHEMA_NAME: &'static str = "other_msgs/msg/Point";
+        const DEFINITION: &'static str = "float64 x\nfloat64 y\n";
+    }
+}
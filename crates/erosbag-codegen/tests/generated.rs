@@ -0,0 +1,115 @@
+//! Compiles the expected sources of `tests/golden.rs` and encodes the generated structs in the
+//! CDR layout of the ROS2 middleware.
+use erosbag::ros_messages::{Header, RosMessage, WString, builtin_msgs, std_msgs};
+
+// not all constants of the generated structs are used
+#[allow(dead_code)]
+mod msg {
+    include!("fixtures/expected/msg.rs");
+}
+
+#[allow(dead_code)]
+mod idl {
+    include!("fixtures/expected/idl.rs");
+}
+
+use msg::{my_msgs, other_msgs};
+
+fn header() -> std_msgs::Header {
+    std_msgs::Header {
+        stamp: builtin_msgs::Time {
+            sec: 1700000000,
+            nanosec: 500,
+        },
+        frame_id: "device".to_string(),
+    }
+}
+
+fn reading(value: f32) -> my_msgs::Reading {
+    my_msgs::Reading {
+        stamp: builtin_msgs::Time::default(),
+        value,
+    }
+}
+
+#[test]
+fn encode_and_decode_msg_structs() {
+    let status = my_msgs::Status {
+        header: header(),
+        level: my_msgs::Status::LEVEL_WARN,
+        name: "device_1".to_string(),
+        label: WString::from("Gerät"),
+        position: [1.0, 2.0, 3.0],
+        covariance: std::array::from_fn(|i| i as f64),
+        readings: vec![-1, 2],
+        flags: vec![7],
+        tags: vec!["a".to_string(), "b".to_string()],
+        last_reading: reading(0.5),
+        previous_readings: [reading(0.25), reading(0.125)],
+        point: other_msgs::Point { x: 4.0, y: 5.0 },
+        path: vec![other_msgs::Point { x: 6.0, y: 7.0 }],
+    };
+
+    let data = cdr::serialize::<_, _, cdr::CdrLe>(&status, cdr::Infinite).unwrap();
+    let decoded = my_msgs::Status::deserialize_cdr(&data).unwrap();
+    assert_eq!(decoded, status);
+    assert_eq!(decoded.label.to_string(), "Gerät");
+    assert_eq!(decoded.header().frame_id, "device");
+    assert_eq!(
+        my_msgs::StatusLevel::try_from(decoded.level),
+        Ok(my_msgs::StatusLevel::Warn)
+    );
+    assert_eq!(my_msgs::Status::NAME_PREFIX, "device_");
+    assert_eq!(my_msgs::Status::MAX_RANGE, 100.0);
+    const { assert!(my_msgs::Status::ENABLED) };
+    assert_eq!(my_msgs::Status::SCHEMA_NAME, "my_msgs/msg/Status");
+    assert_eq!(
+        other_msgs::Empty::default().structure_needs_at_least_one_member,
+        0
+    );
+}
+
+#[test]
+fn encode_wstring_as_32_bit_code_units() {
+    let data = cdr::serialize::<_, _, cdr::CdrLe>(&WString::from("aé"), cdr::Infinite).unwrap();
+
+    // encapsulation header, number of code units and one 32-bit integer per code unit
+    assert_eq!(
+        data,
+        [
+            [0, 1, 0, 0],
+            2u32.to_le_bytes(),
+            0x61u32.to_le_bytes(),
+            0xe9u32.to_le_bytes()
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn encode_and_decode_idl_structs() {
+    let sample = idl::idl_msgs::Sample {
+        header: header(),
+        mode: idl::idl_msgs::Sample::MODE_ACTIVE,
+        name: idl::idl_msgs::Sample::DEFAULT_NAME.to_string(),
+        label: WString::from("label"),
+        covariance: [0.5; 9],
+        values: [1.0, 2.0, 3.0, 4.0],
+        bounded_values: vec![5.0],
+        items: vec![idl::idl_msgs::Item {
+            id: 3,
+            offset: -4,
+            valid: true,
+        }],
+    };
+
+    let data = cdr::serialize::<_, _, cdr::CdrLe>(&sample, cdr::Infinite).unwrap();
+    assert_eq!(
+        idl::idl_msgs::Sample::deserialize_cdr(&data).unwrap(),
+        sample
+    );
+    assert_eq!(
+        idl::idl_msgs::SampleMode::try_from(sample.mode),
+        Ok(idl::idl_msgs::SampleMode::Active)
+    );
+}
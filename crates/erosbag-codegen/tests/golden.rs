@@ -0,0 +1,42 @@
+//! Generates the structs of the `.msg` and `.idl` definitions in `tests/fixtures` and compares
+//! them with the expected sources in `tests/fixtures/expected`.
+//!
+//! Run the tests with `UPDATE_EXPECTED=1` to overwrite the expected sources after an intended
+//! change of the generated code, which is compiled and checked by `tests/generated.rs`.
+use erosbag_codegen::MessageGenerator;
+use std::path::{Path, PathBuf};
+
+fn fixtures_path() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+fn check_generated(input_directory_name: &str) {
+    let mut generator = MessageGenerator::new();
+    generator
+        .add_directory(fixtures_path().join(input_directory_name))
+        .expect("adding the definitions should work");
+    let actual = generator.generate().expect("generating should work");
+
+    let expected_path = fixtures_path()
+        .join("expected")
+        .join(format!("{input_directory_name}.rs"));
+    if std::env::var_os("UPDATE_EXPECTED").is_some() {
+        std::fs::write(&expected_path, &actual).unwrap();
+    }
+    let expected = std::fs::read_to_string(&expected_path)
+        .unwrap_or_else(|e| panic!("reading {} should work: {e}", expected_path.display()));
+    assert_eq!(
+        actual, expected,
+        "generated source of {input_directory_name}"
+    );
+}
+
+#[test]
+fn msg_definitions() {
+    check_generated("msg");
+}
+
+#[test]
+fn idl_definitions() {
+    check_generated("idl");
+}
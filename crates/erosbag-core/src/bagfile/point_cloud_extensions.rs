@@ -1,20 +1,45 @@
 use polars::datatypes::PlSmallStr;
 
+const COLUMN_NAME_ROS_CHANNEL_TOPIC_STR: &str = "ros_channel_topic";
+const COLUMN_NAME_ROS_FILE_NAME_STR: &str = "ros_file_name";
+const COLUMN_NAME_ROS_CHUNK_ID_STR: &str = "ros_chunk_id";
 const COLUMN_NAME_ROS_MESSAGE_ID_STR: &str = "ros_message_id";
+const COLUMN_NAME_ROS_LOG_TIME_STR: &str = "ros_log_time";
 const COLUMN_NAME_ROS_POINT_ID_STR: &str = "ros_point_id";
+const COLUMN_NAME_ROS_ECHO_ID_STR: &str = "ros_echo_id";
+const COLUMN_NAME_ROS_CAMERA_ID_STR: &str = "ros_camera_id";
 
 /// Additional column names for ROS specific fields for `epoint::PointCloud`.
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum RosPointDataColumnType {
+    /// Topic of the channel of the source message.
+    RosChannelTopic,
+    /// Name of the MCAP file containing the source message.
+    RosFileName,
+    /// Id of the chunk containing the source message.
+    RosChunkId,
+    /// Index of the source message within its chunk.
     RosMessageId,
+    /// Log time of the source message.
+    RosLogTime,
     RosPointId,
+    /// Index of the echo of a laser scan beam.
+    RosEchoId,
+    /// Index of the camera a point was colored from, where 0 means uncolored.
+    RosCameraId,
 }
 
 impl RosPointDataColumnType {
     pub fn as_str(&self) -> &'static str {
         match self {
+            RosPointDataColumnType::RosChannelTopic => COLUMN_NAME_ROS_CHANNEL_TOPIC_STR,
+            RosPointDataColumnType::RosFileName => COLUMN_NAME_ROS_FILE_NAME_STR,
+            RosPointDataColumnType::RosChunkId => COLUMN_NAME_ROS_CHUNK_ID_STR,
             RosPointDataColumnType::RosMessageId => COLUMN_NAME_ROS_MESSAGE_ID_STR,
+            RosPointDataColumnType::RosLogTime => COLUMN_NAME_ROS_LOG_TIME_STR,
             RosPointDataColumnType::RosPointId => COLUMN_NAME_ROS_POINT_ID_STR,
+            RosPointDataColumnType::RosEchoId => COLUMN_NAME_ROS_ECHO_ID_STR,
+            RosPointDataColumnType::RosCameraId => COLUMN_NAME_ROS_CAMERA_ID_STR,
         }
     }
 }
@@ -1,18 +1,28 @@
-use crate::Error::{ChannelDoesNotHold, ChannelWithoutSchema};
-use crate::ros_messages::RosMessageType;
-use crate::ros_messages::{geometry_msgs, sensor_msgs};
-use crate::{ChannelTopic, ChunkId, Error, FileName, MessageId, ros_messages};
+use crate::Error::{ChannelDoesNotHold, ChannelWithoutSchema, MultiplePoseFrameIds};
+use crate::dto::{PointCloudGroup, PointCloudGrouping, point_cloud_group};
+use crate::filter;
+use crate::filter::PointFilter;
+use crate::ros_messages::{RosMessage, RosMessageType};
+use crate::ros_messages::{geometry_msgs, nav_msgs, sensor_msgs, tf2_msgs, visualization_msgs};
+use crate::sync::get_closest;
+use crate::table::TableRow;
+use crate::{
+    ChannelTopic, ChunkId, Error, FileName, MessageId, RosPointDataColumnType, ros_messages, table,
+};
 use chrono::{DateTime, Utc};
 use ecoord::{
     ExtrapolationMethod, FrameId, InterpolationMethod, TimedTransform, TransformEdge, TransformId,
+    TransformTree,
 };
 use eimage::ImageSeries;
 use itertools::Itertools;
+use polars::prelude::{DataFrame, DataType, NamedFrom, Series, TimeUnit, TimeZone};
+use rayon::iter::IndexedParallelIterator;
 use rayon::iter::IntoParallelIterator;
+use rayon::iter::IntoParallelRefMutIterator;
 use rayon::iter::ParallelIterator;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::str::FromStr;
-use tracing::warn;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct McapMessageMeta<T> {
@@ -45,21 +55,127 @@ impl<T> McapMessageMeta<T> {
             message,
         }
     }
+
+    /// Returns the metadata of this message with another message payload.
+    pub fn with_message<U>(&self, message: U) -> McapMessageMeta<U> {
+        McapMessageMeta::new(
+            self.file_name.clone(),
+            self.channel_topic.clone(),
+            self.chunk_id,
+            self.message_id,
+            self.log_date_time,
+            self.publish_date_time,
+            message,
+        )
+    }
+
+    /// Returns the message converted by the function with the same meta data.
+    pub fn map_message<U>(self, f: impl FnOnce(T) -> U) -> McapMessageMeta<U> {
+        McapMessageMeta::new(
+            self.file_name,
+            self.channel_topic,
+            self.chunk_id,
+            self.message_id,
+            self.log_date_time,
+            self.publish_date_time,
+            f(self.message),
+        )
+    }
+
+    /// Returns the message converted by the fallible function with the same meta data.
+    pub fn try_map_message<U, E>(
+        self,
+        f: impl FnOnce(T) -> Result<U, E>,
+    ) -> Result<McapMessageMeta<U>, E> {
+        Ok(McapMessageMeta::new(
+            self.file_name,
+            self.channel_topic,
+            self.chunk_id,
+            self.message_id,
+            self.log_date_time,
+            self.publish_date_time,
+            f(self.message)?,
+        ))
+    }
+}
+
+impl<T: ros_messages::Header> McapMessageMeta<T> {
+    /// Returns the stamp of the message header, which may differ from the log time.
+    pub fn header_date_time(&self) -> DateTime<Utc> {
+        self.message.header().stamp.into()
+    }
+}
+
+impl McapMessageMeta<epoint::PointCloud> {
+    /// Adds columns with the channel topic, file name, chunk id, message id and log time of this
+    /// message to its point cloud, so that each point can be traced back to its source message.
+    pub fn add_source_columns(&mut self) -> Result<(), Error> {
+        let point_count = self.message.size();
+        let data_frame = &mut self.message.point_data.data_frame;
+
+        data_frame.with_column(Series::new(
+            RosPointDataColumnType::RosChannelTopic.into(),
+            vec![self.channel_topic.to_string(); point_count],
+        ))?;
+        data_frame.with_column(Series::new(
+            RosPointDataColumnType::RosFileName.into(),
+            vec![self.file_name.to_string(); point_count],
+        ))?;
+        data_frame.with_column(Series::new(
+            RosPointDataColumnType::RosChunkId.into(),
+            vec![usize::from(self.chunk_id) as u64; point_count],
+        ))?;
+        data_frame.with_column(Series::new(
+            RosPointDataColumnType::RosMessageId.into(),
+            vec![u64::from(self.message_id); point_count],
+        ))?;
+        data_frame.with_column(
+            Series::new(
+                RosPointDataColumnType::RosLogTime.into(),
+                vec![self.log_date_time.timestamp_nanos_opt().unwrap_or_default(); point_count],
+            )
+            .cast(&DataType::Datetime(
+                TimeUnit::Nanoseconds,
+                Some(TimeZone::UTC),
+            ))?,
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Undecoded message with the schema name of its channel, such as `sensor_msgs/msg/Imu`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawMessage {
+    pub schema_name: Option<String>,
+    pub data: Vec<u8>,
+}
+
+impl RawMessage {
+    pub fn new(schema_name: Option<String>, data: Vec<u8>) -> Self {
+        Self { schema_name, data }
+    }
+
+    /// Returns the message type, if the schema is one of the supported message types.
+    pub fn ros_message_type(&self) -> Option<RosMessageType> {
+        self.schema_name
+            .as_deref()
+            .and_then(|x| RosMessageType::from_str(x).ok())
+    }
+
+    /// Returns the message decoded as `T`, or `None` if the schema is not the one of `T`.
+    pub fn deserialize<T: RosMessage>(&self) -> Result<Option<T>, Error> {
+        if self.schema_name.as_deref() != Some(T::SCHEMA_NAME) {
+            return Ok(None);
+        }
+        T::deserialize_cdr(&self.data).map(Some)
+    }
 }
 
+/// Messages of multiple channels, which are kept undecoded until they are requested by type.
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct McapMessagePage {
-    pub imu_messages: HashMap<ChannelTopic, Vec<McapMessageMeta<sensor_msgs::Imu>>>,
-    pub nav_sat_fix_messages: HashMap<ChannelTopic, Vec<McapMessageMeta<sensor_msgs::NavSatFix>>>,
-    pub point_cloud_messages: HashMap<ChannelTopic, Vec<McapMessageMeta<sensor_msgs::PointCloud2>>>,
-    pub image_messages: HashMap<ChannelTopic, Vec<McapMessageMeta<sensor_msgs::Image>>>,
-    pub tf_messages: HashMap<ChannelTopic, Vec<McapMessageMeta<ros_messages::tf2_msgs::TFMessage>>>,
-    pub odometry_messages:
-        HashMap<ChannelTopic, Vec<McapMessageMeta<ros_messages::nav_msgs::Odometry>>>,
-    pub visualization_marker_messages:
-        HashMap<ChannelTopic, Vec<McapMessageMeta<ros_messages::visualization_msgs::Marker>>>,
-    pub visualization_marker_array_messages:
-        HashMap<ChannelTopic, Vec<McapMessageMeta<ros_messages::visualization_msgs::MarkerArray>>>,
+    pub messages: HashMap<ChannelTopic, Vec<McapMessageMeta<RawMessage>>>,
 }
 
 impl McapMessagePage {
@@ -72,178 +188,205 @@ impl McapMessagePage {
                     .message
                     .channel
                     .schema
-                    .clone()
+                    .as_ref()
                     .ok_or(ChannelWithoutSchema(
                         current_message.message.channel.topic.as_str().into(),
                     ))?;
+            let raw_message = RawMessage::new(
+                Some(current_channel_schema.name.clone()),
+                current_message.message.data.to_vec(),
+            );
 
-            let ros_message_type = if let Ok(message_type) =
-                RosMessageType::from_str(current_channel_schema.name.as_str())
-            {
-                message_type
-            } else {
-                warn!("Unknown message type {}", current_channel_schema.name);
-                continue;
-            };
-
-            match ros_message_type {
-                RosMessageType::SensorMessagesImu => {
-                    page.imu_messages
-                        .entry(current_message.channel_topic.clone())
-                        .or_default()
-                        .push(convert_message(&current_message)?);
-                }
-                RosMessageType::SensorMessagesNavSatFix => {
-                    page.nav_sat_fix_messages
-                        .entry(current_message.channel_topic.clone())
-                        .or_default()
-                        .push(convert_message(&current_message)?);
-                }
-                RosMessageType::SensorMessagesPointCloud2 => {
-                    page.point_cloud_messages
-                        .entry(current_message.channel_topic.clone())
-                        .or_default()
-                        .push(convert_message(&current_message)?);
-                }
-                RosMessageType::SensorMessagesImage => {
-                    page.image_messages
-                        .entry(current_message.channel_topic.clone())
-                        .or_default()
-                        .push(convert_message(&current_message)?);
-                }
-                RosMessageType::SensorMessagesCameraInfo => {}
-                RosMessageType::Tf2MessagesTFMessage => {
-                    page.tf_messages
-                        .entry(current_message.channel_topic.clone())
-                        .or_default()
-                        .push(convert_message(&current_message)?);
-                }
-                RosMessageType::NavMessagesOdometry => {
-                    page.odometry_messages
-                        .entry(current_message.channel_topic.clone())
-                        .or_default()
-                        .push(convert_message(&current_message)?);
-                }
-                RosMessageType::VisualizationMessagesMarker => {
-                    page.visualization_marker_messages
-                        .entry(current_message.channel_topic.clone())
-                        .or_default()
-                        .push(convert_message(&current_message)?);
-                }
-                RosMessageType::VisualizationMessagesMarkerArray => {
-                    page.visualization_marker_array_messages
-                        .entry(current_message.channel_topic.clone())
-                        .or_default()
-                        .push(convert_message(&current_message)?);
-                }
-            }
+            page.messages
+                .entry(current_message.channel_topic.clone())
+                .or_default()
+                .push(current_message.with_message(raw_message));
         }
 
         Ok(page)
     }
 
+    pub fn combine(pages: Vec<McapMessagePage>) -> Self {
+        pages.into_iter().fold(Self::default(), |mut acc, page| {
+            for (channel_topic, mut messages) in page.messages {
+                acc.messages
+                    .entry(channel_topic)
+                    .or_default()
+                    .append(&mut messages);
+            }
+            acc
+        })
+    }
+
     pub fn get_channel_topics(&self) -> HashSet<ChannelTopic> {
-        self.imu_messages
-            .keys()
-            .chain(self.nav_sat_fix_messages.keys())
-            .chain(self.point_cloud_messages.keys())
-            .chain(self.image_messages.keys())
-            .chain(self.tf_messages.keys())
-            .chain(self.odometry_messages.keys())
-            .chain(self.visualization_marker_messages.keys())
-            .chain(self.visualization_marker_array_messages.keys())
-            .cloned()
-            .collect()
+        self.messages.keys().cloned().collect()
     }
 
-    pub fn point_cloud_frame_ids_by_channel(&self) -> BTreeMap<ChannelTopic, BTreeSet<FrameId>> {
-        self.point_cloud_messages
+    /// Returns the topics of the channels holding messages with the schema of `T`.
+    pub fn get_channel_topics_of<T: RosMessage>(&self) -> HashSet<ChannelTopic> {
+        self.messages
             .iter()
-            .map(|(channel_topic, messages)| {
-                let frame_ids = messages
+            .filter(|(_, messages)| {
+                messages
                     .iter()
-                    .map(|msg| msg.message.header.frame_id.clone().into())
-                    .collect::<BTreeSet<FrameId>>();
-                (channel_topic.clone(), frame_ids)
+                    .any(|x| x.message.schema_name.as_deref() == Some(T::SCHEMA_NAME))
             })
+            .map(|(channel_topic, _)| channel_topic.clone())
             .collect()
     }
 
-    pub fn image_frame_ids_by_channel(&self) -> BTreeMap<ChannelTopic, BTreeSet<FrameId>> {
-        self.image_messages
+    /// Returns the messages with the schema of `T` decoded as `T` grouped by channel, while the
+    /// messages of all other schemas are not decoded.
+    pub fn get_messages<T: RosMessage>(
+        &self,
+    ) -> Result<HashMap<ChannelTopic, Vec<McapMessageMeta<T>>>, Error> {
+        let mut messages: HashMap<ChannelTopic, Vec<McapMessageMeta<T>>> = HashMap::new();
+        for current_channel_topic in self.get_channel_topics_of::<T>() {
+            let current_messages = self.decode_messages_of_channel(&current_channel_topic)?;
+            messages.insert(current_channel_topic, current_messages);
+        }
+
+        Ok(messages)
+    }
+
+    /// Returns the messages of a channel decoded as `T`, or `None` if the channel holds no
+    /// messages with the schema of `T`.
+    pub fn get_messages_of_channel<T: RosMessage>(
+        &self,
+        channel_topic: &ChannelTopic,
+    ) -> Result<Option<Vec<McapMessageMeta<T>>>, Error> {
+        let messages = self.decode_messages_of_channel(channel_topic)?;
+        Ok((!messages.is_empty()).then_some(messages))
+    }
+
+    fn decode_messages_of_channel<T: RosMessage>(
+        &self,
+        channel_topic: &ChannelTopic,
+    ) -> Result<Vec<McapMessageMeta<T>>, Error> {
+        let Some(messages) = self.messages.get(channel_topic) else {
+            return Ok(Vec::new());
+        };
+
+        messages
             .iter()
-            .map(|(channel_topic, messages)| {
-                let frame_ids = messages
-                    .iter()
-                    .map(|msg| msg.message.header.frame_id.clone().into())
-                    .collect::<BTreeSet<FrameId>>();
-                (channel_topic.clone(), frame_ids)
+            .filter_map(|x| {
+                x.message
+                    .deserialize::<T>()
+                    .transpose()
+                    .map(|message| message.map(|message| x.with_message(message)))
             })
             .collect()
     }
-}
 
-fn convert_message<T>(message: &McapMessageMeta<mcap::Message>) -> Result<McapMessageMeta<T>, Error>
-where
-    T: serde::de::DeserializeOwned,
-{
-    let deserialized_message_data = cdr::deserialize::<T>(&message.message.data[..])?;
-
-    let result_message = McapMessageMeta::new(
-        message.file_name.clone(),
-        message.channel_topic.clone(),
-        message.chunk_id,
-        message.message_id,
-        message.log_date_time,
-        message.publish_date_time,
-        deserialized_message_data,
-    );
-    Ok(result_message)
+    pub fn point_cloud_frame_ids_by_channel(
+        &self,
+    ) -> Result<BTreeMap<ChannelTopic, BTreeSet<FrameId>>, Error> {
+        let mut frame_ids =
+            get_frame_ids_by_channel(&self.get_messages::<sensor_msgs::PointCloud2>()?);
+        frame_ids.extend(get_frame_ids_by_channel(
+            &self.get_messages::<sensor_msgs::LaserScan>()?,
+        ));
+        frame_ids.extend(get_frame_ids_by_channel(
+            &self.get_messages::<sensor_msgs::MultiEchoLaserScan>()?,
+        ));
+        Ok(frame_ids)
+    }
+
+    pub fn image_frame_ids_by_channel(
+        &self,
+    ) -> Result<BTreeMap<ChannelTopic, BTreeSet<FrameId>>, Error> {
+        Ok(get_frame_ids_by_channel(
+            &self.get_messages::<sensor_msgs::Image>()?,
+        ))
+    }
 }
 
-impl McapMessagePage {
-    pub fn combine(pages: Vec<McapMessagePage>) -> Self {
-        pages.into_iter().fold(Self::default(), |mut acc, page| {
-            acc.imu_messages = merge_hashmaps(acc.imu_messages, page.imu_messages);
-            acc.nav_sat_fix_messages =
-                merge_hashmaps(acc.nav_sat_fix_messages, page.nav_sat_fix_messages);
-            acc.point_cloud_messages =
-                merge_hashmaps(acc.point_cloud_messages, page.point_cloud_messages);
-            acc.image_messages = merge_hashmaps(acc.image_messages, page.image_messages);
-            acc.tf_messages = merge_hashmaps(acc.tf_messages, page.tf_messages);
-            acc.odometry_messages = merge_hashmaps(acc.odometry_messages, page.odometry_messages);
-            acc.visualization_marker_messages = merge_hashmaps(
-                acc.visualization_marker_messages,
-                page.visualization_marker_messages,
-            );
-            acc.visualization_marker_array_messages = merge_hashmaps(
-                acc.visualization_marker_array_messages,
-                page.visualization_marker_array_messages,
-            );
-            acc
+fn get_frame_ids_by_channel<T: ros_messages::Header>(
+    messages: &HashMap<ChannelTopic, Vec<McapMessageMeta<T>>>,
+) -> BTreeMap<ChannelTopic, BTreeSet<FrameId>> {
+    messages
+        .iter()
+        .map(|(channel_topic, messages)| {
+            let frame_ids = messages
+                .iter()
+                .map(|x| x.message.header().frame_id.clone().into())
+                .collect::<BTreeSet<FrameId>>();
+            (channel_topic.clone(), frame_ids)
         })
-    }
+        .collect()
 }
 
-fn merge_hashmaps<T: Clone>(
-    mut base: HashMap<ChannelTopic, Vec<T>>,
-    other: HashMap<ChannelTopic, Vec<T>>,
-) -> HashMap<ChannelTopic, Vec<T>> {
-    for (channel_id, mut messages) in other {
-        base.entry(channel_id).or_default().append(&mut messages);
+fn insert_data_frames<T: RosMessage + TableRow>(
+    data_frames: &mut HashMap<ChannelTopic, DataFrame>,
+    page: &McapMessagePage,
+    (start_date_time, end_date_time): (&Option<DateTime<Utc>>, &Option<DateTime<Utc>>),
+) -> Result<(), Error> {
+    for (channel_topic, current_messages) in page.get_messages::<T>()? {
+        let current_messages: Vec<&McapMessageMeta<T>> = current_messages
+            .iter()
+            .filter(|x| {
+                start_date_time.is_none_or(|start| start <= x.log_date_time)
+                    && end_date_time.is_none_or(|end| x.log_date_time < end)
+            })
+            .collect();
+        if current_messages.is_empty() {
+            continue;
+        }
+
+        let data_frame = table::messages_to_data_frame(current_messages)?;
+        data_frames.insert(channel_topic, data_frame);
     }
-    base
+
+    Ok(())
 }
 
 impl McapMessagePage {
+    /// Returns a table per channel with one row per message for a time window between
+    /// start_date_time (inclusive) and end_date_time (exclusive), where nested message fields
+    /// are flattened to dotted columns.
+    pub fn get_data_frames(
+        &self,
+        start_date_time: &Option<DateTime<Utc>>,
+        end_date_time: &Option<DateTime<Utc>>,
+    ) -> Result<HashMap<ChannelTopic, DataFrame>, Error> {
+        let time_window = (start_date_time, end_date_time);
+        let mut data_frames: HashMap<ChannelTopic, DataFrame> = HashMap::new();
+        insert_data_frames::<sensor_msgs::Imu>(&mut data_frames, self, time_window)?;
+        insert_data_frames::<sensor_msgs::NavSatFix>(&mut data_frames, self, time_window)?;
+        insert_data_frames::<sensor_msgs::PointCloud2>(&mut data_frames, self, time_window)?;
+        insert_data_frames::<sensor_msgs::Image>(&mut data_frames, self, time_window)?;
+        insert_data_frames::<sensor_msgs::CameraInfo>(&mut data_frames, self, time_window)?;
+        insert_data_frames::<sensor_msgs::LaserScan>(&mut data_frames, self, time_window)?;
+        insert_data_frames::<sensor_msgs::MultiEchoLaserScan>(&mut data_frames, self, time_window)?;
+        insert_data_frames::<tf2_msgs::TFMessage>(&mut data_frames, self, time_window)?;
+        insert_data_frames::<geometry_msgs::PoseStamped>(&mut data_frames, self, time_window)?;
+        insert_data_frames::<geometry_msgs::PoseWithCovarianceStamped>(
+            &mut data_frames,
+            self,
+            time_window,
+        )?;
+        insert_data_frames::<geometry_msgs::TwistStamped>(&mut data_frames, self, time_window)?;
+        insert_data_frames::<nav_msgs::Odometry>(&mut data_frames, self, time_window)?;
+        insert_data_frames::<nav_msgs::Path>(&mut data_frames, self, time_window)?;
+        insert_data_frames::<visualization_msgs::Marker>(&mut data_frames, self, time_window)?;
+        insert_data_frames::<visualization_msgs::MarkerArray>(&mut data_frames, self, time_window)?;
+
+        Ok(data_frames)
+    }
+
     pub fn get_point_cloud_messages_combined(
         &self,
         start_date_time: &Option<DateTime<Utc>>,
         end_date_time: &Option<DateTime<Utc>>,
         channel_topics: &Option<HashSet<ChannelTopic>>,
+        with_source_columns: bool,
     ) -> Result<epoint::PointCloud, Error> {
-        let point_cloud_messages =
-            self.get_point_cloud_messages(start_date_time, end_date_time, channel_topics)?;
+        let point_cloud_messages = self.get_point_cloud_messages(
+            start_date_time,
+            end_date_time,
+            channel_topics,
+            with_source_columns,
+        )?;
         let point_clouds = point_cloud_messages
             .into_par_iter()
             .map(|x| x.message)
@@ -254,81 +397,159 @@ impl McapMessagePage {
         Ok(merged_point_cloud)
     }
 
+    /// Returns the point clouds of optionally selected channels divided into groups, where each
+    /// group is merged into one point cloud with the transforms of this page.
+    ///
+    /// The filters are applied to the point cloud of each message before grouping, where the
+    /// bounding box filters look up the transforms in filter_transform_tree.
+    #[allow(clippy::too_many_arguments)]
+    pub fn get_point_cloud_groups(
+        &self,
+        start_date_time: &Option<DateTime<Utc>>,
+        end_date_time: &Option<DateTime<Utc>>,
+        channel_topics: &Option<HashSet<ChannelTopic>>,
+        filters: &[PointFilter],
+        filter_transform_tree: &TransformTree,
+        grouping: &PointCloudGrouping,
+        with_source_columns: bool,
+    ) -> Result<Vec<PointCloudGroup>, Error> {
+        let point_cloud_messages = self.get_point_cloud_messages(
+            start_date_time,
+            end_date_time,
+            channel_topics,
+            with_source_columns,
+        )?;
+        let point_cloud_messages =
+            filter::apply_filters(point_cloud_messages, filters, filter_transform_tree)?;
+        let transform_tree = self.get_all_transform_tree()?;
+
+        point_cloud_group::group_messages(point_cloud_messages, grouping)?
+            .into_par_iter()
+            .enumerate()
+            .map(
+                |(index, (start_date_time, messages))| -> Result<PointCloudGroup, Error> {
+                    let channel_topic = messages
+                        .iter()
+                        .map(|x| &x.channel_topic)
+                        .all_equal_value()
+                        .ok()
+                        .cloned();
+                    let message_metas: Vec<McapMessageMeta<()>> =
+                        messages.iter().map(|x| x.with_message(())).collect();
+                    let mut point_cloud = epoint::transform::merge(
+                        messages.into_iter().map(|x| x.message).collect(),
+                    )?;
+                    point_cloud.transform_tree = transform_tree.clone();
+
+                    Ok(PointCloudGroup {
+                        index,
+                        channel_topic,
+                        start_date_time,
+                        messages: message_metas,
+                        point_cloud,
+                    })
+                },
+            )
+            .collect()
+    }
+
+    /// Returns the point cloud of each message of optionally selected channels, which
+    /// optionally carry the source columns of [`McapMessageMeta::add_source_columns`].
     pub fn get_point_cloud_messages(
         &self,
         start_date_time: &Option<DateTime<Utc>>,
         end_date_time: &Option<DateTime<Utc>>,
         channel_topics: &Option<HashSet<ChannelTopic>>,
+        with_source_columns: bool,
     ) -> Result<Vec<McapMessageMeta<epoint::PointCloud>>, Error> {
-        let mut messages = if let Some(channel_topics) = channel_topics {
-            channel_topics
-                .iter()
-                .map(|x| {
-                    self.point_cloud_messages.get(x).ok_or(ChannelDoesNotHold(
-                        x.clone(),
-                        RosMessageType::SensorMessagesPointCloud2,
-                    ))
-                })
-                .collect::<Result<Vec<_>, _>>()?
-                .into_iter()
-                .flatten()
-                .collect::<Vec<_>>()
-        } else {
-            self.point_cloud_messages
-                .values()
-                .flat_map(|x| x.iter())
-                .collect()
+        let channel_topics: HashSet<ChannelTopic> = match channel_topics {
+            Some(channel_topics) => channel_topics.clone(),
+            None => self.get_point_cloud_channel_topics(),
+        };
+        let is_within = |x: &DateTime<Utc>| {
+            start_date_time.is_none_or(|start| start <= *x)
+                && end_date_time.is_none_or(|end| *x < end)
         };
 
-        if let Some(start_date_time) = start_date_time {
-            messages.retain(|x| *start_date_time <= x.log_date_time);
+        let mut point_clouds: Vec<McapMessageMeta<epoint::PointCloud>> = Vec::new();
+        for current_channel_topic in channel_topics.iter().sorted() {
+            if let Some(messages) =
+                self.get_messages_of_channel::<sensor_msgs::PointCloud2>(current_channel_topic)?
+            {
+                point_clouds.extend(
+                    messages
+                        .into_iter()
+                        .filter(|x| is_within(&x.log_date_time))
+                        .map(|x| x.map_message(|m| m.into())),
+                );
+            } else if let Some(messages) =
+                self.get_messages_of_channel::<sensor_msgs::LaserScan>(current_channel_topic)?
+            {
+                point_clouds.extend(
+                    messages
+                        .into_iter()
+                        .filter(|x| is_within(&x.log_date_time))
+                        .map(|x| x.try_map_message(epoint::PointCloud::try_from))
+                        .collect::<Result<Vec<_>, Error>>()?,
+                );
+            } else if let Some(messages) = self
+                .get_messages_of_channel::<sensor_msgs::MultiEchoLaserScan>(current_channel_topic)?
+            {
+                point_clouds.extend(
+                    messages
+                        .into_iter()
+                        .filter(|x| is_within(&x.log_date_time))
+                        .map(|x| x.try_map_message(epoint::PointCloud::try_from))
+                        .collect::<Result<Vec<_>, Error>>()?,
+                );
+            } else {
+                return Err(ChannelDoesNotHold(
+                    current_channel_topic.clone(),
+                    RosMessageType::SensorMessagesPointCloud2,
+                ));
+            }
         }
 
-        if let Some(end_date_time) = end_date_time {
-            messages.retain(|x| x.log_date_time < *end_date_time);
+        if with_source_columns {
+            point_clouds
+                .par_iter_mut()
+                .try_for_each(|x| x.add_source_columns())?;
         }
 
-        let point_clouds = messages
-            .into_iter()
-            .map(|x| {
-                let point_cloud: epoint::PointCloud = x.message.clone().into();
-                McapMessageMeta::new(
-                    x.file_name.clone(),
-                    x.channel_topic.clone(),
-                    x.chunk_id,
-                    x.message_id,
-                    x.log_date_time,
-                    x.publish_date_time,
-                    point_cloud,
-                )
-            })
-            .collect();
-
         Ok(point_clouds)
     }
 
+    /// Returns the topics of all channels that can be converted to point clouds.
+    pub fn get_point_cloud_channel_topics(&self) -> HashSet<ChannelTopic> {
+        self.get_channel_topics_of::<sensor_msgs::PointCloud2>()
+            .into_iter()
+            .chain(self.get_channel_topics_of::<sensor_msgs::LaserScan>())
+            .chain(self.get_channel_topics_of::<sensor_msgs::MultiEchoLaserScan>())
+            .collect()
+    }
+
+    /// Returns the merged point cloud of a channel, which optionally carries the source columns
+    /// of [`McapMessageMeta::add_source_columns`].
     pub fn get_point_cloud_of_channel(
         &self,
         channel_topic: &ChannelTopic,
+        with_source_columns: bool,
     ) -> Result<epoint::PointCloud, Error> {
         let point_clouds: Vec<epoint::PointCloud> = self
-            .point_cloud_messages
-            .get(channel_topic)
+            .get_messages_of_channel::<sensor_msgs::PointCloud2>(channel_topic)?
             .ok_or(ChannelDoesNotHold(
                 channel_topic.clone(),
                 RosMessageType::SensorMessagesPointCloud2,
             ))?
             .into_par_iter()
             .map(|x| {
-                let point_cloud: epoint::PointCloud = x.message.clone().into();
-                /*let message_id = vec![x.header. as u32; point_cloud.size()];
-                point_cloud
-                    .point_data
-                    .add_u32_column(RosMessageId.as_str(), message_id)
-                    .expect("Adding the message_id column should work");*/
-                point_cloud
+                let mut point_cloud_message = x.map_message(|m| m.into());
+                if with_source_columns {
+                    point_cloud_message.add_source_columns()?;
+                }
+                Ok(point_cloud_message.message)
             })
-            .collect();
+            .collect::<Result<Vec<_>, Error>>()?;
 
         let merged_point_cloud = epoint::transform::merge(point_clouds)?;
         Ok(merged_point_cloud)
@@ -336,8 +557,8 @@ impl McapMessagePage {
 
     pub fn get_all_images(&self) -> Result<eimage::ImageCollection, Error> {
         let image_series: HashMap<FrameId, ImageSeries> = self
-            .image_messages
-            .keys()
+            .get_channel_topics_of::<sensor_msgs::Image>()
+            .iter()
             .map(|x| {
                 // TODO: this should be the actual frame id and not the channel's topic id
                 let frame_id: ecoord::FrameId = x.to_string().into();
@@ -356,27 +577,102 @@ impl McapMessagePage {
         channel_topic: &ChannelTopic,
     ) -> Result<eimage::ImageSeries, Error> {
         let images: Vec<eimage::Image> = self
-            .image_messages
-            .get(channel_topic)
+            .get_messages_of_channel::<sensor_msgs::Image>(channel_topic)?
             .ok_or(ChannelDoesNotHold(
                 channel_topic.clone(),
                 RosMessageType::SensorMessagesImage,
             ))?
             .into_par_iter()
-            .map(|x| {
-                let image: eimage::Image = x.message.clone().into();
-                image
-            })
+            .map(|x| x.message.into())
             .collect();
 
         let image_series = eimage::ImageSeries::new(images)?;
         Ok(image_series)
     }
 
-    pub fn get_all_transform_tree(&self) -> Result<ecoord::TransformTree, Error> {
+    /// Returns the back-projected point clouds of a depth image channel for a time window between
+    /// start_date_time (inclusive) and end_date_time (exclusive).
+    ///
+    /// For each depth image, the camera info and the optional color image with the closest header
+    /// stamp are used.
+    pub fn get_depth_point_cloud_messages(
+        &self,
+        start_date_time: &Option<DateTime<Utc>>,
+        end_date_time: &Option<DateTime<Utc>>,
+        depth_channel_topic: &ChannelTopic,
+        camera_info_channel_topic: &ChannelTopic,
+        color_channel_topic: &Option<ChannelTopic>,
+        depth_scale: f64,
+    ) -> Result<Vec<McapMessageMeta<epoint::PointCloud>>, Error> {
+        let depth_messages = self
+            .get_messages_of_channel::<sensor_msgs::Image>(depth_channel_topic)?
+            .ok_or(ChannelDoesNotHold(
+                depth_channel_topic.clone(),
+                RosMessageType::SensorMessagesImage,
+            ))?;
+        let camera_infos = self
+            .get_messages_of_channel::<sensor_msgs::CameraInfo>(camera_info_channel_topic)?
+            .ok_or(ChannelDoesNotHold(
+                camera_info_channel_topic.clone(),
+                RosMessageType::SensorMessagesCameraInfo,
+            ))?;
+        let camera_info_messages: BTreeMap<DateTime<Utc>, &sensor_msgs::CameraInfo> = camera_infos
+            .iter()
+            .map(|x| (x.message.header.stamp.into(), &x.message))
+            .collect();
+        let color_images = color_channel_topic
+            .as_ref()
+            .map(|x| {
+                self.get_messages_of_channel::<sensor_msgs::Image>(x)?
+                    .ok_or(ChannelDoesNotHold(
+                        x.clone(),
+                        RosMessageType::SensorMessagesImage,
+                    ))
+            })
+            .transpose()?;
+        let color_messages: Option<BTreeMap<DateTime<Utc>, &sensor_msgs::Image>> =
+            color_images.as_ref().map(|messages| {
+                messages
+                    .iter()
+                    .map(|x| (x.message.header.stamp.into(), &x.message))
+                    .collect()
+            });
+
+        depth_messages
+            .iter()
+            .filter(|x| {
+                start_date_time.is_none_or(|start| start <= x.log_date_time)
+                    && end_date_time.is_none_or(|end| x.log_date_time < end)
+            })
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .map(|x| {
+                let date_time: DateTime<Utc> = x.message.header.stamp.into();
+                let camera_info = get_closest(&camera_info_messages, date_time)
+                    .map(|x| *x.1)
+                    .ok_or(ChannelDoesNotHold(
+                        camera_info_channel_topic.clone(),
+                        RosMessageType::SensorMessagesCameraInfo,
+                    ))?;
+                let color_image = color_messages
+                    .as_ref()
+                    .and_then(|c| get_closest(c, date_time).map(|x| *x.1));
+
+                let point_cloud =
+                    x.message
+                        .back_project_depth(camera_info, depth_scale, color_image)?;
+                Ok(x.with_message(point_cloud))
+            })
+            .collect()
+    }
+
+    /// Returns the transforms of all tf channels grouped by their parent and child frame id.
+    pub fn get_all_timed_transforms(
+        &self,
+    ) -> Result<HashMap<TransformId, Vec<ecoord::TimedTransform>>, Error> {
         let mut timed_transforms: HashMap<TransformId, Vec<ecoord::TimedTransform>> =
             HashMap::new();
-        for current_messages in self.tf_messages.values() {
+        for current_messages in self.get_messages::<tf2_msgs::TFMessage>()?.values() {
             let tf_messages: Vec<geometry_msgs::TransformStamped> = current_messages
                 .iter()
                 .flat_map(|x| x.message.transforms.clone())
@@ -396,6 +692,12 @@ impl McapMessagePage {
             }
         }
 
+        Ok(timed_transforms)
+    }
+
+    pub fn get_all_transform_tree(&self) -> Result<ecoord::TransformTree, Error> {
+        let timed_transforms = self.get_all_timed_transforms()?;
+
         let transform_edges: Vec<ecoord::TransformEdge> = timed_transforms
             .into_par_iter()
             .map(|(transform_id, mut transforms_vec)| {
@@ -416,4 +718,102 @@ impl McapMessagePage {
         let merged_transform_tree = ecoord::TransformTree::new(transform_edges, frames)?;
         Ok(merged_transform_tree)
     }
+
+    /// Returns the poses of a `PoseStamped`, `PoseWithCovarianceStamped`, `Odometry` or `Path`
+    /// channel grouped by the header's frame id.
+    ///
+    /// For `Path` channels, the poses of the latest message are used.
+    pub fn get_pose_timed_transforms(
+        &self,
+        channel_topic: &ChannelTopic,
+    ) -> Result<HashMap<FrameId, Vec<ecoord::TimedTransform>>, Error> {
+        let mut timed_transforms: HashMap<FrameId, Vec<ecoord::TimedTransform>> = HashMap::new();
+        if let Some(messages) =
+            self.get_messages_of_channel::<geometry_msgs::PoseStamped>(channel_topic)?
+        {
+            for current_message in messages {
+                timed_transforms
+                    .entry(current_message.message.header.frame_id.clone().into())
+                    .or_default()
+                    .push((&current_message.message).into());
+            }
+        } else if let Some(messages) =
+            self.get_messages_of_channel::<geometry_msgs::PoseWithCovarianceStamped>(channel_topic)?
+        {
+            for current_message in messages {
+                timed_transforms
+                    .entry(current_message.message.header.frame_id.clone().into())
+                    .or_default()
+                    .push((&current_message.message).into());
+            }
+        } else if let Some(messages) =
+            self.get_messages_of_channel::<nav_msgs::Odometry>(channel_topic)?
+        {
+            for current_message in messages {
+                timed_transforms
+                    .entry(current_message.message.header.frame_id.clone().into())
+                    .or_default()
+                    .push((&current_message.message).into());
+            }
+        } else if let Some(messages) =
+            self.get_messages_of_channel::<nav_msgs::Path>(channel_topic)?
+        {
+            let latest_message = messages
+                .iter()
+                .max_by_key(|x| (x.log_date_time, x.message_id));
+            for current_pose in latest_message.iter().flat_map(|x| &x.message.poses) {
+                timed_transforms
+                    .entry(current_pose.header.frame_id.clone().into())
+                    .or_default()
+                    .push(current_pose.into());
+            }
+        } else {
+            return Err(ChannelDoesNotHold(
+                channel_topic.clone(),
+                RosMessageType::GeometryMessagesPoseStamped,
+            ));
+        }
+
+        Ok(timed_transforms)
+    }
+
+    /// Returns the poses of a `PoseStamped`, `PoseWithCovarianceStamped`, `Odometry` or `Path`
+    /// channel as transform edges from the header's frame id to the provided child frame id.
+    ///
+    /// For `Path` channels, the poses of the latest message are used. Fails, if the poses are
+    /// expressed in multiple frames, since the child frame would then have multiple parents.
+    pub fn get_pose_transform_edges(
+        &self,
+        channel_topic: &ChannelTopic,
+        child_frame_id: FrameId,
+    ) -> Result<Vec<ecoord::TransformEdge>, Error> {
+        let timed_transforms = self.get_pose_timed_transforms(channel_topic)?;
+        if timed_transforms.len() > 1 {
+            let frame_ids = timed_transforms
+                .keys()
+                .map(|x| x.to_string())
+                .sorted()
+                .join(", ");
+            return Err(MultiplePoseFrameIds(channel_topic.clone(), frame_ids));
+        }
+
+        let transform_edges: Vec<ecoord::TransformEdge> = timed_transforms
+            .into_iter()
+            .map(|(parent_frame_id, mut transforms_vec)| {
+                transforms_vec.sort_by_key(|transform| transform.timestamp);
+                transforms_vec.dedup_by_key(|transform| transform.timestamp);
+
+                let dynamic_transform = ecoord::DynamicTransform::new(
+                    parent_frame_id,
+                    child_frame_id.clone(),
+                    Some(InterpolationMethod::Linear),
+                    Some(ExtrapolationMethod::Constant),
+                    transforms_vec,
+                )?;
+                Ok(TransformEdge::Dynamic(dynamic_transform))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(transform_edges)
+    }
 }
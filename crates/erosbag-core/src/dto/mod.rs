@@ -1,6 +1,9 @@
 mod message;
 mod overview;
+mod point_cloud_group;
+mod quality_report;
 mod topics;
+mod transform_report;
 
 #[doc(inline)]
 pub use topics::Topic;
@@ -25,3 +28,39 @@ pub use message::McapMessageMeta;
 
 #[doc(inline)]
 pub use message::McapMessagePage;
+
+#[doc(inline)]
+pub use message::RawMessage;
+
+#[doc(inline)]
+pub use point_cloud_group::PointCloudGroup;
+
+#[doc(inline)]
+pub use point_cloud_group::PointCloudGrouping;
+
+#[doc(inline)]
+pub use point_cloud_group::get_point_cloud_group_index;
+
+#[doc(inline)]
+pub use point_cloud_group::COLUMN_NAME_OUTPUT_FILE_NAME;
+
+#[doc(inline)]
+pub use transform_report::TransformReport;
+
+#[doc(inline)]
+pub use transform_report::TransformIssue;
+
+#[doc(inline)]
+pub use quality_report::QualityReport;
+
+#[doc(inline)]
+pub use quality_report::QualityIssue;
+
+#[doc(inline)]
+pub use quality_report::ChannelQuality;
+
+#[doc(inline)]
+pub use quality_report::DurationStatistics;
+
+#[doc(inline)]
+pub use quality_report::MessageObservation;
@@ -158,6 +158,15 @@ impl McapFileOverview {
             .sum()
     }
 
+    /// Returns the earliest start of the chunks containing messages.
+    pub fn get_start_date_time(&self) -> Option<DateTime<Utc>> {
+        self.chunks
+            .values()
+            .filter(|x| !x.contained_channel.is_empty())
+            .map(|x| x.start_date_time)
+            .min()
+    }
+
     pub fn get_chunk_ids_containing_channel_ids(
         &self,
         start_date_time: &Option<DateTime<Utc>>,
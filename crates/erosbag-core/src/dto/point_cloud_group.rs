@@ -0,0 +1,221 @@
+use crate::dto::McapMessageMeta;
+use crate::table::{CellValue, Row};
+use crate::{ChannelTopic, Error, table};
+use chrono::{DateTime, Duration, Utc};
+use itertools::Itertools;
+use polars::prelude::DataFrame;
+
+pub const COLUMN_NAME_OUTPUT_FILE_NAME: &str = "output_file_name";
+
+/// Grouping of point cloud messages into separately extracted point clouds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointCloudGrouping {
+    /// One point cloud merging all messages of all channels.
+    Combined,
+    /// One point cloud per number of consecutive messages of a channel.
+    MessageCount(usize),
+    /// One point cloud per consecutive time slice of a channel.
+    TimeSlice(Duration),
+    /// One point cloud per window of a channel, where a window starts every step and overlaps
+    /// with the following windows, if the step is shorter than the window.
+    SlidingWindow { window: Duration, step: Duration },
+}
+
+impl PointCloudGrouping {
+    /// Returns one point cloud per message.
+    pub fn per_message() -> Self {
+        Self::MessageCount(1)
+    }
+
+    /// Fails, if the message count, duration, window or step is not positive.
+    pub fn validate(&self) -> Result<(), Error> {
+        let is_valid = match self {
+            PointCloudGrouping::Combined => true,
+            PointCloudGrouping::MessageCount(count) => *count > 0,
+            PointCloudGrouping::TimeSlice(duration) => *duration > Duration::zero(),
+            PointCloudGrouping::SlidingWindow { window, step } => {
+                *window > Duration::zero() && *step > Duration::zero()
+            }
+        };
+        if !is_valid {
+            return Err(Error::InvalidPointCloudGrouping(*self));
+        }
+        Ok(())
+    }
+}
+
+/// Point cloud merged from a group of messages.
+#[derive(Debug, Clone)]
+pub struct PointCloudGroup {
+    /// Index of the group in the order of the channel topics and start date times.
+    pub index: usize,
+    /// Topic of the channel, if all messages were published on the same channel.
+    pub channel_topic: Option<ChannelTopic>,
+    /// Start of the group, which is the start of the time slice or window, or otherwise the log
+    /// time of the first message.
+    pub start_date_time: DateTime<Utc>,
+    /// Metadata of the merged messages ordered by log time.
+    pub messages: Vec<McapMessageMeta<()>>,
+    pub point_cloud: epoint::PointCloud,
+}
+
+impl PointCloudGroup {
+    /// Returns the file stem `<topic>_<index>_<start in nanoseconds>`, where the slashes of the
+    /// topic are replaced by underscores and `combined` is used for groups of multiple channels.
+    ///
+    /// The index keeps the names of overlapping windows with the same start apart.
+    pub fn file_stem(&self) -> String {
+        let topic_name = match &self.channel_topic {
+            Some(channel_topic) => channel_topic
+                .to_string()
+                .trim_start_matches('/')
+                .replace('/', "_"),
+            None => "combined".to_string(),
+        };
+        format!(
+            "{}_{:06}_{}",
+            topic_name,
+            self.index,
+            self.start_date_time
+                .timestamp_nanos_opt()
+                .unwrap_or_default()
+        )
+    }
+}
+
+/// Start of a group of point cloud messages together with its messages.
+type MessageGroup = (DateTime<Utc>, Vec<McapMessageMeta<epoint::PointCloud>>);
+
+/// Returns the messages divided into groups together with the start of each group.
+pub(crate) fn group_messages(
+    messages: Vec<McapMessageMeta<epoint::PointCloud>>,
+    grouping: &PointCloudGrouping,
+) -> Result<Vec<MessageGroup>, Error> {
+    grouping.validate()?;
+    let messages: Vec<McapMessageMeta<epoint::PointCloud>> = messages
+        .into_iter()
+        .sorted_by_key(|x| (x.log_date_time, x.channel_topic.clone(), x.message_id))
+        .collect();
+    if let PointCloudGrouping::Combined = grouping {
+        return Ok(match messages.first() {
+            Some(first_message) => vec![(first_message.log_date_time, messages)],
+            None => Vec::new(),
+        });
+    }
+
+    let mut groups: Vec<MessageGroup> = Vec::new();
+    let channel_messages = messages
+        .into_iter()
+        .into_group_map_by(|x| x.channel_topic.clone());
+    for (_, messages) in channel_messages.into_iter().sorted_by(|a, b| a.0.cmp(&b.0)) {
+        let Some(first_date_time) = messages.first().map(|x| x.log_date_time) else {
+            continue;
+        };
+
+        match grouping {
+            PointCloudGrouping::Combined => unreachable!("handled before"),
+            PointCloudGrouping::MessageCount(count) => {
+                for chunk in &messages.into_iter().chunks(*count) {
+                    let chunk: Vec<McapMessageMeta<epoint::PointCloud>> = chunk.collect();
+                    groups.push((chunk[0].log_date_time, chunk));
+                }
+            }
+            PointCloudGrouping::TimeSlice(duration) => {
+                let duration_nanoseconds = duration.num_nanoseconds().unwrap_or(i64::MAX);
+                let slices = messages.into_iter().chunk_by(|x| {
+                    let offset = (x.log_date_time - first_date_time).num_nanoseconds();
+                    offset.unwrap_or(i64::MAX) / duration_nanoseconds
+                });
+                for (slice_index, slice) in &slices {
+                    let start_date_time = first_date_time
+                        + Duration::nanoseconds(slice_index.saturating_mul(duration_nanoseconds));
+                    groups.push((start_date_time, slice.collect()));
+                }
+            }
+            PointCloudGrouping::SlidingWindow { window, step } => {
+                let mut previous_message_range: Option<(usize, usize)> = None;
+                for window_index in get_window_indices(&messages, first_date_time, *window, *step) {
+                    let window_start_date_time = first_date_time
+                        + Duration::nanoseconds(
+                            window_index.saturating_mul(step.num_nanoseconds().unwrap_or(i64::MAX)),
+                        );
+                    let window_end_date_time = window_start_date_time + *window;
+                    let start_index =
+                        messages.partition_point(|x| x.log_date_time < window_start_date_time);
+                    let end_index =
+                        messages.partition_point(|x| x.log_date_time < window_end_date_time);
+
+                    if start_index < end_index
+                        && previous_message_range != Some((start_index, end_index))
+                    {
+                        groups.push((
+                            window_start_date_time,
+                            messages[start_index..end_index].to_vec(),
+                        ));
+                        previous_message_range = Some((start_index, end_index));
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(groups)
+}
+
+/// Returns the ascending indices of the windows starting every step from first_date_time, at
+/// which a message enters or leaves the window.
+///
+/// The messages of a window only change at these indices, so that the windows in between are
+/// skipped regardless of how short the step is.
+fn get_window_indices(
+    messages: &[McapMessageMeta<epoint::PointCloud>],
+    first_date_time: DateTime<Utc>,
+    window: Duration,
+    step: Duration,
+) -> Vec<i64> {
+    let window_nanoseconds = window.num_nanoseconds().unwrap_or(i64::MAX);
+    let step_nanoseconds = step.num_nanoseconds().unwrap_or(i64::MAX);
+
+    let mut window_indices: Vec<i64> = vec![0];
+    for current_message in messages {
+        let offset = (current_message.log_date_time - first_date_time)
+            .num_nanoseconds()
+            .unwrap_or(i64::MAX);
+        // first window, whose end is after the message
+        let entering_offset = offset.saturating_sub(window_nanoseconds);
+        if entering_offset >= 0 {
+            window_indices.push(entering_offset / step_nanoseconds + 1);
+        }
+        // first window, whose start is after the message
+        window_indices.push(offset / step_nanoseconds + 1);
+    }
+    window_indices.sort_unstable();
+    window_indices.dedup();
+    window_indices
+}
+
+/// Returns the sidecar index with one row per message, which contains the name of the output
+/// file of its group followed by the MCAP metadata.
+pub fn get_point_cloud_group_index(
+    groups: &[PointCloudGroup],
+    extension: &str,
+) -> Result<DataFrame, Error> {
+    let rows: Vec<Row> = groups
+        .iter()
+        .flat_map(|group| {
+            let output_file_name = format!("{}.{}", group.file_stem(), extension);
+            group.messages.iter().map(move |message| {
+                let mut row = Row::default();
+                row.push(
+                    COLUMN_NAME_OUTPUT_FILE_NAME,
+                    CellValue::String(output_file_name.clone()),
+                );
+                table::write_meta_columns(message, &mut row);
+                row
+            })
+        })
+        .collect();
+
+    let data_frame = table::rows_to_data_frame(&rows)?;
+    Ok(data_frame)
+}
@@ -0,0 +1,443 @@
+use crate::ChannelTopic;
+use crate::ros_messages::{AnyMessage, RosMessageType};
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+/// Length of the encapsulation header of CDR serialized data, which is all an empty message
+/// consists of.
+const CDR_ENCAPSULATION_HEADER_LENGTH: usize = 4;
+
+/// Interval as multiple of the expected interval, above which messages are considered dropped.
+const DROP_INTERVAL_FACTOR: f64 = 1.5;
+
+/// Properties of a single message, from which the quality of its channel is derived.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageObservation {
+    pub log_date_time: DateTime<Utc>,
+    pub header_stamp: Option<DateTime<Utc>>,
+    pub is_empty: bool,
+    /// Expected and actual length of the data of a point cloud or image, if they differ.
+    pub data_length_mismatch: Option<(usize, usize)>,
+    /// Width and height of an image.
+    pub image_size: Option<(u32, u32)>,
+    /// Reason, why the message of a supported type could not be decoded.
+    pub decoding_error: Option<String>,
+}
+
+impl MessageObservation {
+    /// Observes the serialized data of a message and the decoded message, if its type is
+    /// supported.
+    pub fn new(log_date_time: DateTime<Utc>, data: &[u8], message: Option<&AnyMessage>) -> Self {
+        let header_stamp = message
+            .and_then(|x| x.header())
+            .map(|x| DateTime::<Utc>::from(x.stamp));
+        let mut observation = Self {
+            log_date_time,
+            header_stamp,
+            is_empty: data.len() <= CDR_ENCAPSULATION_HEADER_LENGTH,
+            data_length_mismatch: None,
+            image_size: None,
+            decoding_error: None,
+        };
+
+        match message {
+            Some(AnyMessage::PointCloud2(x)) => {
+                let min_row_step = x.point_step as usize * x.width as usize;
+                let row_step = x.row_step as usize;
+                let expected_length = row_step.max(min_row_step) * x.height as usize;
+                observation.is_empty |= x.width == 0 || x.height == 0;
+                if row_step < min_row_step || expected_length != x.data.len() {
+                    observation.data_length_mismatch = Some((expected_length, x.data.len()));
+                }
+            }
+            Some(AnyMessage::Image(x)) => {
+                let expected_length = x.step as usize * x.height as usize;
+                observation.is_empty |= x.data.is_empty();
+                observation.image_size = Some((x.width, x.height));
+                if expected_length != x.data.len() {
+                    observation.data_length_mismatch = Some((expected_length, x.data.len()));
+                }
+            }
+            _ => {}
+        }
+
+        observation
+    }
+
+    pub fn with_decoding_error(mut self, decoding_error: Option<String>) -> Self {
+        self.decoding_error = decoding_error;
+        self
+    }
+}
+
+/// Result of a data quality check of the channels of a ROS2 bag.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QualityReport {
+    pub channels: Vec<ChannelQuality>,
+    pub issues: Vec<QualityIssue>,
+}
+
+impl QualityReport {
+    /// Checks the observed messages of each channel, which are in the order they are stored in
+    /// the bag.
+    ///
+    /// The expected rate of a channel is derived from the median interval between consecutive
+    /// log times, so that intervals considerably longer than it indicate dropped messages.
+    pub fn new(
+        observations: &BTreeMap<ChannelTopic, (Option<RosMessageType>, Vec<MessageObservation>)>,
+        max_gap_duration: chrono::Duration,
+    ) -> Self {
+        let mut channels: Vec<ChannelQuality> = Vec::new();
+        let mut issues: Vec<QualityIssue> = Vec::new();
+
+        for (channel_topic, (message_type, messages)) in observations {
+            let channel_issues = find_issues(channel_topic, messages, max_gap_duration);
+
+            let log_date_times: Vec<DateTime<Utc>> =
+                messages.iter().map(|x| x.log_date_time).sorted().collect();
+            let intervals: Vec<f64> = log_date_times
+                .iter()
+                .tuple_windows()
+                .map(|(a, b)| seconds(*b - *a))
+                .collect();
+            let interval_statistics = DurationStatistics::new(&intervals);
+            let expected_interval = interval_statistics.map(|x| x.median).filter(|x| *x > 0.0);
+            let duration = match (log_date_times.first(), log_date_times.last()) {
+                (Some(start), Some(end)) => seconds(*end - *start),
+                _ => 0.0,
+            };
+            let estimated_dropped_messages = expected_interval.map_or(0, |expected| {
+                intervals
+                    .iter()
+                    .filter(|x| **x > expected * DROP_INTERVAL_FACTOR)
+                    .map(|x| (x / expected).round() as usize - 1)
+                    .sum()
+            });
+            let latencies: Vec<f64> = messages
+                .iter()
+                .filter_map(|x| x.header_stamp.map(|stamp| seconds(x.log_date_time - stamp)))
+                .collect();
+
+            let count_issues = |matches: fn(&QualityIssue) -> bool| {
+                channel_issues.iter().filter(|x| matches(x)).count()
+            };
+            channels.push(ChannelQuality {
+                channel_topic: channel_topic.clone(),
+                message_type: message_type.map(|x| x.as_str().to_string()),
+                message_count: messages.len(),
+                start_date_time: log_date_times.first().copied(),
+                end_date_time: log_date_times.last().copied(),
+                expected_rate: expected_interval.map(|x| 1.0 / x),
+                actual_rate: (duration > 0.0).then(|| intervals.len() as f64 / duration),
+                estimated_dropped_messages,
+                interval_statistics,
+                latency_statistics: DurationStatistics::new(&latencies),
+                gap_count: count_issues(|x| matches!(x, QualityIssue::Gap { .. })),
+                out_of_order_count: count_issues(|x| {
+                    matches!(x, QualityIssue::OutOfOrderLogTime { .. })
+                }),
+                duplicate_header_stamp_count: count_issues(|x| {
+                    matches!(x, QualityIssue::DuplicateHeaderStamp { .. })
+                }),
+                empty_message_count: count_issues(|x| {
+                    matches!(x, QualityIssue::EmptyMessage { .. })
+                }),
+                inconsistent_size_count: count_issues(|x| {
+                    matches!(
+                        x,
+                        QualityIssue::InconsistentDataLength { .. }
+                            | QualityIssue::ImageSizeChange { .. }
+                    )
+                }),
+                undecodable_message_count: count_issues(|x| {
+                    matches!(x, QualityIssue::UndecodableMessage { .. })
+                }),
+            });
+            issues.extend(channel_issues);
+        }
+
+        Self { channels, issues }
+    }
+
+    /// Returns true, if no issues were found.
+    pub fn is_consistent(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+fn find_issues(
+    channel_topic: &ChannelTopic,
+    messages: &[MessageObservation],
+    max_gap_duration: chrono::Duration,
+) -> Vec<QualityIssue> {
+    let mut issues: Vec<QualityIssue> = Vec::new();
+
+    for (previous, current) in messages.iter().tuple_windows() {
+        if current.log_date_time < previous.log_date_time {
+            issues.push(QualityIssue::OutOfOrderLogTime {
+                channel_topic: channel_topic.clone(),
+                previous_date_time: previous.log_date_time,
+                date_time: current.log_date_time,
+            });
+        }
+    }
+
+    let log_date_times: Vec<DateTime<Utc>> =
+        messages.iter().map(|x| x.log_date_time).sorted().collect();
+    for (start_date_time, end_date_time) in log_date_times.iter().tuple_windows() {
+        if *end_date_time - *start_date_time > max_gap_duration {
+            issues.push(QualityIssue::Gap {
+                channel_topic: channel_topic.clone(),
+                start_date_time: *start_date_time,
+                end_date_time: *end_date_time,
+            });
+        }
+    }
+
+    let mut header_stamps: HashSet<DateTime<Utc>> = HashSet::new();
+    let mut previous_image_size: Option<(u32, u32)> = None;
+    for current_message in messages {
+        if let Some(stamp) = current_message.header_stamp
+            && !header_stamps.insert(stamp)
+        {
+            issues.push(QualityIssue::DuplicateHeaderStamp {
+                channel_topic: channel_topic.clone(),
+                log_date_time: current_message.log_date_time,
+                stamp,
+            });
+        }
+        if let Some(error) = &current_message.decoding_error {
+            issues.push(QualityIssue::UndecodableMessage {
+                channel_topic: channel_topic.clone(),
+                log_date_time: current_message.log_date_time,
+                error: error.clone(),
+            });
+        }
+        if current_message.is_empty {
+            issues.push(QualityIssue::EmptyMessage {
+                channel_topic: channel_topic.clone(),
+                log_date_time: current_message.log_date_time,
+            });
+        }
+        if let Some((expected_length, actual_length)) = current_message.data_length_mismatch {
+            issues.push(QualityIssue::InconsistentDataLength {
+                channel_topic: channel_topic.clone(),
+                log_date_time: current_message.log_date_time,
+                expected_length,
+                actual_length,
+            });
+        }
+        if let Some(image_size) = current_message.image_size {
+            if let Some(previous_image_size) = previous_image_size
+                && previous_image_size != image_size
+            {
+                issues.push(QualityIssue::ImageSizeChange {
+                    channel_topic: channel_topic.clone(),
+                    log_date_time: current_message.log_date_time,
+                    previous_width: previous_image_size.0,
+                    previous_height: previous_image_size.1,
+                    width: image_size.0,
+                    height: image_size.1,
+                });
+            }
+            previous_image_size = Some(image_size);
+        }
+    }
+
+    issues
+}
+
+fn seconds(duration: chrono::Duration) -> f64 {
+    duration
+        .num_nanoseconds()
+        .map_or(f64::MAX, |x| x as f64 / 1e9)
+}
+
+/// Quality of the messages of a single channel.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChannelQuality {
+    pub channel_topic: ChannelTopic,
+    /// Name of the message type, if it is supported.
+    pub message_type: Option<String>,
+    pub message_count: usize,
+    pub start_date_time: Option<DateTime<Utc>>,
+    pub end_date_time: Option<DateTime<Utc>>,
+    /// Rate in Hz given by the median interval between consecutive log times.
+    pub expected_rate: Option<f64>,
+    /// Average rate in Hz between the first and the last log time.
+    pub actual_rate: Option<f64>,
+    /// Number of messages missing in the intervals that exceed the expected interval.
+    pub estimated_dropped_messages: usize,
+    /// Distribution of the intervals between consecutive log times, whose standard deviation is
+    /// the jitter.
+    pub interval_statistics: Option<DurationStatistics>,
+    /// Distribution of the delays from the header stamps to the log times.
+    pub latency_statistics: Option<DurationStatistics>,
+    pub gap_count: usize,
+    pub out_of_order_count: usize,
+    pub duplicate_header_stamp_count: usize,
+    pub empty_message_count: usize,
+    pub inconsistent_size_count: usize,
+    pub undecodable_message_count: usize,
+}
+
+/// Distribution of durations in seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct DurationStatistics {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub median: f64,
+    pub percentile_95: f64,
+    pub standard_deviation: f64,
+}
+
+impl DurationStatistics {
+    /// Returns the statistics of the durations in seconds, or `None` if there are none.
+    pub fn new(durations: &[f64]) -> Option<Self> {
+        if durations.is_empty() {
+            return None;
+        }
+        let sorted: Vec<f64> = durations
+            .iter()
+            .copied()
+            .sorted_by(f64::total_cmp)
+            .collect();
+        let count = sorted.len();
+        let mean = sorted.iter().sum::<f64>() / count as f64;
+        let variance = sorted.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / count as f64;
+        let percentile = |p: f64| sorted[((count as f64 * p).ceil() as usize).clamp(1, count) - 1];
+
+        Some(Self {
+            count,
+            min: sorted[0],
+            max: sorted[count - 1],
+            mean,
+            median: sorted[count / 2],
+            percentile_95: percentile(0.95),
+            standard_deviation: variance.sqrt(),
+        })
+    }
+}
+
+/// Single data quality problem detected in a channel of a ROS2 bag.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum QualityIssue {
+    /// Two consecutive log times are further apart than the threshold.
+    Gap {
+        channel_topic: ChannelTopic,
+        start_date_time: DateTime<Utc>,
+        end_date_time: DateTime<Utc>,
+    },
+    /// A log time is earlier than the log time of the previously stored message.
+    OutOfOrderLogTime {
+        channel_topic: ChannelTopic,
+        previous_date_time: DateTime<Utc>,
+        date_time: DateTime<Utc>,
+    },
+    /// The header stamp was already used by a previous message.
+    DuplicateHeaderStamp {
+        channel_topic: ChannelTopic,
+        log_date_time: DateTime<Utc>,
+        stamp: DateTime<Utc>,
+    },
+    /// The message has no data, or a point cloud or image has no points or pixels.
+    EmptyMessage {
+        channel_topic: ChannelTopic,
+        log_date_time: DateTime<Utc>,
+    },
+    /// The data length of a point cloud or image differs from the length given by its size.
+    InconsistentDataLength {
+        channel_topic: ChannelTopic,
+        log_date_time: DateTime<Utc>,
+        expected_length: usize,
+        actual_length: usize,
+    },
+    /// The size of an image differs from the size of the previous image.
+    ImageSizeChange {
+        channel_topic: ChannelTopic,
+        log_date_time: DateTime<Utc>,
+        previous_width: u32,
+        previous_height: u32,
+        width: u32,
+        height: u32,
+    },
+    /// The message of a supported type cannot be decoded with the definition of its type.
+    UndecodableMessage {
+        channel_topic: ChannelTopic,
+        log_date_time: DateTime<Utc>,
+        error: String,
+    },
+}
+
+impl fmt::Display for QualityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QualityIssue::Gap {
+                channel_topic,
+                start_date_time,
+                end_date_time,
+            } => write!(
+                f,
+                "channel `{channel_topic}` has a gap of {} between {start_date_time} and {end_date_time}",
+                *end_date_time - *start_date_time
+            ),
+            QualityIssue::OutOfOrderLogTime {
+                channel_topic,
+                previous_date_time,
+                date_time,
+            } => write!(
+                f,
+                "log time on channel `{channel_topic}` jumps back from {previous_date_time} to {date_time}"
+            ),
+            QualityIssue::DuplicateHeaderStamp {
+                channel_topic,
+                log_date_time,
+                stamp,
+            } => write!(
+                f,
+                "message on channel `{channel_topic}` at {log_date_time} repeats the header stamp {stamp}"
+            ),
+            QualityIssue::EmptyMessage {
+                channel_topic,
+                log_date_time,
+            } => write!(
+                f,
+                "message on channel `{channel_topic}` at {log_date_time} is empty"
+            ),
+            QualityIssue::InconsistentDataLength {
+                channel_topic,
+                log_date_time,
+                expected_length,
+                actual_length,
+            } => write!(
+                f,
+                "message on channel `{channel_topic}` at {log_date_time} has {actual_length} bytes of data instead of {expected_length}"
+            ),
+            QualityIssue::ImageSizeChange {
+                channel_topic,
+                log_date_time,
+                previous_width,
+                previous_height,
+                width,
+                height,
+            } => write!(
+                f,
+                "image size on channel `{channel_topic}` changes from {previous_width}x{previous_height} to {width}x{height} at {log_date_time}"
+            ),
+            QualityIssue::UndecodableMessage {
+                channel_topic,
+                log_date_time,
+                error,
+            } => write!(
+                f,
+                "message on channel `{channel_topic}` at {log_date_time} cannot be decoded: {error}"
+            ),
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use crate::ChannelTopic;
+use crate::dto::McapMessageMeta;
+use crate::ros_messages::tf2_msgs;
+use chrono::{DateTime, Utc};
+use ecoord::FrameId;
+use itertools::Itertools;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use tracing::warn;
+
+/// Result of a consistency check of the tf data contained in a ROS2 bag.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransformReport {
+    /// Frame, from which the sensor frames were checked for reachability.
+    pub target_frame_id: Option<FrameId>,
+    pub frame_ids: BTreeSet<FrameId>,
+    pub issues: Vec<TransformIssue>,
+}
+
+impl TransformReport {
+    /// Checks the provided tf messages for structural and temporal problems.
+    ///
+    /// Frames listed in `sensor_frame_ids` are checked for reachability from `target_frame_id`,
+    /// which defaults to the root of the largest subtree. Gaps are only checked for the
+    /// transforms of dynamic channels, since static transforms are published once.
+    pub fn new(
+        tf_messages: &HashMap<ChannelTopic, Vec<McapMessageMeta<tf2_msgs::TFMessage>>>,
+        sensor_frame_ids: &BTreeMap<ChannelTopic, BTreeSet<FrameId>>,
+        target_frame_id: Option<FrameId>,
+        max_gap_duration: chrono::Duration,
+    ) -> Self {
+        let mut issues: Vec<TransformIssue> = Vec::new();
+        let mut stamps_per_edge: BTreeMap<(FrameId, FrameId), Vec<DateTime<Utc>>> = BTreeMap::new();
+        let mut dynamic_stamps_per_edge: BTreeMap<(FrameId, FrameId), Vec<DateTime<Utc>>> =
+            BTreeMap::new();
+
+        for (channel_topic, messages) in tf_messages.iter().sorted_by_key(|x| x.0) {
+            let mut last_stamp_per_edge: HashMap<(FrameId, FrameId), DateTime<Utc>> =
+                HashMap::new();
+
+            let transforms = messages
+                .iter()
+                .sorted_by_key(|x| (x.log_date_time, x.message_id))
+                .flat_map(|x| x.message.transforms.iter());
+            for current_transform in transforms {
+                let edge: (FrameId, FrameId) = (
+                    current_transform.header.frame_id.clone().into(),
+                    current_transform.child_frame_id.clone().into(),
+                );
+                let stamp: DateTime<Utc> = current_transform.header.stamp.into();
+
+                if let Some(previous_stamp) = last_stamp_per_edge.insert(edge.clone(), stamp)
+                    && stamp < previous_stamp
+                {
+                    issues.push(TransformIssue::NonMonotonicTimestamp {
+                        channel_topic: channel_topic.clone(),
+                        parent_frame_id: edge.0.clone(),
+                        child_frame_id: edge.1.clone(),
+                        previous_date_time: previous_stamp,
+                        date_time: stamp,
+                    });
+                }
+                if !channel_topic.is_static_transform_topic() {
+                    dynamic_stamps_per_edge
+                        .entry(edge.clone())
+                        .or_default()
+                        .push(stamp);
+                }
+                stamps_per_edge.entry(edge).or_default().push(stamp);
+            }
+        }
+
+        for ((parent_frame_id, child_frame_id), stamps) in dynamic_stamps_per_edge.iter() {
+            let stamps: Vec<DateTime<Utc>> = stamps.iter().copied().sorted().dedup().collect();
+            for (start_date_time, end_date_time) in stamps.iter().tuple_windows() {
+                if *end_date_time - *start_date_time > max_gap_duration {
+                    issues.push(TransformIssue::DynamicTransformGap {
+                        parent_frame_id: parent_frame_id.clone(),
+                        child_frame_id: child_frame_id.clone(),
+                        start_date_time: *start_date_time,
+                        end_date_time: *end_date_time,
+                    });
+                }
+            }
+        }
+
+        let mut parents_per_child: BTreeMap<FrameId, BTreeSet<FrameId>> = BTreeMap::new();
+        for (parent_frame_id, child_frame_id) in stamps_per_edge.keys() {
+            parents_per_child
+                .entry(child_frame_id.clone())
+                .or_default()
+                .insert(parent_frame_id.clone());
+        }
+        for (child_frame_id, parent_frame_ids) in parents_per_child.iter() {
+            if parent_frame_ids.len() > 1 {
+                issues.push(TransformIssue::MultipleParents {
+                    child_frame_id: child_frame_id.clone(),
+                    parent_frame_ids: parent_frame_ids.clone(),
+                });
+            }
+        }
+
+        issues.extend(
+            find_cycles(&parents_per_child)
+                .into_iter()
+                .map(|frame_ids| TransformIssue::Cycle { frame_ids }),
+        );
+
+        let frame_ids: BTreeSet<FrameId> = stamps_per_edge
+            .keys()
+            .flat_map(|(parent, child)| [parent.clone(), child.clone()])
+            .collect();
+
+        let frame_ids_by_stripped_name: BTreeMap<String, BTreeSet<FrameId>> = frame_ids
+            .iter()
+            .chain(sensor_frame_ids.values().flatten())
+            .map(|x| (x.to_string().trim_start_matches('/').to_string(), x.clone()))
+            .into_group_map()
+            .into_iter()
+            .map(|(name, ids)| (name, ids.into_iter().collect()))
+            .collect();
+        for current_frame_ids in frame_ids_by_stripped_name.into_values() {
+            if current_frame_ids.len() > 1 {
+                issues.push(TransformIssue::LeadingSlashMismatch {
+                    frame_ids: current_frame_ids,
+                });
+            }
+        }
+
+        let components = find_connected_components(&stamps_per_edge);
+        let main_component_index: Option<usize> = match &target_frame_id {
+            Some(target_frame_id) => components.iter().position(|x| x.contains(target_frame_id)),
+            None => components.iter().position_max_by_key(|x| x.len()),
+        };
+        for (current_index, current_component) in components.iter().enumerate() {
+            if Some(current_index) == main_component_index {
+                continue;
+            }
+            issues.push(TransformIssue::DisconnectedSubtree {
+                frame_ids: current_component.clone(),
+            });
+        }
+
+        let target_frame_id: Option<FrameId> = target_frame_id.or_else(|| {
+            let root_frame_id = main_component_index.and_then(|i| {
+                components[i]
+                    .iter()
+                    .find(|x| !parents_per_child.contains_key(*x))
+                    .cloned()
+            });
+            if root_frame_id.is_none() && !sensor_frame_ids.is_empty() {
+                warn!(
+                    "No root frame found, so the reachability of the sensor frames is not checked"
+                );
+            }
+            root_frame_id
+        });
+        if let Some(target_frame_id) = &target_frame_id {
+            let reachable_frame_ids: BTreeSet<FrameId> = main_component_index
+                .map(|i| components[i].clone())
+                .unwrap_or_else(|| BTreeSet::from([target_frame_id.clone()]));
+
+            for (channel_topic, current_frame_ids) in sensor_frame_ids {
+                for current_frame_id in current_frame_ids {
+                    if !reachable_frame_ids.contains(current_frame_id) {
+                        issues.push(TransformIssue::UnreachableSensorFrame {
+                            channel_topic: channel_topic.clone(),
+                            frame_id: current_frame_id.clone(),
+                            target_frame_id: target_frame_id.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        Self {
+            target_frame_id,
+            frame_ids,
+            issues,
+        }
+    }
+
+    /// Returns true, if no issues were found.
+    pub fn is_consistent(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Single problem detected in the tf data of a ROS2 bag.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransformIssue {
+    /// A child frame is attached to more than one parent frame.
+    MultipleParents {
+        child_frame_id: FrameId,
+        parent_frame_ids: BTreeSet<FrameId>,
+    },
+    /// The frames form a cycle when following the parent relation.
+    Cycle { frame_ids: Vec<FrameId> },
+    /// The frames are not connected to the target frame or the largest subtree.
+    DisconnectedSubtree { frame_ids: BTreeSet<FrameId> },
+    /// Two consecutive stamps of a dynamic transform are further apart than the threshold.
+    DynamicTransformGap {
+        parent_frame_id: FrameId,
+        child_frame_id: FrameId,
+        start_date_time: DateTime<Utc>,
+        end_date_time: DateTime<Utc>,
+    },
+    /// A transform stamp is earlier than the previous stamp of the same edge on a channel.
+    NonMonotonicTimestamp {
+        channel_topic: ChannelTopic,
+        parent_frame_id: FrameId,
+        child_frame_id: FrameId,
+        previous_date_time: DateTime<Utc>,
+        date_time: DateTime<Utc>,
+    },
+    /// The frame ids only differ by a leading `/`.
+    LeadingSlashMismatch { frame_ids: BTreeSet<FrameId> },
+    /// A frame id referenced by a sensor message cannot be reached from the target frame.
+    UnreachableSensorFrame {
+        channel_topic: ChannelTopic,
+        frame_id: FrameId,
+        target_frame_id: FrameId,
+    },
+}
+
+impl fmt::Display for TransformIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TransformIssue::MultipleParents {
+                child_frame_id,
+                parent_frame_ids,
+            } => write!(
+                f,
+                "frame `{child_frame_id}` has multiple parents: {}",
+                parent_frame_ids.iter().join(", ")
+            ),
+            TransformIssue::Cycle { frame_ids } => {
+                write!(f, "cycle between frames: {}", frame_ids.iter().join(" -> "))
+            }
+            TransformIssue::DisconnectedSubtree { frame_ids } => {
+                write!(f, "disconnected subtree: {}", frame_ids.iter().join(", "))
+            }
+            TransformIssue::DynamicTransformGap {
+                parent_frame_id,
+                child_frame_id,
+                start_date_time,
+                end_date_time,
+            } => write!(
+                f,
+                "transform `{parent_frame_id}` -> `{child_frame_id}` has a gap of {} between {start_date_time} and {end_date_time}",
+                *end_date_time - *start_date_time
+            ),
+            TransformIssue::NonMonotonicTimestamp {
+                channel_topic,
+                parent_frame_id,
+                child_frame_id,
+                previous_date_time,
+                date_time,
+            } => write!(
+                f,
+                "transform `{parent_frame_id}` -> `{child_frame_id}` on channel `{channel_topic}` jumps back from {previous_date_time} to {date_time}"
+            ),
+            TransformIssue::LeadingSlashMismatch { frame_ids } => write!(
+                f,
+                "frame ids differ only by a leading slash: {}",
+                frame_ids.iter().join(", ")
+            ),
+            TransformIssue::UnreachableSensorFrame {
+                channel_topic,
+                frame_id,
+                target_frame_id,
+            } => write!(
+                f,
+                "frame `{frame_id}` of channel `{channel_topic}` is not reachable from target frame `{target_frame_id}`"
+            ),
+        }
+    }
+}
+
+fn find_cycles(parents_per_child: &BTreeMap<FrameId, BTreeSet<FrameId>>) -> Vec<Vec<FrameId>> {
+    let mut cycles: Vec<Vec<FrameId>> = Vec::new();
+    let mut finished: BTreeSet<FrameId> = BTreeSet::new();
+
+    for start_frame_id in parents_per_child.keys() {
+        let mut path: Vec<FrameId> = Vec::new();
+        let mut stack: Vec<(FrameId, usize)> = vec![(start_frame_id.clone(), 0)];
+
+        while let Some((current_frame_id, depth)) = stack.pop() {
+            path.truncate(depth);
+            if finished.contains(&current_frame_id) {
+                continue;
+            }
+            if let Some(position) = path.iter().position(|x| x == &current_frame_id) {
+                let cycle: Vec<FrameId> = path[position..].to_vec();
+                if !cycles.iter().any(|x| is_same_cycle(x, &cycle)) {
+                    cycles.push(cycle);
+                }
+                continue;
+            }
+
+            path.push(current_frame_id.clone());
+            if let Some(parent_frame_ids) = parents_per_child.get(&current_frame_id) {
+                stack.extend(parent_frame_ids.iter().map(|x| (x.clone(), depth + 1)));
+            }
+        }
+
+        finished.insert(start_frame_id.clone());
+    }
+
+    cycles
+}
+
+fn is_same_cycle(a: &[FrameId], b: &[FrameId]) -> bool {
+    a.len() == b.len() && a.iter().collect::<BTreeSet<_>>() == b.iter().collect::<BTreeSet<_>>()
+}
+
+fn find_connected_components(
+    edges: &BTreeMap<(FrameId, FrameId), Vec<DateTime<Utc>>>,
+) -> Vec<BTreeSet<FrameId>> {
+    let mut neighbours: BTreeMap<&FrameId, BTreeSet<&FrameId>> = BTreeMap::new();
+    for (parent_frame_id, child_frame_id) in edges.keys() {
+        neighbours
+            .entry(parent_frame_id)
+            .or_default()
+            .insert(child_frame_id);
+        neighbours
+            .entry(child_frame_id)
+            .or_default()
+            .insert(parent_frame_id);
+    }
+
+    let mut components: Vec<BTreeSet<FrameId>> = Vec::new();
+    let mut visited: BTreeSet<&FrameId> = BTreeSet::new();
+    for start_frame_id in neighbours.keys() {
+        if !visited.insert(start_frame_id) {
+            continue;
+        }
+
+        let mut component: BTreeSet<FrameId> = BTreeSet::new();
+        let mut stack: Vec<&FrameId> = vec![start_frame_id];
+        while let Some(current_frame_id) = stack.pop() {
+            component.insert(current_frame_id.clone());
+            for next_frame_id in &neighbours[current_frame_id] {
+                if visited.insert(next_frame_id) {
+                    stack.push(next_frame_id);
+                }
+            }
+        }
+        components.push(component);
+    }
+
+    components
+}
@@ -1,5 +1,8 @@
+use crate::dto::PointCloudGrouping;
+use crate::filter::PointFilter;
 use crate::identifier::ChannelId;
 use crate::ros_messages::RosMessageType;
+use crate::sync::SyncPolicy;
 use crate::{ChannelTopic, ChunkId, FileName};
 use thiserror::Error;
 
@@ -23,6 +26,18 @@ pub enum Error {
     CdrError(#[from] cdr::Error),
     #[error(transparent)]
     McapError(#[from] mcap::McapError),
+    #[error(transparent)]
+    PolarsError(#[from] polars::error::PolarsError),
+    #[error(transparent)]
+    RusqliteError(#[from] rusqlite::Error),
+    #[error(transparent)]
+    SerdeYamlError(#[from] serde_yaml::Error),
+    #[error(transparent)]
+    UreqError(#[from] ureq::Error),
+    #[error(transparent)]
+    TungsteniteError(#[from] tungstenite::Error),
+    #[error(transparent)]
+    SerdeJsonError(#[from] serde_json::Error),
 
     #[error("Invalid combinations of open options")]
     InvalidInput,
@@ -30,7 +45,7 @@ pub enum Error {
     #[error("path is not a directory")]
     RosbagPathIsNoDirectory,
 
-    #[error("path is not a directory")]
+    #[error("directory path contains no bag file")]
     ContainsNoRosbagFile,
 
     #[error("directory path contains no mcap file")]
@@ -53,6 +68,58 @@ pub enum Error {
 
     #[error("channel with id `{0}` does not hold messages of type `{1}`")]
     ChannelDoesNotHold(ChannelTopic, RosMessageType),
+    #[error("channel with topic `{0}` does not hold messages with schema `{1}`")]
+    ChannelDoesNotHoldSchema(ChannelTopic, String),
+    #[error("channel with topic `{0}` holds poses in multiple frames: {1}")]
+    MultiplePoseFrameIds(ChannelTopic, String),
+
+    #[error("point cloud grouping `{0:?}` requires a positive message count, duration and step")]
+    InvalidPointCloudGrouping(PointCloudGrouping),
+    #[error("point filter `{0:?}` requires a non-empty range and a positive message count")]
+    InvalidPointFilter(PointFilter),
+    #[error("voxel size must be positive, got `{0}`")]
+    InvalidVoxelSize(f64),
+    #[error("sync policy `{0:?}` requires a positive queue size")]
+    InvalidSyncPolicy(SyncPolicy),
+
+    #[error("image encoding `{0}` is not supported")]
+    UnsupportedImageEncoding(String),
+    #[error("image data is invalid: {0}")]
+    InvalidImageData(String),
+    #[error("laser scan data is invalid: {0}")]
+    InvalidLaserScanData(String),
+
+    #[error("invalid ROS1 bag: {0}")]
+    InvalidRos1Bag(String),
+    #[error("compression `{0}` of ROS1 bag is not supported")]
+    UnsupportedRos1Compression(String),
+    #[error("message data of type `{0}` is invalid: {1}")]
+    InvalidRos1MessageData(String, String),
+    #[error("message definition of `{0}` is invalid: {1}")]
+    InvalidMessageDefinition(String, String),
+
+    #[error("file compression `{0}` is not supported")]
+    UnsupportedFileCompression(String),
+    #[error("storage format of file `{0}` is not supported")]
+    UnsupportedStorageFile(String),
+    #[error("invalid SQLite3 file: {0}")]
+    InvalidSqlite3File(String),
+    #[error("input and output directory must differ")]
+    SameInputAndOutputDirectory,
+
+    #[error("range of `{1}` bytes at offset `{0}` exceeds the file")]
+    InvalidRange(u64, u64),
+    #[error("footer of MCAP file `{0}` is invalid")]
+    InvalidMcapFooter(FileName),
+    #[error("server of `{0}` does not support range requests")]
+    HttpRangeRequestsNotSupported(String),
+    #[error("invalid HTTP response for `{0}`: {1}")]
+    InvalidHttpResponse(String, String),
+
+    #[error("playback rate must be positive, got `{0}`")]
+    InvalidPlaybackRate(f64),
+    #[error("clock period must be positive, got `{0}`")]
+    InvalidClockPeriod(chrono::Duration),
 
     #[error("multiple bagfiles are currently not supported by erosbag")]
     MultipleBagfilesNotSupported,
@@ -0,0 +1,167 @@
+//! Decompression of storage files, which rosbag2 compresses as a whole with
+//! `compression_mode: FILE`, such as `rosbag2_0.mcap.zstd`.
+//!
+//! The files are decompressed once to a cache directory, so that they can be memory-mapped and
+//! queried like uncompressed files. The cache is located in the temporary directory of the
+//! system, unless the `EROSBAG_CACHE_DIR` environment variable is set. Cached files, which have
+//! not been used for a week, are removed when another file is decompressed.
+
+use crate::storage::metadata::BagMetadata;
+use crate::{Error, ZSTD_EXTENSION};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::warn;
+
+const CACHE_DIRECTORY_ENV: &str = "EROSBAG_CACHE_DIR";
+const CACHE_DIRECTORY_NAME: &str = "erosbag-cache";
+const CACHE_MAXIMUM_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+const FILE_COMPRESSION_MODE: &str = "FILE";
+const ZSTD_COMPRESSION_FORMAT: &str = "zstd";
+
+/// Returns true if the file is compressed as a whole, such as `rosbag2_0.mcap.zstd`.
+pub(crate) fn is_compressed_file(file_path: impl AsRef<Path>) -> bool {
+    file_path.as_ref().extension() == Some(std::ffi::OsStr::new(ZSTD_EXTENSION))
+}
+
+/// Returns the path without the extension of the compression, such as `rosbag2_0.mcap` for
+/// `rosbag2_0.mcap.zstd`.
+pub(crate) fn uncompressed_file_path(file_path: impl AsRef<Path>) -> PathBuf {
+    let file_path = file_path.as_ref();
+    if is_compressed_file(file_path) {
+        file_path.with_extension("")
+    } else {
+        file_path.to_owned()
+    }
+}
+
+/// Returns true if the metadata describes files compressed as a whole.
+pub(crate) fn is_file_compression_mode(metadata: &BagMetadata) -> bool {
+    metadata
+        .rosbag2_bagfile_information
+        .compression_mode
+        .eq_ignore_ascii_case(FILE_COMPRESSION_MODE)
+}
+
+/// Checks that the files of the bag are either uncompressed or compressed with zstd, which is
+/// the only format of rosbag2.
+pub(crate) fn check_file_compression(metadata: &BagMetadata) -> Result<(), Error> {
+    let information = &metadata.rosbag2_bagfile_information;
+    if is_file_compression_mode(metadata)
+        && information.compression_format != ZSTD_COMPRESSION_FORMAT
+    {
+        return Err(Error::UnsupportedFileCompression(
+            information.compression_format.clone(),
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the path of the readable file, which is the decompressed file in the cache for
+/// compressed files.
+///
+/// A cached file is reused as long as the compressed file keeps its path, size and modification
+/// time. The file is decompressed to a partial file first, which is renamed when complete and
+/// removed on failure, so that an interrupted decompression is never reused.
+pub(crate) fn decompressed_file_path(file_path: impl AsRef<Path>) -> Result<PathBuf, Error> {
+    let file_path = file_path.as_ref();
+    if !is_compressed_file(file_path) {
+        return Ok(file_path.to_owned());
+    }
+
+    let file_name = uncompressed_file_path(file_path)
+        .file_name()
+        .map(|x| x.to_owned())
+        .ok_or_else(|| Error::UnsupportedStorageFile(file_path.display().to_string()))?;
+    let cache_directory_path = cache_directory_path();
+    let entry_directory_path = cache_directory_path.join(cache_key(file_path)?);
+    let cached_file_path = entry_directory_path.join(file_name);
+    if cached_file_path.is_file() {
+        // keeps the file from being evicted while in use
+        File::options()
+            .append(true)
+            .open(&cached_file_path)?
+            .set_modified(SystemTime::now())?;
+        return Ok(cached_file_path);
+    }
+
+    evict_unused_files(&cache_directory_path);
+    std::fs::create_dir_all(&entry_directory_path)?;
+    let partial_file_path =
+        cached_file_path.with_extension(format!("partial-{}", std::process::id()));
+    if let Err(e) = decompress_file(file_path, &partial_file_path) {
+        let _ = std::fs::remove_file(&partial_file_path);
+        return Err(e);
+    }
+    std::fs::rename(&partial_file_path, &cached_file_path)?;
+
+    Ok(cached_file_path)
+}
+
+/// Returns the hex-encoded SHA-256 digest of the canonical path, size and modification time of
+/// the compressed file, which is stable across Rust versions and runs.
+fn cache_key(file_path: &Path) -> Result<String, Error> {
+    let file_metadata = std::fs::metadata(file_path)?;
+    let modified_nanoseconds = file_metadata
+        .modified()?
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+
+    let mut hasher = Sha256::new();
+    hasher.update(file_path.canonicalize()?.to_string_lossy().as_bytes());
+    hasher.update(file_metadata.len().to_le_bytes());
+    hasher.update(modified_nanoseconds.to_le_bytes());
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|x| format!("{x:02x}"))
+        .collect())
+}
+
+fn decompress_file(file_path: &Path, output_file_path: &Path) -> Result<(), Error> {
+    let mut writer = BufWriter::new(File::create(output_file_path)?);
+    zstd::stream::copy_decode(BufReader::new(File::open(file_path)?), &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Removes the cache entries, whose files or empty directories have not been modified or reused
+/// within the maximum age, where failures are only logged as other processes may remove the same entries.
+fn evict_unused_files(cache_directory_path: &Path) {
+    let Ok(entries) = std::fs::read_dir(cache_directory_path) else {
+        return;
+    };
+    let now = SystemTime::now();
+    for current_entry_path in entries.filter_map(|x| x.ok()).map(|x| x.path()) {
+        if !current_entry_path.is_dir() {
+            continue;
+        }
+        let last_used = std::fs::read_dir(&current_entry_path)
+            .into_iter()
+            .flatten()
+            .filter_map(|x| x.ok()?.metadata().ok()?.modified().ok())
+            .max()
+            .or_else(|| current_entry_path.metadata().ok()?.modified().ok());
+        let is_unused = last_used.is_some_and(|x| {
+            now.duration_since(x)
+                .is_ok_and(|age| age > CACHE_MAXIMUM_AGE)
+        });
+        if is_unused && let Err(e) = std::fs::remove_dir_all(&current_entry_path) {
+            warn!(
+                "Removing cache entry {} failed: {e}",
+                current_entry_path.display()
+            );
+        }
+    }
+}
+
+fn cache_directory_path() -> PathBuf {
+    match std::env::var_os(CACHE_DIRECTORY_ENV) {
+        Some(x) => PathBuf::from(x),
+        None => std::env::temp_dir().join(CACHE_DIRECTORY_NAME),
+    }
+}
@@ -0,0 +1,264 @@
+//! Filters applied to the point clouds of the individual messages before they are merged.
+use crate::Error;
+use crate::dto::McapMessageMeta;
+use chrono::{DateTime, Utc};
+use ecoord::{FrameId, TransformId, TransformTree};
+use itertools::Itertools;
+use nalgebra::{Isometry3, Point3};
+use polars::prelude::{BooleanChunked, DataFrame, DataType, NewChunkedArray};
+use rayon::prelude::*;
+use std::collections::HashSet;
+use tracing::warn;
+
+const COLUMN_NAME_X: &str = "x";
+const COLUMN_NAME_Y: &str = "y";
+const COLUMN_NAME_Z: &str = "z";
+const COLUMN_NAME_INTENSITY: &str = "intensity";
+const COLUMN_NAME_FRAME_ID: &str = "frame_id";
+const COLUMN_NAME_TIMESTAMP: &str = "timestamp";
+
+/// Filter of a point cloud pipeline, where the filters are applied in the provided order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PointFilter {
+    /// Keeps the points with a distance to the sensor origin within min and max (inclusive).
+    BeamLength { min: f64, max: f64 },
+    /// Keeps the points with an intensity within min and max (inclusive), while point clouds
+    /// without intensity remain unchanged.
+    Intensity { min: f32, max: f32 },
+    /// Keeps the points inside the axis-aligned box given in the frame.
+    BoundingBox {
+        frame_id: FrameId,
+        min: Point3<f64>,
+        max: Point3<f64>,
+    },
+    /// Removes the points inside the axis-aligned box given in the frame, such as the ego vehicle
+    /// in its base frame.
+    ExcludeBoundingBox {
+        frame_id: FrameId,
+        min: Point3<f64>,
+        max: Point3<f64>,
+    },
+    /// Keeps every n-th message of each channel ordered by log time.
+    TemporalDecimation { keep_every: usize },
+    /// Removes the points with a NaN or infinite coordinate.
+    RemoveNonFinite,
+}
+
+impl PointFilter {
+    /// Returns true, if the filter transforms the points into another frame.
+    pub fn requires_transforms(&self) -> bool {
+        matches!(
+            self,
+            PointFilter::BoundingBox { .. } | PointFilter::ExcludeBoundingBox { .. }
+        )
+    }
+
+    /// Fails, if a range or box is empty or not every n-th message with a positive n is kept.
+    pub fn validate(&self) -> Result<(), Error> {
+        let is_valid = match self {
+            PointFilter::BeamLength { min, max } => min <= max,
+            PointFilter::Intensity { min, max } => min <= max,
+            PointFilter::BoundingBox { min, max, .. }
+            | PointFilter::ExcludeBoundingBox { min, max, .. } => (0..3).all(|i| min[i] <= max[i]),
+            PointFilter::TemporalDecimation { keep_every } => *keep_every > 0,
+            PointFilter::RemoveNonFinite => true,
+        };
+        if !is_valid {
+            return Err(Error::InvalidPointFilter(self.clone()));
+        }
+        Ok(())
+    }
+}
+
+/// Applies the filters to the point clouds of the messages and drops messages without remaining
+/// points.
+///
+/// The transform tree is only required for the bounding box filters, which transform the points
+/// into the box frame at the stamp of the message.
+pub fn apply_filters(
+    messages: Vec<McapMessageMeta<epoint::PointCloud>>,
+    filters: &[PointFilter],
+    transform_tree: &TransformTree,
+) -> Result<Vec<McapMessageMeta<epoint::PointCloud>>, Error> {
+    filters.iter().try_for_each(|x| x.validate())?;
+
+    let mut messages = messages;
+    for current_filter in filters {
+        messages = match current_filter {
+            PointFilter::TemporalDecimation { keep_every } => decimate(messages, *keep_every),
+            _ => messages
+                .into_par_iter()
+                .map(|mut x| {
+                    let mask = get_mask(&x.message, current_filter, transform_tree)?;
+                    let data_frame = &mut x.message.point_data.data_frame;
+                    *data_frame =
+                        data_frame.filter(&BooleanChunked::from_slice("mask".into(), &mask))?;
+                    Ok(x)
+                })
+                .collect::<Result<Vec<_>, Error>>()?,
+        };
+        messages.retain(|x| x.message.size() > 0);
+    }
+
+    Ok(messages)
+}
+
+fn decimate(
+    messages: Vec<McapMessageMeta<epoint::PointCloud>>,
+    keep_every: usize,
+) -> Vec<McapMessageMeta<epoint::PointCloud>> {
+    messages
+        .into_iter()
+        .into_group_map_by(|x| x.channel_topic.clone())
+        .into_values()
+        .flat_map(|channel_messages| {
+            channel_messages
+                .into_iter()
+                .sorted_by_key(|x| (x.log_date_time, x.message_id))
+                .step_by(keep_every)
+        })
+        .collect()
+}
+
+/// Returns true for each point to keep.
+fn get_mask(
+    point_cloud: &epoint::PointCloud,
+    point_filter: &PointFilter,
+    transform_tree: &TransformTree,
+) -> Result<Vec<bool>, Error> {
+    let data_frame = &point_cloud.point_data.data_frame;
+
+    let mask = match point_filter {
+        PointFilter::BeamLength { min, max } => get_points(data_frame)?
+            .iter()
+            .map(|p| (*min..=*max).contains(&p.coords.norm()))
+            .collect(),
+        PointFilter::Intensity { min, max } => {
+            if data_frame.column(COLUMN_NAME_INTENSITY).is_err() {
+                warn!("Point cloud without intensity is not filtered by intensity");
+                return Ok(vec![true; data_frame.height()]);
+            }
+            data_frame
+                .column(COLUMN_NAME_INTENSITY)?
+                .cast(&DataType::Float32)?
+                .f32()?
+                .into_iter()
+                .map(|x| x.is_some_and(|x| (*min..=*max).contains(&x)))
+                .collect()
+        }
+        PointFilter::BoundingBox { frame_id, min, max } => {
+            let isometry = get_isometry_to_frame(data_frame, frame_id, transform_tree)?;
+            get_points(data_frame)?
+                .iter()
+                .map(|p| is_inside(&isometry.transform_point(p), min, max))
+                .collect()
+        }
+        PointFilter::ExcludeBoundingBox { frame_id, min, max } => {
+            let isometry = get_isometry_to_frame(data_frame, frame_id, transform_tree)?;
+            get_points(data_frame)?
+                .iter()
+                .map(|p| !is_inside(&isometry.transform_point(p), min, max))
+                .collect()
+        }
+        PointFilter::TemporalDecimation { .. } => vec![true; data_frame.height()],
+        PointFilter::RemoveNonFinite => get_points(data_frame)?
+            .iter()
+            .map(|p| p.iter().all(|x| x.is_finite()))
+            .collect(),
+    };
+
+    Ok(mask)
+}
+
+/// Keeps the first point of each voxel with the edge length of voxel_size.
+///
+/// The point cloud is expected to be merged and resolved to its target frame, so that the points
+/// of overlapping scans share the voxels. Points of different frames are never merged.
+pub fn voxel_downsample(
+    point_cloud: &mut epoint::PointCloud,
+    voxel_size: f64,
+) -> Result<(), Error> {
+    if voxel_size.is_nan() || voxel_size <= 0.0 {
+        return Err(Error::InvalidVoxelSize(voxel_size));
+    }
+
+    let data_frame = &mut point_cloud.point_data.data_frame;
+    let frame_ids: Vec<Option<String>> = match data_frame.column(COLUMN_NAME_FRAME_ID) {
+        Ok(frame_ids) => frame_ids
+            .cast(&DataType::String)?
+            .str()?
+            .into_iter()
+            .map(|x| x.map(|x| x.to_string()))
+            .collect(),
+        Err(_) => vec![None; data_frame.height()],
+    };
+    let mut occupied_voxels: HashSet<(Option<String>, i64, i64, i64)> = HashSet::new();
+    let mask: Vec<bool> = get_points(data_frame)?
+        .iter()
+        .zip(frame_ids)
+        .map(|(p, frame_id)| {
+            occupied_voxels.insert((
+                frame_id,
+                (p.x / voxel_size).floor() as i64,
+                (p.y / voxel_size).floor() as i64,
+                (p.z / voxel_size).floor() as i64,
+            ))
+        })
+        .collect();
+    *data_frame = data_frame.filter(&BooleanChunked::from_slice("mask".into(), &mask))?;
+
+    Ok(())
+}
+
+fn get_points(data_frame: &DataFrame) -> Result<Vec<Point3<f64>>, Error> {
+    let x = data_frame.column(COLUMN_NAME_X)?.f64()?;
+    let y = data_frame.column(COLUMN_NAME_Y)?.f64()?;
+    let z = data_frame.column(COLUMN_NAME_Z)?.f64()?;
+
+    let points = x
+        .into_iter()
+        .zip(y)
+        .zip(z)
+        .map(|((x, y), z)| {
+            Point3::new(
+                x.unwrap_or(f64::NAN),
+                y.unwrap_or(f64::NAN),
+                z.unwrap_or(f64::NAN),
+            )
+        })
+        .collect();
+    Ok(points)
+}
+
+/// Returns the isometry transforming the points of a message point cloud into the target frame at
+/// the stamp of the message.
+fn get_isometry_to_frame(
+    data_frame: &DataFrame,
+    target_frame_id: &FrameId,
+    transform_tree: &TransformTree,
+) -> Result<Isometry3<f64>, Error> {
+    let frame_ids = data_frame
+        .column(COLUMN_NAME_FRAME_ID)?
+        .cast(&DataType::String)?;
+    let frame_id: FrameId = frame_ids
+        .str()?
+        .get(0)
+        .unwrap_or_default()
+        .to_string()
+        .into();
+    let timestamps = data_frame
+        .column(COLUMN_NAME_TIMESTAMP)?
+        .cast(&DataType::Int64)?;
+    let date_time: DateTime<Utc> =
+        DateTime::from_timestamp_nanos(timestamps.i64()?.get(0).unwrap_or_default());
+
+    let transform = transform_tree.get_transform_at_time(
+        &TransformId::new(target_frame_id.clone(), frame_id),
+        date_time,
+    )?;
+    Ok(transform.isometry())
+}
+
+fn is_inside(point: &Point3<f64>, min: &Point3<f64>, max: &Point3<f64>) -> bool {
+    (0..3).all(|i| min[i] <= point[i] && point[i] <= max[i])
+}
@@ -0,0 +1,24 @@
+//! Playback of bags to [Foxglove] over the [Foxglove WebSocket protocol].
+//!
+//! The server advertises one channel per topic with its embedded schema and streams the
+//! messages of the subscribed channels in the order of their log times, either in real time or
+//! at a scaled rate. Each connection has its own playback, which starts with the first
+//! subscription and is controlled with the playback control requests of the `playbackControl`
+//! capability.
+//!
+//! [Foxglove]: https://foxglove.dev
+//! [Foxglove WebSocket protocol]: https://github.com/foxglove/ws-protocol
+pub mod protocol;
+mod server;
+
+#[doc(inline)]
+pub use server::FoxgloveServer;
+
+#[doc(inline)]
+pub use server::FoxgloveServerOptions;
+
+/// WebSocket subprotocol negotiated with the clients.
+pub const SUBPROTOCOL: &str = "foxglove.websocket.v1";
+
+/// Default port of Foxglove WebSocket servers.
+pub const DEFAULT_PORT: u16 = 8765;
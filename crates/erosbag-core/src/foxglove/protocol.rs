@@ -0,0 +1,294 @@
+//! Messages of the Foxglove WebSocket protocol, which are exchanged as JSON text frames or as
+//! binary frames starting with an opcode.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Opcode of the binary frame carrying the data of a message.
+pub const MESSAGE_DATA_OPCODE: u8 = 0x01;
+/// Opcode of the binary frame carrying the playback time of the server.
+pub const TIME_OPCODE: u8 = 0x02;
+/// Opcode of the binary frame carrying the playback state of the server.
+pub const PLAYBACK_STATE_OPCODE: u8 = 0x05;
+/// Opcode of the binary frame carrying a playback control request of a client.
+pub const PLAYBACK_CONTROL_REQUEST_OPCODE: u8 = 0x03;
+
+/// Capability of the server to send its playback time.
+pub const TIME_CAPABILITY: &str = "time";
+/// Capability of the server to be controlled with playback control requests.
+pub const PLAYBACK_CONTROL_CAPABILITY: &str = "playbackControl";
+
+/// Text messages sent by the server.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum ServerMessage {
+    #[serde(rename_all = "camelCase")]
+    ServerInfo {
+        name: String,
+        capabilities: Vec<String>,
+        supported_encodings: Vec<String>,
+        metadata: BTreeMap<String, String>,
+        session_id: String,
+        /// Log time of the first message in nanoseconds since the epoch.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        data_start_time: Option<u64>,
+        /// Log time of the last message in nanoseconds since the epoch.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        data_end_time: Option<u64>,
+    },
+    Status {
+        level: StatusLevel,
+        message: String,
+    },
+    Advertise {
+        channels: Vec<Channel>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(into = "u8", try_from = "u8")]
+pub enum StatusLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+impl From<StatusLevel> for u8 {
+    fn from(level: StatusLevel) -> Self {
+        match level {
+            StatusLevel::Info => 0,
+            StatusLevel::Warning => 1,
+            StatusLevel::Error => 2,
+        }
+    }
+}
+
+impl TryFrom<u8> for StatusLevel {
+    type Error = String;
+
+    fn try_from(value: u8) -> Result<Self, String> {
+        match value {
+            0 => Ok(StatusLevel::Info),
+            1 => Ok(StatusLevel::Warning),
+            2 => Ok(StatusLevel::Error),
+            _ => Err(format!("invalid status level {value}")),
+        }
+    }
+}
+
+/// Channel advertised to the clients with its embedded schema.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Channel {
+    pub id: u32,
+    pub topic: String,
+    /// Encoding of the messages, such as `cdr`.
+    pub encoding: String,
+    /// Name of the schema, such as `sensor_msgs/msg/Imu`.
+    pub schema_name: String,
+    /// Schema in the format of the schema encoding.
+    pub schema: String,
+    /// Encoding of the schema, such as `ros2msg`.
+    pub schema_encoding: String,
+}
+
+/// Text messages sent by the clients.
+///
+/// The playback is controlled with the binary [`PlaybackControlRequest`] instead.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum ClientMessage {
+    Subscribe {
+        subscriptions: Vec<Subscription>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Unsubscribe {
+        subscription_ids: Vec<u32>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Subscription {
+    pub id: u32,
+    pub channel_id: u32,
+}
+
+/// Encodes a binary frame with the data of a message received by a subscription.
+pub fn encode_message_data(subscription_id: u32, log_time: u64, data: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(1 + 4 + 8 + data.len());
+    frame.push(MESSAGE_DATA_OPCODE);
+    frame.extend_from_slice(&subscription_id.to_le_bytes());
+    frame.extend_from_slice(&log_time.to_le_bytes());
+    frame.extend_from_slice(data);
+    frame
+}
+
+/// Encodes a binary frame with the playback time in nanoseconds since the epoch.
+pub fn encode_time(time: u64) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(1 + 8);
+    frame.push(TIME_OPCODE);
+    frame.extend_from_slice(&time.to_le_bytes());
+    frame
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaybackCommand {
+    Play,
+    Pause,
+}
+
+/// Request of a client to play or pause the playback, to set its speed and to seek.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaybackControlRequest {
+    pub playback_command: PlaybackCommand,
+    /// Factor of the playback rate relative to real time.
+    pub playback_speed: f32,
+    /// Time in nanoseconds since the epoch to move the playback to.
+    pub seek_time: Option<u64>,
+    /// Identifier of the request, which is returned with the playback state.
+    pub request_id: String,
+}
+
+impl PlaybackControlRequest {
+    /// Encodes the binary frame including the opcode.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(1 + 1 + 4 + 1 + 8 + 4 + self.request_id.len());
+        frame.push(PLAYBACK_CONTROL_REQUEST_OPCODE);
+        frame.push(match self.playback_command {
+            PlaybackCommand::Play => 0,
+            PlaybackCommand::Pause => 1,
+        });
+        frame.extend_from_slice(&self.playback_speed.to_le_bytes());
+        frame.push(self.seek_time.is_some() as u8);
+        frame.extend_from_slice(&self.seek_time.unwrap_or_default().to_le_bytes());
+        encode_string(&mut frame, &self.request_id);
+        frame
+    }
+
+    /// Decodes the binary frame including the opcode.
+    pub fn decode(frame: &[u8]) -> Result<Self, String> {
+        let mut reader = FrameReader::new(frame, PLAYBACK_CONTROL_REQUEST_OPCODE)?;
+        let playback_command = match reader.read_u8()? {
+            0 => PlaybackCommand::Play,
+            1 => PlaybackCommand::Pause,
+            x => return Err(format!("invalid playback command {x}")),
+        };
+        let playback_speed = f32::from_le_bytes(reader.read_array()?);
+        let had_seek = reader.read_u8()? != 0;
+        let seek_time = u64::from_le_bytes(reader.read_array()?);
+        let request_id = reader.read_string()?;
+        Ok(Self {
+            playback_command,
+            playback_speed,
+            seek_time: had_seek.then_some(seek_time),
+            request_id,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaybackStatus {
+    Playing,
+    Paused,
+    Buffering,
+    Ended,
+}
+
+/// State of the playback sent by the server in reply to a request or when it ends.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaybackState {
+    pub status: PlaybackStatus,
+    /// Playback time in nanoseconds since the epoch.
+    pub current_time: u64,
+    pub playback_speed: f32,
+    /// Whether the playback was moved by the request.
+    pub did_seek: bool,
+    /// Identifier of the request, which is answered by the state.
+    pub request_id: Option<String>,
+}
+
+impl PlaybackState {
+    /// Encodes the binary frame including the opcode.
+    pub fn encode(&self) -> Vec<u8> {
+        let request_id = self.request_id.as_deref().unwrap_or_default();
+        let mut frame = Vec::with_capacity(1 + 1 + 8 + 4 + 1 + 4 + request_id.len());
+        frame.push(PLAYBACK_STATE_OPCODE);
+        frame.push(match self.status {
+            PlaybackStatus::Playing => 0,
+            PlaybackStatus::Paused => 1,
+            PlaybackStatus::Buffering => 2,
+            PlaybackStatus::Ended => 3,
+        });
+        frame.extend_from_slice(&self.current_time.to_le_bytes());
+        frame.extend_from_slice(&self.playback_speed.to_le_bytes());
+        frame.push(self.did_seek as u8);
+        encode_string(&mut frame, request_id);
+        frame
+    }
+
+    /// Decodes the binary frame including the opcode, where an empty request id is omitted.
+    pub fn decode(frame: &[u8]) -> Result<Self, String> {
+        let mut reader = FrameReader::new(frame, PLAYBACK_STATE_OPCODE)?;
+        let status = match reader.read_u8()? {
+            0 => PlaybackStatus::Playing,
+            1 => PlaybackStatus::Paused,
+            2 => PlaybackStatus::Buffering,
+            3 => PlaybackStatus::Ended,
+            x => return Err(format!("invalid playback status {x}")),
+        };
+        let current_time = u64::from_le_bytes(reader.read_array()?);
+        let playback_speed = f32::from_le_bytes(reader.read_array()?);
+        let did_seek = reader.read_u8()? != 0;
+        let request_id = reader.read_string()?;
+        Ok(Self {
+            status,
+            current_time,
+            playback_speed,
+            did_seek,
+            request_id: (!request_id.is_empty()).then_some(request_id),
+        })
+    }
+}
+
+/// Appends the string prefixed with its length.
+fn encode_string(frame: &mut Vec<u8>, value: &str) {
+    frame.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    frame.extend_from_slice(value.as_bytes());
+}
+
+/// Reads the little-endian fields of a binary frame after checking its opcode.
+struct FrameReader<'a> {
+    remaining: &'a [u8],
+}
+
+impl<'a> FrameReader<'a> {
+    fn new(frame: &'a [u8], opcode: u8) -> Result<Self, String> {
+        match frame.split_first() {
+            Some((x, remaining)) if *x == opcode => Ok(Self { remaining }),
+            Some((x, _)) => Err(format!("expected opcode {opcode}, got {x}")),
+            None => Err("empty frame".to_string()),
+        }
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let Some((value, remaining)) = self.remaining.split_first_chunk::<N>() else {
+            return Err("frame is too short".to_string());
+        };
+        self.remaining = remaining;
+        Ok(*value)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, String> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    fn read_string(&mut self) -> Result<String, String> {
+        let length = u32::from_le_bytes(self.read_array()?) as usize;
+        if self.remaining.len() < length {
+            return Err("frame is too short".to_string());
+        }
+        let (value, remaining) = self.remaining.split_at(length);
+        self.remaining = remaining;
+        String::from_utf8(value.to_vec()).map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,487 @@
+use crate::foxglove::SUBPROTOCOL;
+use crate::foxglove::protocol::{
+    Channel, ClientMessage, PLAYBACK_CONTROL_CAPABILITY, PLAYBACK_CONTROL_REQUEST_OPCODE,
+    PlaybackCommand, PlaybackControlRequest, PlaybackState, PlaybackStatus, ServerMessage,
+    StatusLevel, Subscription, TIME_CAPABILITY, encode_message_data, encode_time,
+};
+use crate::mcap_source::{McapSource, MmapSource};
+use crate::playback::MessageCursor;
+use crate::ros_messages::RosMessageType;
+use crate::storage::ROS2MSG_SCHEMA_ENCODING;
+use crate::{ChannelTopic, Error, Rosbag};
+use chrono::{DateTime, TimeZone, Utc};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use tungstenite::handshake::server::{Request, Response};
+use tungstenite::http::HeaderValue;
+use tungstenite::http::header::SEC_WEBSOCKET_PROTOCOL;
+use tungstenite::{HandshakeError, Message, WebSocket};
+
+/// Interval in which the connections are polled for client messages, which limits the
+/// precision of the playback timing.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Options of the playback of a [`FoxgloveServer`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FoxgloveServerOptions {
+    /// Name of the server shown by the clients.
+    pub name: String,
+    /// Factor of the playback rate relative to real time.
+    pub rate: f64,
+    /// Keeps the playback paused after the first subscription until a client requests to play.
+    pub paused: bool,
+}
+
+impl FoxgloveServerOptions {
+    pub fn new() -> Self {
+        Self {
+            name: "erosbag".to_string(),
+            rate: 1.0,
+            paused: false,
+        }
+    }
+
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn with_rate(mut self, rate: f64) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    pub fn with_paused(mut self, paused: bool) -> Self {
+        self.paused = paused;
+        self
+    }
+}
+
+impl Default for FoxgloveServerOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Server of the Foxglove WebSocket protocol playing back a bag.
+#[derive(Debug)]
+pub struct FoxgloveServer<'a, S: McapSource = MmapSource> {
+    rosbag: &'a Rosbag<S>,
+    options: FoxgloveServerOptions,
+    channels: Vec<Channel>,
+    start_date_time: DateTime<Utc>,
+    end_date_time: DateTime<Utc>,
+}
+
+impl<'a, S: McapSource> FoxgloveServer<'a, S> {
+    /// Collects the channels of all files, where a topic contained in multiple files is
+    /// advertised once with the schema of the first file.
+    pub fn new(rosbag: &'a Rosbag<S>, options: FoxgloveServerOptions) -> Result<Self, Error> {
+        if !options.rate.is_finite() || options.rate <= 0.0 {
+            return Err(Error::InvalidPlaybackRate(options.rate));
+        }
+
+        let mut channels: BTreeMap<String, Channel> = BTreeMap::new();
+        let mut file_names: Vec<_> = rosbag.mcap_files.keys().collect();
+        file_names.sort();
+        for current_file_name in file_names {
+            let Some(summary) = rosbag.mcap_files[current_file_name].summary()? else {
+                continue;
+            };
+            let mut file_channels: Vec<_> = summary.channels.values().collect();
+            file_channels.sort_by_key(|x| x.id);
+            for current_channel in file_channels {
+                if channels.contains_key(&current_channel.topic) {
+                    continue;
+                }
+                let (schema_name, schema_encoding, schema) = match &current_channel.schema {
+                    Some(x) if !x.data.is_empty() => (
+                        x.name.clone(),
+                        x.encoding.clone(),
+                        String::from_utf8_lossy(&x.data).into_owned(),
+                    ),
+                    // fall back to the built-in definition for schemas without data
+                    Some(x) => match RosMessageType::from_str(&x.name) {
+                        Ok(message_type) => (
+                            x.name.clone(),
+                            ROS2MSG_SCHEMA_ENCODING.to_string(),
+                            message_type.definition().to_string(),
+                        ),
+                        Err(_) => (x.name.clone(), x.encoding.clone(), String::new()),
+                    },
+                    None => (String::new(), String::new(), String::new()),
+                };
+                let channel = Channel {
+                    id: channels.len() as u32,
+                    topic: current_channel.topic.clone(),
+                    encoding: current_channel.message_encoding.clone(),
+                    schema_name,
+                    schema,
+                    schema_encoding,
+                };
+                channels.insert(current_channel.topic.clone(), channel);
+            }
+        }
+        let mut channels: Vec<Channel> = channels.into_values().collect();
+        channels.sort_by_key(|x| x.id);
+
+        let start_date_time = rosbag.get_start_date_time()?.unwrap_or_default();
+        let end_date_time = rosbag
+            .get_end_date_time()?
+            .unwrap_or(start_date_time)
+            .max(start_date_time);
+
+        Ok(Self {
+            rosbag,
+            options,
+            channels,
+            start_date_time,
+            end_date_time,
+        })
+    }
+
+    /// Returns the advertised channels.
+    pub fn channels(&self) -> &[Channel] {
+        &self.channels
+    }
+
+    /// Accepts connections until the listener fails, where each connection is served by its own
+    /// thread.
+    pub fn serve(&self, listener: &TcpListener) -> Result<(), Error> {
+        std::thread::scope(|scope| {
+            for stream in listener.incoming() {
+                let stream = stream?;
+                scope.spawn(move || {
+                    let peer_address = stream.peer_addr().ok();
+                    info!("Client connected: {peer_address:?}");
+                    match self.serve_connection(stream) {
+                        Ok(()) => info!("Client disconnected: {peer_address:?}"),
+                        Err(error) => warn!("Connection to {peer_address:?} failed: {error}"),
+                    }
+                });
+            }
+            Ok(())
+        })
+    }
+
+    /// Performs the WebSocket handshake and plays back the bag until the client disconnects.
+    pub fn serve_connection(&self, stream: TcpStream) -> Result<(), Error> {
+        // the error response type of the handshake callback is given by tungstenite
+        #[allow(clippy::result_large_err)]
+        let callback =
+            |request: &Request, response: Response| Ok(negotiate_subprotocol(request, response));
+        let websocket = tungstenite::accept_hdr(stream, callback).map_err(|error| match error {
+            HandshakeError::Failure(error) => Error::from(error),
+            HandshakeError::Interrupted(_) => {
+                Error::from(std::io::Error::from(ErrorKind::WouldBlock))
+            }
+        })?;
+        websocket.get_ref().set_read_timeout(Some(POLL_INTERVAL))?;
+
+        let mut session = Session {
+            server: self,
+            websocket,
+            cursor: MessageCursor::new(self.rosbag, Some(HashSet::new()))?,
+            subscriptions: HashMap::new(),
+            rate: self.options.rate,
+            is_paused: true,
+            has_subscribed: false,
+            playback_date_time: self.start_date_time,
+            playback_instant: None,
+        };
+        session.run()
+    }
+}
+
+/// Accepts the subprotocol of Foxglove, if the client offers it.
+fn negotiate_subprotocol(request: &Request, mut response: Response) -> Response {
+    let offers_subprotocol = request
+        .headers()
+        .get_all(SEC_WEBSOCKET_PROTOCOL)
+        .iter()
+        .filter_map(|x| x.to_str().ok())
+        .flat_map(|x| x.split(','))
+        .any(|x| x.trim() == SUBPROTOCOL);
+    if offers_subprotocol {
+        response.headers_mut().insert(
+            SEC_WEBSOCKET_PROTOCOL,
+            HeaderValue::from_static(SUBPROTOCOL),
+        );
+    }
+    response
+}
+
+/// Playback of a single connection.
+struct Session<'s, 'a, S: McapSource> {
+    server: &'s FoxgloveServer<'a, S>,
+    websocket: WebSocket<TcpStream>,
+    cursor: MessageCursor<'a, S>,
+    subscriptions: HashMap<u32, ChannelTopic>,
+    rate: f64,
+    is_paused: bool,
+    has_subscribed: bool,
+    /// Playback time, which was reached at the playback instant if playing.
+    playback_date_time: DateTime<Utc>,
+    playback_instant: Option<Instant>,
+}
+
+impl<S: McapSource> Session<'_, '_, S> {
+    fn run(&mut self) -> Result<(), Error> {
+        let server = self.server;
+        self.send_text(&ServerMessage::ServerInfo {
+            name: server.options.name.clone(),
+            capabilities: vec![
+                TIME_CAPABILITY.to_string(),
+                PLAYBACK_CONTROL_CAPABILITY.to_string(),
+            ],
+            supported_encodings: Vec::new(),
+            metadata: BTreeMap::new(),
+            session_id: timestamp(Utc::now()).to_string(),
+            data_start_time: Some(timestamp(server.start_date_time)),
+            data_end_time: Some(timestamp(server.end_date_time)),
+        })?;
+        self.send_text(&ServerMessage::Advertise {
+            channels: server.channels.clone(),
+        })?;
+
+        loop {
+            match self.websocket.read() {
+                Ok(Message::Text(text)) => self.handle_text(text.as_str())?,
+                Ok(Message::Binary(frame)) => self.handle_binary(&frame)?,
+                // the reply to the close frame is sent by the next read
+                Ok(Message::Close(_)) => continue,
+                Ok(_) => {}
+                Err(tungstenite::Error::Io(error))
+                    if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+                    return Ok(());
+                }
+                Err(error) => return Err(error.into()),
+            }
+            if !self.is_paused {
+                self.send_due_messages()?;
+            }
+        }
+    }
+
+    fn handle_text(&mut self, text: &str) -> Result<(), Error> {
+        let message: ClientMessage = match serde_json::from_str(text) {
+            Ok(message) => message,
+            Err(error) => {
+                return self.send_status(StatusLevel::Warning, format!("invalid message: {error}"));
+            }
+        };
+
+        match message {
+            ClientMessage::Subscribe { subscriptions } => {
+                for Subscription { id, channel_id } in subscriptions {
+                    match self.server.channels.iter().find(|x| x.id == channel_id) {
+                        Some(channel) => {
+                            self.subscriptions.insert(id, channel.topic.as_str().into());
+                        }
+                        None => self.send_status(
+                            StatusLevel::Warning,
+                            format!("channel {channel_id} does not exist"),
+                        )?,
+                    }
+                }
+                self.select_subscribed_channels();
+                if !self.has_subscribed && !self.subscriptions.is_empty() {
+                    self.has_subscribed = true;
+                    if !self.server.options.paused {
+                        self.play();
+                        self.send_playback_state(None, false)?;
+                    }
+                }
+            }
+            ClientMessage::Unsubscribe { subscription_ids } => {
+                for current_id in subscription_ids {
+                    self.subscriptions.remove(&current_id);
+                }
+                self.select_subscribed_channels();
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads only the messages of the subscribed channels from the current position on.
+    fn select_subscribed_channels(&mut self) {
+        let channel_topics: HashSet<ChannelTopic> = self.subscriptions.values().cloned().collect();
+        self.cursor.select_channel_topics(Some(channel_topics));
+    }
+
+    fn handle_binary(&mut self, frame: &[u8]) -> Result<(), Error> {
+        if frame.first() != Some(&PLAYBACK_CONTROL_REQUEST_OPCODE) {
+            return self.send_status(
+                StatusLevel::Warning,
+                format!("unsupported binary opcode {:?}", frame.first()),
+            );
+        }
+        let request = match PlaybackControlRequest::decode(frame) {
+            Ok(request) => request,
+            Err(error) => {
+                return self.send_status(
+                    StatusLevel::Warning,
+                    format!("invalid playback control request: {error}"),
+                );
+            }
+        };
+
+        let speed = request.playback_speed as f64;
+        if speed.is_finite() && speed > 0.0 {
+            self.playback_date_time = self.current_date_time();
+            if !self.is_paused {
+                self.playback_instant = Some(Instant::now());
+            }
+            self.rate = speed;
+        } else {
+            self.send_status(
+                StatusLevel::Warning,
+                format!("playback speed must be positive, got {speed}"),
+            )?;
+        }
+
+        let mut seek_time = request.seek_time;
+        if request.playback_command == PlaybackCommand::Play
+            && seek_time.is_none()
+            && self.is_at_end()
+        {
+            // restart an ended playback from the beginning
+            seek_time = Some(timestamp(self.server.start_date_time));
+        }
+        if let Some(time) = seek_time {
+            let date_time = Utc
+                .timestamp_nanos(time as i64)
+                .clamp(self.server.start_date_time, self.server.end_date_time);
+            self.cursor.seek(date_time);
+            self.playback_date_time = date_time;
+            if !self.is_paused {
+                self.playback_instant = Some(Instant::now());
+            }
+        }
+
+        match request.playback_command {
+            PlaybackCommand::Play => self.play(),
+            PlaybackCommand::Pause => self.pause(),
+        }
+        self.send_playback_state(Some(request.request_id), seek_time.is_some())?;
+        if seek_time.is_some() {
+            self.send_binary(encode_time(timestamp(self.playback_date_time)))?;
+        }
+        Ok(())
+    }
+
+    fn send_playback_state(
+        &mut self,
+        request_id: Option<String>,
+        did_seek: bool,
+    ) -> Result<(), Error> {
+        let status = if !self.is_paused {
+            PlaybackStatus::Playing
+        } else if self.is_at_end() {
+            PlaybackStatus::Ended
+        } else {
+            PlaybackStatus::Paused
+        };
+        let state = PlaybackState {
+            status,
+            current_time: timestamp(self.current_date_time()),
+            playback_speed: self.rate as f32,
+            did_seek,
+            request_id,
+        };
+        self.send_binary(state.encode())
+    }
+
+    fn play(&mut self) {
+        if self.is_paused {
+            self.is_paused = false;
+            self.playback_instant = Some(Instant::now());
+        }
+    }
+
+    fn pause(&mut self) {
+        self.playback_date_time = self.current_date_time();
+        self.playback_instant = None;
+        self.is_paused = true;
+    }
+
+    /// Returns whether the playback time reached the end of the bag.
+    fn is_at_end(&self) -> bool {
+        self.server.end_date_time <= self.current_date_time()
+    }
+
+    /// Returns the playback time, which advances with the rate while playing.
+    fn current_date_time(&self) -> DateTime<Utc> {
+        match self.playback_instant {
+            Some(instant) => {
+                let elapsed = chrono::Duration::from_std(instant.elapsed().mul_f64(self.rate))
+                    .unwrap_or(chrono::Duration::MAX);
+                self.playback_date_time
+                    .checked_add_signed(elapsed)
+                    .unwrap_or(DateTime::<Utc>::MAX_UTC)
+            }
+            None => self.playback_date_time,
+        }
+    }
+
+    /// Sends the messages of the subscribed channels logged up to the playback time and pauses at
+    /// the end of the bag.
+    fn send_due_messages(&mut self) -> Result<(), Error> {
+        let date_time = self.current_date_time();
+        while let Some(log_date_time) = self.cursor.peek_date_time()? {
+            if date_time < log_date_time {
+                break;
+            }
+            let Some(message) = self.cursor.next_message()? else {
+                break;
+            };
+            let subscription_ids: Vec<u32> = self
+                .subscriptions
+                .iter()
+                .filter(|(_, topic)| **topic == message.channel_topic)
+                .map(|(id, _)| *id)
+                .collect();
+            for current_id in subscription_ids {
+                let frame = encode_message_data(
+                    current_id,
+                    timestamp(message.log_date_time),
+                    &message.message.data,
+                );
+                self.send_binary(frame)?;
+            }
+        }
+
+        if self.server.end_date_time <= date_time {
+            self.pause();
+            self.playback_date_time = self.server.end_date_time;
+            self.send_binary(encode_time(timestamp(self.playback_date_time)))?;
+            self.send_playback_state(None, false)?;
+            return self.send_status(StatusLevel::Info, "end of bag reached".to_string());
+        }
+        self.send_binary(encode_time(timestamp(date_time)))
+    }
+
+    fn send_text(&mut self, message: &ServerMessage) -> Result<(), Error> {
+        let text = serde_json::to_string(message)?;
+        self.websocket.send(Message::Text(text.into()))?;
+        Ok(())
+    }
+
+    fn send_binary(&mut self, frame: Vec<u8>) -> Result<(), Error> {
+        self.websocket.send(Message::Binary(frame.into()))?;
+        Ok(())
+    }
+
+    fn send_status(&mut self, level: StatusLevel, message: String) -> Result<(), Error> {
+        self.send_text(&ServerMessage::Status { level, message })
+    }
+}
+
+fn timestamp(date_time: DateTime<Utc>) -> u64 {
+    date_time.timestamp_nanos_opt().unwrap_or_default().max(0) as u64
+}
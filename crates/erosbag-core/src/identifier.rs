@@ -1,3 +1,4 @@
+use serde::Serialize;
 use std::fmt;
 
 /// Dedicated type for an identifier of a topic.
@@ -73,9 +74,50 @@ impl From<usize> for ChunkId {
 }
 
 /// Dedicated type for an identifier of a channel.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(transparent)]
 pub struct ChannelTopic(String);
 
+impl ChannelTopic {
+    /// Returns true, if the topic matches the pattern, where `*` matches any sequence of
+    /// characters and `?` matches a single character.
+    pub fn matches_pattern(&self, pattern: &str) -> bool {
+        let topic: Vec<char> = self.0.chars().collect();
+        let pattern: Vec<char> = pattern.chars().collect();
+
+        let (mut topic_index, mut pattern_index) = (0, 0);
+        let mut backtrack: Option<(usize, usize)> = None;
+        while topic_index < topic.len() {
+            match pattern.get(pattern_index) {
+                Some('*') => {
+                    backtrack = Some((pattern_index, topic_index));
+                    pattern_index += 1;
+                }
+                Some(c) if *c == '?' || *c == topic[topic_index] => {
+                    topic_index += 1;
+                    pattern_index += 1;
+                }
+                _ => match backtrack {
+                    Some((star_pattern_index, star_topic_index)) => {
+                        pattern_index = star_pattern_index + 1;
+                        topic_index = star_topic_index + 1;
+                        backtrack = Some((star_pattern_index, star_topic_index + 1));
+                    }
+                    None => return false,
+                },
+            }
+        }
+
+        pattern[pattern_index..].iter().all(|x| *x == '*')
+    }
+
+    /// Returns true, if the topic is a `tf_static` topic, whose transforms are published once
+    /// and stay valid for the whole recording.
+    pub fn is_static_transform_topic(&self) -> bool {
+        self.0.rsplit('/').next() == Some("tf_static")
+    }
+}
+
 impl fmt::Display for ChannelTopic {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.0)
@@ -128,16 +170,16 @@ impl From<&u16> for ChannelId {
     }
 }
 
-/// Dedicated type for an identifier of a message.
+/// Dedicated type for an identifier of a message, which is its index within the chunk.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
-pub struct MessageId(u16);
+pub struct MessageId(u64);
 
 impl MessageId {
     /// The minimum possible value for a `MessageId`.
-    pub const MIN: Self = MessageId(u16::MIN);
+    pub const MIN: Self = MessageId(u64::MIN);
 
     /// The maximum possible value for a `MessageId`.
-    pub const MAX: Self = MessageId(u16::MAX);
+    pub const MAX: Self = MessageId(u64::MAX);
 }
 
 impl fmt::Display for MessageId {
@@ -146,14 +188,14 @@ impl fmt::Display for MessageId {
     }
 }
 
-impl From<MessageId> for u16 {
+impl From<MessageId> for u64 {
     fn from(item: MessageId) -> Self {
         item.0
     }
 }
 
-impl From<u16> for MessageId {
-    fn from(item: u16) -> Self {
+impl From<u64> for MessageId {
+    fn from(item: u64) -> Self {
         Self(item)
     }
 }
@@ -166,6 +208,6 @@ impl From<MessageId> for usize {
 
 impl From<usize> for MessageId {
     fn from(item: usize) -> Self {
-        Self(item as u16)
+        Self(item as u64)
     }
 }
@@ -1,10 +1,19 @@
 mod bagfile;
 pub mod dto;
 pub mod error;
+mod file_compression;
+pub mod filter;
+pub mod foxglove;
 pub mod identifier;
 mod mcap;
 mod mcap_file;
+mod mcap_source;
+pub mod playback;
+pub mod ros1;
 pub mod ros_messages;
+pub mod storage;
+pub mod sync;
+pub mod table;
 pub mod topics;
 
 #[doc(inline)]
@@ -37,5 +46,15 @@ pub use mcap::Rosbag;
 #[doc(inline)]
 pub use mcap_file::McapFile;
 
+#[doc(inline)]
+pub use mcap_source::{BytesSource, HttpSource, McapSource, MmapSource, ReaderSource};
+
+/// Serialization of arrays with more than 32 elements, which is used by the message structs
+/// generated with erosbag-codegen.
+#[doc(hidden)]
+pub use serde_big_array;
+
 pub const MCAP_EXTENSION: &str = "mcap";
 pub const SQLITE3_EXTENSION: &str = "db3";
+pub const ROS1_BAG_EXTENSION: &str = "bag";
+pub const ZSTD_EXTENSION: &str = "zstd";
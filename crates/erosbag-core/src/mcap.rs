@@ -1,32 +1,76 @@
 use crate::Error::ContainsNoMcapFileWithName;
-use crate::dto::{McapFileOverview, McapMessagePage, McapOverview};
+use crate::dto::{
+    McapFileOverview, McapMessageMeta, McapMessagePage, McapOverview, MessageObservation,
+    QualityReport, TransformReport,
+};
+use crate::file_compression::{
+    check_file_compression, decompressed_file_path, uncompressed_file_path,
+};
+use crate::filter::PointFilter;
 use crate::identifier::{ChannelId, FileName};
 use crate::mcap_file::McapFile;
-use crate::ros_messages::RosMessageType;
+use crate::mcap_source::{BytesSource, McapSource, MmapSource};
+use crate::ros_messages::{
+    AnyMessage, POINT_CLOUD_MESSAGE_TYPES, ROS_MESSAGE_TYPES, RosMessage, RosMessageType, tf2_msgs,
+};
+use crate::ros1::Ros1Bag;
+use crate::storage::BagMetadata;
+use crate::sync::{MessageSynchronizer, SyncMessage, SyncPolicy};
+use crate::table::MessageQuery;
 use crate::{ChannelTopic, ChunkId, Error, MCAP_EXTENSION, dto};
 use chrono::{DateTime, Utc};
-use ecoord::TransformTree;
+use ecoord::{FrameId, TransformTree};
 use itertools::Itertools;
+use polars::prelude::DataFrame;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
+/// Bag of MCAP files, which are memory-mapped files of a directory unless it is created from
+/// other sources.
 #[derive(Debug)]
-pub struct Rosbag {
+pub struct Rosbag<S: McapSource = MmapSource> {
+    /// Directory of the files, which is empty for bags created from sources.
     pub directory_path: PathBuf,
-    pub mcap_files: HashMap<FileName, McapFile>,
+    pub mcap_files: HashMap<FileName, McapFile<S>>,
 }
 
 impl Rosbag {
+    /// Opens the MCAP files of a bag directory, which are listed by the `metadata.yaml` file if
+    /// present.
+    ///
+    /// Files compressed as a whole, such as `rosbag2_0.mcap.zstd`, are decompressed to a cache
+    /// directory once and queried like uncompressed files.
     pub fn new(directory_path: impl AsRef<Path>) -> Result<Self, Error> {
-        let mcap_files: Vec<McapFile> = std::fs::read_dir(&directory_path)?
-            .filter_map(|x| x.ok())
-            .filter(|x| x.path().is_file())
-            .filter(|x| x.path().extension() == Some(std::ffi::OsStr::new(MCAP_EXTENSION)))
-            .sorted_by_key(|x| x.path())
+        let metadata = BagMetadata::from_directory(&directory_path)?;
+        if let Some(metadata) = &metadata {
+            check_file_compression(metadata)?;
+        }
+        let file_paths: Vec<PathBuf> = match metadata {
+            Some(x) if !x.rosbag2_bagfile_information.relative_file_paths.is_empty() => {
+                x.file_paths(&directory_path)
+            }
+            _ => std::fs::read_dir(&directory_path)?
+                .filter_map(|x| x.ok())
+                .map(|x| x.path())
+                .filter(|x| x.is_file())
+                .collect(),
+        };
+
+        let mcap_files: Vec<McapFile> = file_paths
+            .into_iter()
+            .filter(|x| {
+                uncompressed_file_path(x).extension() == Some(std::ffi::OsStr::new(MCAP_EXTENSION))
+            })
+            .sorted()
             .map(|x| {
                 McapFile::new(
-                    x.path().file_stem().unwrap().to_str().unwrap().into(),
-                    x.path(),
+                    uncompressed_file_path(&x)
+                        .file_stem()
+                        .unwrap()
+                        .to_str()
+                        .unwrap()
+                        .into(),
+                    decompressed_file_path(&x)?,
                 )
             })
             .collect::<Result<Vec<_>, Error>>()?;
@@ -39,6 +83,39 @@ impl Rosbag {
                 .collect(),
         })
     }
+}
+
+impl Rosbag<BytesSource> {
+    /// Reads a ROS1 bag by converting it to an MCAP file in memory, which is named after the
+    /// ROS1 bag, such as `drive` for `drive.bag`.
+    pub fn from_ros1_bag(ros1_bag_path: impl AsRef<Path>) -> Result<Self, Error> {
+        let ros1_bag = Ros1Bag::new(&ros1_bag_path)?;
+        let file_stem = ros1_bag_path
+            .as_ref()
+            .file_stem()
+            .ok_or_else(|| Error::InvalidRos1Bag("path has no file name".to_string()))?;
+
+        Ok(Self::from_sources([(
+            file_stem.to_string_lossy().as_ref().into(),
+            BytesSource::new(ros1_bag.convert_to_bytes()?),
+        )]))
+    }
+}
+
+impl<S: McapSource> Rosbag<S> {
+    /// Creates a bag of MCAP files read from the sources, such as files held in memory or
+    /// requested from an HTTP server.
+    pub fn from_sources(sources: impl IntoIterator<Item = (FileName, S)>) -> Self {
+        Self {
+            directory_path: PathBuf::new(),
+            mcap_files: sources
+                .into_iter()
+                .map(|(file_name, source)| {
+                    (file_name.clone(), McapFile::from_source(file_name, source))
+                })
+                .collect(),
+        }
+    }
 
     pub fn get_file_ids(&self) -> HashSet<FileName> {
         self.mcap_files.keys().cloned().sorted().collect()
@@ -76,9 +153,9 @@ impl Rosbag {
         let contains_channel = self
             .mcap_files
             .values()
-            .map(|x| {
-                let channel_id = x.get_channel_id(channel_topic)?;
-                x.contains_channel(channel_id)
+            .map(|x| match x.find_channel_id(channel_topic)? {
+                Some(channel_id) => x.contains_channel(channel_id),
+                None => Ok(false),
             })
             .collect::<Result<Vec<bool>, Error>>()?;
 
@@ -103,9 +180,9 @@ impl Rosbag {
         let start_date_times: Vec<DateTime<Utc>> = self
             .mcap_files
             .values()
-            .map(|x| {
-                let channel_id = x.get_channel_id(channel_topic)?;
-                x.get_start_date_time_of_channel(channel_id)
+            .map(|x| match x.find_channel_id(channel_topic)? {
+                Some(channel_id) => x.get_start_date_time_of_channel(channel_id),
+                None => Ok(None),
             })
             .collect::<Result<Vec<_>, Error>>()?
             .into_iter()
@@ -146,9 +223,9 @@ impl Rosbag {
         let end_date_times: Vec<DateTime<Utc>> = self
             .mcap_files
             .values()
-            .map(|x| {
-                let channel_id = x.get_channel_id(channel_topic)?;
-                x.get_end_date_time_of_channel(channel_id)
+            .map(|x| match x.find_channel_id(channel_topic)? {
+                Some(channel_id) => x.get_end_date_time_of_channel(channel_id),
+                None => Ok(None),
             })
             .collect::<Result<Vec<_>, Error>>()?
             .into_iter()
@@ -186,7 +263,7 @@ impl Rosbag {
         &self,
         start_date_time: &Option<DateTime<Utc>>,
         end_date_time: &Option<DateTime<Utc>>,
-    ) -> Vec<&McapFile> {
+    ) -> Vec<&McapFile<S>> {
         self.mcap_files
             .values()
             .filter(|x| {
@@ -219,55 +296,307 @@ impl Rosbag {
             .get(file_name)
             .ok_or(ContainsNoMcapFileWithName(file_name.clone()))?;
 
+        // the channels of a bag need not be present in each of its files
         let channel_ids: Option<HashSet<ChannelId>> = channel_topics
             .as_ref()
-            .map(|x| file.get_channel_ids(x))
+            .map(|x| file.find_channel_ids(x))
             .transpose()?;
 
         let pages = vec![file.read_chunks_with_ids(chunk_ids, &channel_ids)?];
         Ok(McapMessagePage::combine(pages))
     }
 
+    /// Returns the transforms of optionally selected channels for a time window between
+    /// start_date_time (inclusive) and end_date_time (exclusive).
+    ///
+    /// The transforms of `tf_static` channels are read from the whole bag, since they are usually
+    /// published once at the start of the recording and stay valid afterwards.
     pub fn get_transforms(
         &self,
         start_date_time: &Option<DateTime<Utc>>,
         end_date_time: &Option<DateTime<Utc>>,
         channel_topics: &Option<HashSet<ChannelTopic>>,
     ) -> Result<TransformTree, Error> {
-        let combined_page = self.get_message_page_with_type_fallback(
-            start_date_time,
-            end_date_time,
+        let relevant_channel_topics = self.get_channel_topics_with_type_fallback(
             channel_topics,
-            RosMessageType::Tf2MessagesTFMessage,
+            &[RosMessageType::Tf2MessagesTFMessage],
         )?;
+        let (static_channel_topics, dynamic_channel_topics): (HashSet<_>, HashSet<_>) =
+            relevant_channel_topics
+                .into_iter()
+                .partition(|x| x.is_static_transform_topic());
+
+        let dynamic_page =
+            self.get_message_page(start_date_time, end_date_time, &dynamic_channel_topics)?;
+        let static_page = self.get_message_page(&None, &None, &static_channel_topics)?;
+        let combined_page = McapMessagePage::combine(vec![dynamic_page, static_page]);
         let transform_tree = combined_page.get_all_transform_tree()?;
         Ok(transform_tree)
     }
 
+    /// Returns the poses of a `PoseStamped`, `PoseWithCovarianceStamped`, `Odometry` or `Path`
+    /// channel for a time window between start_date_time (inclusive) and end_date_time
+    /// (exclusive) as transform tree, where the poses describe the transform to child_frame_id.
+    pub fn get_pose_transforms(
+        &self,
+        start_date_time: &Option<DateTime<Utc>>,
+        end_date_time: &Option<DateTime<Utc>>,
+        channel_topic: &ChannelTopic,
+        child_frame_id: FrameId,
+    ) -> Result<TransformTree, Error> {
+        let page = self.get_message_page(
+            start_date_time,
+            end_date_time,
+            &HashSet::from([channel_topic.clone()]),
+        )?;
+        let transform_edges = page.get_pose_transform_edges(channel_topic, child_frame_id)?;
+
+        let transform_tree = TransformTree::new(transform_edges, Vec::new())?;
+        Ok(transform_tree)
+    }
+
+    /// Checks the transforms of optionally selected channels for a time window between
+    /// start_date_time (inclusive) and end_date_time (exclusive) for consistency.
+    ///
+    /// The frame ids of the point cloud and image channels are taken from their first chunk and
+    /// checked for reachability from the target frame.
+    pub fn check_transforms(
+        &self,
+        start_date_time: &Option<DateTime<Utc>>,
+        end_date_time: &Option<DateTime<Utc>>,
+        channel_topics: &Option<HashSet<ChannelTopic>>,
+        target_frame_id: Option<FrameId>,
+        max_gap_duration: chrono::Duration,
+    ) -> Result<TransformReport, Error> {
+        let transform_page = self.get_message_page_with_type_fallback(
+            start_date_time,
+            end_date_time,
+            channel_topics,
+            &[RosMessageType::Tf2MessagesTFMessage],
+        )?;
+
+        let overview = self.get_overview()?;
+        let sensor_channel_topics: HashSet<ChannelTopic> = POINT_CLOUD_MESSAGE_TYPES
+            .iter()
+            .chain([RosMessageType::SensorMessagesImage].iter())
+            .flat_map(|x| overview.get_channel_topics_of_message_type(*x))
+            .collect();
+        let sensor_page = self.get_message_page_of_first_chunk_per_channel_topic(
+            start_date_time,
+            end_date_time,
+            &sensor_channel_topics,
+        )?;
+        let mut sensor_frame_ids = sensor_page.point_cloud_frame_ids_by_channel()?;
+        sensor_frame_ids.extend(sensor_page.image_frame_ids_by_channel()?);
+
+        let report = TransformReport::new(
+            &transform_page.get_messages::<tf2_msgs::TFMessage>()?,
+            &sensor_frame_ids,
+            target_frame_id,
+            max_gap_duration,
+        );
+        Ok(report)
+    }
+
+    /// Checks the data quality of optionally selected channels for a time window between
+    /// start_date_time (inclusive) and end_date_time (exclusive).
+    ///
+    /// The files are read in the order of their start times and their chunks in the order they
+    /// are stored, so that log times running backwards are detected. Messages of supported types
+    /// are decoded to check their header stamps and sizes, where messages failing to decode are
+    /// reported as issues.
+    pub fn quality_report(
+        &self,
+        start_date_time: &Option<DateTime<Utc>>,
+        end_date_time: &Option<DateTime<Utc>>,
+        channel_topics: &Option<HashSet<ChannelTopic>>,
+        max_gap_duration: chrono::Duration,
+    ) -> Result<QualityReport, Error> {
+        let overview = self.get_overview()?;
+
+        let mut observations: BTreeMap<
+            ChannelTopic,
+            (Option<RosMessageType>, Vec<MessageObservation>),
+        > = BTreeMap::new();
+        // the files of a split bag are read in the order they were recorded
+        let files = overview
+            .files
+            .iter()
+            .sorted_by_key(|(file_name, file_overview)| {
+                (
+                    file_overview
+                        .get_start_date_time()
+                        .unwrap_or(DateTime::<Utc>::MAX_UTC),
+                    (*file_name).clone(),
+                )
+            });
+        for (file_name, file_overview) in files {
+            let file = self
+                .mcap_files
+                .get(file_name)
+                .ok_or(ContainsNoMcapFileWithName(file_name.clone()))?;
+            let channel_ids: HashSet<ChannelId> = match channel_topics {
+                Some(channel_topics) => file_overview.get_channel_ids_from_topics(channel_topics),
+                None => file_overview.get_channel_ids(),
+            };
+            for (_, current_channel) in file_overview
+                .channels
+                .iter()
+                .filter(|(id, _)| channel_ids.contains(id))
+            {
+                observations
+                    .entry(current_channel.topic.clone())
+                    .or_insert_with(|| (current_channel.ros_message_type, Vec::new()));
+            }
+
+            let chunk_ids = file_overview
+                .get_chunk_ids_containing_channel_ids(start_date_time, end_date_time, &channel_ids)
+                .into_iter()
+                .sorted();
+            for current_chunk_id in chunk_ids {
+                for current_message in
+                    file.read_chunk_messages(current_chunk_id, &Some(channel_ids.clone()))?
+                {
+                    if start_date_time.is_some_and(|start| current_message.log_date_time < start)
+                        || end_date_time.is_some_and(|end| end <= current_message.log_date_time)
+                    {
+                        continue;
+                    }
+                    let (message_type, channel_observations) = observations
+                        .get_mut(&current_message.channel_topic)
+                        .expect("observations should contain all selected channels");
+                    let data = &current_message.message.data;
+                    let (message, decoding_error) =
+                        match message_type.map(|x| AnyMessage::deserialize(x, data)) {
+                            Some(Ok(message)) => (Some(message), None),
+                            Some(Err(error)) => (None, Some(error.to_string())),
+                            None => (None, None),
+                        };
+                    channel_observations.push(
+                        MessageObservation::new(
+                            current_message.log_date_time,
+                            data,
+                            message.as_ref(),
+                        )
+                        .with_decoding_error(decoding_error),
+                    );
+                }
+            }
+        }
+
+        let report = QualityReport::new(&observations, max_gap_duration);
+        Ok(report)
+    }
+
     /// Returns the point cloud of optionally selected channels for a time window between
     /// start_date_time (inclusive) and end_date_time (exclusive).
+    ///
+    /// With source columns, each point carries the channel topic, file name, chunk id, message id
+    /// and log time of its message.
     pub fn get_point_clouds(
         &self,
         start_date_time: &Option<DateTime<Utc>>,
         end_date_time: &Option<DateTime<Utc>>,
         channel_topics: &Option<HashSet<ChannelTopic>>,
+        with_source_columns: bool,
     ) -> Result<epoint::PointCloud, Error> {
         let combined_page = self.get_message_page_with_type_fallback(
             start_date_time,
             end_date_time,
             channel_topics,
-            RosMessageType::SensorMessagesPointCloud2,
+            &POINT_CLOUD_MESSAGE_TYPES,
         )?;
         let point_cloud = combined_page.get_point_cloud_messages_combined(
             start_date_time,
             end_date_time,
             channel_topics,
+            with_source_columns,
         )?;
         Ok(point_cloud)
     }
 
+    /// Returns the point clouds of optionally selected channels for a time window between
+    /// start_date_time (inclusive) and end_date_time (exclusive) divided into groups, such as
+    /// one point cloud per message or per time slice.
+    ///
+    /// The filters are applied to the point cloud of each message before grouping, where the
+    /// bounding box filters use the transforms of all tf channels within the time window.
+    pub fn get_point_cloud_groups(
+        &self,
+        start_date_time: &Option<DateTime<Utc>>,
+        end_date_time: &Option<DateTime<Utc>>,
+        channel_topics: &Option<HashSet<ChannelTopic>>,
+        filters: &[PointFilter],
+        grouping: &dto::PointCloudGrouping,
+        with_source_columns: bool,
+    ) -> Result<Vec<dto::PointCloudGroup>, Error> {
+        grouping.validate()?;
+        filters.iter().try_for_each(|x| x.validate())?;
+        let combined_page = self.get_message_page_with_type_fallback(
+            start_date_time,
+            end_date_time,
+            channel_topics,
+            &POINT_CLOUD_MESSAGE_TYPES,
+        )?;
+        let filter_transform_tree = if filters.iter().any(|x| x.requires_transforms()) {
+            self.get_transforms(start_date_time, end_date_time, &None)?
+        } else {
+            TransformTree::default()
+        };
+        let point_cloud_groups = combined_page.get_point_cloud_groups(
+            start_date_time,
+            end_date_time,
+            channel_topics,
+            filters,
+            &filter_transform_tree,
+            grouping,
+            with_source_columns,
+        )?;
+        Ok(point_cloud_groups)
+    }
+
+    /// Returns the back-projected point cloud of a depth image channel for a time window between
+    /// start_date_time (inclusive) and end_date_time (exclusive).
+    ///
+    /// The 16-bit depth values are multiplied with depth_scale (typically 0.001 for millimeters)
+    /// and, if a color channel is provided, the points are colored with the aligned color image.
+    pub fn get_depth_point_clouds(
+        &self,
+        start_date_time: &Option<DateTime<Utc>>,
+        end_date_time: &Option<DateTime<Utc>>,
+        depth_channel_topic: &ChannelTopic,
+        camera_info_channel_topic: &ChannelTopic,
+        color_channel_topic: &Option<ChannelTopic>,
+        depth_scale: f64,
+    ) -> Result<epoint::PointCloud, Error> {
+        let mut channel_topics: HashSet<ChannelTopic> = HashSet::from([
+            depth_channel_topic.clone(),
+            camera_info_channel_topic.clone(),
+        ]);
+        channel_topics.extend(color_channel_topic.clone());
+        let page = self.get_message_page(start_date_time, end_date_time, &channel_topics)?;
+
+        let point_clouds: Vec<epoint::PointCloud> = page
+            .get_depth_point_cloud_messages(
+                start_date_time,
+                end_date_time,
+                depth_channel_topic,
+                camera_info_channel_topic,
+                color_channel_topic,
+                depth_scale,
+            )?
+            .into_iter()
+            .map(|x| x.message)
+            .collect();
+
+        let mut point_cloud = epoint::transform::merge(point_clouds)?;
+        point_cloud.transform_tree = self.get_transforms(start_date_time, end_date_time, &None)?;
+        Ok(point_cloud)
+    }
+
     /// Returns the point cloud with transforms of optionally selected channels for a time window between
     /// start_date_time (inclusive) and end_date_time (exclusive).
+    #[allow(clippy::too_many_arguments)]
     pub fn get_point_clouds_with_transforms(
         &self,
         point_cloud_start_date_time: &Option<DateTime<Utc>>,
@@ -276,11 +605,13 @@ impl Rosbag {
         transforms_start_date_time: &Option<DateTime<Utc>>,
         transforms_end_date_time: &Option<DateTime<Utc>>,
         transforms_channel_topics: &Option<HashSet<ChannelTopic>>,
+        with_source_columns: bool,
     ) -> Result<epoint::PointCloud, Error> {
         let mut point_cloud = self.get_point_clouds(
             point_cloud_start_date_time,
             point_cloud_end_date_time,
             point_cloud_channel_topics,
+            with_source_columns,
         )?;
 
         point_cloud.transform_tree = self.get_transforms(
@@ -304,12 +635,132 @@ impl Rosbag {
             start_date_time,
             end_date_time,
             channel_topics,
-            RosMessageType::SensorMessagesImage,
+            &[RosMessageType::SensorMessagesImage],
         )?;
         let image_collection = combined_page.get_all_images()?;
         Ok(image_collection)
     }
 
+    /// Returns a table per channel for optionally selected channels for a time window between
+    /// start_date_time (inclusive) and end_date_time (exclusive).
+    ///
+    /// Each table contains one row per message, where nested message fields are flattened to
+    /// dotted columns.
+    pub fn get_data_frames(
+        &self,
+        start_date_time: &Option<DateTime<Utc>>,
+        end_date_time: &Option<DateTime<Utc>>,
+        channel_topics: &Option<HashSet<ChannelTopic>>,
+    ) -> Result<HashMap<ChannelTopic, DataFrame>, Error> {
+        let page = self.get_message_page_with_type_fallback(
+            start_date_time,
+            end_date_time,
+            channel_topics,
+            &ROS_MESSAGE_TYPES,
+        )?;
+
+        page.get_data_frames(start_date_time, end_date_time)
+    }
+
+    /// Returns the query result per channel with at least one resulting row.
+    ///
+    /// Only the chunks overlapping the query's time window and containing the selected
+    /// channels are read.
+    pub fn query(&self, query: &MessageQuery) -> Result<HashMap<ChannelTopic, DataFrame>, Error> {
+        let overview = self.get_overview()?;
+        let available_channel_topics: HashSet<ChannelTopic> = ROS_MESSAGE_TYPES
+            .iter()
+            .flat_map(|x| overview.get_channel_topics_of_message_type(*x))
+            .collect();
+        let channel_topics = query.select_channel_topics(available_channel_topics);
+        if channel_topics.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let page = self.get_message_page(
+            &query.start_date_time,
+            &query.end_date_time,
+            &channel_topics,
+        )?;
+        query.evaluate(&page)
+    }
+
+    /// Returns an iterator over groups of time-aligned messages of the channels for a time window
+    /// between start_date_time (inclusive) and end_date_time (exclusive).
+    ///
+    /// Each group contains one message per channel in the order of the provided channel topics,
+    /// which is decoded as `T`, such as [`crate::ros_messages::AnyMessage`].
+    pub fn synchronize<T: SyncMessage>(
+        &self,
+        channel_topics: Vec<ChannelTopic>,
+        policy: SyncPolicy,
+        start_date_time: &Option<DateTime<Utc>>,
+        end_date_time: &Option<DateTime<Utc>>,
+    ) -> Result<MessageSynchronizer<'_, T, S>, Error> {
+        MessageSynchronizer::new(self, channel_topics, policy, start_date_time, end_date_time)
+    }
+
+    /// Returns the messages decoded as `T` of optionally selected channels for a time window
+    /// between start_date_time (inclusive) and end_date_time (exclusive) ordered by log time.
+    ///
+    /// Without a selection, all channels with the schema of `T` are read. Only the messages of
+    /// these channels are decoded, so that `T` can be any built-in or user-defined message.
+    pub fn messages<T: RosMessage>(
+        &self,
+        channel_topics: &Option<HashSet<ChannelTopic>>,
+        start_date_time: &Option<DateTime<Utc>>,
+        end_date_time: &Option<DateTime<Utc>>,
+    ) -> Result<Vec<McapMessageMeta<T>>, Error> {
+        let overview = self.get_overview()?;
+
+        let mut messages: Vec<McapMessageMeta<T>> = Vec::new();
+        for current_file in self.get_file_within_date_times(start_date_time, end_date_time) {
+            let schema_channel_ids = current_file.get_channel_ids_of_schema_name(T::SCHEMA_NAME)?;
+            let channel_ids: HashSet<ChannelId> = match channel_topics {
+                Some(channel_topics) => {
+                    let selected_channel_ids: HashSet<ChannelId> = channel_topics
+                        .iter()
+                        .filter_map(|x| current_file.get_channel_id(x).ok())
+                        .collect();
+                    if let Some(channel_id) =
+                        selected_channel_ids.difference(&schema_channel_ids).next()
+                    {
+                        return Err(Error::ChannelDoesNotHoldSchema(
+                            current_file.get_channel_topic(*channel_id)?,
+                            T::SCHEMA_NAME.to_string(),
+                        ));
+                    }
+                    selected_channel_ids
+                }
+                None => schema_channel_ids,
+            };
+            if channel_ids.is_empty() {
+                continue;
+            }
+
+            let chunk_ids = overview
+                .files
+                .get(&current_file.file_name)
+                .expect("overview should contain all files")
+                .get_chunk_ids_containing_channel_ids(start_date_time, end_date_time, &channel_ids);
+            messages.extend(current_file.read_chunks_as::<T>(&chunk_ids, &channel_ids)?);
+        }
+
+        messages.retain(|x| {
+            start_date_time.is_none_or(|start| start <= x.log_date_time)
+                && end_date_time.is_none_or(|end| x.log_date_time < end)
+        });
+        messages.sort_by(|a, b| {
+            (a.log_date_time, &a.file_name, a.chunk_id, a.message_id).cmp(&(
+                b.log_date_time,
+                &b.file_name,
+                b.chunk_id,
+                b.message_id,
+            ))
+        });
+        Ok(messages)
+    }
+
     pub fn get_message_page(
         &self,
         start_date_time: &Option<DateTime<Utc>>,
@@ -363,16 +814,29 @@ impl Rosbag {
         start_date_time: &Option<DateTime<Utc>>,
         end_date_time: &Option<DateTime<Utc>>,
         channel_topics: &Option<HashSet<ChannelTopic>>,
-        message_type_fallback: RosMessageType,
+        message_types_fallback: &[RosMessageType],
     ) -> Result<McapMessagePage, Error> {
-        let relevant_channel_topics: HashSet<ChannelTopic> =
-            if let Some(channel_topics) = channel_topics {
-                channel_topics.clone()
-            } else {
-                self.get_overview()?
-                    .get_channel_topics_of_message_type(message_type_fallback)
-            };
+        let relevant_channel_topics =
+            self.get_channel_topics_with_type_fallback(channel_topics, message_types_fallback)?;
 
         self.get_message_page(start_date_time, end_date_time, &relevant_channel_topics)
     }
+
+    /// Returns the selected channel topics or, if none are selected, the topics of all channels
+    /// of the fallback message types.
+    fn get_channel_topics_with_type_fallback(
+        &self,
+        channel_topics: &Option<HashSet<ChannelTopic>>,
+        message_types_fallback: &[RosMessageType],
+    ) -> Result<HashSet<ChannelTopic>, Error> {
+        if let Some(channel_topics) = channel_topics {
+            return Ok(channel_topics.clone());
+        }
+
+        let overview = self.get_overview()?;
+        Ok(message_types_fallback
+            .iter()
+            .flat_map(|x| overview.get_channel_topics_of_message_type(*x))
+            .collect())
+    }
 }
@@ -1,47 +1,147 @@
 use crate::dto::{McapFileOverview, McapMessagePage};
 use crate::identifier::{ChannelId, FileName};
-use crate::ros_messages::RosMessageType;
+use crate::mcap_source::{McapSource, MmapSource};
+use crate::ros_messages::{RosMessage, RosMessageType};
 use crate::{ChannelTopic, ChunkId, Error, MessageId, dto};
 use chrono::{DateTime, TimeZone, Utc};
 use itertools::Itertools;
 use mcap::records::Statistics;
 use mcap::{Summary, records};
-use memmap::Mmap;
 use rayon::iter::ParallelIterator;
 use rayon::prelude::IntoParallelIterator;
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::path::Path;
 use std::str::FromStr;
+use std::sync::OnceLock;
 
-fn map_mcap(file_path: impl AsRef<Path>) -> Mmap {
-    let fd = std::fs::File::open(file_path).expect("Couldn't open MCAP file");
-    unsafe { Mmap::map(&fd) }.expect("Couldn't mmap MCAP file")
-}
+/// Length of the footer record including its opcode and length prefix.
+const FOOTER_RECORD_LENGTH: u64 = 1 + 8 + 8 + 8 + 4;
+const FOOTER_OPCODE: u8 = 0x02;
 
-#[derive(Debug)]
-pub struct McapFile {
+/// MCAP file, which is read from a random-access source.
+///
+/// By default, the file is memory-mapped from a local path. Only the summary and the requested
+/// chunks are read from the source, and the summary is read once.
+pub struct McapFile<S: McapSource = MmapSource> {
     pub(crate) file_name: FileName,
-    pub(crate) mapped: Mmap,
+    pub(crate) source: S,
+    summary: OnceLock<Option<Summary>>,
+}
+
+impl<S: McapSource> fmt::Debug for McapFile<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("McapFile")
+            .field("file_name", &self.file_name)
+            .field("source", &self.source)
+            .finish_non_exhaustive()
+    }
 }
 
 impl McapFile {
+    /// Memory-maps the MCAP file at the path.
     pub fn new(id: FileName, file_path: impl AsRef<Path>) -> Result<Self, Error> {
-        let mapped = map_mcap(&file_path);
+        let source = MmapSource::new(file_path)?;
+        Ok(Self::from_source(id, source))
+    }
+}
 
-        Ok(Self {
+impl<S: McapSource> McapFile<S> {
+    /// Reads the MCAP file from a source, such as in-memory bytes or a file on an HTTP server.
+    pub fn from_source(id: FileName, source: S) -> Self {
+        Self {
             file_name: id,
-            mapped,
-        })
+            source,
+            summary: OnceLock::new(),
+        }
     }
 
-    pub(crate) fn summary(&self) -> Result<Option<Summary>, Error> {
-        let summary = mcap::Summary::read(&self.mapped)?;
+    pub fn file_name(&self) -> &FileName {
+        &self.file_name
+    }
+
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    /// Returns all bytes of the file.
+    pub(crate) fn data(&self) -> Result<Cow<'_, [u8]>, Error> {
+        self.source.read_range(0, self.source.len()?)
+    }
+
+    pub(crate) fn summary(&self) -> Result<Option<&Summary>, Error> {
+        if let Some(summary) = self.summary.get() {
+            return Ok(summary.as_ref());
+        }
+        let summary = self.read_summary()?;
+        Ok(self.summary.get_or_init(|| summary).as_ref())
+    }
+
+    /// Reads the summary section, which is located by the footer at the end of the file.
+    ///
+    /// Only the footer and the summary section are read from the source. They are assembled to a
+    /// compact file, whose footer points to the copied summary section, so that the summary can
+    /// be parsed without the data section.
+    fn read_summary(&self) -> Result<Option<Summary>, Error> {
+        let file_length = self.source.len()?;
+        let magic_length = mcap::MAGIC.len() as u64;
+        let tail_length = FOOTER_RECORD_LENGTH + magic_length;
+        if file_length < magic_length + tail_length {
+            // too short for a footer, so that the parser reports the error
+            return Ok(mcap::Summary::read(&self.data()?)?);
+        }
+
+        let footer_start = file_length - tail_length;
+        let tail = self.source.read_range(footer_start, tail_length)?;
+        if tail[0] != FOOTER_OPCODE || !tail.ends_with(mcap::MAGIC) {
+            return Ok(mcap::Summary::read(&tail)?);
+        }
+        let read_u64 = |offset: usize| {
+            u64::from_le_bytes(
+                tail[offset..offset + 8]
+                    .try_into()
+                    .expect("should be 8 bytes"),
+            )
+        };
+        let summary_start = read_u64(9);
+        let summary_offset_start = read_u64(17);
+        if summary_start == 0 {
+            return Ok(None);
+        }
+        if summary_start < magic_length
+            || summary_start > footer_start
+            || (summary_offset_start != 0
+                && !(summary_start..=footer_start).contains(&summary_offset_start))
+        {
+            return Err(Error::InvalidMcapFooter(self.file_name.clone()));
+        }
+
+        let summary_section = self
+            .source
+            .read_range(summary_start, footer_start - summary_start)?;
+        let compact_summary_offset_start = match summary_offset_start {
+            0 => 0,
+            x => x - summary_start + magic_length,
+        };
+        let mut compact_file =
+            Vec::with_capacity(summary_section.len() + (magic_length + tail_length) as usize);
+        compact_file.extend_from_slice(mcap::MAGIC);
+        compact_file.extend_from_slice(&summary_section);
+        compact_file.extend_from_slice(&tail[..9]);
+        compact_file.extend_from_slice(&magic_length.to_le_bytes());
+        compact_file.extend_from_slice(&compact_summary_offset_start.to_le_bytes());
+        // a CRC of zero skips the validation, since the checksum covers the original offsets
+        compact_file.extend_from_slice(&0u32.to_le_bytes());
+        compact_file.extend_from_slice(mcap::MAGIC);
+
+        let summary = mcap::Summary::read(&compact_file)?;
         Ok(summary)
     }
 
     pub(crate) fn stats(&self) -> Result<Option<Statistics>, Error> {
         let summary = self.summary()?;
-        let stats = summary.and_then(|summary| summary.stats);
+        let stats = summary.and_then(|summary| summary.stats.clone());
         Ok(stats)
     }
 
@@ -63,7 +163,7 @@ impl McapFile {
 
         let chunks: Vec<dto::ChunkOverview> = summary
             .chunk_indexes
-            .into_iter()
+            .iter()
             .enumerate()
             .map(|(current_index, current_chunk_index)| {
                 let id: ChunkId = current_index.into();
@@ -104,7 +204,7 @@ impl McapFile {
 
         let date_time = summary
             .chunk_indexes
-            .into_iter()
+            .iter()
             .filter(|x| x.message_index_offsets.contains_key(&channel_id.into()))
             .min_by_key(|x| x.message_start_time)
             .map(|x| Utc.timestamp_nanos(x.message_start_time as i64));
@@ -120,7 +220,7 @@ impl McapFile {
 
         let date_time = summary
             .chunk_indexes
-            .into_iter()
+            .iter()
             .filter(|x| x.message_index_offsets.contains_key(&channel_id.into()))
             .max_by_key(|x| x.message_end_time)
             .map(|x| Utc.timestamp_nanos(x.message_end_time as i64));
@@ -180,6 +280,34 @@ impl McapFile {
             .collect()
     }
 
+    /// Returns the id of the channel, or `None` if the file has no channel with the topic, such
+    /// as a split file recorded before the channel was advertised.
+    pub fn find_channel_id(
+        &self,
+        channel_topic: &ChannelTopic,
+    ) -> Result<Option<ChannelId>, Error> {
+        let summary = self.summary()?.expect("summary should be present");
+
+        let channel_id = summary
+            .channels
+            .iter()
+            .find(|(_i, x)| x.topic == channel_topic.to_string())
+            .map(|(i, _x)| ChannelId::from(*i));
+        Ok(channel_id)
+    }
+
+    /// Returns the ids of the channels with the topics, which are present in this file.
+    pub fn find_channel_ids(
+        &self,
+        channel_topics: &HashSet<ChannelTopic>,
+    ) -> Result<HashSet<ChannelId>, Error> {
+        let channel_ids = channel_topics
+            .iter()
+            .map(|x| self.find_channel_id(x))
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(channel_ids.into_iter().flatten().collect())
+    }
+
     pub fn get_all_channel_ids(&self) -> Result<HashSet<ChannelId>, Error> {
         let summary = self.summary()?.expect("summary should be present");
 
@@ -191,13 +319,21 @@ impl McapFile {
     pub fn get_channel_ids_of_message_type(
         &self,
         message_type: &RosMessageType,
+    ) -> Result<HashSet<ChannelId>, Error> {
+        self.get_channel_ids_of_schema_name(message_type.as_str())
+    }
+
+    /// Returns the ids of the channels with the schema name, such as `sensor_msgs/msg/Imu`.
+    pub fn get_channel_ids_of_schema_name(
+        &self,
+        schema_name: &str,
     ) -> Result<HashSet<ChannelId>, Error> {
         let summary = self.summary()?.expect("summary should be present");
 
         let channel_ids = summary
             .channels
             .iter()
-            .filter(|(_i, x)| x.schema.clone().unwrap().name.as_str() == message_type.as_str())
+            .filter(|(_i, x)| x.schema.as_ref().is_some_and(|x| x.name == schema_name))
             .map(|(i, _x)| ChannelId::from(*i))
             .collect::<HashSet<ChannelId>>();
         Ok(channel_ids)
@@ -228,9 +364,9 @@ impl McapFile {
     ) -> Result<Vec<ChunkId>, Error> {
         let summary = self.summary()?.unwrap();
 
-        let mut chunk_ids: Vec<(ChunkId, records::ChunkIndex)> = summary
+        let mut chunk_ids: Vec<(ChunkId, &records::ChunkIndex)> = summary
             .chunk_indexes
-            .into_iter()
+            .iter()
             .enumerate()
             .map(|x| (x.0.into(), x.1))
             .collect();
@@ -278,11 +414,65 @@ impl McapFile {
         chunk_id: ChunkId,
         channel_topics: &Option<HashSet<ChannelId>>,
     ) -> Result<McapMessagePage, Error> {
-        let summary = self.summary().expect("should work").unwrap();
-        let chunk_index = summary.chunk_indexes.get::<usize>(chunk_id.into()).unwrap();
-        let mut messages: Vec<(MessageId, mcap::Message)> = summary
-            .stream_chunk(&self.mapped, chunk_index)?
-            .collect::<Result<Vec<_>, _>>()?
+        let messages = self.read_chunk_messages(chunk_id, channel_topics)?;
+
+        let message_page = McapMessagePage::from(messages)?;
+        Ok(message_page)
+    }
+
+    /// Returns the messages of the channels in the chunks decoded as `T`, while the messages of
+    /// all other channels are not decoded.
+    pub(crate) fn read_chunks_as<T: RosMessage>(
+        &self,
+        chunk_ids: &[ChunkId],
+        channel_ids: &HashSet<ChannelId>,
+    ) -> Result<Vec<dto::McapMessageMeta<T>>, Error> {
+        let channel_ids = Some(channel_ids.clone());
+        let messages_per_chunk = chunk_ids
+            .into_par_iter()
+            .map(|x| {
+                self.read_chunk_messages(*x, &channel_ids)?
+                    .into_iter()
+                    .map(|message| {
+                        let decoded_message = T::deserialize_cdr(&message.message.data)?;
+                        Ok(message.with_message(decoded_message))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(messages_per_chunk.into_iter().flatten().collect())
+    }
+
+    /// Returns the undecoded messages of a chunk in the order they are stored.
+    pub(crate) fn read_chunk_messages(
+        &self,
+        chunk_id: ChunkId,
+        channel_topics: &Option<HashSet<ChannelId>>,
+    ) -> Result<Vec<dto::McapMessageMeta<mcap::Message<'_>>>, Error> {
+        let summary = self.summary()?.expect("summary should be present");
+        let chunk_index = summary
+            .chunk_indexes
+            .get::<usize>(chunk_id.into())
+            .ok_or(Error::ChunkIdNotFound(chunk_id))?;
+        let chunk_data = self
+            .source
+            .read_range(chunk_index.chunk_start_offset, chunk_index.chunk_length)?;
+        // the chunk is streamed from its own bytes, which start at the offset zero
+        let relative_chunk_index = records::ChunkIndex {
+            chunk_start_offset: 0,
+            ..chunk_index.clone()
+        };
+        let messages: Vec<mcap::Message> = match chunk_data {
+            Cow::Borrowed(chunk_data) => summary
+                .stream_chunk(chunk_data, &relative_chunk_index)?
+                .collect::<Result<Vec<_>, _>>()?,
+            Cow::Owned(chunk_data) => summary
+                .stream_chunk(&chunk_data, &relative_chunk_index)?
+                .map(|x| x.map(|message| owned_message(summary, message)))
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+        let mut messages: Vec<(MessageId, mcap::Message)> = messages
             .into_iter()
             .enumerate()
             .map(|(i, x)| (i.into(), x))
@@ -307,7 +497,22 @@ impl McapFile {
             })
             .collect::<Vec<_>>();
 
-        let message_page = McapMessagePage::from(messages)?;
-        Ok(message_page)
+        Ok(messages)
+    }
+}
+
+/// Detaches a message from the bytes of its chunk, which are not kept by the source.
+fn owned_message(summary: &Summary, message: mcap::Message) -> mcap::Message<'static> {
+    let channel = summary
+        .channels
+        .get(&message.channel.id)
+        .cloned()
+        .expect("channel of streamed message should be in the summary");
+    mcap::Message {
+        channel,
+        sequence: message.sequence,
+        log_time: message.log_time,
+        publish_time: message.publish_time,
+        data: Cow::Owned(message.data.into_owned()),
     }
 }
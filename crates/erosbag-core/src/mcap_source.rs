@@ -0,0 +1,260 @@
+//! Random-access sources of MCAP files.
+//!
+//! An [`McapFile`](crate::McapFile) only reads the byte ranges it needs, which are the footer,
+//! the summary section and the requested chunks. This allows a file to be memory-mapped, read
+//! from any seekable reader, held in memory or fetched in parts from a file server.
+
+use crate::Error;
+use memmap::Mmap;
+use std::borrow::Cow;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Random-access source of the bytes of an MCAP file.
+///
+/// Sources are shared between threads, since chunks are read in parallel.
+pub trait McapSource: fmt::Debug + Send + Sync {
+    /// Returns the length of the file in bytes.
+    fn len(&self) -> Result<u64, Error>;
+
+    /// Returns true if the file is empty.
+    fn is_empty(&self) -> Result<bool, Error> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Returns `length` bytes starting at `offset`, which are borrowed if the source holds them
+    /// in memory.
+    fn read_range(&self, offset: u64, length: u64) -> Result<Cow<'_, [u8]>, Error>;
+}
+
+/// Returns the slice of the range or an error if it exceeds the data.
+fn slice_range(data: &[u8], offset: u64, length: u64) -> Result<&[u8], Error> {
+    offset
+        .checked_add(length)
+        .filter(|x| *x <= data.len() as u64)
+        .map(|x| &data[offset as usize..x as usize])
+        .ok_or(Error::InvalidRange(offset, length))
+}
+
+/// Memory-mapped local file.
+pub struct MmapSource {
+    mapped: Mmap,
+}
+
+impl MmapSource {
+    pub fn new(file_path: impl AsRef<Path>) -> Result<Self, Error> {
+        let file = File::open(file_path)?;
+        // SAFETY: the bag files are only read and are expected not to be modified while mapped
+        let mapped = unsafe { Mmap::map(&file) }?;
+        Ok(Self { mapped })
+    }
+}
+
+impl fmt::Debug for MmapSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MmapSource")
+            .field("len", &self.mapped.len())
+            .finish()
+    }
+}
+
+impl McapSource for MmapSource {
+    fn len(&self) -> Result<u64, Error> {
+        Ok(self.mapped.len() as u64)
+    }
+
+    fn read_range(&self, offset: u64, length: u64) -> Result<Cow<'_, [u8]>, Error> {
+        slice_range(&self.mapped, offset, length).map(Cow::Borrowed)
+    }
+}
+
+/// In-memory bytes of a file, such as a bag received over an API.
+#[derive(Clone, PartialEq, Eq)]
+pub struct BytesSource {
+    data: Vec<u8>,
+}
+
+impl BytesSource {
+    pub fn new(data: impl Into<Vec<u8>>) -> Self {
+        Self { data: data.into() }
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+impl From<Vec<u8>> for BytesSource {
+    fn from(data: Vec<u8>) -> Self {
+        Self::new(data)
+    }
+}
+
+impl fmt::Debug for BytesSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BytesSource")
+            .field("len", &self.data.len())
+            .finish()
+    }
+}
+
+impl McapSource for BytesSource {
+    fn len(&self) -> Result<u64, Error> {
+        Ok(self.data.len() as u64)
+    }
+
+    fn read_range(&self, offset: u64, length: u64) -> Result<Cow<'_, [u8]>, Error> {
+        slice_range(&self.data, offset, length).map(Cow::Borrowed)
+    }
+}
+
+/// Seekable reader, such as a buffered file, which is locked while a range is read.
+pub struct ReaderSource<R> {
+    reader: Mutex<R>,
+    len: u64,
+}
+
+impl ReaderSource<BufReader<File>> {
+    /// Opens a local file with a buffered reader.
+    pub fn from_file(file_path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::new(BufReader::new(File::open(file_path)?))
+    }
+}
+
+impl<R: Read + Seek> ReaderSource<R> {
+    pub fn new(mut reader: R) -> Result<Self, Error> {
+        let len = reader.seek(SeekFrom::End(0))?;
+        Ok(Self {
+            reader: Mutex::new(reader),
+            len,
+        })
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<R> fmt::Debug for ReaderSource<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReaderSource")
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl<R: Read + Seek + Send> McapSource for ReaderSource<R> {
+    fn len(&self) -> Result<u64, Error> {
+        Ok(self.len)
+    }
+
+    fn read_range(&self, offset: u64, length: u64) -> Result<Cow<'_, [u8]>, Error> {
+        if offset.checked_add(length).is_none_or(|x| x > self.len) {
+            return Err(Error::InvalidRange(offset, length));
+        }
+
+        let mut reader = self
+            .reader
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        reader.seek(SeekFrom::Start(offset))?;
+        let mut data = vec![0; length as usize];
+        reader.read_exact(&mut data)?;
+        Ok(Cow::Owned(data))
+    }
+}
+
+/// File on an HTTP server, which is read with range requests.
+///
+/// Only the footer, the summary and the requested chunks are fetched, so that a bag can be
+/// inspected without downloading it completely. The server must support range requests.
+#[derive(Debug)]
+pub struct HttpSource {
+    url: String,
+    agent: ureq::Agent,
+    len: u64,
+}
+
+impl HttpSource {
+    /// Requests the length of the file and checks that the server accepts range requests.
+    pub fn new(url: impl Into<String>) -> Result<Self, Error> {
+        Self::with_agent(url, ureq::Agent::new_with_defaults())
+    }
+
+    /// Uses an agent with a custom configuration, such as timeouts or a proxy.
+    pub fn with_agent(url: impl Into<String>, agent: ureq::Agent) -> Result<Self, Error> {
+        let url = url.into();
+        let response = agent.head(&url).call()?;
+        let accepts_ranges = response
+            .headers()
+            .get(ureq::http::header::ACCEPT_RANGES)
+            .and_then(|x| x.to_str().ok())
+            .is_some_and(|x| x.trim() == "bytes");
+        if !accepts_ranges {
+            return Err(Error::HttpRangeRequestsNotSupported(url));
+        }
+        let len = response
+            .headers()
+            .get(ureq::http::header::CONTENT_LENGTH)
+            .and_then(|x| x.to_str().ok())
+            .and_then(|x| x.trim().parse::<u64>().ok())
+            .ok_or_else(|| {
+                Error::InvalidHttpResponse(url.clone(), "missing content length".to_string())
+            })?;
+
+        Ok(Self { url, agent, len })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl McapSource for HttpSource {
+    fn len(&self) -> Result<u64, Error> {
+        Ok(self.len)
+    }
+
+    fn read_range(&self, offset: u64, length: u64) -> Result<Cow<'_, [u8]>, Error> {
+        if offset.checked_add(length).is_none_or(|x| x > self.len) {
+            return Err(Error::InvalidRange(offset, length));
+        }
+        if length == 0 {
+            return Ok(Cow::Owned(Vec::new()));
+        }
+
+        let mut response = self
+            .agent
+            .get(&self.url)
+            .header(
+                ureq::http::header::RANGE,
+                format!("bytes={}-{}", offset, offset + length - 1),
+            )
+            .call()?;
+        if response.status() != ureq::http::StatusCode::PARTIAL_CONTENT {
+            return Err(Error::InvalidHttpResponse(
+                self.url.clone(),
+                format!("expected partial content, got status {}", response.status()),
+            ));
+        }
+
+        let mut data = Vec::with_capacity(length as usize);
+        response
+            .body_mut()
+            .as_reader()
+            .take(length)
+            .read_to_end(&mut data)?;
+        if data.len() as u64 != length {
+            return Err(Error::InvalidHttpResponse(
+                self.url.clone(),
+                format!("expected {} bytes, got {}", length, data.len()),
+            ));
+        }
+        Ok(Cow::Owned(data))
+    }
+}
@@ -0,0 +1,184 @@
+use crate::dto::{McapMessageMeta, McapOverview, RawMessage};
+use crate::mcap_source::{McapSource, MmapSource};
+use crate::{ChannelId, ChannelTopic, ChunkId, Error, FileName, MessageId, Rosbag};
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+type MessageKey = (DateTime<Utc>, FileName, ChunkId, MessageId);
+
+/// Cursor over the messages of a bag in the order of their log times.
+///
+/// Chunks are read one at a time in the order of their start time, where the chunk index of the
+/// overview determines the chunks to read. Seeking therefore only reads the chunks overlapping
+/// the new position instead of all preceding messages.
+#[derive(Debug)]
+pub struct MessageCursor<'a, S: McapSource = MmapSource> {
+    rosbag: &'a Rosbag<S>,
+    overview: McapOverview,
+    channel_topics: Option<HashSet<ChannelTopic>>,
+    date_time: Option<DateTime<Utc>>,
+    /// Key of the last returned message, after which the cursor continues.
+    last_key: Option<MessageKey>,
+    remaining_chunks: VecDeque<(DateTime<Utc>, FileName, ChunkId)>,
+    pending_messages: BTreeMap<MessageKey, McapMessageMeta<RawMessage>>,
+}
+
+impl<'a, S: McapSource> MessageCursor<'a, S> {
+    /// Creates a cursor at the beginning of the bag over the messages of the channels, or of all
+    /// channels if none are selected.
+    pub fn new(
+        rosbag: &'a Rosbag<S>,
+        channel_topics: Option<HashSet<ChannelTopic>>,
+    ) -> Result<Self, Error> {
+        let overview = rosbag.get_overview()?;
+        let mut cursor = Self {
+            rosbag,
+            overview,
+            channel_topics,
+            date_time: None,
+            last_key: None,
+            remaining_chunks: VecDeque::new(),
+            pending_messages: BTreeMap::new(),
+        };
+        cursor.rewind();
+        Ok(cursor)
+    }
+
+    /// Returns the overview of the bag, which was read when creating the cursor.
+    pub fn overview(&self) -> &McapOverview {
+        &self.overview
+    }
+
+    /// Moves the cursor to the first message.
+    pub fn rewind(&mut self) {
+        self.reset(None);
+    }
+
+    /// Moves the cursor to the first message logged at or after the date time.
+    pub fn seek(&mut self, date_time: DateTime<Utc>) {
+        self.reset(Some(date_time));
+    }
+
+    /// Selects the channels of the following messages, or all channels if none are selected,
+    /// where the cursor continues after the last returned message.
+    pub fn select_channel_topics(&mut self, channel_topics: Option<HashSet<ChannelTopic>>) {
+        self.channel_topics = channel_topics;
+        let last_key = self.last_key.take();
+        self.reset(self.date_time);
+        self.last_key = last_key;
+    }
+
+    fn reset(&mut self, date_time: Option<DateTime<Utc>>) {
+        self.date_time = date_time;
+        self.last_key = None;
+        self.pending_messages.clear();
+        self.remaining_chunks = self
+            .overview
+            .files
+            .iter()
+            .flat_map(|(file_name, file_overview)| {
+                file_overview
+                    .chunks
+                    .values()
+                    .filter(|x| date_time.is_none_or(|start| x.end_date_time >= start))
+                    .filter(|x| {
+                        self.channel_topics.as_ref().is_none_or(|channel_topics| {
+                            !x.contained_channel.is_disjoint(
+                                &file_overview.get_channel_ids_from_topics(channel_topics),
+                            )
+                        })
+                    })
+                    .map(|x| (x.start_date_time, file_name.clone(), x.id))
+            })
+            .sorted()
+            .collect();
+    }
+
+    /// Returns the log time of the next message without consuming it.
+    pub fn peek_date_time(&mut self) -> Result<Option<DateTime<Utc>>, Error> {
+        self.fill_pending_messages()?;
+        Ok(self.pending_messages.keys().next().map(|x| x.0))
+    }
+
+    /// Returns the next message and advances the cursor.
+    pub fn next_message(&mut self) -> Result<Option<McapMessageMeta<RawMessage>>, Error> {
+        self.fill_pending_messages()?;
+        let Some((key, message)) = self.pending_messages.pop_first() else {
+            return Ok(None);
+        };
+        self.date_time = Some(message.log_date_time);
+        self.last_key = Some(key);
+        Ok(Some(message))
+    }
+
+    /// Reads chunks until the earliest pending message precedes all remaining chunks, so that no
+    /// earlier message can follow.
+    fn fill_pending_messages(&mut self) -> Result<(), Error> {
+        while let Some((next_start_date_time, _, _)) = self.remaining_chunks.front() {
+            let is_final = self
+                .pending_messages
+                .keys()
+                .next()
+                .is_some_and(|x| x.0 < *next_start_date_time);
+            if is_final {
+                break;
+            }
+            self.read_next_chunk()?;
+        }
+        Ok(())
+    }
+
+    fn read_next_chunk(&mut self) -> Result<(), Error> {
+        let Some((_, file_name, chunk_id)) = self.remaining_chunks.pop_front() else {
+            return Ok(());
+        };
+        let mcap_file = self
+            .rosbag
+            .mcap_files
+            .get(&file_name)
+            .ok_or(Error::ContainsNoMcapFileWithName(file_name.clone()))?;
+        let channel_ids: Option<HashSet<ChannelId>> = self
+            .channel_topics
+            .as_ref()
+            .map(|x| self.overview.files[&file_name].get_channel_ids_from_topics(x));
+
+        for current_message in mcap_file.read_chunk_messages(chunk_id, &channel_ids)? {
+            if self
+                .date_time
+                .is_some_and(|start| current_message.log_date_time < start)
+            {
+                continue;
+            }
+            let key = (
+                current_message.log_date_time,
+                current_message.file_name.clone(),
+                current_message.chunk_id,
+                current_message.message_id,
+            );
+            if self.last_key.as_ref().is_some_and(|x| key <= *x) {
+                continue;
+            }
+            let raw_message = RawMessage::new(
+                current_message
+                    .message
+                    .channel
+                    .schema
+                    .as_ref()
+                    .map(|x| x.name.clone()),
+                current_message.message.data.to_vec(),
+            );
+            self.pending_messages
+                .insert(key, current_message.with_message(raw_message));
+        }
+        Ok(())
+    }
+}
+
+impl<S: McapSource> Iterator for MessageCursor<'_, S> {
+    type Item = Result<McapMessageMeta<RawMessage>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_message().transpose()
+    }
+}
@@ -0,0 +1,38 @@
+//! Playback of bags in the order of the log times of the messages.
+//!
+//! The [`MessageCursor`] reads the undecoded messages and can be moved to any position, while the
+//! [`Player`] replays them paced by their original timing to a [`PlaybackSink`], such as a
+//! callback or a channel, so that processing nodes can run against recordings.
+mod cursor;
+mod player;
+mod sink;
+
+#[doc(inline)]
+pub use cursor::MessageCursor;
+
+#[doc(inline)]
+pub use crate::dto::RawMessage;
+
+#[doc(inline)]
+pub use player::Player;
+
+#[doc(inline)]
+pub use player::PlayerOptions;
+
+#[doc(inline)]
+pub use player::SimulatedClock;
+
+#[doc(inline)]
+pub use player::DEFAULT_CLOCK_TOPIC;
+
+#[doc(inline)]
+pub use sink::FromRawMessage;
+
+#[doc(inline)]
+pub use sink::PlaybackEvent;
+
+#[doc(inline)]
+pub use sink::PlaybackMessage;
+
+#[doc(inline)]
+pub use sink::PlaybackSink;
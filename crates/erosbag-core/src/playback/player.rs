@@ -0,0 +1,264 @@
+use crate::mcap_source::{McapSource, MmapSource};
+use crate::playback::{
+    FromRawMessage, MessageCursor, PlaybackEvent, PlaybackMessage, PlaybackSink,
+};
+use crate::ros_messages::rosgraph_msgs;
+use crate::{ChannelTopic, Error, Rosbag};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::ops::ControlFlow;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+
+/// Topic, on which `rosgraph_msgs/Clock` messages are published in ROS2.
+pub const DEFAULT_CLOCK_TOPIC: &str = "/clock";
+
+/// Options of a [`Player`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerOptions {
+    /// Factor of the playback rate relative to real time.
+    pub rate: f64,
+    /// Starts the playback over after the end of the bag is reached.
+    pub looping: bool,
+    /// Offset from the start of the bag, at which the playback starts.
+    pub start_offset: chrono::Duration,
+    /// Selected channels, or all channels if none are selected.
+    pub channel_topics: Option<HashSet<ChannelTopic>>,
+    /// Topics, under which the messages of the channels are emitted instead of their own.
+    pub topic_remappings: HashMap<ChannelTopic, ChannelTopic>,
+    /// Period in log time, in which the simulated time is published as `rosgraph_msgs/Clock`.
+    pub clock_period: Option<chrono::Duration>,
+    /// Topic of the published clock messages.
+    pub clock_topic: ChannelTopic,
+}
+
+impl PlayerOptions {
+    pub fn new() -> Self {
+        Self {
+            rate: 1.0,
+            looping: false,
+            start_offset: chrono::Duration::zero(),
+            channel_topics: None,
+            topic_remappings: HashMap::new(),
+            clock_period: None,
+            clock_topic: DEFAULT_CLOCK_TOPIC.into(),
+        }
+    }
+
+    pub fn with_rate(mut self, rate: f64) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    pub fn with_start_offset(mut self, start_offset: chrono::Duration) -> Self {
+        self.start_offset = start_offset;
+        self
+    }
+
+    pub fn with_channel_topics(mut self, channel_topics: HashSet<ChannelTopic>) -> Self {
+        self.channel_topics = Some(channel_topics);
+        self
+    }
+
+    /// Emits the messages of the channel under another topic.
+    pub fn with_topic_remapping(mut self, from: ChannelTopic, to: ChannelTopic) -> Self {
+        self.topic_remappings.insert(from, to);
+        self
+    }
+
+    pub fn with_clock_period(mut self, clock_period: chrono::Duration) -> Self {
+        self.clock_period = Some(clock_period);
+        self
+    }
+
+    pub fn with_clock_topic(mut self, clock_topic: ChannelTopic) -> Self {
+        self.clock_topic = clock_topic;
+        self
+    }
+}
+
+impl Default for PlayerOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Simulated time of a playback, which is the log time of the last emitted event.
+///
+/// The clock is shared between its clones, so that downstream code can read the time of a
+/// playback running in another thread.
+#[derive(Debug, Clone, Default)]
+pub struct SimulatedClock {
+    date_time: Arc<RwLock<Option<DateTime<Utc>>>>,
+}
+
+impl SimulatedClock {
+    /// Returns the simulated time, or `None` if the playback has not emitted any event yet.
+    pub fn now(&self) -> Option<DateTime<Utc>> {
+        *self.date_time.read().expect("lock should not be poisoned")
+    }
+
+    fn set(&self, date_time: DateTime<Utc>) {
+        *self.date_time.write().expect("lock should not be poisoned") = Some(date_time);
+    }
+}
+
+/// Replays the messages of a bag in the order of their log times to a [`PlaybackSink`], paced
+/// by their original timing scaled by the playback rate.
+#[derive(Debug)]
+pub struct Player<'a, S: McapSource = MmapSource> {
+    cursor: MessageCursor<'a, S>,
+    options: PlayerOptions,
+    clock: SimulatedClock,
+    start_date_time: Option<DateTime<Utc>>,
+}
+
+impl<'a, S: McapSource> Player<'a, S> {
+    pub fn new(rosbag: &'a Rosbag<S>, options: PlayerOptions) -> Result<Self, Error> {
+        if !options.rate.is_finite() || options.rate <= 0.0 {
+            return Err(Error::InvalidPlaybackRate(options.rate));
+        }
+        if let Some(clock_period) = options.clock_period
+            && clock_period <= chrono::Duration::zero()
+        {
+            return Err(Error::InvalidClockPeriod(clock_period));
+        }
+
+        let cursor = MessageCursor::new(rosbag, options.channel_topics.clone())?;
+        let start_date_time = rosbag
+            .get_start_date_time()?
+            .map(|x| x + options.start_offset.max(chrono::Duration::zero()));
+
+        Ok(Self {
+            cursor,
+            options,
+            clock: SimulatedClock::default(),
+            start_date_time,
+        })
+    }
+
+    pub fn options(&self) -> &PlayerOptions {
+        &self.options
+    }
+
+    /// Returns the simulated clock, which follows the playback.
+    pub fn clock(&self) -> SimulatedClock {
+        self.clock.clone()
+    }
+
+    /// Plays back the bag until its end is reached, or until the sink stops the playback if
+    /// looping.
+    ///
+    /// The messages are decoded to `T`, where messages that cannot be represented by `T` are
+    /// skipped. Looping ends after a pass without any message, as every further pass would be
+    /// empty as well.
+    pub fn play<T: FromRawMessage>(&mut self, mut sink: impl PlaybackSink<T>) -> Result<(), Error> {
+        let Some(start_date_time) = self.start_date_time else {
+            return Ok(());
+        };
+
+        loop {
+            self.cursor.seek(start_date_time);
+            let ControlFlow::Continue(message_count) =
+                self.play_once(start_date_time, &mut sink)?
+            else {
+                return Ok(());
+            };
+            if !self.options.looping || message_count == 0 {
+                return Ok(());
+            }
+            if sink.send(PlaybackEvent::Loop).is_break() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Plays back a single pass and returns the number of emitted messages, unless the sink
+    /// stopped the playback.
+    fn play_once<T: FromRawMessage>(
+        &mut self,
+        start_date_time: DateTime<Utc>,
+        sink: &mut impl PlaybackSink<T>,
+    ) -> Result<ControlFlow<(), usize>, Error> {
+        let start_instant = Instant::now();
+        let mut message_count: usize = 0;
+        let mut next_clock_date_time = self.options.clock_period.map(|_| start_date_time);
+
+        while let Some(next_date_time) = self.cursor.peek_date_time()? {
+            // publish the clock up to the next message, so that the time never runs backwards
+            while let Some(clock_date_time) = next_clock_date_time
+                && clock_date_time <= next_date_time
+            {
+                self.wait_until(start_instant, start_date_time, clock_date_time);
+                self.clock.set(clock_date_time);
+                let clock = PlaybackMessage::new(
+                    self.options.clock_topic.clone(),
+                    clock_date_time,
+                    clock_date_time,
+                    rosgraph_msgs::Clock::from(clock_date_time),
+                );
+                if sink.send(PlaybackEvent::Clock(clock)).is_break() {
+                    return Ok(ControlFlow::Break(()));
+                }
+                next_clock_date_time = self
+                    .options
+                    .clock_period
+                    .and_then(|x| clock_date_time.checked_add_signed(x));
+            }
+
+            let Some(current_message) = self.cursor.next_message()? else {
+                break;
+            };
+            let Some(message) = T::from_raw_message(current_message.message)? else {
+                continue;
+            };
+            self.wait_until(
+                start_instant,
+                start_date_time,
+                current_message.log_date_time,
+            );
+            self.clock.set(current_message.log_date_time);
+            let channel_topic = self
+                .options
+                .topic_remappings
+                .get(&current_message.channel_topic)
+                .cloned()
+                .unwrap_or(current_message.channel_topic);
+            let message = PlaybackMessage::new(
+                channel_topic,
+                current_message.log_date_time,
+                current_message.publish_date_time,
+                message,
+            );
+            if sink.send(PlaybackEvent::Message(message)).is_break() {
+                return Ok(ControlFlow::Break(()));
+            }
+            message_count += 1;
+        }
+
+        Ok(ControlFlow::Continue(message_count))
+    }
+
+    /// Sleeps until the log time is due relative to the start of the playback.
+    fn wait_until(
+        &self,
+        start_instant: Instant,
+        start_date_time: DateTime<Utc>,
+        date_time: DateTime<Utc>,
+    ) {
+        let offset = (date_time - start_date_time)
+            .to_std()
+            .unwrap_or_default()
+            .div_f64(self.options.rate);
+        let due_instant = start_instant + offset;
+        let now = Instant::now();
+        if due_instant > now {
+            std::thread::sleep(due_instant - now);
+        }
+    }
+}
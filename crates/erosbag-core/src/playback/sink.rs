@@ -0,0 +1,109 @@
+use crate::playback::RawMessage;
+use crate::ros_messages::{AnyMessage, RosMessage, rosgraph_msgs};
+use crate::{ChannelTopic, Error};
+use chrono::{DateTime, Utc};
+use std::ops::ControlFlow;
+use std::sync::mpsc::{Sender, SyncSender};
+
+/// Message emitted by a [`crate::playback::Player`] under its possibly remapped topic.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaybackMessage<T> {
+    pub channel_topic: ChannelTopic,
+    pub log_date_time: DateTime<Utc>,
+    pub publish_date_time: DateTime<Utc>,
+    pub message: T,
+}
+
+impl<T> PlaybackMessage<T> {
+    pub fn new(
+        channel_topic: ChannelTopic,
+        log_date_time: DateTime<Utc>,
+        publish_date_time: DateTime<Utc>,
+        message: T,
+    ) -> Self {
+        Self {
+            channel_topic,
+            log_date_time,
+            publish_date_time,
+            message,
+        }
+    }
+}
+
+/// Event emitted by a [`crate::playback::Player`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlaybackEvent<T> {
+    /// Message of the bag.
+    Message(PlaybackMessage<T>),
+    /// Simulated time published on the clock topic.
+    Clock(PlaybackMessage<rosgraph_msgs::Clock>),
+    /// Start of another loop of the playback, after which the log times start over.
+    Loop,
+}
+
+/// Receiver of the events of a playback, which stops the playback by returning
+/// [`ControlFlow::Break`].
+///
+/// The trait is implemented for callbacks and for the senders of `std::sync::mpsc` channels, where
+/// the playback stops once the receiver is dropped.
+pub trait PlaybackSink<T> {
+    fn send(&mut self, event: PlaybackEvent<T>) -> ControlFlow<()>;
+}
+
+impl<T, F> PlaybackSink<T> for F
+where
+    F: FnMut(PlaybackEvent<T>) -> ControlFlow<()>,
+{
+    fn send(&mut self, event: PlaybackEvent<T>) -> ControlFlow<()> {
+        self(event)
+    }
+}
+
+impl<T> PlaybackSink<T> for Sender<PlaybackEvent<T>> {
+    fn send(&mut self, event: PlaybackEvent<T>) -> ControlFlow<()> {
+        match Sender::send(self, event) {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(_) => ControlFlow::Break(()),
+        }
+    }
+}
+
+impl<T> PlaybackSink<T> for SyncSender<PlaybackEvent<T>> {
+    fn send(&mut self, event: PlaybackEvent<T>) -> ControlFlow<()> {
+        match SyncSender::send(self, event) {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(_) => ControlFlow::Break(()),
+        }
+    }
+}
+
+/// Message, in which a [`crate::playback::Player`] emits the undecoded messages of the bag.
+///
+/// The trait is implemented for [`RawMessage`] to pass the messages undecoded, for
+/// [`AnyMessage`] to decode the messages of all supported types and for each [`RosMessage`] to
+/// decode the messages with its schema.
+pub trait FromRawMessage: Sized {
+    /// Returns the decoded message, or `None` if messages of this schema are skipped.
+    fn from_raw_message(raw_message: RawMessage) -> Result<Option<Self>, Error>;
+}
+
+impl FromRawMessage for RawMessage {
+    fn from_raw_message(raw_message: RawMessage) -> Result<Option<Self>, Error> {
+        Ok(Some(raw_message))
+    }
+}
+
+impl FromRawMessage for AnyMessage {
+    fn from_raw_message(raw_message: RawMessage) -> Result<Option<Self>, Error> {
+        raw_message
+            .ros_message_type()
+            .map(|x| AnyMessage::deserialize(x, &raw_message.data))
+            .transpose()
+    }
+}
+
+impl<M: RosMessage> FromRawMessage for M {
+    fn from_raw_message(raw_message: RawMessage) -> Result<Option<Self>, Error> {
+        raw_message.deserialize::<M>()
+    }
+}
@@ -0,0 +1,344 @@
+use crate::Error;
+use chrono::{DateTime, Utc};
+use memmap::Mmap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Magic at the start of bags of the format version 2.0.
+const BAG_MAGIC: &[u8] = b"#ROSBAG V2.0\n";
+
+const OP_MESSAGE_DATA: u8 = 0x02;
+const OP_BAG_HEADER: u8 = 0x03;
+const OP_CHUNK: u8 = 0x05;
+const OP_CHUNK_INFO: u8 = 0x06;
+const OP_CONNECTION: u8 = 0x07;
+
+const COMPRESSION_NONE: &str = "none";
+const COMPRESSION_BZ2: &str = "bz2";
+const COMPRESSION_LZ4: &str = "lz4";
+
+/// Connection of a ROS1 bag, which corresponds to a publisher of a topic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ros1Connection {
+    pub id: u32,
+    pub topic: String,
+    /// Message type, such as `sensor_msgs/Imu`.
+    pub message_type: String,
+    pub md5sum: String,
+    /// Definition in the ROS1 msg format including the definitions of the nested messages.
+    pub message_definition: String,
+    pub latching: bool,
+}
+
+/// Index entry of a chunk of a ROS1 bag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ros1ChunkInfo {
+    /// Offset of the chunk record in the file.
+    pub position: u64,
+    pub start_date_time: DateTime<Utc>,
+    pub end_date_time: DateTime<Utc>,
+    /// Message count per connection id.
+    pub message_counts: BTreeMap<u32, u32>,
+}
+
+/// Message of a ROS1 bag with its ROS1 serialized data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ros1Message {
+    pub connection_id: u32,
+    pub log_date_time: DateTime<Utc>,
+    pub data: Vec<u8>,
+}
+
+/// Reader of a ROS1 bag of the format version 2.0, which is memory-mapped.
+///
+/// The connections and chunks are read from the index at the end of the file, so that bags,
+/// whose recording was interrupted, must be reindexed with `rosbag reindex` beforehand.
+#[derive(Debug)]
+pub struct Ros1Bag {
+    pub file_path: PathBuf,
+    mapped: Mmap,
+    connections: BTreeMap<u32, Ros1Connection>,
+    chunk_infos: Vec<Ros1ChunkInfo>,
+}
+
+impl Ros1Bag {
+    pub fn new(file_path: impl AsRef<Path>) -> Result<Self, Error> {
+        let file = std::fs::File::open(&file_path)?;
+        let mapped = unsafe { Mmap::map(&file) }?;
+        if !mapped.starts_with(BAG_MAGIC) {
+            return Err(Error::InvalidRos1Bag(
+                "file does not start with `#ROSBAG V2.0`".to_string(),
+            ));
+        }
+
+        let mut position = BAG_MAGIC.len();
+        let bag_header = Record::read(&mapped, &mut position)?;
+        if bag_header.op()? != OP_BAG_HEADER {
+            return Err(Error::InvalidRos1Bag(
+                "first record is not the bag header".to_string(),
+            ));
+        }
+        let index_position = bag_header.get_u64("index_pos")?;
+        if index_position == 0 {
+            return Err(Error::InvalidRos1Bag(
+                "bag is not indexed, reindex it with `rosbag reindex`".to_string(),
+            ));
+        }
+
+        let mut connections: BTreeMap<u32, Ros1Connection> = BTreeMap::new();
+        let mut chunk_infos: Vec<Ros1ChunkInfo> = Vec::new();
+        let mut position = index_position as usize;
+        while position < mapped.len() {
+            let record = Record::read(&mapped, &mut position)?;
+            match record.op()? {
+                OP_CONNECTION => {
+                    let connection = record.to_connection()?;
+                    connections.insert(connection.id, connection);
+                }
+                OP_CHUNK_INFO => chunk_infos.push(record.to_chunk_info()?),
+                _ => {}
+            }
+        }
+        chunk_infos.sort_by_key(|x| (x.start_date_time, x.position));
+
+        Ok(Self {
+            file_path: file_path.as_ref().to_owned(),
+            mapped,
+            connections,
+            chunk_infos,
+        })
+    }
+
+    pub fn connections(&self) -> &BTreeMap<u32, Ros1Connection> {
+        &self.connections
+    }
+
+    /// Returns the chunk index ordered by the start date times of the chunks.
+    pub fn chunk_infos(&self) -> &[Ros1ChunkInfo] {
+        &self.chunk_infos
+    }
+
+    pub fn get_start_date_time(&self) -> Option<DateTime<Utc>> {
+        self.chunk_infos.iter().map(|x| x.start_date_time).min()
+    }
+
+    pub fn get_end_date_time(&self) -> Option<DateTime<Utc>> {
+        self.chunk_infos.iter().map(|x| x.end_date_time).max()
+    }
+
+    pub fn get_message_count(&self) -> u64 {
+        self.chunk_infos
+            .iter()
+            .flat_map(|x| x.message_counts.values())
+            .map(|x| *x as u64)
+            .sum()
+    }
+
+    /// Returns the messages of a chunk ordered by log time.
+    pub fn read_chunk(&self, chunk_info: &Ros1ChunkInfo) -> Result<Vec<Ros1Message>, Error> {
+        let mut position = chunk_info.position as usize;
+        let chunk = Record::read(&self.mapped, &mut position)?;
+        if chunk.op()? != OP_CHUNK {
+            return Err(Error::InvalidRos1Bag(format!(
+                "no chunk record at position {}",
+                chunk_info.position
+            )));
+        }
+        let data = chunk.decompress()?;
+
+        let mut messages: Vec<Ros1Message> = Vec::new();
+        let mut position = 0;
+        while position < data.len() {
+            let record = Record::read(&data, &mut position)?;
+            if record.op()? == OP_MESSAGE_DATA {
+                messages.push(Ros1Message {
+                    connection_id: record.get_u32("conn")?,
+                    log_date_time: record.get_date_time("time")?,
+                    data: record.data.to_vec(),
+                });
+            }
+        }
+        messages.sort_by_key(|x| x.log_date_time);
+
+        Ok(messages)
+    }
+
+    /// Returns the messages for a time window between start_date_time (inclusive) and
+    /// end_date_time (exclusive) ordered by log time.
+    pub fn messages(
+        &self,
+        start_date_time: &Option<DateTime<Utc>>,
+        end_date_time: &Option<DateTime<Utc>>,
+    ) -> Result<Vec<Ros1Message>, Error> {
+        let mut messages: Vec<Ros1Message> = Vec::new();
+        for current_chunk_info in self.chunk_infos.iter().filter(|x| {
+            start_date_time.is_none_or(|start| start <= x.end_date_time)
+                && end_date_time.is_none_or(|end| x.start_date_time < end)
+        }) {
+            messages.extend(
+                self.read_chunk(current_chunk_info)?
+                    .into_iter()
+                    .filter(|x| {
+                        start_date_time.is_none_or(|start| start <= x.log_date_time)
+                            && end_date_time.is_none_or(|end| x.log_date_time < end)
+                    }),
+            );
+        }
+        messages.sort_by_key(|x| x.log_date_time);
+
+        Ok(messages)
+    }
+}
+
+/// Record consisting of the header fields and the data.
+struct Record<'a> {
+    fields: HashMap<&'a str, &'a [u8]>,
+    data: &'a [u8],
+}
+
+impl<'a> Record<'a> {
+    /// Reads the record at the position and advances the position to the next record.
+    fn read(buffer: &'a [u8], position: &mut usize) -> Result<Self, Error> {
+        let header = read_length_prefixed(buffer, position)?;
+        let data = read_length_prefixed(buffer, position)?;
+        let fields = parse_fields(header)?;
+
+        Ok(Self { fields, data })
+    }
+
+    fn get(&self, name: &str) -> Result<&'a [u8], Error> {
+        self.fields
+            .get(name)
+            .copied()
+            .ok_or_else(|| Error::InvalidRos1Bag(format!("record has no field `{name}`")))
+    }
+
+    fn get_array<const N: usize>(&self, name: &str) -> Result<[u8; N], Error> {
+        self.get(name)?
+            .try_into()
+            .map_err(|_| Error::InvalidRos1Bag(format!("field `{name}` does not have {N} bytes")))
+    }
+
+    fn get_u32(&self, name: &str) -> Result<u32, Error> {
+        Ok(u32::from_le_bytes(self.get_array(name)?))
+    }
+
+    fn get_u64(&self, name: &str) -> Result<u64, Error> {
+        Ok(u64::from_le_bytes(self.get_array(name)?))
+    }
+
+    fn get_date_time(&self, name: &str) -> Result<DateTime<Utc>, Error> {
+        let bytes: [u8; 8] = self.get_array(name)?;
+        let sec = u32::from_le_bytes(bytes[..4].try_into().expect("should have four bytes"));
+        let nanosec = u32::from_le_bytes(bytes[4..].try_into().expect("should have four bytes"));
+        DateTime::from_timestamp(sec as i64, nanosec)
+            .ok_or_else(|| Error::InvalidRos1Bag(format!("field `{name}` is no valid time")))
+    }
+
+    fn get_string(&self, name: &str) -> Result<String, Error> {
+        Ok(String::from_utf8_lossy(self.get(name)?).into_owned())
+    }
+
+    fn op(&self) -> Result<u8, Error> {
+        Ok(u8::from_le_bytes(self.get_array("op")?))
+    }
+
+    /// Returns the connection, whose topic, type and definition are stored as header fields in
+    /// the data of the record.
+    fn to_connection(&self) -> Result<Ros1Connection, Error> {
+        let connection_header = Record {
+            fields: parse_fields(self.data)?,
+            data: &[],
+        };
+
+        Ok(Ros1Connection {
+            id: self.get_u32("conn")?,
+            topic: self.get_string("topic")?,
+            message_type: connection_header.get_string("type")?,
+            md5sum: connection_header.get_string("md5sum")?,
+            message_definition: connection_header.get_string("message_definition")?,
+            latching: connection_header
+                .get("latching")
+                .is_ok_and(|x| x == b"1".as_slice()),
+        })
+    }
+
+    fn to_chunk_info(&self) -> Result<Ros1ChunkInfo, Error> {
+        let message_counts = self
+            .data
+            .chunks_exact(8)
+            .map(|x| {
+                let connection_id = u32::from_le_bytes(x[..4].try_into().expect("should fit"));
+                let count = u32::from_le_bytes(x[4..].try_into().expect("should fit"));
+                (connection_id, count)
+            })
+            .collect();
+
+        Ok(Ros1ChunkInfo {
+            position: self.get_u64("chunk_pos")?,
+            start_date_time: self.get_date_time("start_time")?,
+            end_date_time: self.get_date_time("end_time")?,
+            message_counts,
+        })
+    }
+
+    /// Returns the decompressed data of a chunk record.
+    fn decompress(&self) -> Result<Cow<'a, [u8]>, Error> {
+        let compression = self.get_string("compression")?;
+        let size = self.get_u32("size")? as usize;
+        let data: Cow<'a, [u8]> = match compression.as_str() {
+            COMPRESSION_NONE => Cow::Borrowed(self.data),
+            COMPRESSION_BZ2 => {
+                let mut decompressed = Vec::with_capacity(size);
+                bzip2::read::BzDecoder::new(self.data).read_to_end(&mut decompressed)?;
+                Cow::Owned(decompressed)
+            }
+            COMPRESSION_LZ4 => {
+                let mut decompressed = Vec::with_capacity(size);
+                lz4_flex::frame::FrameDecoder::new(self.data).read_to_end(&mut decompressed)?;
+                Cow::Owned(decompressed)
+            }
+            _ => return Err(Error::UnsupportedRos1Compression(compression)),
+        };
+        if data.len() != size {
+            return Err(Error::InvalidRos1Bag(format!(
+                "chunk has {} instead of {size} bytes",
+                data.len()
+            )));
+        }
+
+        Ok(data)
+    }
+}
+
+fn read_length_prefixed<'a>(buffer: &'a [u8], position: &mut usize) -> Result<&'a [u8], Error> {
+    let truncated = || Error::InvalidRos1Bag(format!("record at {position} is truncated"));
+    let length_bytes = buffer.get(*position..*position + 4).ok_or_else(truncated)?;
+    let length = u32::from_le_bytes(length_bytes.try_into().expect("should fit")) as usize;
+    let start = *position + 4;
+    let bytes = buffer.get(start..start + length).ok_or_else(truncated)?;
+    *position = start + length;
+
+    Ok(bytes)
+}
+
+/// Parses the header fields, which are length prefixed `name=value` pairs with binary values.
+fn parse_fields(mut header: &[u8]) -> Result<HashMap<&str, &[u8]>, Error> {
+    let mut fields: HashMap<&str, &[u8]> = HashMap::new();
+    while !header.is_empty() {
+        let mut position = 0;
+        let field = read_length_prefixed(header, &mut position)?;
+        header = &header[position..];
+
+        let separator = field.iter().position(|x| *x == b'=').ok_or_else(|| {
+            Error::InvalidRos1Bag("header field without `=` separator".to_string())
+        })?;
+        let name = std::str::from_utf8(&field[..separator])
+            .map_err(|_| Error::InvalidRos1Bag("header field name is no UTF-8".to_string()))?;
+        fields.insert(name, &field[separator + 1..]);
+    }
+
+    Ok(fields)
+}
@@ -0,0 +1,206 @@
+use crate::Error;
+use crate::ros_messages::RosMessageType;
+use crate::ros1::bag::{Ros1Bag, Ros1Connection};
+use crate::ros1::definition::{
+    BaseType, DURATION_TYPE, FieldDefinition, HEADER_TYPE, MessageDefinitions, PrimitiveType,
+    TIME_TYPE,
+};
+use crate::ros1::serialization::{deserialize_ros1, serialize_cdr};
+use crate::storage::{
+    CDR_MESSAGE_ENCODING, OFFERED_QOS_PROFILES_KEY, ROS2_PROFILE, ROS2MSG_SCHEMA_ENCODING,
+};
+use crate::topics::qos_profile::QualityOfServiceProfile;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufWriter, Cursor, Seek, Write};
+use std::path::Path;
+use std::str::FromStr;
+use tracing::info;
+
+/// ROS1 message types, whose ROS2 counterparts are named differently.
+const ROS1_TYPE_RENAMES: [(&str, &str); 1] = [("tf/tfMessage", "tf2_msgs/TFMessage")];
+
+/// Returns the ROS2 schema name of a ROS1 message type, such as `sensor_msgs/msg/Imu` for
+/// `sensor_msgs/Imu`.
+pub fn get_ros2_schema_name(ros1_message_type: &str) -> String {
+    let message_type = ROS1_TYPE_RENAMES
+        .iter()
+        .find(|(ros1_type, _)| *ros1_type == ros1_message_type)
+        .map_or(ros1_message_type, |(_, ros2_type)| *ros2_type);
+
+    match message_type.split_once('/') {
+        Some((package, name)) => format!("{package}/msg/{name}"),
+        None => message_type.to_string(),
+    }
+}
+
+/// Converter of the messages of a connection from the ROS1 serialization to CDR.
+struct ConnectionConverter {
+    schema_name: String,
+    source_definitions: MessageDefinitions,
+    target_definitions: MessageDefinitions,
+}
+
+impl ConnectionConverter {
+    /// Creates the converter, whose target definition is the built-in ROS2 definition for the
+    /// supported message types.
+    ///
+    /// Other message types keep their ROS1 definition, where `std_msgs/Header` loses the `seq`
+    /// field and `time` and `duration` become the `builtin_interfaces` messages.
+    fn new(connection: &Ros1Connection) -> Result<Self, Error> {
+        let source_definitions =
+            MessageDefinitions::parse(&connection.message_type, &connection.message_definition)?;
+        let schema_name = get_ros2_schema_name(&connection.message_type);
+
+        let target_definitions = match RosMessageType::from_str(&schema_name) {
+            Ok(message_type) => MessageDefinitions::parse(&schema_name, message_type.definition())?,
+            Err(_) => {
+                let mut target_definitions = source_definitions.clone();
+                let time_fields = vec![
+                    FieldDefinition::new("sec", BaseType::Primitive(PrimitiveType::Int32)),
+                    FieldDefinition::new("nanosec", BaseType::Primitive(PrimitiveType::UInt32)),
+                ];
+                target_definitions.insert(TIME_TYPE, time_fields.clone());
+                target_definitions.insert(DURATION_TYPE, time_fields);
+                target_definitions.insert(
+                    HEADER_TYPE,
+                    vec![
+                        FieldDefinition::new("stamp", BaseType::Message(TIME_TYPE.to_string())),
+                        FieldDefinition::new(
+                            "frame_id",
+                            BaseType::Primitive(PrimitiveType::String),
+                        ),
+                    ],
+                );
+                target_definitions
+            }
+        };
+
+        Ok(Self {
+            schema_name,
+            source_definitions,
+            target_definitions,
+        })
+    }
+
+    fn convert(&self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        let value = deserialize_ros1(&self.source_definitions, data)?;
+        serialize_cdr(&self.target_definitions, &value)
+    }
+}
+
+impl Ros1Bag {
+    /// Converts the bag to an MCAP file with the ROS2 profile.
+    ///
+    /// The connections of a topic with the same type are merged into a single channel, where
+    /// latched topics are offered with transient local durability.
+    pub fn convert(&self, mcap_file_path: impl AsRef<Path>) -> Result<(), Error> {
+        let mut file = self.write_mcap(BufWriter::new(File::create(mcap_file_path.as_ref())?))?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Converts the bag to the bytes of an MCAP file with the ROS2 profile, like [`Self::convert`].
+    pub fn convert_to_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.write_mcap(Cursor::new(Vec::new()))?.into_inner())
+    }
+
+    fn write_mcap<W: Write + Seek>(&self, output: W) -> Result<W, Error> {
+        let mut writer = mcap::WriteOptions::new()
+            .profile(ROS2_PROFILE)
+            .create(output)?;
+
+        let mut schema_ids: HashMap<String, u16> = HashMap::new();
+        let mut channel_ids: HashMap<(String, String), u16> = HashMap::new();
+        let mut connection_converters: HashMap<u32, (u16, ConnectionConverter)> = HashMap::new();
+        for current_connection in self.connections().values() {
+            let converter = ConnectionConverter::new(current_connection)?;
+
+            let schema_id = match schema_ids.get(&converter.schema_name) {
+                Some(schema_id) => *schema_id,
+                None => {
+                    let definition = converter.target_definitions.to_ros2msg()?;
+                    let schema_id = writer.add_schema(
+                        &converter.schema_name,
+                        ROS2MSG_SCHEMA_ENCODING,
+                        definition.as_bytes(),
+                    )?;
+                    schema_ids.insert(converter.schema_name.clone(), schema_id);
+                    schema_id
+                }
+            };
+
+            let channel_key = (
+                current_connection.topic.clone(),
+                converter.schema_name.clone(),
+            );
+            let channel_id = match channel_ids.get(&channel_key) {
+                Some(channel_id) => *channel_id,
+                None => {
+                    let mut metadata: BTreeMap<String, String> = BTreeMap::new();
+                    if current_connection.latching {
+                        let profiles = vec![QualityOfServiceProfile::new_for_static_tf_topic()];
+                        metadata.insert(
+                            OFFERED_QOS_PROFILES_KEY.to_string(),
+                            serde_yaml::to_string(&profiles)?,
+                        );
+                    }
+                    let channel_id = writer.add_channel(
+                        schema_id,
+                        &current_connection.topic,
+                        CDR_MESSAGE_ENCODING,
+                        &metadata,
+                    )?;
+                    channel_ids.insert(channel_key, channel_id);
+                    channel_id
+                }
+            };
+            connection_converters.insert(current_connection.id, (channel_id, converter));
+        }
+        info!(
+            "Converting {} connections to {} channels",
+            connection_converters.len(),
+            channel_ids.len()
+        );
+
+        let mut sequences: HashMap<u16, u32> = HashMap::new();
+        for current_chunk_info in self.chunk_infos() {
+            for current_message in self.read_chunk(current_chunk_info)? {
+                let (channel_id, converter) = connection_converters
+                    .get(&current_message.connection_id)
+                    .ok_or_else(|| {
+                        Error::InvalidRos1Bag(format!(
+                            "connection `{}` does not exist",
+                            current_message.connection_id
+                        ))
+                    })?;
+                let data = converter.convert(&current_message.data)?;
+
+                let sequence = sequences.entry(*channel_id).or_default();
+                *sequence += 1;
+                let log_time = current_message
+                    .log_date_time
+                    .timestamp_nanos_opt()
+                    .and_then(|x| u64::try_from(x).ok())
+                    .ok_or_else(|| {
+                        Error::InvalidRos1Bag(format!(
+                            "log time `{}` is out of the range of MCAP",
+                            current_message.log_date_time
+                        ))
+                    })?;
+                writer.write_to_known_channel(
+                    &mcap::records::MessageHeader {
+                        channel_id: *channel_id,
+                        sequence: *sequence,
+                        log_time,
+                        publish_time: log_time,
+                    },
+                    &data,
+                )?;
+            }
+        }
+        writer.finish()?;
+
+        Ok(writer.into_inner())
+    }
+}
@@ -0,0 +1,283 @@
+//! Message definitions in the ROS1 msg format, as stored in the connection records of the bags,
+//! and in the ros2msg format of the MCAP schemas.
+//!
+//! Both formats share the syntax, where the definitions of the nested message types are appended
+//! after separator lines. The types are named without the `msg` infix, such as `std_msgs/Header`.
+
+use crate::Error;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+pub(crate) const TIME_TYPE: &str = "builtin_interfaces/Time";
+pub(crate) const DURATION_TYPE: &str = "builtin_interfaces/Duration";
+pub(crate) const HEADER_TYPE: &str = "std_msgs/Header";
+
+const SEPARATOR_PREFIX: &str = "===";
+const SEPARATOR: &str =
+    "================================================================================";
+const MESSAGE_NAME_PREFIX: &str = "MSG:";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PrimitiveType {
+    Bool,
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Int64,
+    UInt64,
+    Float32,
+    Float64,
+    String,
+}
+
+impl PrimitiveType {
+    /// Returns the primitive type, where bounded strings are treated as strings.
+    ///
+    /// `byte` and `char` are both read as `uint8`, since they only differ in the signedness
+    /// between ROS1 and ROS2 and values are converted bitwise.
+    fn from_name(name: &str) -> Option<Self> {
+        let primitive_type = match name {
+            "bool" => PrimitiveType::Bool,
+            "int8" => PrimitiveType::Int8,
+            "uint8" | "byte" | "char" => PrimitiveType::UInt8,
+            "int16" => PrimitiveType::Int16,
+            "uint16" => PrimitiveType::UInt16,
+            "int32" => PrimitiveType::Int32,
+            "uint32" => PrimitiveType::UInt32,
+            "int64" => PrimitiveType::Int64,
+            "uint64" => PrimitiveType::UInt64,
+            "float32" => PrimitiveType::Float32,
+            "float64" => PrimitiveType::Float64,
+            _ if name == "string" || name.starts_with("string<=") => PrimitiveType::String,
+            _ => return None,
+        };
+        Some(primitive_type)
+    }
+
+    const fn as_str(&self) -> &'static str {
+        match self {
+            PrimitiveType::Bool => "bool",
+            PrimitiveType::Int8 => "int8",
+            PrimitiveType::UInt8 => "uint8",
+            PrimitiveType::Int16 => "int16",
+            PrimitiveType::UInt16 => "uint16",
+            PrimitiveType::Int32 => "int32",
+            PrimitiveType::UInt32 => "uint32",
+            PrimitiveType::Int64 => "int64",
+            PrimitiveType::UInt64 => "uint64",
+            PrimitiveType::Float32 => "float32",
+            PrimitiveType::Float64 => "float64",
+            PrimitiveType::String => "string",
+        }
+    }
+
+    pub(crate) const fn is_byte(&self) -> bool {
+        matches!(self, PrimitiveType::Int8 | PrimitiveType::UInt8)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum BaseType {
+    Primitive(PrimitiveType),
+    /// Nested message with its type name, such as `geometry_msgs/Vector3`.
+    Message(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ArrayLength {
+    Fixed(usize),
+    /// Unbounded or bounded sequence, whose length is serialized.
+    Variable,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FieldDefinition {
+    pub name: String,
+    pub base_type: BaseType,
+    pub array_length: Option<ArrayLength>,
+}
+
+impl FieldDefinition {
+    pub fn new(name: &str, base_type: BaseType) -> Self {
+        Self {
+            name: name.to_string(),
+            base_type,
+            array_length: None,
+        }
+    }
+}
+
+/// Definition of a message type with the definitions of all nested message types.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MessageDefinitions {
+    pub root_type: String,
+    messages: HashMap<String, Vec<FieldDefinition>>,
+}
+
+impl MessageDefinitions {
+    /// Parses the definition of the root type with the appended definitions of nested types.
+    ///
+    /// Constants are skipped, since they are not serialized.
+    pub fn parse(root_type: &str, text: &str) -> Result<Self, Error> {
+        let root_type = normalize_type_name(root_type, "");
+        let mut messages: HashMap<String, Vec<FieldDefinition>> = HashMap::new();
+
+        let mut current_type = root_type.clone();
+        let mut current_fields: Vec<FieldDefinition> = Vec::new();
+        let mut lines = text.lines();
+        while let Some(current_line) = lines.next() {
+            let line = current_line.trim();
+            if line.starts_with(SEPARATOR_PREFIX) {
+                messages.insert(current_type, std::mem::take(&mut current_fields));
+                let name_line = lines
+                    .by_ref()
+                    .map(|x| x.trim())
+                    .find(|x| !x.is_empty())
+                    .unwrap_or_default();
+                let name = name_line.strip_prefix(MESSAGE_NAME_PREFIX).ok_or_else(|| {
+                    Error::InvalidMessageDefinition(
+                        root_type.clone(),
+                        format!("expected message name after separator, found `{name_line}`"),
+                    )
+                })?;
+                current_type = normalize_type_name(name.trim(), "");
+                continue;
+            }
+
+            let package = current_type.split('/').next().unwrap_or_default();
+            if let Some(field) = parse_field(line, package)
+                .map_err(|e| Error::InvalidMessageDefinition(current_type.clone(), e))?
+            {
+                current_fields.push(field);
+            }
+        }
+        messages.insert(current_type, current_fields);
+
+        Ok(Self {
+            root_type,
+            messages,
+        })
+    }
+
+    pub fn get(&self, message_type: &str) -> Result<&[FieldDefinition], Error> {
+        self.messages
+            .get(message_type)
+            .map(|x| x.as_slice())
+            .ok_or_else(|| {
+                Error::InvalidMessageDefinition(
+                    self.root_type.clone(),
+                    format!("definition of `{message_type}` is missing"),
+                )
+            })
+    }
+
+    pub fn insert(&mut self, message_type: &str, fields: Vec<FieldDefinition>) {
+        self.messages.insert(message_type.to_string(), fields);
+    }
+
+    /// Renders the definition in the ros2msg format, where the nested types are appended in the
+    /// order of their first use.
+    pub fn to_ros2msg(&self) -> Result<String, Error> {
+        let mut text = String::new();
+        let mut visited: HashSet<&str> = HashSet::from([self.root_type.as_str()]);
+        let mut queue: VecDeque<&str> = VecDeque::from([self.root_type.as_str()]);
+        while let Some(current_type) = queue.pop_front() {
+            if current_type != self.root_type {
+                text.push_str(&format!(
+                    "{SEPARATOR}\n{MESSAGE_NAME_PREFIX} {current_type}\n"
+                ));
+            }
+
+            for current_field in self.get(current_type)? {
+                let type_name = match &current_field.base_type {
+                    BaseType::Primitive(x) => x.as_str(),
+                    BaseType::Message(x) => {
+                        if visited.insert(x.as_str()) {
+                            queue.push_back(x.as_str());
+                        }
+                        x.as_str()
+                    }
+                };
+                let array_suffix = match current_field.array_length {
+                    None => String::new(),
+                    Some(ArrayLength::Fixed(length)) => format!("[{length}]"),
+                    Some(ArrayLength::Variable) => "[]".to_string(),
+                };
+                text.push_str(&format!(
+                    "{type_name}{array_suffix} {}\n",
+                    current_field.name
+                ));
+            }
+        }
+
+        Ok(text)
+    }
+}
+
+/// Parses a line of a definition, which is `None` for empty lines, comments and constants.
+fn parse_field(line: &str, package: &str) -> Result<Option<FieldDefinition>, String> {
+    let line = line.split('#').next().unwrap_or_default().trim();
+    if line.is_empty() {
+        return Ok(None);
+    }
+
+    let mut tokens = line.split_whitespace();
+    let (Some(type_token), Some(name)) = (tokens.next(), tokens.next()) else {
+        return Err(format!("expected type and name in `{line}`"));
+    };
+    if name.contains('=') || tokens.next().is_some_and(|x| x.starts_with('=')) {
+        return Ok(None);
+    }
+
+    let (base_token, array_length) = match type_token.split_once('[') {
+        Some((base_token, array_token)) => {
+            let array_token = array_token
+                .strip_suffix(']')
+                .ok_or_else(|| format!("unclosed array type `{type_token}`"))?;
+            let array_length = if array_token.is_empty() || array_token.starts_with("<=") {
+                ArrayLength::Variable
+            } else {
+                let length = array_token
+                    .parse::<usize>()
+                    .map_err(|_| format!("invalid array length of `{type_token}`"))?;
+                ArrayLength::Fixed(length)
+            };
+            (base_token, Some(array_length))
+        }
+        None => (type_token, None),
+    };
+
+    let base_type = match PrimitiveType::from_name(base_token) {
+        Some(x) => BaseType::Primitive(x),
+        None if base_token == "wstring" || base_token.starts_with("wstring<=") => {
+            return Err(format!("type `{base_token}` is not supported"));
+        }
+        None => BaseType::Message(normalize_type_name(base_token, package)),
+    };
+
+    Ok(Some(FieldDefinition {
+        name: name.to_string(),
+        base_type,
+        array_length,
+    }))
+}
+
+/// Returns the type name without the `msg` infix and qualified by the package.
+///
+/// The ROS1 built-in types `time` and `duration` are mapped to their ROS2 messages.
+fn normalize_type_name(name: &str, package: &str) -> String {
+    match name {
+        "time" => return TIME_TYPE.to_string(),
+        "duration" => return DURATION_TYPE.to_string(),
+        "Header" => return HEADER_TYPE.to_string(),
+        _ => {}
+    }
+
+    match name.split('/').collect::<Vec<_>>().as_slice() {
+        [name] => format!("{package}/{name}"),
+        [package, "msg", name] => format!("{package}/{name}"),
+        _ => name.to_string(),
+    }
+}
@@ -0,0 +1,26 @@
+//! Reading of ROS1 bags of the format version 2.0 and their conversion to ROS2 MCAP files.
+//!
+//! The messages are converted from the ROS1 serialization to CDR, where the definitions of the
+//! supported message types are mapped to their ROS2 counterparts in [`crate::ros_messages`].
+//! Fields are matched by name, so that `Header.seq` is dropped and fields added in ROS2 are
+//! filled with default values.
+
+mod bag;
+mod convert;
+mod definition;
+mod serialization;
+
+#[doc(inline)]
+pub use bag::Ros1Bag;
+
+#[doc(inline)]
+pub use bag::Ros1Connection;
+
+#[doc(inline)]
+pub use bag::Ros1ChunkInfo;
+
+#[doc(inline)]
+pub use bag::Ros1Message;
+
+#[doc(inline)]
+pub use convert::get_ros2_schema_name;
@@ -0,0 +1,350 @@
+//! Decoding of the ROS1 serialization and encoding of CDR against a possibly different
+//! definition, so that messages can be converted between the ROS1 and ROS2 definitions.
+//!
+//! The ROS1 serialization is little endian without alignment, where strings and sequences are
+//! prefixed by their `uint32` length and times consist of `uint32` seconds and nanoseconds.
+
+use crate::Error;
+use crate::ros1::definition::{
+    ArrayLength, BaseType, DURATION_TYPE, FieldDefinition, MessageDefinitions, PrimitiveType,
+    TIME_TYPE,
+};
+use std::borrow::Cow;
+
+const CDR_LE_ENCAPSULATION_HEADER: [u8; 4] = [0x00, 0x01, 0x00, 0x00];
+const NANOSECONDS_PER_SECOND: i64 = 1_000_000_000;
+
+/// Decoded value of a message field.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    String(String),
+    /// Array of `int8` or `uint8`, which is kept as is for images and point clouds.
+    Bytes(Vec<u8>),
+    Array(Vec<Value>),
+    Message(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn as_i64(&self) -> i64 {
+        match self {
+            Value::Bool(x) => *x as i64,
+            Value::Int(x) => *x,
+            Value::UInt(x) => *x as i64,
+            Value::Float(x) => *x as i64,
+            _ => 0,
+        }
+    }
+
+    fn as_f64(&self) -> f64 {
+        match self {
+            Value::Bool(x) => *x as u8 as f64,
+            Value::Int(x) => *x as f64,
+            Value::UInt(x) => *x as f64,
+            Value::Float(x) => *x,
+            _ => 0.0,
+        }
+    }
+
+    /// Returns the elements of an array, which is empty for other values.
+    fn elements(&self) -> Cow<'_, [Value]> {
+        match self {
+            Value::Array(x) => Cow::Borrowed(x.as_slice()),
+            Value::Bytes(x) => Cow::Owned(x.iter().map(|x| Value::UInt(*x as u64)).collect()),
+            _ => Cow::Borrowed(&[]),
+        }
+    }
+}
+
+/// Decodes the ROS1 serialized data of a message with the root type of the definitions.
+pub(crate) fn deserialize_ros1(
+    definitions: &MessageDefinitions,
+    data: &[u8],
+) -> Result<Value, Error> {
+    let mut reader = Ros1Reader {
+        data,
+        position: 0,
+        message_type: &definitions.root_type,
+    };
+    reader.read_message(definitions, &definitions.root_type)
+}
+
+/// Encodes a value as little endian CDR with the root type of the definitions.
+///
+/// The fields are matched by their names ignoring the case, where missing fields are encoded
+/// with their default values and fields without counterpart are dropped.
+pub(crate) fn serialize_cdr(
+    definitions: &MessageDefinitions,
+    value: &Value,
+) -> Result<Vec<u8>, Error> {
+    let mut writer = CdrWriter {
+        buffer: CDR_LE_ENCAPSULATION_HEADER.to_vec(),
+    };
+    writer.write_message(definitions, &definitions.root_type, Some(value))?;
+    Ok(writer.buffer)
+}
+
+struct Ros1Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+    message_type: &'a str,
+}
+
+impl<'a> Ros1Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], Error> {
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|x| *x <= self.data.len())
+            .ok_or_else(|| {
+                Error::InvalidRos1MessageData(
+                    self.message_type.to_string(),
+                    format!("data ends before byte {}", self.position + length),
+                )
+            })?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        Ok(self.take(N)?.try_into().expect("should have the length"))
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_le_bytes(self.take_array()?))
+    }
+
+    fn read_message(
+        &mut self,
+        definitions: &MessageDefinitions,
+        message_type: &str,
+    ) -> Result<Value, Error> {
+        match message_type {
+            TIME_TYPE => {
+                let sec = self.read_u32()?;
+                let nanosec = self.read_u32()?;
+                Ok(time_value(sec as i64, nanosec))
+            }
+            DURATION_TYPE => {
+                let sec = i32::from_le_bytes(self.take_array()?) as i64;
+                let nanosec = i32::from_le_bytes(self.take_array()?) as i64;
+                let total = sec * NANOSECONDS_PER_SECOND + nanosec;
+                Ok(time_value(
+                    total.div_euclid(NANOSECONDS_PER_SECOND),
+                    total.rem_euclid(NANOSECONDS_PER_SECOND) as u32,
+                ))
+            }
+            _ => {
+                let fields = definitions
+                    .get(message_type)?
+                    .iter()
+                    .map(|x| Ok((x.name.clone(), self.read_field(definitions, x)?)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                Ok(Value::Message(fields))
+            }
+        }
+    }
+
+    fn read_field(
+        &mut self,
+        definitions: &MessageDefinitions,
+        field: &FieldDefinition,
+    ) -> Result<Value, Error> {
+        let Some(array_length) = field.array_length else {
+            return self.read_base(definitions, &field.base_type);
+        };
+
+        let length = match array_length {
+            ArrayLength::Fixed(length) => length,
+            ArrayLength::Variable => self.read_u32()? as usize,
+        };
+        if let BaseType::Primitive(primitive_type) = &field.base_type
+            && primitive_type.is_byte()
+        {
+            return Ok(Value::Bytes(self.take(length)?.to_vec()));
+        }
+        let elements = (0..length)
+            .map(|_| self.read_base(definitions, &field.base_type))
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(Value::Array(elements))
+    }
+
+    fn read_base(
+        &mut self,
+        definitions: &MessageDefinitions,
+        base_type: &BaseType,
+    ) -> Result<Value, Error> {
+        match base_type {
+            BaseType::Primitive(x) => self.read_primitive(*x),
+            BaseType::Message(x) => self.read_message(definitions, x),
+        }
+    }
+
+    fn read_primitive(&mut self, primitive_type: PrimitiveType) -> Result<Value, Error> {
+        let value = match primitive_type {
+            PrimitiveType::Bool => Value::Bool(self.take(1)?[0] != 0),
+            PrimitiveType::Int8 => Value::Int(self.take(1)?[0] as i8 as i64),
+            PrimitiveType::UInt8 => Value::UInt(self.take(1)?[0] as u64),
+            PrimitiveType::Int16 => Value::Int(i16::from_le_bytes(self.take_array()?) as i64),
+            PrimitiveType::UInt16 => Value::UInt(u16::from_le_bytes(self.take_array()?) as u64),
+            PrimitiveType::Int32 => Value::Int(i32::from_le_bytes(self.take_array()?) as i64),
+            PrimitiveType::UInt32 => Value::UInt(self.read_u32()? as u64),
+            PrimitiveType::Int64 => Value::Int(i64::from_le_bytes(self.take_array()?)),
+            PrimitiveType::UInt64 => Value::UInt(u64::from_le_bytes(self.take_array()?)),
+            PrimitiveType::Float32 => Value::Float(f32::from_le_bytes(self.take_array()?) as f64),
+            PrimitiveType::Float64 => Value::Float(f64::from_le_bytes(self.take_array()?)),
+            PrimitiveType::String => {
+                let length = self.read_u32()? as usize;
+                Value::String(String::from_utf8_lossy(self.take(length)?).into_owned())
+            }
+        };
+        Ok(value)
+    }
+}
+
+/// Returns a value of `builtin_interfaces/Time` or `builtin_interfaces/Duration`.
+fn time_value(sec: i64, nanosec: u32) -> Value {
+    Value::Message(vec![
+        ("sec".to_string(), Value::Int(sec)),
+        ("nanosec".to_string(), Value::UInt(nanosec as u64)),
+    ])
+}
+
+struct CdrWriter {
+    buffer: Vec<u8>,
+}
+
+impl CdrWriter {
+    /// Pads to the alignment, which is relative to the end of the encapsulation header.
+    fn align(&mut self, alignment: usize) {
+        let offset = self.buffer.len() - CDR_LE_ENCAPSULATION_HEADER.len();
+        let padding = (alignment - offset % alignment) % alignment;
+        self.buffer.extend(std::iter::repeat_n(0, padding));
+    }
+
+    fn write_aligned(&mut self, bytes: &[u8]) {
+        self.align(bytes.len());
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    fn write_message(
+        &mut self,
+        definitions: &MessageDefinitions,
+        message_type: &str,
+        value: Option<&Value>,
+    ) -> Result<(), Error> {
+        let source_fields: &[(String, Value)] = match value {
+            Some(Value::Message(x)) => x.as_slice(),
+            _ => &[],
+        };
+
+        for current_field in definitions.get(message_type)? {
+            let source = source_fields
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(&current_field.name))
+                .map(|(_, value)| value);
+            self.write_field(definitions, current_field, source)?;
+        }
+        Ok(())
+    }
+
+    fn write_field(
+        &mut self,
+        definitions: &MessageDefinitions,
+        field: &FieldDefinition,
+        value: Option<&Value>,
+    ) -> Result<(), Error> {
+        let Some(array_length) = field.array_length else {
+            return self.write_base(definitions, &field.base_type, value);
+        };
+
+        if let (BaseType::Primitive(primitive_type), Some(Value::Bytes(bytes))) =
+            (&field.base_type, value)
+            && primitive_type.is_byte()
+        {
+            match array_length {
+                ArrayLength::Fixed(length) => {
+                    self.buffer.extend(
+                        bytes
+                            .iter()
+                            .copied()
+                            .chain(std::iter::repeat(0))
+                            .take(length),
+                    );
+                }
+                ArrayLength::Variable => {
+                    self.write_aligned(&(bytes.len() as u32).to_le_bytes());
+                    self.buffer.extend_from_slice(bytes);
+                }
+            }
+            return Ok(());
+        }
+
+        let elements = value.map(|x| x.elements()).unwrap_or_default();
+        let length = match array_length {
+            ArrayLength::Fixed(length) => length,
+            ArrayLength::Variable => {
+                self.write_aligned(&(elements.len() as u32).to_le_bytes());
+                elements.len()
+            }
+        };
+        for i in 0..length {
+            self.write_base(definitions, &field.base_type, elements.get(i))?;
+        }
+        Ok(())
+    }
+
+    fn write_base(
+        &mut self,
+        definitions: &MessageDefinitions,
+        base_type: &BaseType,
+        value: Option<&Value>,
+    ) -> Result<(), Error> {
+        match base_type {
+            BaseType::Primitive(x) => {
+                self.write_primitive(*x, value);
+                Ok(())
+            }
+            BaseType::Message(x) => self.write_message(definitions, x, value),
+        }
+    }
+
+    /// Writes a primitive, where numbers are converted with wrapping casts.
+    fn write_primitive(&mut self, primitive_type: PrimitiveType, value: Option<&Value>) {
+        let integer = value.map(|x| x.as_i64()).unwrap_or_default();
+        let float = value.map(|x| x.as_f64()).unwrap_or_default();
+        match primitive_type {
+            PrimitiveType::Bool => self.buffer.push((integer != 0) as u8),
+            PrimitiveType::Int8 | PrimitiveType::UInt8 => self.buffer.push(integer as u8),
+            PrimitiveType::Int16 | PrimitiveType::UInt16 => {
+                self.write_aligned(&(integer as u16).to_le_bytes())
+            }
+            PrimitiveType::Int32 | PrimitiveType::UInt32 => {
+                self.write_aligned(&(integer as u32).to_le_bytes())
+            }
+            PrimitiveType::Int64 => self.write_aligned(&integer.to_le_bytes()),
+            PrimitiveType::UInt64 => {
+                let unsigned = match value {
+                    Some(Value::UInt(x)) => *x,
+                    _ => integer as u64,
+                };
+                self.write_aligned(&unsigned.to_le_bytes())
+            }
+            PrimitiveType::Float32 => self.write_aligned(&(float as f32).to_le_bytes()),
+            PrimitiveType::Float64 => self.write_aligned(&float.to_le_bytes()),
+            PrimitiveType::String => {
+                let text = match value {
+                    Some(Value::String(x)) => x.as_str(),
+                    _ => "",
+                };
+                self.write_aligned(&(text.len() as u32 + 1).to_le_bytes());
+                self.buffer.extend_from_slice(text.as_bytes());
+                self.buffer.push(0);
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use crate::Error;
+use crate::ros_messages::{
+    RosMessageType, geometry_msgs, nav_msgs, sensor_msgs, std_msgs, tf2_msgs, visualization_msgs,
+};
+
+/// Decoded message of any of the supported message types.
+// the variants are matched by value like the message structs themselves
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnyMessage {
+    Imu(sensor_msgs::Imu),
+    NavSatFix(sensor_msgs::NavSatFix),
+    PointCloud2(sensor_msgs::PointCloud2),
+    Image(sensor_msgs::Image),
+    CameraInfo(sensor_msgs::CameraInfo),
+    LaserScan(sensor_msgs::LaserScan),
+    MultiEchoLaserScan(sensor_msgs::MultiEchoLaserScan),
+    PoseStamped(geometry_msgs::PoseStamped),
+    PoseWithCovarianceStamped(geometry_msgs::PoseWithCovarianceStamped),
+    TwistStamped(geometry_msgs::TwistStamped),
+    TFMessage(tf2_msgs::TFMessage),
+    Odometry(nav_msgs::Odometry),
+    Path(nav_msgs::Path),
+    Marker(visualization_msgs::Marker),
+    MarkerArray(visualization_msgs::MarkerArray),
+}
+
+impl AnyMessage {
+    /// Decodes the CDR serialized data of a message of the provided type.
+    pub fn deserialize(message_type: RosMessageType, data: &[u8]) -> Result<Self, Error> {
+        let message = match message_type {
+            RosMessageType::SensorMessagesImu => Self::Imu(cdr::deserialize(data)?),
+            RosMessageType::SensorMessagesNavSatFix => Self::NavSatFix(cdr::deserialize(data)?),
+            RosMessageType::SensorMessagesPointCloud2 => Self::PointCloud2(cdr::deserialize(data)?),
+            RosMessageType::SensorMessagesImage => Self::Image(cdr::deserialize(data)?),
+            RosMessageType::SensorMessagesCameraInfo => Self::CameraInfo(cdr::deserialize(data)?),
+            RosMessageType::SensorMessagesLaserScan => Self::LaserScan(cdr::deserialize(data)?),
+            RosMessageType::SensorMessagesMultiEchoLaserScan => {
+                Self::MultiEchoLaserScan(cdr::deserialize(data)?)
+            }
+            RosMessageType::GeometryMessagesPoseStamped => {
+                Self::PoseStamped(cdr::deserialize(data)?)
+            }
+            RosMessageType::GeometryMessagesPoseWithCovarianceStamped => {
+                Self::PoseWithCovarianceStamped(cdr::deserialize(data)?)
+            }
+            RosMessageType::GeometryMessagesTwistStamped => {
+                Self::TwistStamped(cdr::deserialize(data)?)
+            }
+            RosMessageType::Tf2MessagesTFMessage => Self::TFMessage(cdr::deserialize(data)?),
+            RosMessageType::NavMessagesOdometry => Self::Odometry(cdr::deserialize(data)?),
+            RosMessageType::NavMessagesPath => Self::Path(cdr::deserialize(data)?),
+            RosMessageType::VisualizationMessagesMarker => Self::Marker(cdr::deserialize(data)?),
+            RosMessageType::VisualizationMessagesMarkerArray => {
+                Self::MarkerArray(cdr::deserialize(data)?)
+            }
+        };
+
+        Ok(message)
+    }
+
+    pub fn ros_message_type(&self) -> RosMessageType {
+        match self {
+            Self::Imu(_) => RosMessageType::SensorMessagesImu,
+            Self::NavSatFix(_) => RosMessageType::SensorMessagesNavSatFix,
+            Self::PointCloud2(_) => RosMessageType::SensorMessagesPointCloud2,
+            Self::Image(_) => RosMessageType::SensorMessagesImage,
+            Self::CameraInfo(_) => RosMessageType::SensorMessagesCameraInfo,
+            Self::LaserScan(_) => RosMessageType::SensorMessagesLaserScan,
+            Self::MultiEchoLaserScan(_) => RosMessageType::SensorMessagesMultiEchoLaserScan,
+            Self::PoseStamped(_) => RosMessageType::GeometryMessagesPoseStamped,
+            Self::PoseWithCovarianceStamped(_) => {
+                RosMessageType::GeometryMessagesPoseWithCovarianceStamped
+            }
+            Self::TwistStamped(_) => RosMessageType::GeometryMessagesTwistStamped,
+            Self::TFMessage(_) => RosMessageType::Tf2MessagesTFMessage,
+            Self::Odometry(_) => RosMessageType::NavMessagesOdometry,
+            Self::Path(_) => RosMessageType::NavMessagesPath,
+            Self::Marker(_) => RosMessageType::VisualizationMessagesMarker,
+            Self::MarkerArray(_) => RosMessageType::VisualizationMessagesMarkerArray,
+        }
+    }
+
+    /// Returns the header of the message, if the message type has one.
+    pub fn header(&self) -> Option<&std_msgs::Header> {
+        match self {
+            Self::Imu(x) => Some(&x.header),
+            Self::NavSatFix(x) => Some(&x.header),
+            Self::PointCloud2(x) => Some(&x.header),
+            Self::Image(x) => Some(&x.header),
+            Self::CameraInfo(x) => Some(&x.header),
+            Self::LaserScan(x) => Some(&x.header),
+            Self::MultiEchoLaserScan(x) => Some(&x.header),
+            Self::PoseStamped(x) => Some(&x.header),
+            Self::PoseWithCovarianceStamped(x) => Some(&x.header),
+            Self::TwistStamped(x) => Some(&x.header),
+            Self::TFMessage(_) => None,
+            Self::Odometry(x) => Some(&x.header),
+            Self::Path(x) => Some(&x.header),
+            Self::Marker(x) => Some(&x.header),
+            Self::MarkerArray(x) => x.markers.first().map(|m| &m.header),
+        }
+    }
+}
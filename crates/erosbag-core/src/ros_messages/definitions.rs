@@ -0,0 +1,209 @@
+//! Message definitions in the ros2msg format, where the definitions of the nested message types
+//! are appended as done by rosbag2 when writing the schemas of MCAP files.
+//!
+//! The definitions of the single message types are read from the `.msg` files in the `msg`
+//! directory of the crate.
+
+macro_rules! msg_file {
+    ($package:literal, $name:literal) => {
+        include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/msg/",
+            $package,
+            "/msg/",
+            $name,
+            ".msg"
+        ))
+    };
+}
+
+macro_rules! dependency {
+    ($package:literal, $name:literal) => {
+        concat!(
+            "================================================================================\nMSG: ",
+            $package,
+            "/",
+            $name,
+            "\n",
+            msg_file!($package, $name)
+        )
+    };
+}
+
+macro_rules! time {
+    () => {
+        dependency!("builtin_interfaces", "Time")
+    };
+}
+
+macro_rules! duration {
+    () => {
+        dependency!("builtin_interfaces", "Duration")
+    };
+}
+
+macro_rules! header {
+    () => {
+        concat!(dependency!("std_msgs", "Header"), time!())
+    };
+}
+
+macro_rules! color_rgba {
+    () => {
+        dependency!("std_msgs", "ColorRGBA")
+    };
+}
+
+macro_rules! vector3 {
+    () => {
+        dependency!("geometry_msgs", "Vector3")
+    };
+}
+
+macro_rules! point {
+    () => {
+        dependency!("geometry_msgs", "Point")
+    };
+}
+
+macro_rules! quaternion {
+    () => {
+        dependency!("geometry_msgs", "Quaternion")
+    };
+}
+
+macro_rules! pose {
+    () => {
+        concat!(
+            dependency!("geometry_msgs", "Pose"),
+            point!(),
+            quaternion!()
+        )
+    };
+}
+
+macro_rules! pose_with_covariance {
+    () => {
+        concat!(dependency!("geometry_msgs", "PoseWithCovariance"), pose!())
+    };
+}
+
+macro_rules! twist {
+    () => {
+        concat!(dependency!("geometry_msgs", "Twist"), vector3!())
+    };
+}
+
+macro_rules! twist_with_covariance {
+    () => {
+        concat!(
+            dependency!("geometry_msgs", "TwistWithCovariance"),
+            twist!()
+        )
+    };
+}
+
+macro_rules! marker_dependencies {
+    () => {
+        concat!(
+            header!(),
+            pose!(),
+            vector3!(),
+            color_rgba!(),
+            duration!(),
+            dependency!("sensor_msgs", "CompressedImage"),
+            dependency!("visualization_msgs", "UVCoordinate"),
+            dependency!("visualization_msgs", "MeshFile")
+        )
+    };
+}
+
+pub(crate) const SENSOR_MSGS_IMU: &str = concat!(
+    msg_file!("sensor_msgs", "Imu"),
+    header!(),
+    quaternion!(),
+    vector3!()
+);
+
+pub(crate) const SENSOR_MSGS_NAV_SAT_FIX: &str = concat!(
+    msg_file!("sensor_msgs", "NavSatFix"),
+    header!(),
+    dependency!("sensor_msgs", "NavSatStatus")
+);
+
+pub(crate) const SENSOR_MSGS_POINT_CLOUD_2: &str = concat!(
+    msg_file!("sensor_msgs", "PointCloud2"),
+    header!(),
+    dependency!("sensor_msgs", "PointField")
+);
+
+pub(crate) const SENSOR_MSGS_IMAGE: &str = concat!(msg_file!("sensor_msgs", "Image"), header!());
+
+pub(crate) const SENSOR_MSGS_CAMERA_INFO: &str = concat!(
+    msg_file!("sensor_msgs", "CameraInfo"),
+    header!(),
+    dependency!("sensor_msgs", "RegionOfInterest")
+);
+
+pub(crate) const SENSOR_MSGS_LASER_SCAN: &str =
+    concat!(msg_file!("sensor_msgs", "LaserScan"), header!());
+
+pub(crate) const SENSOR_MSGS_MULTI_ECHO_LASER_SCAN: &str = concat!(
+    msg_file!("sensor_msgs", "MultiEchoLaserScan"),
+    header!(),
+    dependency!("sensor_msgs", "LaserEcho")
+);
+
+pub(crate) const GEOMETRY_MSGS_POSE_STAMPED: &str = concat!(
+    msg_file!("geometry_msgs", "PoseStamped"),
+    header!(),
+    pose!()
+);
+
+pub(crate) const GEOMETRY_MSGS_POSE_WITH_COVARIANCE_STAMPED: &str = concat!(
+    msg_file!("geometry_msgs", "PoseWithCovarianceStamped"),
+    header!(),
+    pose_with_covariance!()
+);
+
+pub(crate) const GEOMETRY_MSGS_TWIST_STAMPED: &str = concat!(
+    msg_file!("geometry_msgs", "TwistStamped"),
+    header!(),
+    twist!()
+);
+
+pub(crate) const ROSGRAPH_MSGS_CLOCK: &str = concat!(msg_file!("rosgraph_msgs", "Clock"), time!());
+
+pub(crate) const TF2_MSGS_TF_MESSAGE: &str = concat!(
+    msg_file!("tf2_msgs", "TFMessage"),
+    dependency!("geometry_msgs", "TransformStamped"),
+    header!(),
+    dependency!("geometry_msgs", "Transform"),
+    vector3!(),
+    quaternion!()
+);
+
+pub(crate) const NAV_MSGS_ODOMETRY: &str = concat!(
+    msg_file!("nav_msgs", "Odometry"),
+    header!(),
+    pose_with_covariance!(),
+    twist_with_covariance!()
+);
+
+pub(crate) const NAV_MSGS_PATH: &str = concat!(
+    msg_file!("nav_msgs", "Path"),
+    header!(),
+    dependency!("geometry_msgs", "PoseStamped"),
+    pose!()
+);
+
+pub(crate) const VISUALIZATION_MSGS_MARKER: &str = concat!(
+    msg_file!("visualization_msgs", "Marker"),
+    marker_dependencies!()
+);
+
+pub(crate) const VISUALIZATION_MSGS_MARKER_ARRAY: &str = concat!(
+    msg_file!("visualization_msgs", "MarkerArray"),
+    dependency!("visualization_msgs", "Marker"),
+    marker_dependencies!()
+);
@@ -1,7 +1,7 @@
 ///! Implementation of the [`geometry_msgs`] messages of ROS2.
 ///!
 ///! [`geometry_msgs`]: https://github.com/ros2/common_interfaces/tree/rolling/geometry_msgs/msg
-use crate::ros_messages::std_msgs;
+use crate::ros_messages::{Header, MessageType, RosMessageType, std_msgs};
 use serde_big_array::BigArray;
 use serde_derive::{Deserialize, Serialize};
 
@@ -36,7 +36,9 @@ impl From<nalgebra::Vector3<f64>> for Point {
 /// [`Pose`]: https://github.com/ros2/common_interfaces/blob/rolling/geometry_msgs/msg/Pose.msg
 #[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize, Serialize)]
 pub struct Pose {
+    #[serde(rename = "position")]
     pub point: Point,
+    #[serde(rename = "orientation")]
     pub quaternion: Quaternion,
 }
 
@@ -67,6 +69,41 @@ impl From<nalgebra::Isometry3<f64>> for Pose {
     }
 }
 
+impl From<&Pose> for ecoord::Transform {
+    fn from(item: &Pose) -> Self {
+        ecoord::Transform::new(item.point.into(), item.quaternion.into())
+    }
+}
+
+/// Implements the [`PoseStamped`] message of ROS2.
+///
+/// [`PoseStamped`]: https://github.com/ros2/common_interfaces/blob/rolling/geometry_msgs/msg/PoseStamped.msg
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+pub struct PoseStamped {
+    pub header: std_msgs::Header,
+    pub pose: Pose,
+}
+
+impl MessageType for PoseStamped {
+    const ROS_MESSAGE_TYPE: RosMessageType = RosMessageType::GeometryMessagesPoseStamped;
+
+    fn ros_message_type(&self) -> &RosMessageType {
+        &RosMessageType::GeometryMessagesPoseStamped
+    }
+}
+
+impl Header for PoseStamped {
+    fn header(&self) -> &std_msgs::Header {
+        &self.header
+    }
+}
+
+impl From<&PoseStamped> for ecoord::TimedTransform {
+    fn from(item: &PoseStamped) -> Self {
+        Self::new(item.header.stamp.into(), (&item.pose).into())
+    }
+}
+
 /// Implements the [`PoseWithCovariance`] message of ROS2.
 ///
 /// [`PoseWithCovariance`]: https://github.com/ros2/common_interfaces/blob/rolling/geometry_msgs/msg/PoseWithCovariance.msg
@@ -77,6 +114,36 @@ pub struct PoseWithCovariance {
     pub covariance: [f64; 36],
 }
 
+/// Implements the [`PoseWithCovarianceStamped`] message of ROS2.
+///
+/// [`PoseWithCovarianceStamped`]: https://github.com/ros2/common_interfaces/blob/rolling/geometry_msgs/msg/PoseWithCovarianceStamped.msg
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PoseWithCovarianceStamped {
+    pub header: std_msgs::Header,
+    pub pose: PoseWithCovariance,
+}
+
+impl MessageType for PoseWithCovarianceStamped {
+    const ROS_MESSAGE_TYPE: RosMessageType =
+        RosMessageType::GeometryMessagesPoseWithCovarianceStamped;
+
+    fn ros_message_type(&self) -> &RosMessageType {
+        &RosMessageType::GeometryMessagesPoseWithCovarianceStamped
+    }
+}
+
+impl Header for PoseWithCovarianceStamped {
+    fn header(&self) -> &std_msgs::Header {
+        &self.header
+    }
+}
+
+impl From<&PoseWithCovarianceStamped> for ecoord::TimedTransform {
+    fn from(item: &PoseWithCovarianceStamped) -> Self {
+        Self::new(item.header.stamp.into(), (&item.pose.pose).into())
+    }
+}
+
 /// Implements the [`Quaternion`] message of ROS2.
 ///
 /// [`Quaternion`]: https://github.com/ros2/common_interfaces/blob/rolling/geometry_msgs/msg/Quaternion.msg
@@ -191,6 +258,29 @@ pub struct Twist {
     pub angular: Vector3,
 }
 
+/// Implements the [`TwistStamped`] message of ROS2.
+///
+/// [`TwistStamped`]: https://github.com/ros2/common_interfaces/blob/rolling/geometry_msgs/msg/TwistStamped.msg
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TwistStamped {
+    pub header: std_msgs::Header,
+    pub twist: Twist,
+}
+
+impl MessageType for TwistStamped {
+    const ROS_MESSAGE_TYPE: RosMessageType = RosMessageType::GeometryMessagesTwistStamped;
+
+    fn ros_message_type(&self) -> &RosMessageType {
+        &RosMessageType::GeometryMessagesTwistStamped
+    }
+}
+
+impl Header for TwistStamped {
+    fn header(&self) -> &std_msgs::Header {
+        &self.header
+    }
+}
+
 /// Implements the [`TwistWithCovariance`] message of ROS2.
 ///
 /// [`TwistWithCovariance`]: https://github.com/ros2/common_interfaces/blob/rolling/geometry_msgs/msg/TwistWithCovariance.msg
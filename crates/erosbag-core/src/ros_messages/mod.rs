@@ -1,21 +1,41 @@
+use crate::Error;
+use serde::de::DeserializeOwned;
 use std::fmt;
 use std::str::FromStr;
 
+mod any_message;
 pub mod builtin_msgs;
+mod definitions;
 pub mod geometry_msgs;
 pub mod nav_msgs;
+pub mod rosgraph_msgs;
 pub mod sensor_msgs;
 pub mod std_msgs;
 pub mod tf2_msgs;
 pub mod visualization_msgs;
+mod wstring;
+
+#[doc(inline)]
+pub use any_message::AnyMessage;
+
+#[doc(inline)]
+pub use wstring::WString;
 
 const ROS_MESSAGE_TYPE_SENSOR_MESSAGES_IMU_STR: &str = "sensor_msgs/msg/Imu";
 const ROS_MESSAGE_TYPE_SENSOR_MESSAGES_NAV_SAT_FIX_STR: &str = "sensor_msgs/msg/NavSatFix";
 const ROS_MESSAGE_TYPE_SENSOR_MESSAGES_POINT_CLOUD_2_STR: &str = "sensor_msgs/msg/PointCloud2";
 const ROS_MESSAGE_TYPE_SENSOR_MESSAGES_IMAGE_STR: &str = "sensor_msgs/msg/Image";
 const ROS_MESSAGE_TYPE_SENSOR_MESSAGES_CAMERA_INFO_STR: &str = "sensor_msgs/msg/CameraInfo";
+const ROS_MESSAGE_TYPE_SENSOR_MESSAGES_LASER_SCAN_STR: &str = "sensor_msgs/msg/LaserScan";
+const ROS_MESSAGE_TYPE_SENSOR_MESSAGES_MULTI_ECHO_LASER_SCAN_STR: &str =
+    "sensor_msgs/msg/MultiEchoLaserScan";
+const ROS_MESSAGE_TYPE_GEOMETRY_MESSAGES_POSE_STAMPED_STR: &str = "geometry_msgs/msg/PoseStamped";
+const ROS_MESSAGE_TYPE_GEOMETRY_MESSAGES_POSE_WITH_COVARIANCE_STAMPED_STR: &str =
+    "geometry_msgs/msg/PoseWithCovarianceStamped";
+const ROS_MESSAGE_TYPE_GEOMETRY_MESSAGES_TWIST_STAMPED_STR: &str = "geometry_msgs/msg/TwistStamped";
 const ROS_MESSAGE_TYPE_TF2_MESSAGES_TF_MESSAGE_STR: &str = "tf2_msgs/msg/TFMessage";
 const ROS_MESSAGE_TYPE_NAV_MESSAGES_ODOMETRY_STR: &str = "nav_msgs/msg/Odometry";
+const ROS_MESSAGE_TYPE_NAV_MESSAGES_PATH_STR: &str = "nav_msgs/msg/Path";
 const ROS_MESSAGE_TYPE_VISUALIZATION_MESSAGES_MARKER_STR: &str = "visualization_msgs/msg/Marker";
 const ROS_MESSAGE_TYPE_VISUALIZATION_MESSAGES_MARKER_ARRAY_STR: &str =
     "visualization_msgs/msg/MarkerArray";
@@ -27,12 +47,44 @@ pub enum RosMessageType {
     SensorMessagesPointCloud2,
     SensorMessagesImage,
     SensorMessagesCameraInfo,
+    SensorMessagesLaserScan,
+    SensorMessagesMultiEchoLaserScan,
+    GeometryMessagesPoseStamped,
+    GeometryMessagesPoseWithCovarianceStamped,
+    GeometryMessagesTwistStamped,
     Tf2MessagesTFMessage,
     NavMessagesOdometry,
+    NavMessagesPath,
     VisualizationMessagesMarker,
     VisualizationMessagesMarkerArray,
 }
 
+/// All supported message types.
+pub const ROS_MESSAGE_TYPES: [RosMessageType; 15] = [
+    RosMessageType::SensorMessagesImu,
+    RosMessageType::SensorMessagesNavSatFix,
+    RosMessageType::SensorMessagesPointCloud2,
+    RosMessageType::SensorMessagesImage,
+    RosMessageType::SensorMessagesCameraInfo,
+    RosMessageType::SensorMessagesLaserScan,
+    RosMessageType::SensorMessagesMultiEchoLaserScan,
+    RosMessageType::GeometryMessagesPoseStamped,
+    RosMessageType::GeometryMessagesPoseWithCovarianceStamped,
+    RosMessageType::GeometryMessagesTwistStamped,
+    RosMessageType::Tf2MessagesTFMessage,
+    RosMessageType::NavMessagesOdometry,
+    RosMessageType::NavMessagesPath,
+    RosMessageType::VisualizationMessagesMarker,
+    RosMessageType::VisualizationMessagesMarkerArray,
+];
+
+/// Message types that can be converted to `epoint::PointCloud`.
+pub const POINT_CLOUD_MESSAGE_TYPES: [RosMessageType; 3] = [
+    RosMessageType::SensorMessagesPointCloud2,
+    RosMessageType::SensorMessagesLaserScan,
+    RosMessageType::SensorMessagesMultiEchoLaserScan,
+];
+
 impl FromStr for RosMessageType {
     type Err = ();
 
@@ -49,10 +101,26 @@ impl FromStr for RosMessageType {
             ROS_MESSAGE_TYPE_SENSOR_MESSAGES_CAMERA_INFO_STR => {
                 Ok(RosMessageType::SensorMessagesCameraInfo)
             }
+            ROS_MESSAGE_TYPE_SENSOR_MESSAGES_LASER_SCAN_STR => {
+                Ok(RosMessageType::SensorMessagesLaserScan)
+            }
+            ROS_MESSAGE_TYPE_SENSOR_MESSAGES_MULTI_ECHO_LASER_SCAN_STR => {
+                Ok(RosMessageType::SensorMessagesMultiEchoLaserScan)
+            }
+            ROS_MESSAGE_TYPE_GEOMETRY_MESSAGES_POSE_STAMPED_STR => {
+                Ok(RosMessageType::GeometryMessagesPoseStamped)
+            }
+            ROS_MESSAGE_TYPE_GEOMETRY_MESSAGES_POSE_WITH_COVARIANCE_STAMPED_STR => {
+                Ok(RosMessageType::GeometryMessagesPoseWithCovarianceStamped)
+            }
+            ROS_MESSAGE_TYPE_GEOMETRY_MESSAGES_TWIST_STAMPED_STR => {
+                Ok(RosMessageType::GeometryMessagesTwistStamped)
+            }
             ROS_MESSAGE_TYPE_TF2_MESSAGES_TF_MESSAGE_STR => {
                 Ok(RosMessageType::Tf2MessagesTFMessage)
             }
             ROS_MESSAGE_TYPE_NAV_MESSAGES_ODOMETRY_STR => Ok(RosMessageType::NavMessagesOdometry),
+            ROS_MESSAGE_TYPE_NAV_MESSAGES_PATH_STR => Ok(RosMessageType::NavMessagesPath),
             ROS_MESSAGE_TYPE_VISUALIZATION_MESSAGES_MARKER_STR => {
                 Ok(RosMessageType::VisualizationMessagesMarker)
             }
@@ -65,7 +133,7 @@ impl FromStr for RosMessageType {
 }
 
 impl RosMessageType {
-    pub fn as_str(&self) -> &'static str {
+    pub const fn as_str(&self) -> &'static str {
         match self {
             RosMessageType::SensorMessagesImu => ROS_MESSAGE_TYPE_SENSOR_MESSAGES_IMU_STR,
             RosMessageType::SensorMessagesNavSatFix => {
@@ -78,8 +146,24 @@ impl RosMessageType {
             RosMessageType::SensorMessagesCameraInfo => {
                 ROS_MESSAGE_TYPE_SENSOR_MESSAGES_CAMERA_INFO_STR
             }
+            RosMessageType::SensorMessagesLaserScan => {
+                ROS_MESSAGE_TYPE_SENSOR_MESSAGES_LASER_SCAN_STR
+            }
+            RosMessageType::SensorMessagesMultiEchoLaserScan => {
+                ROS_MESSAGE_TYPE_SENSOR_MESSAGES_MULTI_ECHO_LASER_SCAN_STR
+            }
+            RosMessageType::GeometryMessagesPoseStamped => {
+                ROS_MESSAGE_TYPE_GEOMETRY_MESSAGES_POSE_STAMPED_STR
+            }
+            RosMessageType::GeometryMessagesPoseWithCovarianceStamped => {
+                ROS_MESSAGE_TYPE_GEOMETRY_MESSAGES_POSE_WITH_COVARIANCE_STAMPED_STR
+            }
+            RosMessageType::GeometryMessagesTwistStamped => {
+                ROS_MESSAGE_TYPE_GEOMETRY_MESSAGES_TWIST_STAMPED_STR
+            }
             RosMessageType::Tf2MessagesTFMessage => ROS_MESSAGE_TYPE_TF2_MESSAGES_TF_MESSAGE_STR,
             RosMessageType::NavMessagesOdometry => ROS_MESSAGE_TYPE_NAV_MESSAGES_ODOMETRY_STR,
+            RosMessageType::NavMessagesPath => ROS_MESSAGE_TYPE_NAV_MESSAGES_PATH_STR,
             RosMessageType::VisualizationMessagesMarker => {
                 ROS_MESSAGE_TYPE_VISUALIZATION_MESSAGES_MARKER_STR
             }
@@ -88,6 +172,35 @@ impl RosMessageType {
             }
         }
     }
+
+    /// Returns the definition in the ros2msg format, as given by [`RosMessage::DEFINITION`].
+    pub const fn definition(&self) -> &'static str {
+        match self {
+            RosMessageType::SensorMessagesImu => definitions::SENSOR_MSGS_IMU,
+            RosMessageType::SensorMessagesNavSatFix => definitions::SENSOR_MSGS_NAV_SAT_FIX,
+            RosMessageType::SensorMessagesPointCloud2 => definitions::SENSOR_MSGS_POINT_CLOUD_2,
+            RosMessageType::SensorMessagesImage => definitions::SENSOR_MSGS_IMAGE,
+            RosMessageType::SensorMessagesCameraInfo => definitions::SENSOR_MSGS_CAMERA_INFO,
+            RosMessageType::SensorMessagesLaserScan => definitions::SENSOR_MSGS_LASER_SCAN,
+            RosMessageType::SensorMessagesMultiEchoLaserScan => {
+                definitions::SENSOR_MSGS_MULTI_ECHO_LASER_SCAN
+            }
+            RosMessageType::GeometryMessagesPoseStamped => definitions::GEOMETRY_MSGS_POSE_STAMPED,
+            RosMessageType::GeometryMessagesPoseWithCovarianceStamped => {
+                definitions::GEOMETRY_MSGS_POSE_WITH_COVARIANCE_STAMPED
+            }
+            RosMessageType::GeometryMessagesTwistStamped => {
+                definitions::GEOMETRY_MSGS_TWIST_STAMPED
+            }
+            RosMessageType::Tf2MessagesTFMessage => definitions::TF2_MSGS_TF_MESSAGE,
+            RosMessageType::NavMessagesOdometry => definitions::NAV_MSGS_ODOMETRY,
+            RosMessageType::NavMessagesPath => definitions::NAV_MSGS_PATH,
+            RosMessageType::VisualizationMessagesMarker => definitions::VISUALIZATION_MSGS_MARKER,
+            RosMessageType::VisualizationMessagesMarkerArray => {
+                definitions::VISUALIZATION_MSGS_MARKER_ARRAY
+            }
+        }
+    }
 }
 
 impl fmt::Display for RosMessageType {
@@ -97,6 +210,9 @@ impl fmt::Display for RosMessageType {
 }
 
 pub trait MessageType {
+    /// Supported message type of all messages of this struct.
+    const ROS_MESSAGE_TYPE: RosMessageType;
+
     fn ros_message_type(&self) -> &RosMessageType;
 }
 
@@ -116,3 +232,36 @@ where
         &self.header().stamp
     }
 }
+
+/// Message, which is decoded from the CDR serialized data of the channels with its schema.
+///
+/// The trait is implemented for all built-in messages and can be implemented for downstream
+/// structs deriving `serde::Deserialize` in the field order of the definition, so that they can
+/// be read with [`crate::Rosbag::messages`].
+pub trait RosMessage: DeserializeOwned + Send {
+    /// Name of the schema, such as `sensor_msgs/msg/Imu`.
+    const SCHEMA_NAME: &'static str;
+    /// Definition in the ros2msg format including the definitions of the nested messages.
+    const DEFINITION: &'static str;
+
+    /// Decodes the CDR serialized data of a message.
+    fn deserialize_cdr(data: &[u8]) -> Result<Self, Error> {
+        let message = cdr::deserialize::<Self>(data)?;
+        Ok(message)
+    }
+}
+
+/// All built-in messages of a supported [`RosMessageType`] are read by their schema name and
+/// definition.
+impl<T> RosMessage for T
+where
+    T: MessageType + DeserializeOwned + Send,
+{
+    const SCHEMA_NAME: &'static str = T::ROS_MESSAGE_TYPE.as_str();
+    const DEFINITION: &'static str = T::ROS_MESSAGE_TYPE.definition();
+}
+
+impl RosMessage for rosgraph_msgs::Clock {
+    const SCHEMA_NAME: &'static str = "rosgraph_msgs/msg/Clock";
+    const DEFINITION: &'static str = definitions::ROSGRAPH_MSGS_CLOCK;
+}
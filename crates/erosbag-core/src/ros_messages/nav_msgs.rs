@@ -3,7 +3,7 @@
 ///! [`nav_msgs`]: https://github.com/ros2/common_interfaces/tree/rolling/nav_msgs/msg
 use super::geometry_msgs;
 use super::std_msgs;
-use crate::ros_messages::{MessageType, RosMessageType};
+use crate::ros_messages::{Header, MessageType, RosMessageType};
 use serde_derive::{Deserialize, Serialize};
 
 /// Implements the [`Odometry`] message of ROS2.
@@ -18,7 +18,44 @@ pub struct Odometry {
 }
 
 impl MessageType for Odometry {
+    const ROS_MESSAGE_TYPE: RosMessageType = RosMessageType::NavMessagesOdometry;
+
     fn ros_message_type(&self) -> &RosMessageType {
         &RosMessageType::NavMessagesOdometry
     }
 }
+
+impl Header for Odometry {
+    fn header(&self) -> &std_msgs::Header {
+        &self.header
+    }
+}
+
+impl From<&Odometry> for ecoord::TimedTransform {
+    fn from(item: &Odometry) -> Self {
+        Self::new(item.header.stamp.into(), (&item.pose.pose).into())
+    }
+}
+
+/// Implements the [`Path`] message of ROS2.
+///
+/// [`Path`]: https://github.com/ros2/common_interfaces/blob/rolling/nav_msgs/msg/Path.msg
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+pub struct Path {
+    pub header: std_msgs::Header,
+    pub poses: Vec<geometry_msgs::PoseStamped>,
+}
+
+impl MessageType for Path {
+    const ROS_MESSAGE_TYPE: RosMessageType = RosMessageType::NavMessagesPath;
+
+    fn ros_message_type(&self) -> &RosMessageType {
+        &RosMessageType::NavMessagesPath
+    }
+}
+
+impl Header for Path {
+    fn header(&self) -> &std_msgs::Header {
+        &self.header
+    }
+}
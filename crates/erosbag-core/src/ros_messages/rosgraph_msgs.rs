@@ -0,0 +1,21 @@
+///! Implementation of the [`rosgraph_msgs`] messages of ROS2.
+///!
+///! [`rosgraph_msgs`]: https://github.com/ros2/rcl_interfaces/tree/rolling/rosgraph_msgs/msg
+use super::builtin_msgs;
+use chrono::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+
+/// Implements the [`Clock`] message of ROS2, which is published on `/clock` to provide the
+/// simulated time.
+///
+/// [`Clock`]: https://github.com/ros2/rcl_interfaces/blob/rolling/rosgraph_msgs/msg/Clock.msg
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub struct Clock {
+    pub clock: builtin_msgs::Time,
+}
+
+impl From<DateTime<Utc>> for Clock {
+    fn from(item: DateTime<Utc>) -> Self {
+        Self { clock: item.into() }
+    }
+}
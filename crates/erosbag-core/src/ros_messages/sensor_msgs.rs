@@ -3,15 +3,81 @@
 ///! [`sensor_msgs`]: https://github.com/ros2/common_interfaces/tree/rolling/sensor_msgs/msg
 use super::geometry_msgs;
 use super::std_msgs;
-use crate::ros_messages::{MessageType, RosMessageType};
+use crate::ros_messages::{Header, MessageType, RosMessageType};
 
 use serde_derive::{Deserialize, Serialize};
 
+use crate::Error;
 use crate::bagfile::point_cloud_extensions::RosPointDataColumnType;
 use chrono::{DateTime, Utc};
 use image::{ImageBuffer, Rgb};
 use itertools::izip;
 use nalgebra::Point3;
+use serde_big_array::BigArray;
+use tracing::warn;
+
+const IMAGE_ENCODING_RGB8: &str = "rgb8";
+const IMAGE_ENCODING_BGR8: &str = "bgr8";
+const IMAGE_ENCODING_RGBA8: &str = "rgba8";
+const IMAGE_ENCODING_BGRA8: &str = "bgra8";
+const IMAGE_ENCODING_MONO8: &str = "mono8";
+const IMAGE_ENCODING_16UC1: &str = "16UC1";
+const IMAGE_ENCODING_32FC1: &str = "32FC1";
+
+/// Implements the [`CameraInfo`] message of ROS2.
+///
+/// [`CameraInfo`]: https://github.com/ros2/common_interfaces/blob/rolling/sensor_msgs/msg/CameraInfo.msg
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CameraInfo {
+    pub header: std_msgs::Header,
+    pub height: u32,
+    pub width: u32,
+    pub distortion_model: String,
+    pub d: Vec<f64>,
+    pub k: [f64; 9],
+    pub r: [f64; 9],
+    #[serde(with = "BigArray")]
+    pub p: [f64; 12],
+    pub binning_x: u32,
+    pub binning_y: u32,
+    pub roi: RegionOfInterest,
+}
+
+impl MessageType for CameraInfo {
+    const ROS_MESSAGE_TYPE: RosMessageType = RosMessageType::SensorMessagesCameraInfo;
+
+    fn ros_message_type(&self) -> &RosMessageType {
+        &RosMessageType::SensorMessagesCameraInfo
+    }
+}
+
+impl Header for CameraInfo {
+    fn header(&self) -> &std_msgs::Header {
+        &self.header
+    }
+}
+
+impl CameraInfo {
+    /// Focal length in x direction in pixels.
+    pub fn fx(&self) -> f64 {
+        self.k[0]
+    }
+
+    /// Focal length in y direction in pixels.
+    pub fn fy(&self) -> f64 {
+        self.k[4]
+    }
+
+    /// Principal point in x direction in pixels.
+    pub fn cx(&self) -> f64 {
+        self.k[2]
+    }
+
+    /// Principal point in y direction in pixels.
+    pub fn cy(&self) -> f64 {
+        self.k[5]
+    }
+}
 
 /// Implements the [`CompressedImage`] message of ROS2.
 ///
@@ -39,11 +105,19 @@ pub struct Image {
 }
 
 impl MessageType for Image {
+    const ROS_MESSAGE_TYPE: RosMessageType = RosMessageType::SensorMessagesImage;
+
     fn ros_message_type(&self) -> &RosMessageType {
         &RosMessageType::SensorMessagesImage
     }
 }
 
+impl Header for Image {
+    fn header(&self) -> &std_msgs::Header {
+        &self.header
+    }
+}
+
 impl From<Image> for eimage::Image {
     fn from(item: Image) -> Self {
         let buffer: Vec<u8> = item
@@ -59,28 +133,477 @@ impl From<Image> for eimage::Image {
     }
 }
 
+impl Image {
+    /// Returns the depth values in meters of a `16UC1` or `32FC1` encoded depth image in row-major
+    /// order.
+    ///
+    /// `16UC1` values are multiplied with `depth_scale`, while `32FC1` values are taken as meters.
+    /// Zero, non-finite and negative values are returned as `None`.
+    pub fn get_depth_values(&self, depth_scale: f64) -> Result<Vec<Option<f64>>, Error> {
+        let depth_values: Vec<f64> = match self.encoding.as_str() {
+            IMAGE_ENCODING_16UC1 => self
+                .get_row_major_values::<2>()?
+                .map(|x| {
+                    let value = if self.is_bigendian {
+                        u16::from_be_bytes(x)
+                    } else {
+                        u16::from_le_bytes(x)
+                    };
+                    value as f64 * depth_scale
+                })
+                .collect(),
+            IMAGE_ENCODING_32FC1 => self
+                .get_row_major_values::<4>()?
+                .map(|x| {
+                    let value = if self.is_bigendian {
+                        f32::from_be_bytes(x)
+                    } else {
+                        f32::from_le_bytes(x)
+                    };
+                    value as f64
+                })
+                .collect(),
+            _ => return Err(Error::UnsupportedImageEncoding(self.encoding.clone())),
+        };
+
+        let depth_values = depth_values
+            .into_iter()
+            .map(|x| (x.is_finite() && x > 0.0).then_some(x))
+            .collect();
+        Ok(depth_values)
+    }
+
+    /// Returns the RGB values of a `rgb8`, `bgr8`, `rgba8`, `bgra8` or `mono8` encoded image in
+    /// row-major order.
+    pub fn get_rgb_values(&self) -> Result<Vec<[u8; 3]>, Error> {
+        let rgb_values: Vec<[u8; 3]> = match self.encoding.as_str() {
+            IMAGE_ENCODING_RGB8 => self.get_row_major_values::<3>()?.collect(),
+            IMAGE_ENCODING_BGR8 => self
+                .get_row_major_values::<3>()?
+                .map(|x| [x[2], x[1], x[0]])
+                .collect(),
+            IMAGE_ENCODING_RGBA8 => self
+                .get_row_major_values::<4>()?
+                .map(|x| [x[0], x[1], x[2]])
+                .collect(),
+            IMAGE_ENCODING_BGRA8 => self
+                .get_row_major_values::<4>()?
+                .map(|x| [x[2], x[1], x[0]])
+                .collect(),
+            IMAGE_ENCODING_MONO8 => self
+                .get_row_major_values::<1>()?
+                .map(|x| [x[0], x[0], x[0]])
+                .collect(),
+            _ => return Err(Error::UnsupportedImageEncoding(self.encoding.clone())),
+        };
+
+        Ok(rgb_values)
+    }
+
+    /// Returns the pixel values of `N` bytes in row-major order, where the row padding is
+    /// skipped.
+    ///
+    /// Fails, if a row does not fit the width or the data is shorter than the rows.
+    fn get_row_major_values<const N: usize>(
+        &self,
+    ) -> Result<impl Iterator<Item = [u8; N]> + '_, Error> {
+        let row_length = self.width as usize * N;
+        if (self.step as usize) < row_length {
+            return Err(Error::InvalidImageData(format!(
+                "step of {} bytes is shorter than the {} pixels of {N} bytes per row",
+                self.step, self.width
+            )));
+        }
+        let data_length = self.height as usize * self.step as usize;
+        if self.data.len() < data_length {
+            return Err(Error::InvalidImageData(format!(
+                "data of {} bytes is shorter than the {} rows of {} bytes",
+                self.data.len(),
+                self.height,
+                self.step
+            )));
+        }
+
+        Ok((0..self.height as usize).flat_map(move |row| {
+            let row_start = row * self.step as usize;
+            (0..self.width as usize).map(move |column| {
+                let start = row_start + column * N;
+                let mut value: [u8; N] = [0; N];
+                value.copy_from_slice(&self.data[start..start + N]);
+                value
+            })
+        }))
+    }
+
+    /// Back-projects a depth image into a point cloud in the camera's optical frame using the
+    /// pinhole intrinsics of the camera info.
+    ///
+    /// If an aligned color image with the same resolution is provided, the points are colored.
+    /// Fails, if the camera info does not match the resolution of the depth image or provides no
+    /// focal lengths, as for an uncalibrated camera.
+    pub fn back_project_depth(
+        &self,
+        camera_info: &CameraInfo,
+        depth_scale: f64,
+        color_image: Option<&Image>,
+    ) -> Result<epoint::PointCloud, Error> {
+        if camera_info.width != self.width || camera_info.height != self.height {
+            return Err(Error::InvalidImageData(format!(
+                "camera info with {}x{} pixels does not match depth image with {}x{} pixels",
+                camera_info.width, camera_info.height, self.width, self.height
+            )));
+        }
+        let (fx, fy, cx, cy) = (
+            camera_info.fx(),
+            camera_info.fy(),
+            camera_info.cx(),
+            camera_info.cy(),
+        );
+        if !fx.is_normal() || !fy.is_normal() {
+            return Err(Error::InvalidImageData(format!(
+                "camera info provides no focal lengths with fx = {fx} and fy = {fy}"
+            )));
+        }
+
+        let depth_values = self.get_depth_values(depth_scale)?;
+        let rgb_values: Option<Vec<[u8; 3]>> = match color_image {
+            Some(color_image)
+                if color_image.width == self.width && color_image.height == self.height =>
+            {
+                Some(color_image.get_rgb_values()?)
+            }
+            Some(color_image) => {
+                warn!(
+                    "Color image with {}x{} pixels does not match depth image with {}x{} pixels",
+                    color_image.width, color_image.height, self.width, self.height
+                );
+                None
+            }
+            None => None,
+        };
+
+        let valid_pixels: Vec<(usize, f64)> = depth_values
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, depth)| depth.map(|d| (i, d)))
+            .collect();
+
+        let points: Vec<Point3<f64>> = valid_pixels
+            .iter()
+            .map(|(i, depth)| {
+                let u = (i % self.width as usize) as f64;
+                let v = (i / self.width as usize) as f64;
+                Point3::new((u - cx) * depth / fx, (v - cy) * depth / fy, *depth)
+            })
+            .collect();
+        let color: Option<Vec<palette::Srgb<u16>>> = rgb_values.map(|rgb_values| {
+            valid_pixels
+                .iter()
+                .map(|(i, _)| {
+                    let [r, g, b] = rgb_values[*i];
+                    palette::Srgb::new(r as u16 * 257, g as u16 * 257, b as u16 * 257)
+                })
+                .collect()
+        });
+
+        let mut point_data =
+            epoint::PointDataColumns::new(points, None, None, None, None, None, color)?;
+        point_data.frame_id = Some(vec![self.header.frame_id.clone(); valid_pixels.len()]);
+        point_data.timestamp = Some(vec![self.header.stamp.into(); valid_pixels.len()]);
+
+        let mut point_cloud = epoint::PointCloud::new(
+            point_data,
+            epoint::PointCloudInfo::new(None),
+            ecoord::TransformTree::default(),
+        )?;
+
+        let point_id: Vec<u32> = valid_pixels.iter().map(|(i, _)| *i as u32).collect();
+        point_cloud
+            .point_data
+            .add_u32_column(RosPointDataColumnType::RosPointId.as_str(), point_id)?;
+
+        Ok(point_cloud)
+    }
+}
+
 /// Implements the [`Imu`] message of ROS2.
 ///
 /// [`Imu`]: https://github.com/ros2/common_interfaces/blob/rolling/sensor_msgs/msg/Imu.msg
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct Imu {
     pub header: std_msgs::Header,
+    pub orientation: geometry_msgs::Quaternion,
+    pub orientation_covariance: [f64; 9],
     pub angular_velocity: geometry_msgs::Vector3,
+    pub angular_velocity_covariance: [f64; 9],
     pub linear_acceleration: geometry_msgs::Vector3,
+    pub linear_acceleration_covariance: [f64; 9],
 }
 
 impl MessageType for Imu {
+    const ROS_MESSAGE_TYPE: RosMessageType = RosMessageType::SensorMessagesImu;
+
     fn ros_message_type(&self) -> &RosMessageType {
         &RosMessageType::SensorMessagesImu
     }
 }
 
+impl Header for Imu {
+    fn header(&self) -> &std_msgs::Header {
+        &self.header
+    }
+}
+
+/// Implements the [`LaserEcho`] message of ROS2.
+///
+/// [`LaserEcho`]: https://github.com/ros2/common_interfaces/blob/rolling/sensor_msgs/msg/LaserEcho.msg
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+pub struct LaserEcho {
+    pub echoes: Vec<f32>,
+}
+
+/// Implements the [`LaserScan`] message of ROS2.
+///
+/// [`LaserScan`]: https://github.com/ros2/common_interfaces/blob/rolling/sensor_msgs/msg/LaserScan.msg
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct LaserScan {
+    pub header: std_msgs::Header,
+    pub angle_min: f32,
+    pub angle_max: f32,
+    pub angle_increment: f32,
+    pub time_increment: f32,
+    pub scan_time: f32,
+    pub range_min: f32,
+    pub range_max: f32,
+    pub ranges: Vec<f32>,
+    pub intensities: Vec<f32>,
+}
+
+impl MessageType for LaserScan {
+    const ROS_MESSAGE_TYPE: RosMessageType = RosMessageType::SensorMessagesLaserScan;
+
+    fn ros_message_type(&self) -> &RosMessageType {
+        &RosMessageType::SensorMessagesLaserScan
+    }
+}
+
+impl Header for LaserScan {
+    fn header(&self) -> &std_msgs::Header {
+        &self.header
+    }
+}
+
+impl TryFrom<LaserScan> for epoint::PointCloud {
+    type Error = Error;
+
+    fn try_from(item: LaserScan) -> Result<Self, Self::Error> {
+        let has_intensities = item.intensities.len() == item.ranges.len();
+        let beams: Vec<ScanBeam> = item
+            .ranges
+            .iter()
+            .enumerate()
+            .map(|(beam_index, range)| ScanBeam {
+                beam_index,
+                echo_index: 0,
+                range: *range,
+                intensity: item.intensities.get(beam_index).copied(),
+            })
+            .collect();
+
+        scan_to_point_cloud(
+            &item.header,
+            item.angle_min,
+            item.angle_increment,
+            item.time_increment,
+            item.range_min,
+            item.range_max,
+            beams,
+            has_intensities,
+        )
+    }
+}
+
+/// Implements the [`MultiEchoLaserScan`] message of ROS2.
+///
+/// [`MultiEchoLaserScan`]: https://github.com/ros2/common_interfaces/blob/rolling/sensor_msgs/msg/MultiEchoLaserScan.msg
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct MultiEchoLaserScan {
+    pub header: std_msgs::Header,
+    pub angle_min: f32,
+    pub angle_max: f32,
+    pub angle_increment: f32,
+    pub time_increment: f32,
+    pub scan_time: f32,
+    pub range_min: f32,
+    pub range_max: f32,
+    pub ranges: Vec<LaserEcho>,
+    pub intensities: Vec<LaserEcho>,
+}
+
+impl MessageType for MultiEchoLaserScan {
+    const ROS_MESSAGE_TYPE: RosMessageType = RosMessageType::SensorMessagesMultiEchoLaserScan;
+
+    fn ros_message_type(&self) -> &RosMessageType {
+        &RosMessageType::SensorMessagesMultiEchoLaserScan
+    }
+}
+
+impl Header for MultiEchoLaserScan {
+    fn header(&self) -> &std_msgs::Header {
+        &self.header
+    }
+}
+
+impl TryFrom<MultiEchoLaserScan> for epoint::PointCloud {
+    type Error = Error;
+
+    fn try_from(item: MultiEchoLaserScan) -> Result<Self, Self::Error> {
+        let has_intensities = item.intensities.len() == item.ranges.len()
+            && item
+                .ranges
+                .iter()
+                .zip(&item.intensities)
+                .all(|(r, i)| r.echoes.len() == i.echoes.len());
+        let beams: Vec<ScanBeam> = item
+            .ranges
+            .iter()
+            .enumerate()
+            .flat_map(|(beam_index, echo)| {
+                let intensities = item.intensities.get(beam_index);
+                echo.echoes
+                    .iter()
+                    .enumerate()
+                    .map(move |(echo_index, range)| ScanBeam {
+                        beam_index,
+                        echo_index,
+                        range: *range,
+                        intensity: intensities.and_then(|x| x.echoes.get(echo_index).copied()),
+                    })
+            })
+            .collect();
+
+        scan_to_point_cloud(
+            &item.header,
+            item.angle_min,
+            item.angle_increment,
+            item.time_increment,
+            item.range_min,
+            item.range_max,
+            beams,
+            has_intensities,
+        )
+    }
+}
+
+struct ScanBeam {
+    beam_index: usize,
+    echo_index: usize,
+    range: f32,
+    intensity: Option<f32>,
+}
+
+/// Converts the returns of a planar laser scan into a point cloud in the scan's frame.
+///
+/// Returns outside of [range_min, range_max] are dropped and each point is stamped with the
+/// header stamp plus the beam's time increment, which must lie within the range of date times.
+#[allow(clippy::too_many_arguments)]
+fn scan_to_point_cloud(
+    header: &std_msgs::Header,
+    angle_min: f32,
+    angle_increment: f32,
+    time_increment: f32,
+    range_min: f32,
+    range_max: f32,
+    beams: Vec<ScanBeam>,
+    has_intensities: bool,
+) -> Result<epoint::PointCloud, Error> {
+    let beams: Vec<ScanBeam> = beams
+        .into_iter()
+        .filter(|x| x.range.is_finite() && range_min <= x.range && x.range <= range_max)
+        .collect();
+
+    let header_date_time = DateTime::from_timestamp(header.stamp.sec as i64, header.stamp.nanosec)
+        .ok_or_else(|| {
+            Error::InvalidLaserScanData(format!(
+                "header stamp of {}s and {}ns is out of range",
+                header.stamp.sec, header.stamp.nanosec
+            ))
+        })?;
+    let points: Vec<Point3<f64>> = beams
+        .iter()
+        .map(|x| {
+            let angle = angle_min as f64 + x.beam_index as f64 * angle_increment as f64;
+            let range = x.range as f64;
+            Point3::new(range * angle.cos(), range * angle.sin(), 0.0)
+        })
+        .collect();
+    let timestamp: Vec<DateTime<Utc>> = beams
+        .iter()
+        .map(|x| {
+            let offset_nanoseconds = (x.beam_index as f64 * time_increment as f64 * 1.0e9).round();
+            (offset_nanoseconds.is_finite() && offset_nanoseconds.abs() < i64::MAX as f64)
+                .then(|| {
+                    header_date_time.checked_add_signed(chrono::Duration::nanoseconds(
+                        offset_nanoseconds as i64,
+                    ))
+                })
+                .flatten()
+                .ok_or_else(|| {
+                    Error::InvalidLaserScanData(format!(
+                        "time increment of {time_increment}s exceeds the range of date times"
+                    ))
+                })
+        })
+        .collect::<Result<_, _>>()?;
+    let intensity: Option<Vec<f32>> = has_intensities.then(|| {
+        beams
+            .iter()
+            .map(|x| x.intensity.unwrap_or_default())
+            .collect()
+    });
+
+    let mut point_data =
+        epoint::PointDataColumns::new(points, None, None, None, intensity, None, None)?;
+    point_data.frame_id = Some(vec![header.frame_id.clone(); beams.len()]);
+    point_data.timestamp = Some(timestamp);
+
+    let mut point_cloud = epoint::PointCloud::new(
+        point_data,
+        epoint::PointCloudInfo::new(None),
+        ecoord::TransformTree::default(),
+    )?;
+
+    let point_id: Vec<u32> = beams.iter().map(|x| x.beam_index as u32).collect();
+    point_cloud
+        .point_data
+        .add_u32_column(RosPointDataColumnType::RosPointId.as_str(), point_id)?;
+    let echo_id: Vec<u32> = beams.iter().map(|x| x.echo_index as u32).collect();
+    point_cloud
+        .point_data
+        .add_u32_column(RosPointDataColumnType::RosEchoId.as_str(), echo_id)?;
+
+    Ok(point_cloud)
+}
+
+/// Implements the [`RegionOfInterest`] message of ROS2.
+///
+/// [`RegionOfInterest`]: https://github.com/ros2/common_interfaces/blob/rolling/sensor_msgs/msg/RegionOfInterest.msg
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub struct RegionOfInterest {
+    pub x_offset: u32,
+    pub y_offset: u32,
+    pub height: u32,
+    pub width: u32,
+    pub do_rectify: bool,
+}
+
 /// Implements the [`NavSatFix`] message of ROS2.
 ///
 /// [`NavSatFix`]: https://github.com/ros2/common_interfaces/blob/rolling/sensor_msgs/msg/NavSatFix.msg
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct NavSatFix {
     pub header: std_msgs::Header,
+    pub status: NavSatStatus,
     pub latitude: f64,
     pub longitude: f64,
     pub altitude: f64,
@@ -89,11 +612,28 @@ pub struct NavSatFix {
 }
 
 impl MessageType for NavSatFix {
+    const ROS_MESSAGE_TYPE: RosMessageType = RosMessageType::SensorMessagesNavSatFix;
+
     fn ros_message_type(&self) -> &RosMessageType {
         &RosMessageType::SensorMessagesNavSatFix
     }
 }
 
+impl Header for NavSatFix {
+    fn header(&self) -> &std_msgs::Header {
+        &self.header
+    }
+}
+
+/// Implements the [`NavSatStatus`] message of ROS2.
+///
+/// [`NavSatStatus`]: https://github.com/ros2/common_interfaces/blob/rolling/sensor_msgs/msg/NavSatStatus.msg
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub struct NavSatStatus {
+    pub status: i8,
+    pub service: u16,
+}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
 pub enum NavSatFixPositionCovarianceType {
     CovarianceTypeUnknown = 0,
@@ -122,17 +662,32 @@ pub struct PointCloud2 {
 }
 
 impl MessageType for PointCloud2 {
+    const ROS_MESSAGE_TYPE: RosMessageType = RosMessageType::SensorMessagesPointCloud2;
+
     fn ros_message_type(&self) -> &RosMessageType {
         &RosMessageType::SensorMessagesPointCloud2
     }
 }
 
+impl Header for PointCloud2 {
+    fn header(&self) -> &std_msgs::Header {
+        &self.header
+    }
+}
+
 impl From<PointCloud2> for epoint::PointCloud {
     fn from(item: PointCloud2) -> Self {
-        let mut point_data = item.get_epoint();
-        let frame_id: Vec<String> = vec![item.header.frame_id.clone(); point_data.len()];
+        item.to_point_cloud(None)
+    }
+}
+
+impl PointCloud2 {
+    /// Converts the message into a point cloud, which is optionally colored.
+    pub fn to_point_cloud(&self, color: Option<Vec<palette::Srgb<u16>>>) -> epoint::PointCloud {
+        let mut point_data = self.get_epoint_with_color(color);
+        let frame_id: Vec<String> = vec![self.header.frame_id.clone(); point_data.len()];
         point_data.frame_id = Some(frame_id);
-        let timestamp: Vec<DateTime<Utc>> = vec![item.header.stamp.into(); point_data.len()];
+        let timestamp: Vec<DateTime<Utc>> = vec![self.header.stamp.into(); point_data.len()];
         point_data.timestamp = Some(timestamp);
 
         let mut point_cloud = epoint::PointCloud::new(
@@ -150,20 +705,28 @@ impl From<PointCloud2> for epoint::PointCloud {
 
         point_cloud
     }
-}
 
-impl PointCloud2 {
-    pub fn get_epoint(&self) -> epoint::PointDataColumns {
+    pub fn get_points(&self) -> Vec<Point3<f64>> {
         let x_values = self.get_field_as_f32("x");
         let y_values = self.get_field_as_f32("y");
         let z_values = self.get_field_as_f32("z");
-        let points: Vec<Point3<f64>> = izip!(&x_values, &y_values, &z_values)
+        izip!(&x_values, &y_values, &z_values)
             .map(|v| Point3::<f64>::new(*v.0 as f64, *v.1 as f64, *v.2 as f64))
-            .collect();
+            .collect()
+    }
+
+    pub fn get_epoint(&self) -> epoint::PointDataColumns {
+        self.get_epoint_with_color(None)
+    }
 
+    fn get_epoint_with_color(
+        &self,
+        color: Option<Vec<palette::Srgb<u16>>>,
+    ) -> epoint::PointDataColumns {
+        let points = self.get_points();
         let intensity = self.get_field_as_f32("intensity");
 
-        epoint::PointDataColumns::new(points, None, None, None, Some(intensity), None, None)
+        epoint::PointDataColumns::new(points, None, None, None, Some(intensity), None, color)
             .unwrap()
     }
 
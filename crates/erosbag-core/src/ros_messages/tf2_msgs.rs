@@ -13,6 +13,8 @@ pub struct TFMessage {
 }
 
 impl MessageType for TFMessage {
+    const ROS_MESSAGE_TYPE: RosMessageType = RosMessageType::Tf2MessagesTFMessage;
+
     fn ros_message_type(&self) -> &RosMessageType {
         &RosMessageType::Tf2MessagesTFMessage
     }
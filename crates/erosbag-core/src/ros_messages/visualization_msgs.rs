@@ -40,6 +40,8 @@ pub struct Marker {
 }
 
 impl MessageType for Marker {
+    const ROS_MESSAGE_TYPE: RosMessageType = RosMessageType::VisualizationMessagesMarker;
+
     fn ros_message_type(&self) -> &RosMessageType {
         &RosMessageType::VisualizationMessagesMarker
     }
@@ -90,6 +92,8 @@ pub struct MarkerArray {
 }
 
 impl MessageType for MarkerArray {
+    const ROS_MESSAGE_TYPE: RosMessageType = RosMessageType::VisualizationMessagesMarkerArray;
+
     fn ros_message_type(&self) -> &RosMessageType {
         &RosMessageType::VisualizationMessagesMarkerArray
     }
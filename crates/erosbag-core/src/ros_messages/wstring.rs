@@ -0,0 +1,41 @@
+use serde_derive::{Deserialize, Serialize};
+use std::fmt;
+
+/// String of the `wstring` type of ROS2 messages.
+///
+/// rmw_fastrtps encodes each UTF-16 code unit of a `wstring` as a 32-bit integer following the
+/// number of code units, which is the CDR encoding of a sequence of `uint32` values.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct WString {
+    pub code_units: Vec<u32>,
+}
+
+impl WString {
+    /// Decodes the UTF-16 code units, where invalid code units are replaced.
+    pub fn to_string_lossy(&self) -> String {
+        char::decode_utf16(self.code_units.iter().map(|x| *x as u16))
+            .map(|x| x.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    }
+}
+
+impl From<&str> for WString {
+    fn from(item: &str) -> Self {
+        Self {
+            code_units: item.encode_utf16().map(u32::from).collect(),
+        }
+    }
+}
+
+impl From<String> for WString {
+    fn from(item: String) -> Self {
+        Self::from(item.as_str())
+    }
+}
+
+impl fmt::Display for WString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_string_lossy())
+    }
+}
@@ -0,0 +1,666 @@
+use crate::Error;
+use crate::file_compression::{
+    check_file_compression, decompressed_file_path, is_file_compression_mode,
+    uncompressed_file_path,
+};
+use crate::mcap_file::McapFile;
+use crate::ros_messages::RosMessageType;
+use crate::storage::format::{StorageConversionOptions, StorageFormat};
+use crate::storage::metadata::{
+    BagFileInformation, BagMetadata, Duration, FileInformation, METADATA_VERSION, StartingTime,
+    TopicInformation, TopicMetadata,
+};
+use crate::storage::sqlite3::{Sqlite3Message, Sqlite3Reader, Sqlite3Writer, write_metadata};
+use crate::storage::{
+    OFFERED_QOS_PROFILES_KEY, ROS2_PROFILE, ROS2MSG_SCHEMA_ENCODING, TOPIC_TYPE_HASH_KEY,
+};
+use itertools::Itertools;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tracing::info;
+
+/// Encoding of the `message_definitions` table for types without known definition.
+const UNKNOWN_SCHEMA_ENCODING: &str = "unknown";
+const ROS_DISTRO_KEY: &str = "ros_distro";
+
+/// Topic as stored in both formats, whose fields are kept as is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Topic {
+    name: String,
+    message_type: String,
+    serialization_format: String,
+    offered_qos_profiles: String,
+    type_description_hash: String,
+}
+
+/// Message definition of a type, whose encoding is empty if the definition is unknown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Schema {
+    encoding: String,
+    data: Vec<u8>,
+}
+
+impl Schema {
+    /// Returns the recorded definition or the built-in definition of the supported types.
+    fn new_or_builtin(message_type: &str, encoding: &str, data: Vec<u8>) -> Self {
+        if !encoding.is_empty() && encoding != UNKNOWN_SCHEMA_ENCODING {
+            return Self {
+                encoding: encoding.to_string(),
+                data,
+            };
+        }
+
+        match RosMessageType::from_str(message_type) {
+            Ok(x) => Self {
+                encoding: ROS2MSG_SCHEMA_ENCODING.to_string(),
+                data: x.definition().as_bytes().to_vec(),
+            },
+            Err(_) => Self {
+                encoding: String::new(),
+                data: Vec::new(),
+            },
+        }
+    }
+}
+
+enum StorageWriter {
+    Mcap {
+        writer: Box<mcap::Writer<BufWriter<File>>>,
+        schema_ids: HashMap<String, u16>,
+        channel_ids: Vec<u16>,
+        sequences: Vec<u32>,
+    },
+    Sqlite3 {
+        writer: Sqlite3Writer,
+        message_types: HashSet<String>,
+        topic_ids: Vec<i64>,
+    },
+}
+
+impl StorageWriter {
+    fn new(
+        file_path: &Path,
+        options: &StorageConversionOptions,
+        ros_distro: &str,
+    ) -> Result<Self, Error> {
+        let storage_writer = match options.storage_format {
+            StorageFormat::Mcap => {
+                let file = BufWriter::new(File::create(file_path)?);
+                let writer = mcap::WriteOptions::new()
+                    .profile(ROS2_PROFILE)
+                    .compression(options.compression.to_mcap())
+                    .chunk_size(Some(options.chunk_size))
+                    .create(file)?;
+                StorageWriter::Mcap {
+                    writer: Box::new(writer),
+                    schema_ids: HashMap::new(),
+                    channel_ids: Vec::new(),
+                    sequences: Vec::new(),
+                }
+            }
+            StorageFormat::Sqlite3 => StorageWriter::Sqlite3 {
+                writer: Sqlite3Writer::new(file_path, ros_distro)?,
+                message_types: HashSet::new(),
+                topic_ids: Vec::new(),
+            },
+        };
+        Ok(storage_writer)
+    }
+
+    /// Adds the topic and returns its index for writing messages.
+    fn add_topic(&mut self, topic: &Topic, schema: &Schema) -> Result<usize, Error> {
+        match self {
+            StorageWriter::Mcap {
+                writer,
+                schema_ids,
+                channel_ids,
+                sequences,
+            } => {
+                let schema_id = match schema_ids.get(&topic.message_type) {
+                    Some(schema_id) => *schema_id,
+                    None => {
+                        let schema_id = writer.add_schema(
+                            &topic.message_type,
+                            &schema.encoding,
+                            &schema.data,
+                        )?;
+                        schema_ids.insert(topic.message_type.clone(), schema_id);
+                        schema_id
+                    }
+                };
+
+                let mut metadata: BTreeMap<String, String> = BTreeMap::new();
+                if !topic.offered_qos_profiles.is_empty() {
+                    metadata.insert(
+                        OFFERED_QOS_PROFILES_KEY.to_string(),
+                        topic.offered_qos_profiles.clone(),
+                    );
+                }
+                if !topic.type_description_hash.is_empty() {
+                    metadata.insert(
+                        TOPIC_TYPE_HASH_KEY.to_string(),
+                        topic.type_description_hash.clone(),
+                    );
+                }
+                let channel_id = writer.add_channel(
+                    schema_id,
+                    &topic.name,
+                    &topic.serialization_format,
+                    &metadata,
+                )?;
+                channel_ids.push(channel_id);
+                sequences.push(0);
+                Ok(channel_ids.len() - 1)
+            }
+            StorageWriter::Sqlite3 {
+                writer,
+                message_types,
+                topic_ids,
+            } => {
+                if message_types.insert(topic.message_type.clone()) {
+                    let encoding = if schema.encoding.is_empty() {
+                        UNKNOWN_SCHEMA_ENCODING
+                    } else {
+                        schema.encoding.as_str()
+                    };
+                    writer.add_message_definition(
+                        &topic.message_type,
+                        encoding,
+                        &String::from_utf8_lossy(&schema.data),
+                        &topic.type_description_hash,
+                    )?;
+                }
+
+                let topic_id = writer.add_topic(
+                    &topic.name,
+                    &topic.message_type,
+                    &topic.serialization_format,
+                    &topic.offered_qos_profiles,
+                    &topic.type_description_hash,
+                )?;
+                topic_ids.push(topic_id);
+                Ok(topic_ids.len() - 1)
+            }
+        }
+    }
+
+    /// Writes a message, whose publish time and sequence are kept if known.
+    ///
+    /// Messages without publish time are published at the timestamp and messages without
+    /// sequence are numbered per topic for MCAP files.
+    fn write_message(
+        &mut self,
+        topic_index: usize,
+        message: &ConvertedMessage,
+    ) -> Result<(), Error> {
+        match self {
+            StorageWriter::Mcap {
+                writer,
+                channel_ids,
+                sequences,
+                ..
+            } => {
+                sequences[topic_index] += 1;
+                writer.write_to_known_channel(
+                    &mcap::records::MessageHeader {
+                        channel_id: channel_ids[topic_index],
+                        sequence: message.sequence.unwrap_or(sequences[topic_index]),
+                        log_time: message.timestamp as u64,
+                        publish_time: message.publish_time.unwrap_or(message.timestamp) as u64,
+                    },
+                    message.data,
+                )?;
+            }
+            StorageWriter::Sqlite3 {
+                writer, topic_ids, ..
+            } => {
+                writer.write_message(&Sqlite3Message {
+                    topic_id: topic_ids[topic_index],
+                    timestamp: message.timestamp,
+                    publish_time: message.publish_time,
+                    sequence: message.sequence,
+                    data: message.data,
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes an MCAP metadata record, which is kept in a separate table for SQLite3 files.
+    fn write_mcap_metadata(&mut self, metadata: &mcap::records::Metadata) -> Result<(), Error> {
+        match self {
+            StorageWriter::Mcap { writer, .. } => {
+                writer.write_metadata(metadata)?;
+            }
+            StorageWriter::Sqlite3 { writer, .. } => {
+                writer.add_mcap_metadata(metadata)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), Error> {
+        match self {
+            StorageWriter::Mcap { mut writer, .. } => {
+                writer.finish()?;
+            }
+            StorageWriter::Sqlite3 { writer, .. } => {
+                writer.finish()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Message with the timestamp in nanoseconds and the MCAP header fields if known.
+struct ConvertedMessage<'a> {
+    timestamp: i64,
+    publish_time: Option<i64>,
+    sequence: Option<u32>,
+    data: &'a [u8],
+}
+
+/// Writer of a converted file, which counts the written messages for the metadata.
+struct FileConverter {
+    storage_writer: StorageWriter,
+    topics: Vec<(Topic, u64)>,
+    start_timestamp: Option<i64>,
+    end_timestamp: Option<i64>,
+}
+
+/// Written file with its topics and message counts.
+struct ConvertedFile {
+    relative_file_path: String,
+    topics: Vec<(Topic, u64)>,
+    start_timestamp: Option<i64>,
+    end_timestamp: Option<i64>,
+}
+
+impl ConvertedFile {
+    fn message_count(&self) -> u64 {
+        self.topics.iter().map(|x| x.1).sum()
+    }
+
+    fn file_information(&self) -> FileInformation {
+        let start_timestamp = self.start_timestamp.unwrap_or_default();
+        FileInformation {
+            path: self.relative_file_path.clone(),
+            starting_time: StartingTime {
+                nanoseconds_since_epoch: start_timestamp,
+            },
+            duration: Duration {
+                nanoseconds: (self.end_timestamp.unwrap_or_default() - start_timestamp) as u64,
+            },
+            message_count: self.message_count(),
+        }
+    }
+}
+
+impl FileConverter {
+    fn add_topic(&mut self, topic: Topic, schema: &Schema) -> Result<usize, Error> {
+        let topic_index = self.storage_writer.add_topic(&topic, schema)?;
+        self.topics.push((topic, 0));
+        Ok(topic_index)
+    }
+
+    fn write_message(
+        &mut self,
+        topic_index: usize,
+        message: &ConvertedMessage,
+    ) -> Result<(), Error> {
+        self.storage_writer.write_message(topic_index, message)?;
+        self.topics[topic_index].1 += 1;
+        let timestamp = message.timestamp;
+        self.start_timestamp = Some(self.start_timestamp.map_or(timestamp, |x| x.min(timestamp)));
+        self.end_timestamp = Some(self.end_timestamp.map_or(timestamp, |x| x.max(timestamp)));
+        Ok(())
+    }
+
+    fn finish(self, relative_file_path: String) -> Result<ConvertedFile, Error> {
+        self.storage_writer.finish()?;
+        Ok(ConvertedFile {
+            relative_file_path,
+            topics: self.topics,
+            start_timestamp: self.start_timestamp,
+            end_timestamp: self.end_timestamp,
+        })
+    }
+
+    /// Copies the channels, messages and metadata records of an MCAP file, where channels without
+    /// messages are taken from the summary.
+    fn convert_mcap_file(&mut self, file_path: &Path) -> Result<(), Error> {
+        let file_name = file_path.file_stem().unwrap_or_default().to_string_lossy();
+        let mcap_file = McapFile::new(file_name.as_ref().into(), file_path)?;
+
+        let mut topic_indices: HashMap<u16, usize> = HashMap::new();
+        if let Some(summary) = mcap_file.summary()? {
+            for current_channel in summary.channels.values().sorted_by_key(|x| x.id) {
+                let (topic, schema) = topic_of_mcap_channel(current_channel);
+                topic_indices.insert(current_channel.id, self.add_topic(topic, &schema)?);
+            }
+        }
+
+        let data = mcap_file.data()?;
+        for current_message in mcap::MessageStream::new(&data)? {
+            let current_message = current_message?;
+            let topic_index = match topic_indices.get(&current_message.channel.id) {
+                Some(topic_index) => *topic_index,
+                None => {
+                    let (topic, schema) = topic_of_mcap_channel(&current_message.channel);
+                    let topic_index = self.add_topic(topic, &schema)?;
+                    topic_indices.insert(current_message.channel.id, topic_index);
+                    topic_index
+                }
+            };
+            self.write_message(
+                topic_index,
+                &ConvertedMessage {
+                    timestamp: current_message.log_time as i64,
+                    publish_time: Some(current_message.publish_time as i64),
+                    sequence: Some(current_message.sequence),
+                    data: &current_message.data,
+                },
+            )?;
+        }
+
+        for current_record in mcap::read::LinearReader::new(&data)? {
+            if let mcap::records::Record::Metadata(metadata) = current_record? {
+                self.storage_writer.write_mcap_metadata(&metadata)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Copies the topics, messages and MCAP metadata records of a SQLite3 file, where message
+    /// definitions missing in older files are taken from the built-in definitions.
+    fn convert_sqlite3_file(&mut self, file_path: &Path) -> Result<(), Error> {
+        let reader = Sqlite3Reader::new(file_path)?;
+        let message_definitions = reader.message_definitions()?;
+
+        let mut topic_indices: HashMap<i64, usize> = HashMap::new();
+        for current_topic in reader.topics()? {
+            let schema = match message_definitions.get(&current_topic.message_type) {
+                Some(x) => Schema::new_or_builtin(
+                    &current_topic.message_type,
+                    &x.encoding,
+                    x.encoded_message_definition.as_bytes().to_vec(),
+                ),
+                None => Schema::new_or_builtin(&current_topic.message_type, "", Vec::new()),
+            };
+            let topic = Topic {
+                name: current_topic.name,
+                message_type: current_topic.message_type,
+                serialization_format: current_topic.serialization_format,
+                offered_qos_profiles: current_topic.offered_qos_profiles,
+                type_description_hash: current_topic.type_description_hash,
+            };
+            topic_indices.insert(current_topic.id, self.add_topic(topic, &schema)?);
+        }
+
+        reader.for_each_message(|message| {
+            let topic_index = *topic_indices.get(&message.topic_id).ok_or_else(|| {
+                Error::InvalidSqlite3File(format!(
+                    "topic with id `{}` does not exist",
+                    message.topic_id
+                ))
+            })?;
+            self.write_message(
+                topic_index,
+                &ConvertedMessage {
+                    timestamp: message.timestamp,
+                    publish_time: message.publish_time,
+                    sequence: message.sequence,
+                    data: message.data,
+                },
+            )
+        })?;
+
+        for current_metadata in reader.mcap_metadata()? {
+            self.storage_writer.write_mcap_metadata(&current_metadata)?;
+        }
+        Ok(())
+    }
+}
+
+fn topic_of_mcap_channel(channel: &mcap::Channel) -> (Topic, Schema) {
+    let message_type = channel
+        .schema
+        .as_ref()
+        .map(|x| x.name.clone())
+        .unwrap_or_default();
+    let schema = match &channel.schema {
+        Some(x) => Schema::new_or_builtin(&message_type, &x.encoding, x.data.to_vec()),
+        None => Schema::new_or_builtin(&message_type, "", Vec::new()),
+    };
+    let topic = Topic {
+        name: channel.topic.clone(),
+        message_type,
+        serialization_format: channel.message_encoding.clone(),
+        offered_qos_profiles: channel
+            .metadata
+            .get(OFFERED_QOS_PROFILES_KEY)
+            .cloned()
+            .unwrap_or_default(),
+        type_description_hash: channel
+            .metadata
+            .get(TOPIC_TYPE_HASH_KEY)
+            .cloned()
+            .unwrap_or_default(),
+    };
+    (topic, schema)
+}
+
+/// Converts the files of a ROS2 bag directory to the storage format of the options.
+///
+/// The files are named after the input files, such as `rosbag2_0.mcap` for `rosbag2_0.db3`, and
+/// are listed by the `metadata.yaml` file if present. The metadata file of the input is kept with
+/// the updated storage identifier and file paths, while it is created from the converted messages
+/// otherwise. Input files compressed as a whole, such as `rosbag2_0.db3.zstd`, are decompressed
+/// and written uncompressed.
+pub fn convert_storage(
+    input_directory_path: impl AsRef<Path>,
+    output_directory_path: impl AsRef<Path>,
+    options: &StorageConversionOptions,
+) -> Result<BagMetadata, Error> {
+    let input_directory_path = input_directory_path.as_ref();
+    let output_directory_path = output_directory_path.as_ref();
+    if !input_directory_path.is_dir() {
+        return Err(Error::RosbagPathIsNoDirectory);
+    }
+    std::fs::create_dir_all(output_directory_path)?;
+    if input_directory_path.canonicalize()? == output_directory_path.canonicalize()? {
+        return Err(Error::SameInputAndOutputDirectory);
+    }
+
+    let input_metadata = BagMetadata::from_directory(input_directory_path)?;
+    if let Some(input_metadata) = &input_metadata {
+        check_file_compression(input_metadata)?;
+    }
+    let input_file_paths: Vec<(String, PathBuf)> = match &input_metadata {
+        Some(x) if !x.rosbag2_bagfile_information.relative_file_paths.is_empty() => x
+            .rosbag2_bagfile_information
+            .relative_file_paths
+            .iter()
+            .cloned()
+            .zip(x.file_paths(input_directory_path))
+            .collect(),
+        _ => std::fs::read_dir(input_directory_path)?
+            .filter_map(|x| x.ok())
+            .map(|x| x.path())
+            .filter(|x| x.is_file())
+            .filter(|x| storage_format_of_file(x).is_some())
+            .sorted()
+            .map(|x| (x.file_name().unwrap().to_string_lossy().into_owned(), x))
+            .collect(),
+    };
+    if input_file_paths.is_empty() {
+        return Err(Error::ContainsNoRosbagFile);
+    }
+    let ros_distro = input_metadata
+        .as_ref()
+        .and_then(|x| x.rosbag2_bagfile_information.other.get(ROS_DISTRO_KEY))
+        .and_then(|x| x.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    let mut converted_files: Vec<ConvertedFile> = Vec::new();
+    for (current_relative_file_path, current_file_path) in &input_file_paths {
+        let input_storage_format = storage_format_of_file(current_file_path)
+            .ok_or_else(|| Error::UnsupportedStorageFile(current_relative_file_path.clone()))?;
+        let file_stem = uncompressed_file_path(current_file_path)
+            .file_stem()
+            .map(|x| x.to_string_lossy().into_owned())
+            .ok_or_else(|| Error::UnsupportedStorageFile(current_relative_file_path.clone()))?;
+        let output_relative_file_path =
+            format!("{file_stem}.{}", options.storage_format.extension());
+        info!(
+            "Converting {} from {} to {}",
+            current_relative_file_path, input_storage_format, options.storage_format
+        );
+
+        let mut file_converter = FileConverter {
+            storage_writer: StorageWriter::new(
+                &output_directory_path.join(&output_relative_file_path),
+                options,
+                &ros_distro,
+            )?,
+            topics: Vec::new(),
+            start_timestamp: None,
+            end_timestamp: None,
+        };
+        let input_file_path = decompressed_file_path(current_file_path)?;
+        match input_storage_format {
+            StorageFormat::Mcap => file_converter.convert_mcap_file(&input_file_path)?,
+            StorageFormat::Sqlite3 => file_converter.convert_sqlite3_file(&input_file_path)?,
+        }
+        converted_files.push(file_converter.finish(output_relative_file_path)?);
+    }
+
+    let metadata = match input_metadata {
+        Some(x) => update_metadata(x, options.storage_format, &converted_files),
+        None => create_metadata(options.storage_format, &converted_files),
+    };
+    if options.storage_format == StorageFormat::Sqlite3 {
+        let metadata_yaml = metadata.to_yaml_string()?;
+        for current_file in &converted_files {
+            write_metadata(
+                output_directory_path.join(&current_file.relative_file_path),
+                metadata.rosbag2_bagfile_information.version,
+                &metadata_yaml,
+            )?;
+        }
+    }
+    metadata.write_to_directory(output_directory_path)?;
+
+    Ok(metadata)
+}
+
+/// Returns the storage format of a file, which may be compressed as a whole.
+fn storage_format_of_file(file_path: &Path) -> Option<StorageFormat> {
+    uncompressed_file_path(file_path)
+        .extension()
+        .and_then(|x| x.to_str())
+        .and_then(StorageFormat::from_extension)
+}
+
+/// Replaces the storage identifier and the file paths, while all other entries are kept.
+///
+/// The converted files are not compressed, so that the file compression is reset.
+fn update_metadata(
+    mut metadata: BagMetadata,
+    storage_format: StorageFormat,
+    converted_files: &[ConvertedFile],
+) -> BagMetadata {
+    let file_compression_mode = is_file_compression_mode(&metadata);
+    let information = &mut metadata.rosbag2_bagfile_information;
+    let renamed_paths: HashMap<String, String> = information
+        .relative_file_paths
+        .iter()
+        .cloned()
+        .zip(converted_files.iter().map(|x| x.relative_file_path.clone()))
+        .collect();
+
+    information.storage_identifier = storage_format.as_str().to_string();
+    if file_compression_mode {
+        information.compression_format = String::new();
+        information.compression_mode = String::new();
+    }
+    information.relative_file_paths = converted_files
+        .iter()
+        .map(|x| x.relative_file_path.clone())
+        .collect();
+    for current_file in &mut information.files {
+        if let Some(path) = renamed_paths.get(&current_file.path) {
+            current_file.path = path.clone();
+        }
+    }
+    metadata
+}
+
+/// Creates the metadata from the converted files, where the message counts of topics with the
+/// same name and type are summed up.
+fn create_metadata(
+    storage_format: StorageFormat,
+    converted_files: &[ConvertedFile],
+) -> BagMetadata {
+    let start_timestamp = converted_files
+        .iter()
+        .filter_map(|x| x.start_timestamp)
+        .min()
+        .unwrap_or_default();
+    let end_timestamp = converted_files
+        .iter()
+        .filter_map(|x| x.end_timestamp)
+        .max()
+        .unwrap_or_default();
+
+    let mut topics_with_message_count: Vec<TopicInformation> = Vec::new();
+    for (current_topic, current_message_count) in converted_files.iter().flat_map(|x| &x.topics) {
+        match topics_with_message_count.iter_mut().find(|x| {
+            x.topic_metadata.name == current_topic.name
+                && x.topic_metadata.message_type == current_topic.message_type
+        }) {
+            Some(x) => x.message_count += current_message_count,
+            None => topics_with_message_count.push(TopicInformation {
+                topic_metadata: TopicMetadata {
+                    name: current_topic.name.clone(),
+                    message_type: current_topic.message_type.clone(),
+                    serialization_format: current_topic.serialization_format.clone(),
+                    offered_qos_profiles: current_topic.offered_qos_profiles.clone(),
+                    type_description_hash: current_topic.type_description_hash.clone(),
+                    other: BTreeMap::new(),
+                },
+                message_count: *current_message_count,
+            }),
+        }
+    }
+
+    BagMetadata {
+        rosbag2_bagfile_information: BagFileInformation {
+            version: METADATA_VERSION,
+            storage_identifier: storage_format.as_str().to_string(),
+            duration: Duration {
+                nanoseconds: (end_timestamp - start_timestamp) as u64,
+            },
+            starting_time: StartingTime {
+                nanoseconds_since_epoch: start_timestamp,
+            },
+            message_count: converted_files.iter().map(|x| x.message_count()).sum(),
+            topics_with_message_count,
+            compression_format: String::new(),
+            compression_mode: String::new(),
+            relative_file_paths: converted_files
+                .iter()
+                .map(|x| x.relative_file_path.clone())
+                .collect(),
+            files: converted_files
+                .iter()
+                .map(|x| x.file_information())
+                .collect(),
+            other: BTreeMap::new(),
+        },
+    }
+}
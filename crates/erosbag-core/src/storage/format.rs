@@ -0,0 +1,139 @@
+use crate::{MCAP_EXTENSION, SQLITE3_EXTENSION};
+use std::fmt;
+use std::str::FromStr;
+
+const STORAGE_FORMAT_MCAP_STR: &str = "mcap";
+const STORAGE_FORMAT_SQLITE3_STR: &str = "sqlite3";
+
+const MCAP_COMPRESSION_NONE_STR: &str = "none";
+const MCAP_COMPRESSION_LZ4_STR: &str = "lz4";
+const MCAP_COMPRESSION_ZSTD_STR: &str = "zstd";
+
+/// Chunk size of the written MCAP files in bytes, which is the default of the `mcap` crate.
+pub const DEFAULT_MCAP_CHUNK_SIZE: u64 = 768 * 1024;
+
+/// Storage format of the files of a ROS2 bag, which is named by its rosbag2 storage identifier.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum StorageFormat {
+    Mcap,
+    /// SQLite3 database, which was the default storage before ROS2 Iron.
+    Sqlite3,
+}
+
+impl StorageFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StorageFormat::Mcap => STORAGE_FORMAT_MCAP_STR,
+            StorageFormat::Sqlite3 => STORAGE_FORMAT_SQLITE3_STR,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            StorageFormat::Mcap => MCAP_EXTENSION,
+            StorageFormat::Sqlite3 => SQLITE3_EXTENSION,
+        }
+    }
+
+    /// Returns the storage format of a file extension, such as `db3`.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            MCAP_EXTENSION => Some(StorageFormat::Mcap),
+            SQLITE3_EXTENSION => Some(StorageFormat::Sqlite3),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for StorageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for StorageFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            STORAGE_FORMAT_MCAP_STR => Ok(StorageFormat::Mcap),
+            STORAGE_FORMAT_SQLITE3_STR => Ok(StorageFormat::Sqlite3),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Compression of the chunks of written MCAP files.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum McapCompression {
+    None,
+    Lz4,
+    Zstd,
+}
+
+impl McapCompression {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            McapCompression::None => MCAP_COMPRESSION_NONE_STR,
+            McapCompression::Lz4 => MCAP_COMPRESSION_LZ4_STR,
+            McapCompression::Zstd => MCAP_COMPRESSION_ZSTD_STR,
+        }
+    }
+
+    pub(crate) fn to_mcap(self) -> Option<mcap::Compression> {
+        match self {
+            McapCompression::None => None,
+            McapCompression::Lz4 => Some(mcap::Compression::Lz4),
+            McapCompression::Zstd => Some(mcap::Compression::Zstd),
+        }
+    }
+}
+
+impl fmt::Display for McapCompression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for McapCompression {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            MCAP_COMPRESSION_NONE_STR => Ok(McapCompression::None),
+            MCAP_COMPRESSION_LZ4_STR => Ok(McapCompression::Lz4),
+            MCAP_COMPRESSION_ZSTD_STR => Ok(McapCompression::Zstd),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Options of the conversion between storage formats.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct StorageConversionOptions {
+    pub storage_format: StorageFormat,
+    /// Compression of the chunks, which only applies to MCAP files.
+    pub compression: McapCompression,
+    /// Chunk size in bytes, which only applies to MCAP files.
+    pub chunk_size: u64,
+}
+
+impl StorageConversionOptions {
+    pub fn new(storage_format: StorageFormat) -> Self {
+        Self {
+            storage_format,
+            compression: McapCompression::Zstd,
+            chunk_size: DEFAULT_MCAP_CHUNK_SIZE,
+        }
+    }
+
+    pub fn with_compression(mut self, compression: McapCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn with_chunk_size(mut self, chunk_size: u64) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+}
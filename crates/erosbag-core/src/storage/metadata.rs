@@ -0,0 +1,140 @@
+use crate::Error;
+use crate::topics::qos_profile::QualityOfServiceProfile;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Name of the metadata file in the directory of a ROS2 bag.
+pub const METADATA_FILE_NAME: &str = "metadata.yaml";
+
+/// Version of the metadata, which is written for bags without a metadata file.
+pub const METADATA_VERSION: u32 = 5;
+
+/// Content of the `metadata.yaml` file of a ROS2 bag.
+///
+/// Keys unknown to this version are kept in `other`, so that reading and writing the metadata
+/// preserves them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BagMetadata {
+    pub rosbag2_bagfile_information: BagFileInformation,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct BagFileInformation {
+    pub version: u32,
+    pub storage_identifier: String,
+    pub duration: Duration,
+    pub starting_time: StartingTime,
+    pub message_count: u64,
+    pub topics_with_message_count: Vec<TopicInformation>,
+    pub compression_format: String,
+    pub compression_mode: String,
+    pub relative_file_paths: Vec<String>,
+    pub files: Vec<FileInformation>,
+    #[serde(flatten)]
+    pub other: BTreeMap<String, serde_yaml::Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Duration {
+    pub nanoseconds: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StartingTime {
+    pub nanoseconds_since_epoch: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct TopicInformation {
+    pub topic_metadata: TopicMetadata,
+    pub message_count: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct TopicMetadata {
+    pub name: String,
+    /// Message type, such as `sensor_msgs/msg/Imu`.
+    #[serde(rename = "type")]
+    pub message_type: String,
+    pub serialization_format: String,
+    /// QoS profiles serialized as YAML, which is kept as is.
+    pub offered_qos_profiles: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub type_description_hash: String,
+    #[serde(flatten)]
+    pub other: BTreeMap<String, serde_yaml::Value>,
+}
+
+impl TopicMetadata {
+    /// Parses the offered QoS profiles, which are empty if none are recorded.
+    pub fn parse_offered_qos_profiles(&self) -> Result<Vec<QualityOfServiceProfile>, Error> {
+        if self.offered_qos_profiles.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        let profiles = serde_yaml::from_str(&self.offered_qos_profiles)?;
+        Ok(profiles)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct FileInformation {
+    pub path: String,
+    pub starting_time: StartingTime,
+    pub duration: Duration,
+    pub message_count: u64,
+}
+
+impl BagMetadata {
+    pub fn from_file(file_path: impl AsRef<Path>) -> Result<Self, Error> {
+        let content = std::fs::read_to_string(file_path)?;
+        let metadata = serde_yaml::from_str(&content)?;
+        Ok(metadata)
+    }
+
+    /// Reads the metadata file of a bag directory, if it exists.
+    pub fn from_directory(directory_path: impl AsRef<Path>) -> Result<Option<Self>, Error> {
+        let file_path = directory_path.as_ref().join(METADATA_FILE_NAME);
+        if !file_path.is_file() {
+            return Ok(None);
+        }
+        Self::from_file(file_path).map(Some)
+    }
+
+    /// Returns the paths of the storage files in the bag directory.
+    ///
+    /// Older versions of rosbag2 list the files relative to the parent directory of the bag, so
+    /// that only their file names are kept if the paths do not exist.
+    pub fn file_paths(&self, directory_path: impl AsRef<Path>) -> Vec<PathBuf> {
+        self.rosbag2_bagfile_information
+            .relative_file_paths
+            .iter()
+            .map(|x| {
+                let file_path = directory_path.as_ref().join(x);
+                match Path::new(x).file_name() {
+                    Some(file_name) if !file_path.exists() => {
+                        directory_path.as_ref().join(file_name)
+                    }
+                    _ => file_path,
+                }
+            })
+            .collect()
+    }
+
+    pub fn to_yaml_string(&self) -> Result<String, Error> {
+        let content = serde_yaml::to_string(self)?;
+        Ok(content)
+    }
+
+    pub fn write_to_directory(&self, directory_path: impl AsRef<Path>) -> Result<(), Error> {
+        std::fs::write(
+            directory_path.as_ref().join(METADATA_FILE_NAME),
+            self.to_yaml_string()?,
+        )?;
+        Ok(())
+    }
+}
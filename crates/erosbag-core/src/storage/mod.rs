@@ -0,0 +1,35 @@
+//! Conversion of ROS2 bags between the MCAP and SQLite3 storage formats of rosbag2.
+//!
+//! The topics with their QoS profiles and type hashes, the message definitions and the message
+//! timestamps are kept, while the `metadata.yaml` file is carried over with updated file paths.
+mod convert;
+mod format;
+pub mod metadata;
+mod sqlite3;
+
+#[doc(inline)]
+pub use convert::convert_storage;
+
+#[doc(inline)]
+pub use format::StorageFormat;
+
+#[doc(inline)]
+pub use format::McapCompression;
+
+#[doc(inline)]
+pub use format::StorageConversionOptions;
+
+#[doc(inline)]
+pub use format::DEFAULT_MCAP_CHUNK_SIZE;
+
+#[doc(inline)]
+pub use metadata::BagMetadata;
+
+pub(crate) const ROS2_PROFILE: &str = "ros2";
+pub(crate) const ROS2MSG_SCHEMA_ENCODING: &str = "ros2msg";
+pub(crate) const CDR_MESSAGE_ENCODING: &str = "cdr";
+
+/// Key of the MCAP channel metadata holding the QoS profiles serialized as YAML.
+pub(crate) const OFFERED_QOS_PROFILES_KEY: &str = "offered_qos_profiles";
+/// Key of the MCAP channel metadata holding the type description hash.
+pub(crate) const TOPIC_TYPE_HASH_KEY: &str = "topic_type_hash";
@@ -0,0 +1,314 @@
+//! Reading and writing of the SQLite3 files of rosbag2, whose schema is described in the
+//! [storage plugin](https://github.com/ros2/rosbag2/tree/rolling/rosbag2_storage_sqlite3).
+
+use crate::Error;
+use rusqlite::{Connection, OpenFlags, params};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Version of the written schema, which contains the message definitions and type hashes.
+const SCHEMA_VERSION: i64 = 4;
+
+/// Table of the MCAP metadata records, which is not part of the rosbag2 schema just as the
+/// `publish_time` and `sequence` columns of the `messages` table. rosbag2 selects its tables and
+/// columns by name, so that they are ignored, while converting back to MCAP keeps the records and
+/// message headers.
+const MCAP_METADATA_TABLE_NAME: &str = "mcap_metadata";
+
+const CREATE_TABLES_SQL: &str = "
+CREATE TABLE schema(schema_version INTEGER PRIMARY KEY, ros_distro TEXT NOT NULL);
+CREATE TABLE metadata(id INTEGER PRIMARY KEY, metadata_version INTEGER NOT NULL, metadata TEXT NOT NULL);
+CREATE TABLE topics(id INTEGER PRIMARY KEY, name TEXT NOT NULL, type TEXT NOT NULL, serialization_format TEXT NOT NULL, offered_qos_profiles TEXT NOT NULL, type_description_hash TEXT NOT NULL);
+CREATE TABLE message_definitions(id INTEGER PRIMARY KEY, topic_type TEXT NOT NULL, encoding TEXT NOT NULL, encoded_message_definition TEXT NOT NULL, type_description_hash TEXT NOT NULL);
+CREATE TABLE messages(id INTEGER PRIMARY KEY, topic_id INTEGER NOT NULL, timestamp INTEGER NOT NULL, data BLOB NOT NULL, publish_time INTEGER, sequence INTEGER);
+CREATE INDEX timestamp_idx ON messages (timestamp ASC);
+CREATE TABLE mcap_metadata(id INTEGER PRIMARY KEY, name TEXT NOT NULL, metadata TEXT NOT NULL);
+";
+
+/// Row of the `topics` table, whose columns missing in older schema versions are empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Sqlite3Topic {
+    pub id: i64,
+    pub name: String,
+    pub message_type: String,
+    pub serialization_format: String,
+    pub offered_qos_profiles: String,
+    pub type_description_hash: String,
+}
+
+/// Row of the `messages` table, whose publish time and sequence are only present in files
+/// converted from MCAP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Sqlite3Message<'a> {
+    pub topic_id: i64,
+    pub timestamp: i64,
+    pub publish_time: Option<i64>,
+    pub sequence: Option<u32>,
+    pub data: &'a [u8],
+}
+
+/// Row of the `message_definitions` table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Sqlite3MessageDefinition {
+    pub encoding: String,
+    pub encoded_message_definition: String,
+}
+
+pub(crate) struct Sqlite3Reader {
+    connection: Connection,
+}
+
+impl Sqlite3Reader {
+    pub fn new(file_path: impl AsRef<Path>) -> Result<Self, Error> {
+        let connection = Connection::open_with_flags(file_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        Ok(Self { connection })
+    }
+
+    fn table_exists(&self, table_name: &str) -> Result<bool, Error> {
+        let count: i64 = self.connection.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            [table_name],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    fn column_names(&self, table_name: &str) -> Result<Vec<String>, Error> {
+        let mut statement = self
+            .connection
+            .prepare(&format!("PRAGMA table_info({table_name})"))?;
+        let column_names = statement
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(column_names)
+    }
+
+    pub fn topics(&self) -> Result<Vec<Sqlite3Topic>, Error> {
+        let column_names = self.column_names("topics")?;
+        let optional_column = |name: &str| {
+            if column_names.iter().any(|x| x == name) {
+                name.to_string()
+            } else {
+                format!("'' AS {name}")
+            }
+        };
+        let sql = format!(
+            "SELECT id, name, type, serialization_format, {}, {} FROM topics ORDER BY id",
+            optional_column("offered_qos_profiles"),
+            optional_column("type_description_hash")
+        );
+
+        let mut statement = self.connection.prepare(&sql)?;
+        let topics = statement
+            .query_map([], |row| {
+                Ok(Sqlite3Topic {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    message_type: row.get(2)?,
+                    serialization_format: row.get(3)?,
+                    offered_qos_profiles: row.get(4)?,
+                    type_description_hash: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(topics)
+    }
+
+    /// Returns the message definitions per topic type, which are empty for schema versions
+    /// before 4.
+    pub fn message_definitions(&self) -> Result<HashMap<String, Sqlite3MessageDefinition>, Error> {
+        if !self.table_exists("message_definitions")? {
+            return Ok(HashMap::new());
+        }
+
+        let mut statement = self.connection.prepare(
+            "SELECT topic_type, encoding, encoded_message_definition FROM message_definitions",
+        )?;
+        let message_definitions = statement
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    Sqlite3MessageDefinition {
+                        encoding: row.get(1)?,
+                        encoded_message_definition: row.get(2)?,
+                    },
+                ))
+            })?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        Ok(message_definitions)
+    }
+
+    /// Returns the MCAP metadata records of a file converted from MCAP.
+    pub fn mcap_metadata(&self) -> Result<Vec<mcap::records::Metadata>, Error> {
+        if !self.table_exists(MCAP_METADATA_TABLE_NAME)? {
+            return Ok(Vec::new());
+        }
+
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT name, metadata FROM {MCAP_METADATA_TABLE_NAME} ORDER BY id"
+        ))?;
+        let rows = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get::<_, String>(1)?)))?
+            .collect::<Result<Vec<(String, String)>, _>>()?;
+        rows.into_iter()
+            .map(|(name, metadata)| {
+                Ok(mcap::records::Metadata {
+                    name,
+                    metadata: serde_json::from_str(&metadata)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Calls the function with each message in the order of the timestamps.
+    pub fn for_each_message(
+        &self,
+        mut function: impl FnMut(Sqlite3Message) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let column_names = self.column_names("messages")?;
+        let optional_column = |name: &str| {
+            if column_names.iter().any(|x| x == name) {
+                name.to_string()
+            } else {
+                format!("NULL AS {name}")
+            }
+        };
+        let sql = format!(
+            "SELECT topic_id, timestamp, data, {}, {} FROM messages ORDER BY timestamp, id",
+            optional_column("publish_time"),
+            optional_column("sequence")
+        );
+
+        let mut statement = self.connection.prepare(&sql)?;
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            let data = row.get_ref(2)?.as_blob().map_err(rusqlite::Error::from)?;
+            function(Sqlite3Message {
+                topic_id: row.get(0)?,
+                timestamp: row.get(1)?,
+                publish_time: row.get(3)?,
+                sequence: row.get(4)?,
+                data,
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// Writer of a SQLite3 file, whose rows are inserted in a single transaction.
+pub(crate) struct Sqlite3Writer {
+    connection: Connection,
+}
+
+impl Sqlite3Writer {
+    /// Creates the file with the tables, where an existing file is replaced.
+    pub fn new(file_path: impl AsRef<Path>, ros_distro: &str) -> Result<Self, Error> {
+        if file_path.as_ref().exists() {
+            std::fs::remove_file(&file_path)?;
+        }
+        let connection = Connection::open(file_path)?;
+        connection.execute_batch(CREATE_TABLES_SQL)?;
+        connection.execute(
+            "INSERT INTO schema (schema_version, ros_distro) VALUES (?1, ?2)",
+            params![SCHEMA_VERSION, ros_distro],
+        )?;
+        connection.execute_batch("BEGIN TRANSACTION")?;
+
+        Ok(Self { connection })
+    }
+
+    /// Inserts the topic and returns its id.
+    pub fn add_topic(
+        &self,
+        name: &str,
+        message_type: &str,
+        serialization_format: &str,
+        offered_qos_profiles: &str,
+        type_description_hash: &str,
+    ) -> Result<i64, Error> {
+        self.connection
+            .prepare_cached(
+                "INSERT INTO topics (name, type, serialization_format, offered_qos_profiles, type_description_hash) VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?
+            .execute(params![
+                name,
+                message_type,
+                serialization_format,
+                offered_qos_profiles,
+                type_description_hash
+            ])?;
+        Ok(self.connection.last_insert_rowid())
+    }
+
+    pub fn add_message_definition(
+        &self,
+        topic_type: &str,
+        encoding: &str,
+        encoded_message_definition: &str,
+        type_description_hash: &str,
+    ) -> Result<(), Error> {
+        self.connection
+            .prepare_cached(
+                "INSERT INTO message_definitions (topic_type, encoding, encoded_message_definition, type_description_hash) VALUES (?1, ?2, ?3, ?4)",
+            )?
+            .execute(params![
+                topic_type,
+                encoding,
+                encoded_message_definition,
+                type_description_hash
+            ])?;
+        Ok(())
+    }
+
+    pub fn write_message(&self, message: &Sqlite3Message) -> Result<(), Error> {
+        self.connection
+            .prepare_cached(
+                "INSERT INTO messages (topic_id, timestamp, data, publish_time, sequence) VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?
+            .execute(params![
+                message.topic_id,
+                message.timestamp,
+                message.data,
+                message.publish_time,
+                message.sequence
+            ])?;
+        Ok(())
+    }
+
+    pub fn add_mcap_metadata(&self, metadata: &mcap::records::Metadata) -> Result<(), Error> {
+        self.connection
+            .prepare_cached(&format!(
+                "INSERT INTO {MCAP_METADATA_TABLE_NAME} (name, metadata) VALUES (?1, ?2)"
+            ))?
+            .execute(params![
+                metadata.name,
+                serde_json::to_string(&metadata.metadata)?
+            ])?;
+        Ok(())
+    }
+
+    /// Commits the transaction and closes the file.
+    pub fn finish(self) -> Result<(), Error> {
+        self.connection.execute_batch("COMMIT TRANSACTION")?;
+        self.connection
+            .close()
+            .map_err(|(_, error)| Error::from(error))?;
+        Ok(())
+    }
+}
+
+/// Stores the serialized `metadata.yaml` in the `metadata` table of a written file, as rosbag2
+/// does for schema versions from 3 on.
+pub(crate) fn write_metadata(
+    file_path: impl AsRef<Path>,
+    metadata_version: u32,
+    metadata: &str,
+) -> Result<(), Error> {
+    let connection = Connection::open(file_path)?;
+    connection.execute("DELETE FROM metadata", [])?;
+    connection.execute(
+        "INSERT INTO metadata (metadata_version, metadata) VALUES (?1, ?2)",
+        params![metadata_version, metadata],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,496 @@
+//! Synchronization of messages from multiple channels by their header stamps, similar to the
+//! [`message_filters`] of ROS.
+//!
+//! [`message_filters`]: https://github.com/ros2/message_filters
+use crate::dto::{McapMessageMeta, McapOverview, RawMessage};
+use crate::mcap_source::{McapSource, MmapSource};
+use crate::playback::FromRawMessage;
+use crate::ros_messages::{AnyMessage, Header, RosMessage};
+use crate::{ChannelTopic, ChunkId, Error, FileName, MessageId, Rosbag};
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+/// Policy for matching messages of multiple channels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncPolicy {
+    /// Matches messages with identical header stamps.
+    ExactTime {
+        /// Maximum number of pending stamps.
+        queue_size: usize,
+    },
+    /// Matches the messages closest to each other, whose header stamps lie within the slop.
+    ApproximateTime {
+        /// Maximum difference between the header stamps of a group.
+        slop: chrono::Duration,
+        /// Maximum number of pending messages per channel.
+        queue_size: usize,
+    },
+}
+
+impl SyncPolicy {
+    fn queue_size(&self) -> usize {
+        match self {
+            SyncPolicy::ExactTime { queue_size } => *queue_size,
+            SyncPolicy::ApproximateTime { queue_size, .. } => *queue_size,
+        }
+    }
+}
+
+/// Message, in which a [`MessageSynchronizer`] emits the messages of the bag.
+///
+/// The trait is implemented for [`AnyMessage`], for [`RawMessage`] and for each [`RosMessage`]
+/// with a header, as the messages are matched by their header stamps.
+pub trait SyncMessage: FromRawMessage {
+    /// Returns the header stamp, or `None` if the message has no header, so that its log time
+    /// is used instead.
+    fn stamp(&self) -> Option<DateTime<Utc>>;
+}
+
+impl SyncMessage for AnyMessage {
+    fn stamp(&self) -> Option<DateTime<Utc>> {
+        self.header().map(|x| x.stamp.into())
+    }
+}
+
+impl SyncMessage for RawMessage {
+    fn stamp(&self) -> Option<DateTime<Utc>> {
+        None
+    }
+}
+
+impl<M: RosMessage + Header> SyncMessage for M {
+    fn stamp(&self) -> Option<DateTime<Utc>> {
+        Some(self.header().stamp.into())
+    }
+}
+
+/// Group of time-aligned messages with one message per channel in the order of the
+/// synchronized channels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SynchronizedGroup<T = AnyMessage> {
+    pub messages: Vec<McapMessageMeta<T>>,
+}
+
+impl<T: SyncMessage> SynchronizedGroup<T> {
+    /// Returns the earliest header stamp of the group.
+    pub fn start_date_time(&self) -> Option<DateTime<Utc>> {
+        self.messages.iter().map(get_stamp).min()
+    }
+
+    /// Returns the latest header stamp of the group.
+    pub fn end_date_time(&self) -> Option<DateTime<Utc>> {
+        self.messages.iter().map(get_stamp).max()
+    }
+}
+
+/// Counts of the messages processed by a [`MessageSynchronizer`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SyncStatistics {
+    pub matched_groups: usize,
+    pub received_messages: BTreeMap<ChannelTopic, usize>,
+    pub unmatched_messages: BTreeMap<ChannelTopic, usize>,
+}
+
+/// Streams groups of time-aligned messages of multiple channels decoded as `T`.
+///
+/// Chunks are read one at a time in the order of their start time, so that only the messages
+/// pending for a match are kept in memory. Messages, which are not decoded as `T`, are skipped.
+#[derive(Debug)]
+pub struct MessageSynchronizer<'a, T: SyncMessage = AnyMessage, S: McapSource = MmapSource> {
+    rosbag: &'a Rosbag<S>,
+    overview: McapOverview,
+    channel_topics: Vec<ChannelTopic>,
+    start_date_time: Option<DateTime<Utc>>,
+    end_date_time: Option<DateTime<Utc>>,
+    remaining_chunks: VecDeque<(DateTime<Utc>, FileName, ChunkId)>,
+    pending_messages: BTreeMap<(DateTime<Utc>, FileName, ChunkId, MessageId), McapMessageMeta<T>>,
+    matcher: Matcher<T>,
+    groups: VecDeque<SynchronizedGroup<T>>,
+    statistics: SyncStatistics,
+    is_finished: bool,
+}
+
+impl<'a, T: SyncMessage, S: McapSource> MessageSynchronizer<'a, T, S> {
+    /// Fails, if the queue size of the policy is zero, as no message could be kept for a match.
+    pub fn new(
+        rosbag: &'a Rosbag<S>,
+        channel_topics: Vec<ChannelTopic>,
+        policy: SyncPolicy,
+        start_date_time: &Option<DateTime<Utc>>,
+        end_date_time: &Option<DateTime<Utc>>,
+    ) -> Result<Self, Error> {
+        if policy.queue_size() == 0 {
+            return Err(Error::InvalidSyncPolicy(policy));
+        }
+        let overview = rosbag.get_overview()?;
+        let channel_topic_set: HashSet<ChannelTopic> = channel_topics.iter().cloned().collect();
+        let remaining_chunks: VecDeque<(DateTime<Utc>, FileName, ChunkId)> = overview
+            .get_chunk_ids_of_channel_topics(start_date_time, end_date_time, &channel_topic_set)
+            .into_iter()
+            .flat_map(|(file_name, chunk_ids)| {
+                let file_overview = &overview.files[&file_name];
+                chunk_ids
+                    .into_iter()
+                    .map(|x| {
+                        (
+                            file_overview.chunks[&x].start_date_time,
+                            file_name.clone(),
+                            x,
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .sorted()
+            .collect();
+
+        let matcher = match policy {
+            SyncPolicy::ExactTime { queue_size } => Matcher::ExactTime {
+                queue_size,
+                pending: BTreeMap::new(),
+            },
+            SyncPolicy::ApproximateTime { slop, queue_size } => Matcher::ApproximateTime {
+                slop,
+                queue_size,
+                queues: channel_topics.iter().map(|_| VecDeque::new()).collect(),
+                latest_date_times: vec![None; channel_topics.len()],
+            },
+        };
+
+        Ok(Self {
+            rosbag,
+            overview,
+            channel_topics,
+            start_date_time: *start_date_time,
+            end_date_time: *end_date_time,
+            remaining_chunks,
+            pending_messages: BTreeMap::new(),
+            matcher,
+            groups: VecDeque::new(),
+            statistics: SyncStatistics::default(),
+            is_finished: false,
+        })
+    }
+
+    /// Returns the statistics of the messages processed so far.
+    pub fn statistics(&self) -> &SyncStatistics {
+        &self.statistics
+    }
+
+    fn read_next_chunk(&mut self) -> Result<(), Error> {
+        let Some((_, file_name, chunk_id)) = self.remaining_chunks.pop_front() else {
+            let released = std::mem::take(&mut self.pending_messages);
+            self.process_messages(released.into_values());
+            self.matcher.finish(&mut self.groups, &mut self.statistics);
+            self.is_finished = true;
+            return Ok(());
+        };
+
+        let mcap_file = self
+            .rosbag
+            .mcap_files
+            .get(&file_name)
+            .ok_or(Error::ContainsNoMcapFileWithName(file_name.clone()))?;
+        let channel_ids = self.overview.files[&file_name]
+            .get_channel_ids_from_topics(&self.channel_topics.iter().cloned().collect());
+        for current_message in mcap_file.read_chunk_messages(chunk_id, &Some(channel_ids))? {
+            let Some(schema) = current_message.message.channel.schema.as_ref() else {
+                continue;
+            };
+            let raw_message = RawMessage::new(
+                Some(schema.name.clone()),
+                current_message.message.data.to_vec(),
+            );
+            let Some(message) = T::from_raw_message(raw_message)? else {
+                continue;
+            };
+            let key = (
+                current_message.log_date_time,
+                current_message.file_name.clone(),
+                current_message.chunk_id,
+                current_message.message_id,
+            );
+            self.pending_messages
+                .insert(key, current_message.with_message(message));
+        }
+
+        // messages before the start of the next chunk cannot be preceded by any unread message
+        let released = match self.remaining_chunks.front() {
+            Some((next_start_date_time, _, _)) => {
+                let later_messages = self.pending_messages.split_off(&(
+                    *next_start_date_time,
+                    FileName::from(""),
+                    ChunkId::from(0),
+                    MessageId::MIN,
+                ));
+                std::mem::replace(&mut self.pending_messages, later_messages)
+            }
+            None => std::mem::take(&mut self.pending_messages),
+        };
+        self.process_messages(released.into_values());
+
+        Ok(())
+    }
+
+    fn process_messages(&mut self, messages: impl Iterator<Item = McapMessageMeta<T>>) {
+        for current_message in messages {
+            if self
+                .start_date_time
+                .is_some_and(|start| current_message.log_date_time < start)
+                || self
+                    .end_date_time
+                    .is_some_and(|end| end <= current_message.log_date_time)
+            {
+                continue;
+            }
+            let Some(channel_index) = self
+                .channel_topics
+                .iter()
+                .position(|x| x == &current_message.channel_topic)
+            else {
+                continue;
+            };
+
+            *self
+                .statistics
+                .received_messages
+                .entry(current_message.channel_topic.clone())
+                .or_default() += 1;
+            self.matcher.add(
+                channel_index,
+                current_message,
+                &self.channel_topics,
+                &mut self.groups,
+                &mut self.statistics,
+            );
+        }
+    }
+}
+
+impl<T: SyncMessage, S: McapSource> Iterator for MessageSynchronizer<'_, T, S> {
+    type Item = Result<SynchronizedGroup<T>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(group) = self.groups.pop_front() {
+                return Some(Ok(group));
+            }
+            if self.is_finished {
+                return None;
+            }
+            if let Err(error) = self.read_next_chunk() {
+                self.is_finished = true;
+                return Some(Err(error));
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Matcher<T> {
+    ExactTime {
+        queue_size: usize,
+        pending: BTreeMap<DateTime<Utc>, Vec<Option<McapMessageMeta<T>>>>,
+    },
+    ApproximateTime {
+        slop: chrono::Duration,
+        queue_size: usize,
+        queues: Vec<VecDeque<McapMessageMeta<T>>>,
+        latest_date_times: Vec<Option<DateTime<Utc>>>,
+    },
+}
+
+impl<T: SyncMessage> Matcher<T> {
+    fn add(
+        &mut self,
+        channel_index: usize,
+        message: McapMessageMeta<T>,
+        channel_topics: &[ChannelTopic],
+        groups: &mut VecDeque<SynchronizedGroup<T>>,
+        statistics: &mut SyncStatistics,
+    ) {
+        let stamp = get_stamp(&message);
+
+        match self {
+            Matcher::ExactTime {
+                queue_size,
+                pending,
+            } => {
+                let slots = pending
+                    .entry(stamp)
+                    .or_insert_with(|| channel_topics.iter().map(|_| None).collect());
+                if let Some(replaced) = slots[channel_index].replace(message) {
+                    count_unmatched(statistics, &replaced);
+                }
+
+                if slots.iter().all(|x| x.is_some()) {
+                    let mut later_stamps = pending.split_off(&stamp);
+                    let matched = later_stamps.remove(&stamp).expect("should contain stamp");
+                    for earlier_message in std::mem::replace(pending, later_stamps)
+                        .into_values()
+                        .flatten()
+                        .flatten()
+                    {
+                        count_unmatched(statistics, &earlier_message);
+                    }
+
+                    statistics.matched_groups += 1;
+                    groups.push_back(SynchronizedGroup {
+                        messages: matched.into_iter().flatten().collect(),
+                    });
+                }
+
+                while pending.len() > *queue_size {
+                    let (_, dropped) = pending.pop_first().expect("should not be empty");
+                    dropped
+                        .iter()
+                        .flatten()
+                        .for_each(|x| count_unmatched(statistics, x));
+                }
+            }
+            Matcher::ApproximateTime {
+                queue_size,
+                queues,
+                latest_date_times,
+                ..
+            } => {
+                let queue = &mut queues[channel_index];
+                let position = queue.partition_point(|x| get_stamp(x) <= stamp);
+                queue.insert(position, message);
+                if queue.len() > *queue_size
+                    && let Some(dropped) = queue.pop_front()
+                {
+                    count_unmatched(statistics, &dropped);
+                }
+                latest_date_times[channel_index] =
+                    latest_date_times[channel_index].max(Some(stamp));
+
+                self.match_approximate(false, groups, statistics);
+            }
+        }
+    }
+
+    fn match_approximate(
+        &mut self,
+        is_finished: bool,
+        groups: &mut VecDeque<SynchronizedGroup<T>>,
+        statistics: &mut SyncStatistics,
+    ) {
+        let Matcher::ApproximateTime {
+            slop,
+            queues,
+            latest_date_times,
+            ..
+        } = self
+        else {
+            return;
+        };
+
+        while queues.iter().all(|x| !x.is_empty()) {
+            let pivot = queues
+                .iter()
+                .map(|x| get_stamp(&x[0]))
+                .max()
+                .expect("should contain queues");
+
+            // a closer candidate may still arrive on channels that have not reached the pivot yet
+            if !is_finished
+                && latest_date_times
+                    .iter()
+                    .any(|x| x.is_none_or(|t| t < pivot))
+            {
+                break;
+            }
+
+            let candidates: Vec<usize> = queues
+                .iter()
+                .map(|queue| {
+                    queue
+                        .iter()
+                        .position_min_by_key(|x| (get_stamp(x) - pivot).abs())
+                        .expect("should not be empty")
+                })
+                .collect();
+            let candidate_stamps: Vec<DateTime<Utc>> = queues
+                .iter()
+                .zip(&candidates)
+                .map(|(queue, i)| get_stamp(&queue[*i]))
+                .collect();
+            let spread = *candidate_stamps.iter().max().expect("should not be empty")
+                - *candidate_stamps.iter().min().expect("should not be empty");
+
+            if spread <= *slop {
+                let mut messages: Vec<McapMessageMeta<T>> = Vec::with_capacity(queues.len());
+                for (queue, candidate) in queues.iter_mut().zip(candidates) {
+                    for earlier_message in queue.drain(..candidate) {
+                        count_unmatched(statistics, &earlier_message);
+                    }
+                    messages.push(queue.pop_front().expect("should contain candidate"));
+                }
+
+                statistics.matched_groups += 1;
+                groups.push_back(SynchronizedGroup { messages });
+            } else {
+                let (earliest_queue, _) = queues
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, x)| get_stamp(&x[0]))
+                    .expect("should contain queues");
+                let dropped = queues[earliest_queue]
+                    .pop_front()
+                    .expect("should not be empty");
+                count_unmatched(statistics, &dropped);
+            }
+        }
+    }
+
+    fn finish(
+        &mut self,
+        groups: &mut VecDeque<SynchronizedGroup<T>>,
+        statistics: &mut SyncStatistics,
+    ) {
+        self.match_approximate(true, groups, statistics);
+
+        match self {
+            Matcher::ExactTime { pending, .. } => {
+                for message in std::mem::take(pending).into_values().flatten().flatten() {
+                    count_unmatched(statistics, &message);
+                }
+            }
+            Matcher::ApproximateTime { queues, .. } => {
+                for message in queues.iter_mut().flat_map(|x| x.drain(..)) {
+                    count_unmatched(statistics, &message);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the value with the key closest to the date time together with its key, where the
+/// earlier value is preferred on ties.
+pub fn get_closest<T>(
+    values: &BTreeMap<DateTime<Utc>, T>,
+    date_time: DateTime<Utc>,
+) -> Option<(DateTime<Utc>, &T)> {
+    let before = values.range(..=date_time).next_back();
+    let after = values.range(date_time..).next();
+
+    match (before, after) {
+        (Some(b), Some(a)) if date_time - *b.0 <= *a.0 - date_time => Some((*b.0, b.1)),
+        (_, Some(a)) => Some((*a.0, a.1)),
+        (Some(b), None) => Some((*b.0, b.1)),
+        (None, None) => None,
+    }
+}
+
+/// Returns the header stamp of a message or its log time, if the message has no header.
+fn get_stamp<T: SyncMessage>(message: &McapMessageMeta<T>) -> DateTime<Utc> {
+    message.message.stamp().unwrap_or(message.log_date_time)
+}
+
+fn count_unmatched<T>(statistics: &mut SyncStatistics, message: &McapMessageMeta<T>) {
+    *statistics
+        .unmatched_messages
+        .entry(message.channel_topic.clone())
+        .or_default() += 1;
+}
@@ -0,0 +1,92 @@
+use crate::Error;
+use polars::prelude::{CsvWriter, DataFrame, DataType, IpcWriter, ParquetWriter, SerWriter};
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+use std::str::FromStr;
+use tracing::warn;
+
+const TABLE_FORMAT_PARQUET_STR: &str = "parquet";
+const TABLE_FORMAT_CSV_STR: &str = "csv";
+const TABLE_FORMAT_IPC_STR: &str = "ipc";
+
+/// File format for writing tables.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum TableFormat {
+    Parquet,
+    /// Comma-separated values, which cannot hold list and binary columns.
+    Csv,
+    /// Arrow IPC file format, also known as Feather.
+    Ipc,
+}
+
+impl TableFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TableFormat::Parquet => TABLE_FORMAT_PARQUET_STR,
+            TableFormat::Csv => TABLE_FORMAT_CSV_STR,
+            TableFormat::Ipc => TABLE_FORMAT_IPC_STR,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TableFormat::Parquet => "parquet",
+            TableFormat::Csv => "csv",
+            TableFormat::Ipc => "arrow",
+        }
+    }
+
+    /// Writes the table to a file.
+    ///
+    /// For CSV, list and binary columns are skipped.
+    pub fn write(&self, data_frame: &mut DataFrame, path: impl AsRef<Path>) -> Result<(), Error> {
+        let file = File::create(path)?;
+
+        match self {
+            TableFormat::Parquet => {
+                ParquetWriter::new(file).finish(data_frame)?;
+            }
+            TableFormat::Csv => {
+                let nested_column_names: Vec<String> = data_frame
+                    .get_columns()
+                    .iter()
+                    .filter(|x| matches!(x.dtype(), DataType::List(_) | DataType::Binary))
+                    .map(|x| x.name().to_string())
+                    .collect();
+                if !nested_column_names.is_empty() {
+                    warn!(
+                        "Skipping columns not representable in CSV: {}",
+                        nested_column_names.join(", ")
+                    );
+                }
+                let mut flat_data_frame = data_frame.drop_many(nested_column_names);
+                CsvWriter::new(file).finish(&mut flat_data_frame)?;
+            }
+            TableFormat::Ipc => {
+                IpcWriter::new(file).finish(data_frame)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for TableFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for TableFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            TABLE_FORMAT_PARQUET_STR => Ok(TableFormat::Parquet),
+            TABLE_FORMAT_CSV_STR => Ok(TableFormat::Csv),
+            TABLE_FORMAT_IPC_STR => Ok(TableFormat::Ipc),
+            _ => Err(()),
+        }
+    }
+}
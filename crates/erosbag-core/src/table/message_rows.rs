@@ -0,0 +1,367 @@
+use crate::ros_messages::{
+    builtin_msgs, geometry_msgs, nav_msgs, sensor_msgs, std_msgs, tf2_msgs, visualization_msgs,
+};
+use crate::table::row::{CellValue, Row, TableRow, join_column_name};
+use chrono::{DateTime, Utc};
+
+impl TableRow for builtin_msgs::Time {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        let date_time: DateTime<Utc> = (*self).into();
+        row.push(
+            column_name,
+            CellValue::DateTime(date_time.timestamp_nanos_opt().unwrap_or_default()),
+        );
+    }
+}
+
+impl TableRow for builtin_msgs::Duration {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        let nanoseconds = self.sec as i64 * 1_000_000_000 + self.nanosec as i64;
+        row.push(column_name, CellValue::Duration(nanoseconds));
+    }
+}
+
+impl TableRow for std_msgs::Header {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "stamp", &self.stamp);
+        row.write(column_name, "frame_id", &self.frame_id);
+    }
+}
+
+impl TableRow for std_msgs::ColorRGBA {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "r", &self.r);
+        row.write(column_name, "g", &self.g);
+        row.write(column_name, "b", &self.b);
+        row.write(column_name, "a", &self.a);
+    }
+}
+
+impl TableRow for geometry_msgs::Point {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "x", &self.x);
+        row.write(column_name, "y", &self.y);
+        row.write(column_name, "z", &self.z);
+    }
+}
+
+impl TableRow for geometry_msgs::Vector3 {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "x", &self.x);
+        row.write(column_name, "y", &self.y);
+        row.write(column_name, "z", &self.z);
+    }
+}
+
+impl TableRow for geometry_msgs::Quaternion {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "x", &self.x);
+        row.write(column_name, "y", &self.y);
+        row.write(column_name, "z", &self.z);
+        row.write(column_name, "w", &self.w);
+    }
+}
+
+/// Uses the field names of the ROS2 message definition.
+impl TableRow for geometry_msgs::Pose {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "position", &self.point);
+        row.write(column_name, "orientation", &self.quaternion);
+    }
+}
+
+impl TableRow for geometry_msgs::PoseStamped {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "header", &self.header);
+        row.write(column_name, "pose", &self.pose);
+    }
+}
+
+impl TableRow for geometry_msgs::PoseWithCovariance {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "pose", &self.pose);
+        row.write(column_name, "covariance", &self.covariance);
+    }
+}
+
+impl TableRow for geometry_msgs::PoseWithCovarianceStamped {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "header", &self.header);
+        row.write(column_name, "pose", &self.pose);
+    }
+}
+
+impl TableRow for geometry_msgs::Transform {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "translation", &self.translation);
+        row.write(column_name, "rotation", &self.rotation);
+    }
+}
+
+impl TableRow for geometry_msgs::TransformStamped {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "header", &self.header);
+        row.write(column_name, "child_frame_id", &self.child_frame_id);
+        row.write(column_name, "transform", &self.transform);
+    }
+}
+
+impl TableRow for geometry_msgs::Twist {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "linear", &self.linear);
+        row.write(column_name, "angular", &self.angular);
+    }
+}
+
+impl TableRow for geometry_msgs::TwistStamped {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "header", &self.header);
+        row.write(column_name, "twist", &self.twist);
+    }
+}
+
+impl TableRow for geometry_msgs::TwistWithCovariance {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "twist", &self.twist);
+        row.write(column_name, "covariance", &self.covariance);
+    }
+}
+
+impl TableRow for sensor_msgs::Imu {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "header", &self.header);
+        row.write(column_name, "orientation", &self.orientation);
+        row.write(
+            column_name,
+            "orientation_covariance",
+            &self.orientation_covariance,
+        );
+        row.write(column_name, "angular_velocity", &self.angular_velocity);
+        row.write(
+            column_name,
+            "angular_velocity_covariance",
+            &self.angular_velocity_covariance,
+        );
+        row.write(
+            column_name,
+            "linear_acceleration",
+            &self.linear_acceleration,
+        );
+        row.write(
+            column_name,
+            "linear_acceleration_covariance",
+            &self.linear_acceleration_covariance,
+        );
+    }
+}
+
+impl TableRow for sensor_msgs::NavSatStatus {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "status", &self.status);
+        row.write(column_name, "service", &self.service);
+    }
+}
+
+impl TableRow for sensor_msgs::NavSatFix {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "header", &self.header);
+        row.write(column_name, "status", &self.status);
+        row.write(column_name, "latitude", &self.latitude);
+        row.write(column_name, "longitude", &self.longitude);
+        row.write(column_name, "altitude", &self.altitude);
+        row.write(
+            column_name,
+            "position_covariance",
+            &self.position_covariance,
+        );
+        row.write(
+            column_name,
+            "position_covariance_type",
+            &self.position_covariance_type,
+        );
+    }
+}
+
+impl TableRow for sensor_msgs::PointField {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "name", &self.name);
+        row.write(column_name, "offset", &self.offset);
+        row.write(column_name, "datatype", &self.datatype);
+        row.write(column_name, "count", &self.count);
+    }
+}
+
+/// The point data is kept as binary column.
+impl TableRow for sensor_msgs::PointCloud2 {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "header", &self.header);
+        row.write(column_name, "height", &self.height);
+        row.write(column_name, "width", &self.width);
+        row.write(column_name, "fields", &self.fields);
+        row.write(column_name, "is_bigendian", &self.is_bigendian);
+        row.write(column_name, "point_step", &self.point_step);
+        row.write(column_name, "row_step", &self.row_step);
+        write_binary(column_name, "data", &self.data, row);
+        row.write(column_name, "is_dense", &self.is_dense);
+    }
+}
+
+/// The pixel data is kept as binary column.
+impl TableRow for sensor_msgs::Image {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "header", &self.header);
+        row.write(column_name, "height", &self.height);
+        row.write(column_name, "width", &self.width);
+        row.write(column_name, "encoding", &self.encoding);
+        row.write(column_name, "is_bigendian", &self.is_bigendian);
+        row.write(column_name, "step", &self.step);
+        write_binary(column_name, "data", &self.data, row);
+    }
+}
+
+impl TableRow for sensor_msgs::CompressedImage {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "header", &self.header);
+        row.write(column_name, "format", &self.format);
+        write_binary(column_name, "data", &self.data, row);
+    }
+}
+
+impl TableRow for sensor_msgs::RegionOfInterest {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "x_offset", &self.x_offset);
+        row.write(column_name, "y_offset", &self.y_offset);
+        row.write(column_name, "height", &self.height);
+        row.write(column_name, "width", &self.width);
+        row.write(column_name, "do_rectify", &self.do_rectify);
+    }
+}
+
+impl TableRow for sensor_msgs::CameraInfo {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "header", &self.header);
+        row.write(column_name, "height", &self.height);
+        row.write(column_name, "width", &self.width);
+        row.write(column_name, "distortion_model", &self.distortion_model);
+        row.write(column_name, "d", &self.d);
+        row.write(column_name, "k", &self.k);
+        row.write(column_name, "r", &self.r);
+        row.write(column_name, "p", &self.p);
+        row.write(column_name, "binning_x", &self.binning_x);
+        row.write(column_name, "binning_y", &self.binning_y);
+        row.write(column_name, "roi", &self.roi);
+    }
+}
+
+impl TableRow for sensor_msgs::LaserScan {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "header", &self.header);
+        row.write(column_name, "angle_min", &self.angle_min);
+        row.write(column_name, "angle_max", &self.angle_max);
+        row.write(column_name, "angle_increment", &self.angle_increment);
+        row.write(column_name, "time_increment", &self.time_increment);
+        row.write(column_name, "scan_time", &self.scan_time);
+        row.write(column_name, "range_min", &self.range_min);
+        row.write(column_name, "range_max", &self.range_max);
+        row.write(column_name, "ranges", &self.ranges);
+        row.write(column_name, "intensities", &self.intensities);
+    }
+}
+
+impl TableRow for sensor_msgs::LaserEcho {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "echoes", &self.echoes);
+    }
+}
+
+impl TableRow for sensor_msgs::MultiEchoLaserScan {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "header", &self.header);
+        row.write(column_name, "angle_min", &self.angle_min);
+        row.write(column_name, "angle_max", &self.angle_max);
+        row.write(column_name, "angle_increment", &self.angle_increment);
+        row.write(column_name, "time_increment", &self.time_increment);
+        row.write(column_name, "scan_time", &self.scan_time);
+        row.write(column_name, "range_min", &self.range_min);
+        row.write(column_name, "range_max", &self.range_max);
+        row.write(column_name, "ranges", &self.ranges);
+        row.write(column_name, "intensities", &self.intensities);
+    }
+}
+
+impl TableRow for tf2_msgs::TFMessage {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "transforms", &self.transforms);
+    }
+}
+
+impl TableRow for nav_msgs::Odometry {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "header", &self.header);
+        row.write(column_name, "child_frame_id", &self.child_frame_id);
+        row.write(column_name, "pose", &self.pose);
+        row.write(column_name, "twist", &self.twist);
+    }
+}
+
+impl TableRow for nav_msgs::Path {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "header", &self.header);
+        row.write(column_name, "poses", &self.poses);
+    }
+}
+
+impl TableRow for visualization_msgs::UVCoordinate {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "u", &self.u);
+        row.write(column_name, "v", &self.v);
+    }
+}
+
+impl TableRow for visualization_msgs::MeshFile {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "filename", &self.filename);
+        write_binary(column_name, "data", &self.data, row);
+    }
+}
+
+impl TableRow for visualization_msgs::Marker {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "header", &self.header);
+        row.write(column_name, "ns", &self.ns);
+        row.write(column_name, "id", &self.id);
+        row.write(column_name, "type", &self.type_);
+        row.write(column_name, "action", &self.action);
+        row.write(column_name, "pose", &self.pose);
+        row.write(column_name, "scale", &self.scale);
+        row.write(column_name, "color", &self.color);
+        row.write(column_name, "lifetime", &self.lifetime);
+        row.write(column_name, "frame_locked", &self.frame_locked);
+        row.write(column_name, "points", &self.points);
+        row.write(column_name, "colors", &self.colors);
+        row.write(column_name, "texture_resource", &self.texture_resource);
+        row.write(column_name, "texture", &self.texture);
+        row.write(column_name, "uv_coordinates", &self.uv_coordinates);
+        row.write(column_name, "text", &self.text);
+        row.write(column_name, "mesh_resource", &self.mesh_resource);
+        row.write(column_name, "mesh_file", &self.mesh_file);
+        row.write(
+            column_name,
+            "mesh_use_embedded_materials",
+            &self.mesh_use_embedded_materials,
+        );
+    }
+}
+
+impl TableRow for visualization_msgs::MarkerArray {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.write(column_name, "markers", &self.markers);
+    }
+}
+
+fn write_binary(prefix: &str, field_name: &str, data: &[u8], row: &mut Row) {
+    row.push(
+        join_column_name(prefix, field_name),
+        CellValue::Binary(data.to_vec()),
+    );
+}
@@ -0,0 +1,103 @@
+//! Flattening of messages into tables with one row per message, where nested fields are named
+//! with dotted column names.
+mod format;
+mod message_rows;
+mod query;
+mod row;
+
+#[doc(inline)]
+pub use format::TableFormat;
+
+#[doc(inline)]
+pub use query::MessageQuery;
+
+#[doc(inline)]
+pub use query::QUERY_TABLE_NAME;
+
+#[doc(inline)]
+pub use row::CellValue;
+
+#[doc(inline)]
+pub use row::Row;
+
+#[doc(inline)]
+pub use row::TableRow;
+
+use crate::Error;
+use crate::dto::McapMessageMeta;
+use itertools::Itertools;
+use polars::prelude::DataFrame;
+
+pub(crate) use row::rows_to_data_frame;
+
+pub const COLUMN_NAME_FILE_NAME: &str = "file_name";
+pub const COLUMN_NAME_CHANNEL_TOPIC: &str = "channel_topic";
+pub const COLUMN_NAME_CHUNK_ID: &str = "chunk_id";
+pub const COLUMN_NAME_MESSAGE_ID: &str = "message_id";
+pub const COLUMN_NAME_LOG_TIME: &str = "log_time";
+pub const COLUMN_NAME_PUBLISH_TIME: &str = "publish_time";
+
+/// Returns a table with one row per message ordered by log time, which contains the MCAP
+/// metadata followed by the flattened message fields.
+pub fn messages_to_data_frame<'a, T: TableRow + 'a>(
+    messages: impl IntoIterator<Item = &'a McapMessageMeta<T>>,
+) -> Result<DataFrame, Error> {
+    let rows: Vec<Row> = messages
+        .into_iter()
+        .sorted_by_key(|x| {
+            (
+                x.log_date_time,
+                x.file_name.clone(),
+                x.chunk_id,
+                x.message_id,
+            )
+        })
+        .map(|x| {
+            let mut row = Row::default();
+            write_meta_columns(x, &mut row);
+            x.message.write_row("", &mut row);
+            row
+        })
+        .collect();
+
+    let data_frame = rows_to_data_frame(&rows)?;
+    Ok(data_frame)
+}
+
+/// Writes the MCAP metadata columns of a message to the row.
+pub(crate) fn write_meta_columns<T>(message: &McapMessageMeta<T>, row: &mut Row) {
+    row.push(
+        COLUMN_NAME_FILE_NAME,
+        CellValue::String(message.file_name.to_string()),
+    );
+    row.push(
+        COLUMN_NAME_CHANNEL_TOPIC,
+        CellValue::String(message.channel_topic.to_string()),
+    );
+    row.push(
+        COLUMN_NAME_CHUNK_ID,
+        CellValue::UInt64(usize::from(message.chunk_id) as u64),
+    );
+    row.push(
+        COLUMN_NAME_MESSAGE_ID,
+        CellValue::UInt64(u64::from(message.message_id)),
+    );
+    row.push(
+        COLUMN_NAME_LOG_TIME,
+        CellValue::DateTime(
+            message
+                .log_date_time
+                .timestamp_nanos_opt()
+                .unwrap_or_default(),
+        ),
+    );
+    row.push(
+        COLUMN_NAME_PUBLISH_TIME,
+        CellValue::DateTime(
+            message
+                .publish_date_time
+                .timestamp_nanos_opt()
+                .unwrap_or_default(),
+        ),
+    );
+}
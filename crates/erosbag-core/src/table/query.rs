@@ -0,0 +1,133 @@
+use crate::dto::McapMessagePage;
+use crate::{ChannelTopic, Error};
+use chrono::{DateTime, Utc};
+use polars::prelude::{DataFrame, Expr, IntoLazy, PolarsError};
+use polars::sql::SQLContext;
+use std::collections::{HashMap, HashSet};
+use tracing::warn;
+
+/// Name of the table holding the messages of a channel in SQL queries.
+pub const QUERY_TABLE_NAME: &str = "messages";
+
+/// Query over the flattened messages of the channels of a ROS bag.
+///
+/// The channel topics and the time window are used to select the chunks to read, while the
+/// filter expression and the SQL statement are evaluated on the table of each channel.
+#[derive(Debug, Clone, Default)]
+pub struct MessageQuery {
+    pub channel_topic_patterns: Vec<String>,
+    pub start_date_time: Option<DateTime<Utc>>,
+    pub end_date_time: Option<DateTime<Utc>>,
+    pub filter: Option<Expr>,
+    pub sql: Option<String>,
+}
+
+impl MessageQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restricts the query to channels, whose topic matches the pattern, where `*` matches any
+    /// sequence of characters and `?` matches a single character.
+    ///
+    /// If no pattern is provided, all channels with supported message types are queried.
+    pub fn with_channel_topic_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.channel_topic_patterns.push(pattern.into());
+        self
+    }
+
+    /// Restricts the query to messages logged between start_date_time (inclusive) and
+    /// end_date_time (exclusive).
+    pub fn with_time_window(
+        mut self,
+        start_date_time: Option<DateTime<Utc>>,
+        end_date_time: Option<DateTime<Utc>>,
+    ) -> Self {
+        self.start_date_time = start_date_time;
+        self.end_date_time = end_date_time;
+        self
+    }
+
+    /// Filters the rows of each channel's table by a polars expression.
+    ///
+    /// Example: `col("linear_acceleration.z").abs().gt(lit(15.0))`
+    pub fn with_filter(mut self, filter: Expr) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Evaluates an SQL statement on each channel's table, which is registered as
+    /// [`QUERY_TABLE_NAME`]. Dotted column names need to be quoted.
+    ///
+    /// Example: `SELECT log_time, "linear_acceleration.z" FROM messages WHERE
+    /// abs("linear_acceleration.z") > 15`
+    pub fn with_sql(mut self, sql: impl Into<String>) -> Self {
+        self.sql = Some(sql.into());
+        self
+    }
+
+    /// Returns the channel topics matching the patterns out of the available channel topics.
+    pub(crate) fn select_channel_topics(
+        &self,
+        channel_topics: HashSet<ChannelTopic>,
+    ) -> HashSet<ChannelTopic> {
+        if self.channel_topic_patterns.is_empty() {
+            return channel_topics;
+        }
+
+        channel_topics
+            .into_iter()
+            .filter(|x| {
+                self.channel_topic_patterns
+                    .iter()
+                    .any(|pattern| x.matches_pattern(pattern))
+            })
+            .collect()
+    }
+
+    /// Evaluates the filter expression and the SQL statement on the tables of a message page.
+    ///
+    /// Channels without any resulting rows are omitted. Without channel topic patterns, channels
+    /// lacking a referenced column are skipped with a warning, since the statement usually only
+    /// targets some of the message types.
+    pub(crate) fn evaluate(
+        &self,
+        page: &McapMessagePage,
+    ) -> Result<HashMap<ChannelTopic, DataFrame>, Error> {
+        let mut results: HashMap<ChannelTopic, DataFrame> = HashMap::new();
+
+        for (channel_topic, data_frame) in
+            page.get_data_frames(&self.start_date_time, &self.end_date_time)?
+        {
+            let result = match self.evaluate_data_frame(data_frame) {
+                Ok(result) => result,
+                Err(PolarsError::ColumnNotFound(message))
+                    if self.channel_topic_patterns.is_empty() =>
+                {
+                    warn!("Skipping channel {channel_topic}: {message}");
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            if result.height() > 0 {
+                results.insert(channel_topic, result);
+            }
+        }
+
+        Ok(results)
+    }
+
+    fn evaluate_data_frame(&self, data_frame: DataFrame) -> Result<DataFrame, PolarsError> {
+        let mut lazy_frame = data_frame.lazy();
+        if let Some(filter) = &self.filter {
+            lazy_frame = lazy_frame.filter(filter.clone());
+        }
+        if let Some(sql) = &self.sql {
+            let mut context = SQLContext::new();
+            context.register(QUERY_TABLE_NAME, lazy_frame);
+            lazy_frame = context.execute(sql)?;
+        }
+
+        lazy_frame.collect()
+    }
+}
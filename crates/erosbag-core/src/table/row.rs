@@ -0,0 +1,328 @@
+use polars::prelude::{
+    BinaryChunked, Column, DataFrame, DataType, IntoColumn, IntoSeries, NamedFrom, PlSmallStr,
+    PolarsResult, Series, TimeUnit, TimeZone,
+};
+use std::collections::HashMap;
+
+/// Value of a single cell of a table.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellValue {
+    Null,
+    Boolean(bool),
+    Int64(i64),
+    UInt64(u64),
+    Float32(f32),
+    Float64(f64),
+    String(String),
+    /// Nanoseconds since the UNIX epoch in UTC.
+    DateTime(i64),
+    /// Duration in nanoseconds.
+    Duration(i64),
+    Binary(Vec<u8>),
+    List(Vec<CellValue>),
+}
+
+/// Flattened row of a table, where nested fields are named with dotted column names.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Row {
+    cells: Vec<(String, CellValue)>,
+}
+
+impl Row {
+    pub fn push(&mut self, column_name: impl Into<String>, value: CellValue) {
+        self.cells.push((column_name.into(), value));
+    }
+
+    /// Writes the value of a field, whose column name is prefixed by the name of the parent.
+    pub fn write<T: TableRow + ?Sized>(&mut self, prefix: &str, field_name: &str, value: &T) {
+        value.write_row(&join_column_name(prefix, field_name), self);
+    }
+
+    pub fn cells(&self) -> &[(String, CellValue)] {
+        &self.cells
+    }
+}
+
+/// Conversion of a message or one of its fields into cells of a flattened row.
+pub trait TableRow {
+    /// Writes the cells of the value, where `column_name` is the column name of a scalar value
+    /// or the prefix of the columns of a nested value.
+    fn write_row(&self, column_name: &str, row: &mut Row);
+}
+
+/// Lists are written as list columns per flattened field of the elements.
+impl<T: TableRow> TableRow for [T] {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        let element_rows: Vec<Row> = self
+            .iter()
+            .map(|x| {
+                let mut element_row = Row::default();
+                x.write_row(column_name, &mut element_row);
+                element_row
+            })
+            .collect();
+
+        let mut column_names: Vec<&str> = Vec::new();
+        for (current_name, _) in element_rows.iter().flat_map(|x| x.cells.iter()) {
+            if !column_names.contains(&current_name.as_str()) {
+                column_names.push(current_name.as_str());
+            }
+        }
+        let indexed_rows: Vec<HashMap<&str, &CellValue>> = element_rows
+            .iter()
+            .map(|x| x.cells.iter().map(|(n, v)| (n.as_str(), v)).collect())
+            .collect();
+
+        for current_name in column_names {
+            let values: Vec<CellValue> = indexed_rows
+                .iter()
+                .map(|x| {
+                    x.get(current_name)
+                        .map_or(CellValue::Null, |v| (*v).clone())
+                })
+                .collect();
+            row.push(current_name, CellValue::List(values));
+        }
+    }
+}
+
+impl<T: TableRow> TableRow for Vec<T> {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        self.as_slice().write_row(column_name, row);
+    }
+}
+
+impl<T: TableRow, const N: usize> TableRow for [T; N] {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        self.as_slice().write_row(column_name, row);
+    }
+}
+
+impl TableRow for bool {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.push(column_name, CellValue::Boolean(*self));
+    }
+}
+
+impl TableRow for u8 {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.push(column_name, CellValue::UInt64(*self as u64));
+    }
+}
+
+impl TableRow for u16 {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.push(column_name, CellValue::UInt64(*self as u64));
+    }
+}
+
+impl TableRow for u32 {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.push(column_name, CellValue::UInt64(*self as u64));
+    }
+}
+
+impl TableRow for i8 {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.push(column_name, CellValue::Int64(*self as i64));
+    }
+}
+
+impl TableRow for i32 {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.push(column_name, CellValue::Int64(*self as i64));
+    }
+}
+
+impl TableRow for f32 {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.push(column_name, CellValue::Float32(*self));
+    }
+}
+
+impl TableRow for f64 {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.push(column_name, CellValue::Float64(*self));
+    }
+}
+
+impl TableRow for String {
+    fn write_row(&self, column_name: &str, row: &mut Row) {
+        row.push(column_name, CellValue::String(self.clone()));
+    }
+}
+
+/// Builds a data frame from rows, where columns missing in a row are null.
+///
+/// The columns are ordered by their first occurrence.
+pub fn rows_to_data_frame(rows: &[Row]) -> PolarsResult<DataFrame> {
+    let mut column_names: Vec<&str> = Vec::new();
+    for (current_name, _) in rows.iter().flat_map(|x| x.cells.iter()) {
+        if !column_names.contains(&current_name.as_str()) {
+            column_names.push(current_name.as_str());
+        }
+    }
+    let indexed_rows: Vec<HashMap<&str, &CellValue>> = rows
+        .iter()
+        .map(|x| x.cells.iter().map(|(n, v)| (n.as_str(), v)).collect())
+        .collect();
+
+    let columns: Vec<Column> = column_names
+        .into_iter()
+        .map(|current_name| {
+            let values: Vec<Option<&CellValue>> = indexed_rows
+                .iter()
+                .map(|x| x.get(current_name).copied())
+                .collect();
+            let present_values: Vec<&CellValue> = values.iter().flatten().copied().collect();
+            let data_type = infer_data_type(&present_values);
+            build_series(current_name.into(), &values, &data_type).map(|x| x.into_column())
+        })
+        .collect::<PolarsResult<Vec<_>>>()?;
+
+    DataFrame::new(columns)
+}
+
+pub(crate) fn join_column_name(prefix: &str, field_name: &str) -> String {
+    if prefix.is_empty() {
+        field_name.to_string()
+    } else {
+        format!("{prefix}.{field_name}")
+    }
+}
+
+fn infer_data_type(values: &[&CellValue]) -> DataType {
+    let Some(first_value) = values.iter().find(|x| ***x != CellValue::Null) else {
+        return DataType::Null;
+    };
+
+    match first_value {
+        CellValue::Null => DataType::Null,
+        CellValue::Boolean(_) => DataType::Boolean,
+        CellValue::Int64(_) => DataType::Int64,
+        CellValue::UInt64(_) => DataType::UInt64,
+        CellValue::Float32(_) => DataType::Float32,
+        CellValue::Float64(_) => DataType::Float64,
+        CellValue::String(_) => DataType::String,
+        CellValue::DateTime(_) => DataType::Datetime(TimeUnit::Nanoseconds, Some(TimeZone::UTC)),
+        CellValue::Duration(_) => DataType::Duration(TimeUnit::Nanoseconds),
+        CellValue::Binary(_) => DataType::Binary,
+        CellValue::List(_) => {
+            let inner_values: Vec<&CellValue> = values
+                .iter()
+                .flat_map(|x| match x {
+                    CellValue::List(elements) => elements.as_slice(),
+                    _ => &[],
+                })
+                .collect();
+            DataType::List(Box::new(infer_data_type(&inner_values)))
+        }
+    }
+}
+
+fn build_series(
+    name: PlSmallStr,
+    values: &[Option<&CellValue>],
+    data_type: &DataType,
+) -> PolarsResult<Series> {
+    let series = match data_type {
+        DataType::Boolean => Series::new(
+            name,
+            values
+                .iter()
+                .map(|x| match x {
+                    Some(CellValue::Boolean(v)) => Some(*v),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        ),
+        DataType::Int64 => Series::new(
+            name,
+            values
+                .iter()
+                .map(|x| match x {
+                    Some(CellValue::Int64(v)) => Some(*v),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        ),
+        DataType::UInt64 => Series::new(
+            name,
+            values
+                .iter()
+                .map(|x| match x {
+                    Some(CellValue::UInt64(v)) => Some(*v),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        ),
+        DataType::Float32 => Series::new(
+            name,
+            values
+                .iter()
+                .map(|x| match x {
+                    Some(CellValue::Float32(v)) => Some(*v),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        ),
+        DataType::Float64 => Series::new(
+            name,
+            values
+                .iter()
+                .map(|x| match x {
+                    Some(CellValue::Float64(v)) => Some(*v),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        ),
+        DataType::String => Series::new(
+            name,
+            values
+                .iter()
+                .map(|x| match x {
+                    Some(CellValue::String(v)) => Some(v.as_str()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        ),
+        DataType::Datetime(_, _) | DataType::Duration(_) => Series::new(
+            name,
+            values
+                .iter()
+                .map(|x| match x {
+                    Some(CellValue::DateTime(v)) | Some(CellValue::Duration(v)) => Some(*v),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        )
+        .cast(data_type)?,
+        DataType::Binary => {
+            let binary: BinaryChunked = values
+                .iter()
+                .map(|x| match x {
+                    Some(CellValue::Binary(v)) => Some(v.as_slice()),
+                    _ => None,
+                })
+                .collect();
+            binary.with_name(name).into_series()
+        }
+        DataType::List(inner_data_type) => {
+            let lists: Vec<Option<Series>> = values
+                .iter()
+                .map(|x| match x {
+                    Some(CellValue::List(elements)) => {
+                        let inner_values: Vec<Option<&CellValue>> =
+                            elements.iter().map(Some).collect();
+                        build_series(PlSmallStr::EMPTY, &inner_values, inner_data_type).map(Some)
+                    }
+                    _ => Ok(None),
+                })
+                .collect::<PolarsResult<Vec<_>>>()?;
+            Series::new(name, lists).cast(data_type)?
+        }
+        _ => Series::full_null(name, values.len(), &DataType::Null),
+    };
+
+    Ok(series)
+}
@@ -0,0 +1,439 @@
+//! Queries synthetic bags with known message counts.
+mod common;
+
+use common::{
+    SyntheticBag, SyntheticChannel, date_time, header, imu, point_cloud, read_cdr_fixture,
+    start_date_time, transform,
+};
+use ecoord::TransformId;
+use erosbag_core::dto::TransformIssue;
+use erosbag_core::ros_messages::{
+    RosMessage, RosMessageType, geometry_msgs, nav_msgs, sensor_msgs, tf2_msgs, visualization_msgs,
+};
+use erosbag_core::{ChannelTopic, Error, Rosbag};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt::Debug;
+
+const IMU_TOPIC: &str = "/imu";
+const LIDAR_TOPIC: &str = "/lidar";
+const LIDAR_REAR_TOPIC: &str = "/lidar_rear";
+
+/// Creates a bag of two files:
+///
+/// - `bag_0`: 10 IMU messages every 100ms from 0ms, 4 point clouds of 3 points every 200ms from
+///   50ms on `/lidar` and 2 point clouds of 5 points at 100ms and 600ms on `/lidar_rear`
+/// - `bag_1`: 2 point clouds of 3 points at 1050ms and 1250ms on `/lidar`
+fn create_bag(name: &str) -> SyntheticBag {
+    let bag = SyntheticBag::new(name);
+    bag.write_file(
+        "bag_0",
+        &[
+            SyntheticChannel::new(
+                IMU_TOPIC,
+                (0..10).map(|i| (date_time(i * 100), imu(date_time(i * 100)))),
+            ),
+            SyntheticChannel::new(
+                LIDAR_TOPIC,
+                [50, 250, 450, 650].map(|x| (date_time(x), point_cloud("lidar", date_time(x), 3))),
+            ),
+            SyntheticChannel::new(
+                LIDAR_REAR_TOPIC,
+                [100, 600].map(|x| (date_time(x), point_cloud("lidar_rear", date_time(x), 5))),
+            ),
+        ],
+    );
+    bag.write_file(
+        "bag_1",
+        &[SyntheticChannel::new(
+            LIDAR_TOPIC,
+            [1050, 1250].map(|x| (date_time(x), point_cloud("lidar", date_time(x), 3))),
+        )],
+    );
+    bag
+}
+
+fn topics(values: &[&str]) -> Option<HashSet<ChannelTopic>> {
+    Some(values.iter().map(|x| (*x).into()).collect())
+}
+
+#[test]
+fn overview() {
+    let bag = create_bag("overview");
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+    let overview = rosbag.get_overview().unwrap();
+
+    assert_eq!(overview.files.len(), 2);
+    let first_file = overview.files.get(&"bag_0".into()).unwrap();
+    assert_eq!(first_file.len(), 3);
+    // one chunk per message after the chunk of the schemas and channels
+    assert_eq!(first_file.chunks.len(), 17);
+    assert_eq!(first_file.total_messages(), 16);
+    let second_file = overview.files.get(&"bag_1".into()).unwrap();
+    assert_eq!(second_file.len(), 1);
+    assert_eq!(second_file.chunks.len(), 3);
+
+    assert_eq!(
+        overview.get_channel_topics_of_message_type(RosMessageType::SensorMessagesImu),
+        HashSet::from([IMU_TOPIC.into()])
+    );
+    assert_eq!(
+        overview.get_channel_topics_of_message_type(RosMessageType::SensorMessagesPointCloud2),
+        HashSet::from([LIDAR_TOPIC.into(), LIDAR_REAR_TOPIC.into()])
+    );
+
+    assert_eq!(
+        rosbag.get_start_date_time().unwrap(),
+        Some(start_date_time())
+    );
+    assert_eq!(rosbag.get_end_date_time().unwrap(), Some(date_time(1250)));
+    assert_eq!(
+        rosbag
+            .get_start_date_time_of_channel(&LIDAR_REAR_TOPIC.into())
+            .unwrap(),
+        Some(date_time(100))
+    );
+}
+
+#[test]
+fn time_window() {
+    let bag = create_bag("time_window");
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    let messages = rosbag
+        .messages::<sensor_msgs::Imu>(&None, &None, &None)
+        .unwrap();
+    assert_eq!(messages.len(), 10);
+    assert!(messages.is_sorted_by_key(|x| x.log_date_time));
+
+    // the start is inclusive and the end is exclusive
+    let messages = rosbag
+        .messages::<sensor_msgs::Imu>(&None, &Some(date_time(200)), &Some(date_time(500)))
+        .unwrap();
+    assert_eq!(
+        messages.iter().map(|x| x.log_date_time).collect::<Vec<_>>(),
+        vec![date_time(200), date_time(300), date_time(400)]
+    );
+    assert_eq!(messages[0].header_date_time(), date_time(200));
+
+    let messages = rosbag
+        .messages::<sensor_msgs::Imu>(&None, &Some(date_time(850)), &None)
+        .unwrap();
+    assert_eq!(messages.len(), 1);
+
+    let messages = rosbag
+        .messages::<sensor_msgs::PointCloud2>(
+            &topics(&[LIDAR_TOPIC]),
+            &Some(date_time(400)),
+            &Some(date_time(1100)),
+        )
+        .unwrap();
+    assert_eq!(messages.len(), 3);
+}
+
+#[test]
+fn messages_of_other_schema() {
+    let bag = create_bag("messages_of_other_schema");
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    let result = rosbag.messages::<sensor_msgs::Imu>(&topics(&[LIDAR_TOPIC]), &None, &None);
+    assert!(result.is_err());
+}
+
+#[test]
+fn point_cloud_extraction() {
+    let bag = create_bag("point_cloud_extraction");
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    let point_cloud = rosbag.get_point_clouds(&None, &None, &None, false).unwrap();
+    assert_eq!(point_cloud.size(), 4 * 3 + 2 * 5 + 2 * 3);
+
+    let point_cloud = rosbag
+        .get_point_clouds(&None, &None, &topics(&[LIDAR_TOPIC]), false)
+        .unwrap();
+    assert_eq!(point_cloud.size(), 6 * 3);
+
+    let point_cloud = rosbag
+        .get_point_clouds(&Some(start_date_time()), &Some(date_time(500)), &None, true)
+        .unwrap();
+    assert_eq!(point_cloud.size(), 3 * 3 + 5);
+}
+
+#[test]
+fn static_transforms_before_time_window() {
+    let bag = SyntheticBag::new("static_transforms_before_time_window");
+    bag.write_file(
+        "bag_0",
+        &[
+            SyntheticChannel::new(
+                "/tf_static",
+                [(date_time(0), transform("base_link", "lidar", date_time(0)))],
+            ),
+            SyntheticChannel::new(
+                "/tf",
+                (0..10).map(|i| {
+                    (
+                        date_time(i * 100),
+                        transform("map", "base_link", date_time(i * 100)),
+                    )
+                }),
+            ),
+        ],
+    );
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    let transform_tree = rosbag
+        .get_transforms(&Some(date_time(500)), &Some(date_time(800)), &None)
+        .unwrap();
+    assert!(
+        transform_tree.contains_transform(&TransformId::new("base_link".into(), "lidar".into()))
+    );
+    assert!(transform_tree.contains_transform(&TransformId::new("map".into(), "base_link".into())));
+
+    let transform_tree = rosbag
+        .get_transforms(
+            &Some(date_time(500)),
+            &Some(date_time(800)),
+            &topics(&["/tf"]),
+        )
+        .unwrap();
+    assert!(
+        !transform_tree.contains_transform(&TransformId::new("base_link".into(), "lidar".into()))
+    );
+}
+
+#[test]
+fn transform_report_without_target_frame() {
+    let bag = SyntheticBag::new("transform_report_without_target_frame");
+    bag.write_file(
+        "bag_0",
+        &[
+            SyntheticChannel::new(
+                "/tf_static",
+                [0, 5000].map(|x| (date_time(x), transform("base_link", "lidar", date_time(x)))),
+            ),
+            SyntheticChannel::new(
+                "/tf",
+                (0..10).map(|i| {
+                    (
+                        date_time(i * 100),
+                        transform("map", "base_link", date_time(i * 100)),
+                    )
+                }),
+            ),
+            SyntheticChannel::new(
+                LIDAR_TOPIC,
+                [(date_time(50), point_cloud("lidar", date_time(50), 3))],
+            ),
+            SyntheticChannel::new(
+                "/radar",
+                [(date_time(50), point_cloud("radar", date_time(50), 3))],
+            ),
+        ],
+    );
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    let report = rosbag
+        .check_transforms(&None, &None, &None, None, chrono::Duration::seconds(1))
+        .unwrap();
+    assert_eq!(report.target_frame_id, Some("map".into()));
+    assert_eq!(
+        report.issues,
+        vec![TransformIssue::UnreachableSensorFrame {
+            channel_topic: "/radar".into(),
+            frame_id: "radar".into(),
+            target_frame_id: "map".into(),
+        }]
+    );
+}
+
+#[test]
+fn pose_transforms_of_multiple_frames() {
+    let pose = |frame_id: &str, offset_milliseconds: i64| geometry_msgs::PoseStamped {
+        header: header(frame_id, date_time(offset_milliseconds)),
+        pose: geometry_msgs::Pose {
+            point: geometry_msgs::Point {
+                x: offset_milliseconds as f64,
+                y: 0.0,
+                z: 0.0,
+            },
+            quaternion: geometry_msgs::Quaternion {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+                w: 1.0,
+            },
+        },
+    };
+    let bag = SyntheticBag::new("pose_transforms_of_multiple_frames");
+    bag.write_file(
+        "bag_0",
+        &[
+            SyntheticChannel::new(
+                "/pose",
+                [0, 100, 200].map(|x| (date_time(x), pose("map", x))),
+            ),
+            SyntheticChannel::new(
+                "/mixed_pose",
+                [
+                    (date_time(0), pose("map", 0)),
+                    (date_time(100), pose("odom", 100)),
+                ],
+            ),
+        ],
+    );
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    let transform_tree = rosbag
+        .get_pose_transforms(&None, &None, &"/pose".into(), "base_link".into())
+        .unwrap();
+    assert!(transform_tree.contains_transform(&TransformId::new("map".into(), "base_link".into())));
+
+    let result =
+        rosbag.get_pose_transforms(&None, &None, &"/mixed_pose".into(), "base_link".into());
+    assert!(matches!(result, Err(Error::MultiplePoseFrameIds(..))));
+}
+
+/// Creates a camera info message of a 2x2 pixel camera with the given focal lengths.
+fn camera_info(
+    date_time: chrono::DateTime<chrono::Utc>,
+    focal_length: f64,
+) -> sensor_msgs::CameraInfo {
+    sensor_msgs::CameraInfo {
+        header: header("camera_optical", date_time),
+        height: 2,
+        width: 2,
+        distortion_model: "plumb_bob".to_string(),
+        d: vec![0.0; 5],
+        k: [
+            focal_length,
+            0.0,
+            0.5,
+            0.0,
+            focal_length,
+            0.5,
+            0.0,
+            0.0,
+            1.0,
+        ],
+        r: [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0],
+        p: [0.0; 12],
+        binning_x: 0,
+        binning_y: 0,
+        roi: Default::default(),
+    }
+}
+
+#[test]
+fn depth_point_clouds() {
+    let depth_image = |width: u32| sensor_msgs::Image {
+        header: header("camera_optical", date_time(0)),
+        height: 2,
+        width,
+        encoding: "16UC1".to_string(),
+        is_bigendian: false,
+        step: width * 2,
+        data: (0..width * 2).flat_map(|_| 1000u16.to_le_bytes()).collect(),
+    };
+    let bag = SyntheticBag::new("depth_point_clouds");
+    bag.write_file(
+        "bag_0",
+        &[
+            SyntheticChannel::new(
+                "/tf_static",
+                [(
+                    date_time(0),
+                    transform("base_link", "camera_optical", date_time(0)),
+                )],
+            ),
+            SyntheticChannel::new("/depth", [(date_time(0), depth_image(2))]),
+            SyntheticChannel::new("/wide_depth", [(date_time(0), depth_image(3))]),
+            SyntheticChannel::new(
+                "/camera_info",
+                [(date_time(0), camera_info(date_time(0), 1.0))],
+            ),
+            SyntheticChannel::new(
+                "/uncalibrated_camera_info",
+                [(date_time(0), camera_info(date_time(0), 0.0))],
+            ),
+        ],
+    );
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    let point_cloud = rosbag
+        .get_depth_point_clouds(
+            &None,
+            &None,
+            &"/depth".into(),
+            &"/camera_info".into(),
+            &None,
+            0.001,
+        )
+        .unwrap();
+    assert_eq!(point_cloud.size(), 4);
+    assert!(
+        point_cloud
+            .transform_tree
+            .contains_transform(&TransformId::new(
+                "base_link".into(),
+                "camera_optical".into()
+            ))
+    );
+
+    let result = rosbag.get_depth_point_clouds(
+        &None,
+        &None,
+        &"/depth".into(),
+        &"/uncalibrated_camera_info".into(),
+        &None,
+        0.001,
+    );
+    assert!(matches!(result, Err(Error::InvalidImageData(_))));
+    let result = rosbag.get_depth_point_clouds(
+        &None,
+        &None,
+        &"/wide_depth".into(),
+        &"/camera_info".into(),
+        &None,
+        0.001,
+    );
+    assert!(matches!(result, Err(Error::InvalidImageData(_))));
+}
+
+/// Writes the decoded fixture of `T` to a bag and checks that it is read back unchanged.
+fn assert_bag_round_trip<T: RosMessage + Serialize + PartialEq + Debug>() {
+    let message = T::deserialize_cdr(&read_cdr_fixture(T::SCHEMA_NAME)).unwrap();
+    let bag = SyntheticBag::new(&format!("round_trip_{}", T::SCHEMA_NAME.replace('/', "_")));
+    bag.write_file(
+        "bag_0",
+        &[SyntheticChannel::new(
+            "/topic",
+            [(start_date_time(), message)],
+        )],
+    );
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    let read_messages = rosbag.messages::<T>(&None, &None, &None).unwrap();
+    let expected = T::deserialize_cdr(&read_cdr_fixture(T::SCHEMA_NAME)).unwrap();
+    assert_eq!(read_messages.len(), 1, "messages of {}", T::SCHEMA_NAME);
+    assert_eq!(read_messages[0].message, expected);
+}
+
+#[test]
+fn bag_round_trip() {
+    assert_bag_round_trip::<sensor_msgs::Imu>();
+    assert_bag_round_trip::<sensor_msgs::NavSatFix>();
+    assert_bag_round_trip::<sensor_msgs::PointCloud2>();
+    assert_bag_round_trip::<sensor_msgs::Image>();
+    assert_bag_round_trip::<sensor_msgs::CameraInfo>();
+    assert_bag_round_trip::<sensor_msgs::LaserScan>();
+    assert_bag_round_trip::<sensor_msgs::MultiEchoLaserScan>();
+    assert_bag_round_trip::<geometry_msgs::PoseStamped>();
+    assert_bag_round_trip::<geometry_msgs::PoseWithCovarianceStamped>();
+    assert_bag_round_trip::<geometry_msgs::TwistStamped>();
+    assert_bag_round_trip::<tf2_msgs::TFMessage>();
+    assert_bag_round_trip::<nav_msgs::Odometry>();
+    assert_bag_round_trip::<nav_msgs::Path>();
+    assert_bag_round_trip::<visualization_msgs::Marker>();
+    assert_bag_round_trip::<visualization_msgs::MarkerArray>();
+}
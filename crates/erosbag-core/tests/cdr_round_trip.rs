@@ -0,0 +1,215 @@
+//! Decodes the reference CDR payloads of the supported message types and checks that encoding
+//! the decoded messages reproduces the payloads byte by byte.
+//!
+//! The message values are defined by `tests/fixtures/cdr/generate_fixtures.py`, which regenerates
+//! the payloads with rclpy, and are checked here.
+mod common;
+
+use common::read_cdr_fixture;
+use erosbag_core::Error;
+use erosbag_core::ros_messages::{
+    RosMessage, geometry_msgs, nav_msgs, sensor_msgs, tf2_msgs, visualization_msgs,
+};
+use serde::Serialize;
+
+/// Decodes the fixture of `T`, encodes the message again and compares both payloads including
+/// the encapsulation header and any trailing padding.
+fn round_trip<T: RosMessage + Serialize>() -> T {
+    let expected = read_cdr_fixture(T::SCHEMA_NAME);
+    let message = T::deserialize_cdr(&expected).expect("decoding should work");
+    let actual =
+        cdr::serialize::<_, _, cdr::CdrLe>(&message, cdr::Infinite).expect("encoding should work");
+    assert_eq!(expected, actual, "payload of {}", T::SCHEMA_NAME);
+
+    message
+}
+
+#[test]
+fn imu() {
+    let message = round_trip::<sensor_msgs::Imu>();
+    assert_eq!(message.header.frame_id, "imu_link");
+    assert_eq!(message.header.stamp.sec, 1700000000);
+    assert_eq!(message.header.stamp.nanosec, 123456789);
+    assert_eq!(message.orientation.z, 0.6);
+    assert_eq!(message.orientation.w, 0.8);
+    assert_eq!(message.orientation_covariance[0], 0.01);
+    assert_eq!(message.angular_velocity.y, -0.2);
+    assert_eq!(message.angular_velocity_covariance[4], 0.02);
+    assert_eq!(message.linear_acceleration.z, 9.81);
+    assert_eq!(message.linear_acceleration_covariance[8], 0.04);
+}
+
+#[test]
+fn nav_sat_fix() {
+    let message = round_trip::<sensor_msgs::NavSatFix>();
+    assert_eq!(message.header.frame_id, "gnss_link");
+    assert_eq!(message.status.status, 0);
+    assert_eq!(message.status.service, 1);
+    assert_eq!(message.latitude, 48.1486);
+    assert_eq!(message.longitude, 11.568);
+    assert_eq!(message.altitude, 519.5);
+    assert_eq!(message.position_covariance[4], 0.25);
+    assert_eq!(message.position_covariance_type, 2);
+}
+
+#[test]
+fn point_cloud_2() {
+    let message = round_trip::<sensor_msgs::PointCloud2>();
+    assert_eq!(message.width, 3);
+    assert_eq!(message.point_step, 16);
+    assert_eq!(
+        message
+            .fields
+            .iter()
+            .map(|x| x.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["x", "y", "z", "intensity"]
+    );
+    assert_eq!(
+        message.get_points()[2],
+        nalgebra::Point3::new(7.0, 8.0, 9.0)
+    );
+    assert_eq!(
+        message.get_field_as_f32("intensity"),
+        vec![10.0, 20.0, 30.0]
+    );
+    assert!(message.is_dense);
+}
+
+#[test]
+fn image() {
+    let message = round_trip::<sensor_msgs::Image>();
+    assert_eq!(message.encoding, "rgb8");
+    assert_eq!(message.step, 6);
+    assert_eq!(message.data.len(), 12);
+    assert_eq!(message.get_rgb_values().unwrap()[3], [9, 10, 11]);
+
+    let mut truncated = message.clone();
+    truncated.data.truncate(10);
+    assert!(truncated.get_rgb_values().is_err());
+
+    let mut narrow_step = message;
+    narrow_step.step = 4;
+    assert!(narrow_step.get_rgb_values().is_err());
+}
+
+#[test]
+fn camera_info() {
+    let message = round_trip::<sensor_msgs::CameraInfo>();
+    assert_eq!(message.distortion_model, "plumb_bob");
+    assert_eq!(message.d.len(), 5);
+    assert_eq!(message.fx(), 500.0);
+    assert_eq!(message.cy(), 240.0);
+    assert_eq!(message.p[10], 1.0);
+    assert!(!message.roi.do_rectify);
+}
+
+#[test]
+fn laser_scan() {
+    let message = round_trip::<sensor_msgs::LaserScan>();
+    assert_eq!(message.angle_min, -1.5);
+    assert_eq!(message.scan_time, 0.125);
+    assert_eq!(message.ranges, vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+    assert_eq!(message.intensities[4], 50.0);
+}
+
+#[test]
+fn laser_scan_to_point_cloud() {
+    let message = round_trip::<sensor_msgs::LaserScan>();
+    let point_cloud = epoint::PointCloud::try_from(message.clone()).unwrap();
+    assert_eq!(point_cloud.size(), 5);
+
+    let mut invalid_stamp = message.clone();
+    invalid_stamp.header.stamp.nanosec = u32::MAX;
+    let result = epoint::PointCloud::try_from(invalid_stamp);
+    assert!(matches!(result, Err(Error::InvalidLaserScanData(_))));
+
+    let mut invalid_time_increment = message;
+    invalid_time_increment.time_increment = f32::INFINITY;
+    let result = epoint::PointCloud::try_from(invalid_time_increment);
+    assert!(matches!(result, Err(Error::InvalidLaserScanData(_))));
+}
+
+#[test]
+fn multi_echo_laser_scan() {
+    let message = round_trip::<sensor_msgs::MultiEchoLaserScan>();
+    assert_eq!(message.ranges.len(), 2);
+    assert_eq!(message.ranges[0].echoes, vec![1.0, 1.5]);
+    assert_eq!(message.intensities[1].echoes, vec![20.0]);
+}
+
+#[test]
+fn pose_stamped() {
+    let message = round_trip::<geometry_msgs::PoseStamped>();
+    assert_eq!(message.header.frame_id, "map");
+    assert_eq!(message.pose.point.z, 3.0);
+    assert_eq!(message.pose.quaternion.w, 1.0);
+}
+
+#[test]
+fn pose_with_covariance_stamped() {
+    let message = round_trip::<geometry_msgs::PoseWithCovarianceStamped>();
+    assert_eq!(message.pose.pose.quaternion.z, 0.6);
+    assert_eq!(message.pose.covariance[7], 0.1);
+    assert_eq!(message.pose.covariance[8], 0.0);
+}
+
+#[test]
+fn twist_stamped() {
+    let message = round_trip::<geometry_msgs::TwistStamped>();
+    assert_eq!(message.twist.linear.x, 1.0);
+    assert_eq!(message.twist.angular.z, 0.5);
+}
+
+#[test]
+fn tf_message() {
+    let message = round_trip::<tf2_msgs::TFMessage>();
+    assert_eq!(message.transforms.len(), 2);
+    assert_eq!(message.transforms[1].header.frame_id, "base_link");
+    assert_eq!(message.transforms[1].child_frame_id, "lidar");
+    assert_eq!(message.transforms[1].transform.translation.z, 1.5);
+    assert_eq!(message.transforms[1].transform.rotation.w, 0.8);
+}
+
+#[test]
+fn odometry() {
+    let message = round_trip::<nav_msgs::Odometry>();
+    assert_eq!(message.header.frame_id, "odom");
+    assert_eq!(message.child_frame_id, "base_link");
+    assert_eq!(message.pose.pose.point.y, 2.0);
+    assert_eq!(message.twist.covariance[35], 0.2);
+}
+
+#[test]
+fn path() {
+    let message = round_trip::<nav_msgs::Path>();
+    assert_eq!(message.poses.len(), 2);
+    assert_eq!(message.poses[1].header.stamp.nanosec, 500000000);
+    assert_eq!(message.poses[1].pose.point.y, 0.5);
+}
+
+#[test]
+fn marker() {
+    let message = round_trip::<visualization_msgs::Marker>();
+    assert_eq!(message.ns, "test");
+    assert_eq!(message.id, 7);
+    assert_eq!(message.type_, 2);
+    assert_eq!(message.action, 0);
+    assert_eq!(message.color.r, 1.0);
+    assert_eq!(message.lifetime.sec, 2);
+    assert_eq!(message.lifetime.nanosec, 500000000);
+    assert!(message.frame_locked);
+    assert_eq!(message.points.len(), 2);
+    assert_eq!(message.colors.len(), 1);
+    assert_eq!(message.text, "hello");
+    assert!(message.uv_coordinates.is_empty());
+    assert!(!message.mesh_use_embedded_materials);
+}
+
+#[test]
+fn marker_array() {
+    let message = round_trip::<visualization_msgs::MarkerArray>();
+    assert_eq!(message.markers.len(), 2);
+    assert_eq!(message.markers[1].id, 8);
+    assert_eq!(message.markers[1].action, 2);
+}
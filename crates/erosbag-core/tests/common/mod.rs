@@ -0,0 +1,271 @@
+//! Generator of synthetic bags, so that the integration tests run without external data.
+//!
+//! Any message implementing `RosMessage` and `Serialize` can be written, where the schema is
+//! taken from its definition.
+#![allow(dead_code)]
+
+pub mod ros1;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use erosbag_core::ros_messages::{
+    RosMessage, builtin_msgs, geometry_msgs, sensor_msgs, std_msgs, tf2_msgs,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+const CDR_FIXTURE_DIRECTORY: &str = "tests/fixtures/cdr";
+const SCHEMA_ENCODING: &str = "ros2msg";
+const MESSAGE_ENCODING: &str = "cdr";
+
+/// Reads the reference CDR payload of a schema, such as `sensor_msgs/msg/Imu`.
+pub fn read_cdr_fixture(schema_name: &str) -> Vec<u8> {
+    let file_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join(CDR_FIXTURE_DIRECTORY)
+        .join(format!("{}.cdr", schema_name.replace('/', "__")));
+    std::fs::read(&file_path)
+        .unwrap_or_else(|e| panic!("reading {} should work: {e}", file_path.display()))
+}
+
+/// Start of the synthetic recordings at 2023-11-14T22:13:20Z.
+pub fn start_date_time() -> DateTime<Utc> {
+    Utc.timestamp_opt(1700000000, 0).unwrap()
+}
+
+/// Returns the date time after the start of the synthetic recordings.
+pub fn date_time(offset_milliseconds: i64) -> DateTime<Utc> {
+    start_date_time() + Duration::milliseconds(offset_milliseconds)
+}
+
+pub fn header(frame_id: &str, date_time: DateTime<Utc>) -> std_msgs::Header {
+    std_msgs::Header {
+        stamp: builtin_msgs::Time {
+            sec: date_time.timestamp() as i32,
+            nanosec: date_time.timestamp_subsec_nanos(),
+        },
+        frame_id: frame_id.to_string(),
+    }
+}
+
+/// Creates an IMU message stamped at the date time.
+pub fn imu(date_time: DateTime<Utc>) -> sensor_msgs::Imu {
+    sensor_msgs::Imu {
+        header: header("imu_link", date_time),
+        orientation: geometry_msgs::Quaternion {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            w: 1.0,
+        },
+        orientation_covariance: [0.0; 9],
+        angular_velocity: geometry_msgs::Vector3 {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        angular_velocity_covariance: [0.0; 9],
+        linear_acceleration: geometry_msgs::Vector3 {
+            x: 0.0,
+            y: 0.0,
+            z: 9.81,
+        },
+        linear_acceleration_covariance: [0.0; 9],
+    }
+}
+
+/// Creates a transform message with a single translation along the x axis from the parent to the
+/// child frame.
+pub fn transform(
+    parent_frame_id: &str,
+    child_frame_id: &str,
+    date_time: DateTime<Utc>,
+) -> tf2_msgs::TFMessage {
+    tf2_msgs::TFMessage {
+        transforms: vec![geometry_msgs::TransformStamped {
+            header: header(parent_frame_id, date_time),
+            child_frame_id: child_frame_id.to_string(),
+            transform: geometry_msgs::Transform {
+                translation: geometry_msgs::Vector3 {
+                    x: 1.0,
+                    y: 0.0,
+                    z: 0.0,
+                },
+                rotation: geometry_msgs::Quaternion {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                    w: 1.0,
+                },
+            },
+        }],
+    }
+}
+
+/// Creates a point cloud message with `x`, `y`, `z` and `intensity` fields, whose points lie on
+/// a line along the x axis.
+pub fn point_cloud(
+    frame_id: &str,
+    date_time: DateTime<Utc>,
+    point_count: u32,
+) -> sensor_msgs::PointCloud2 {
+    let point_step = 16;
+    let data: Vec<u8> = (0..point_count)
+        .flat_map(|i| [i as f32, 0.0, 0.0, 100.0])
+        .flat_map(|x| x.to_le_bytes())
+        .collect();
+    let fields = ["x", "y", "z", "intensity"]
+        .iter()
+        .enumerate()
+        .map(|(i, name)| sensor_msgs::PointField {
+            name: name.to_string(),
+            offset: 4 * i as u32,
+            datatype: 7,
+            count: 1,
+        })
+        .collect();
+
+    sensor_msgs::PointCloud2 {
+        header: header(frame_id, date_time),
+        height: 1,
+        width: point_count,
+        fields,
+        is_bigendian: false,
+        point_step,
+        row_step: point_step * point_count,
+        data,
+        is_dense: true,
+    }
+}
+
+/// Channel of a synthetic bag file with its encoded messages.
+pub struct SyntheticChannel {
+    topic: String,
+    schema_name: &'static str,
+    definition: &'static str,
+    metadata: BTreeMap<String, String>,
+    messages: Vec<(DateTime<Utc>, Vec<u8>)>,
+}
+
+impl SyntheticChannel {
+    /// Creates a channel, whose messages are logged at the given date times.
+    pub fn new<T: RosMessage + Serialize>(
+        topic: &str,
+        messages: impl IntoIterator<Item = (DateTime<Utc>, T)>,
+    ) -> Self {
+        let messages = messages
+            .into_iter()
+            .map(|(date_time, message)| {
+                let data = cdr::serialize::<_, _, cdr::CdrLe>(&message, cdr::Infinite)
+                    .expect("encoding should work");
+                (date_time, data)
+            })
+            .collect();
+
+        Self {
+            topic: topic.to_string(),
+            schema_name: T::SCHEMA_NAME,
+            definition: T::DEFINITION,
+            metadata: BTreeMap::new(),
+            messages,
+        }
+    }
+
+    /// Adds an entry to the channel metadata, such as the `offered_qos_profiles`.
+    pub fn with_metadata(mut self, key: &str, value: &str) -> Self {
+        self.metadata.insert(key.to_string(), value.to_string());
+        self
+    }
+}
+
+/// Bag in a temporary directory, which is removed when dropped.
+pub struct SyntheticBag {
+    pub directory_path: PathBuf,
+}
+
+impl SyntheticBag {
+    /// Creates an empty bag directory, whose name is unique per test and process.
+    pub fn new(name: &str) -> Self {
+        let directory_path = std::env::temp_dir()
+            .join("erosbag-tests")
+            .join(format!("{name}-{}", std::process::id()));
+        if directory_path.exists() {
+            std::fs::remove_dir_all(&directory_path).expect("removing should work");
+        }
+        std::fs::create_dir_all(&directory_path).expect("creating should work");
+
+        Self { directory_path }
+    }
+
+    /// Writes an MCAP file with the channels, where each message is written to its own chunk
+    /// after a first chunk holding the schemas and channels, so that the chunk count of the
+    /// overview is known.
+    ///
+    /// The messages of all channels are written in the order of their log times.
+    pub fn write_file(&self, file_name: &str, channels: &[SyntheticChannel]) {
+        let file_path = self.directory_path.join(format!("{file_name}.mcap"));
+        let file = BufWriter::new(File::create(file_path).expect("creating should work"));
+        let mut writer = mcap::WriteOptions::new()
+            .compression(None)
+            .chunk_size(Some(1))
+            .create(file)
+            .expect("creating writer should work");
+
+        let mut schema_ids: BTreeMap<&str, u16> = BTreeMap::new();
+        let mut messages: Vec<(DateTime<Utc>, u16, &[u8])> = Vec::new();
+        for current_channel in channels {
+            let schema_id = match schema_ids.get(current_channel.schema_name) {
+                Some(schema_id) => *schema_id,
+                None => {
+                    let schema_id = writer
+                        .add_schema(
+                            current_channel.schema_name,
+                            SCHEMA_ENCODING,
+                            current_channel.definition.as_bytes(),
+                        )
+                        .expect("adding schema should work");
+                    schema_ids.insert(current_channel.schema_name, schema_id);
+                    schema_id
+                }
+            };
+            let channel_id = writer
+                .add_channel(
+                    schema_id,
+                    &current_channel.topic,
+                    MESSAGE_ENCODING,
+                    &current_channel.metadata,
+                )
+                .expect("adding channel should work");
+            messages.extend(
+                current_channel
+                    .messages
+                    .iter()
+                    .map(|(date_time, data)| (*date_time, channel_id, data.as_slice())),
+            );
+        }
+
+        messages.sort_by_key(|(date_time, channel_id, _)| (*date_time, *channel_id));
+        for (sequence, (date_time, channel_id, data)) in messages.into_iter().enumerate() {
+            let log_time = date_time.timestamp_nanos_opt().expect("should be in range") as u64;
+            writer
+                .write_to_known_channel(
+                    &mcap::records::MessageHeader {
+                        channel_id,
+                        sequence: sequence as u32,
+                        log_time,
+                        publish_time: log_time,
+                    },
+                    data,
+                )
+                .expect("writing message should work");
+        }
+        writer.finish().expect("finishing should work");
+    }
+}
+
+impl Drop for SyntheticBag {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.directory_path);
+    }
+}
@@ -0,0 +1,236 @@
+//! Writer of synthetic ROS1 bags of the format version 2.0 and encoder of the ROS1 serialization.
+
+use chrono::{DateTime, Utc};
+use std::io::Write;
+use std::path::Path;
+
+const BAG_MAGIC: &[u8] = b"#ROSBAG V2.0\n";
+const BAG_HEADER_LENGTH: usize = 4096;
+
+const OP_MESSAGE_DATA: u8 = 0x02;
+const OP_BAG_HEADER: u8 = 0x03;
+const OP_CHUNK: u8 = 0x05;
+const OP_CHUNK_INFO: u8 = 0x06;
+const OP_CONNECTION: u8 = 0x07;
+
+/// Encoder of the ROS1 serialization, which is little endian without alignment.
+#[derive(Debug, Default)]
+pub struct Ros1Encoder {
+    pub buffer: Vec<u8>,
+}
+
+impl Ros1Encoder {
+    pub fn bool(&mut self, value: bool) -> &mut Self {
+        self.buffer.push(value as u8);
+        self
+    }
+
+    pub fn u8(&mut self, value: u8) -> &mut Self {
+        self.buffer.push(value);
+        self
+    }
+
+    pub fn i32(&mut self, value: i32) -> &mut Self {
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    pub fn u32(&mut self, value: u32) -> &mut Self {
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    pub fn f32(&mut self, value: f32) -> &mut Self {
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    pub fn f64s(&mut self, values: &[f64]) -> &mut Self {
+        values
+            .iter()
+            .for_each(|x| self.buffer.extend_from_slice(&x.to_le_bytes()));
+        self
+    }
+
+    pub fn string(&mut self, value: &str) -> &mut Self {
+        self.u32(value.len() as u32);
+        self.buffer.extend_from_slice(value.as_bytes());
+        self
+    }
+
+    pub fn time(&mut self, date_time: DateTime<Utc>) -> &mut Self {
+        self.u32(date_time.timestamp() as u32)
+            .u32(date_time.timestamp_subsec_nanos())
+    }
+
+    /// Writes a `std_msgs/Header` including the `seq` field.
+    pub fn header(&mut self, seq: u32, date_time: DateTime<Utc>, frame_id: &str) -> &mut Self {
+        self.u32(seq).time(date_time).string(frame_id)
+    }
+
+    pub fn finish(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.buffer)
+    }
+}
+
+/// Connection of a synthetic ROS1 bag.
+pub struct Ros1TestConnection {
+    pub topic: &'static str,
+    pub message_type: &'static str,
+    pub message_definition: &'static str,
+    pub latching: bool,
+}
+
+/// Writes a ROS1 bag, where each chunk holds up to `chunk_message_count` messages.
+///
+/// The messages are tuples of the connection index, the log time and the ROS1 serialized data.
+pub fn write_ros1_bag(
+    file_path: impl AsRef<Path>,
+    connections: &[Ros1TestConnection],
+    messages: &[(u32, DateTime<Utc>, Vec<u8>)],
+    compression: &str,
+    chunk_message_count: usize,
+) {
+    let mut buffer: Vec<u8> = BAG_MAGIC.to_vec();
+    let bag_header_position = buffer.len();
+    buffer.resize(bag_header_position + BAG_HEADER_LENGTH, b' ');
+
+    let mut chunk_infos: Vec<Vec<u8>> = Vec::new();
+    for current_messages in messages.chunks(chunk_message_count) {
+        let mut chunk_data: Vec<u8> = Vec::new();
+        for (id, connection) in connections.iter().enumerate() {
+            write_connection_record(&mut chunk_data, id as u32, connection);
+        }
+        for (connection_id, date_time, data) in current_messages {
+            write_record(
+                &mut chunk_data,
+                &[
+                    field("op", &[OP_MESSAGE_DATA]),
+                    field("conn", &connection_id.to_le_bytes()),
+                    field("time", &time_bytes(*date_time)),
+                ],
+                data,
+            );
+        }
+
+        let chunk_position = buffer.len() as u64;
+        write_record(
+            &mut buffer,
+            &[
+                field("op", &[OP_CHUNK]),
+                field("compression", compression.as_bytes()),
+                field("size", &(chunk_data.len() as u32).to_le_bytes()),
+            ],
+            &compress(&chunk_data, compression),
+        );
+
+        let start_date_time = current_messages.iter().map(|x| x.1).min().unwrap();
+        let end_date_time = current_messages.iter().map(|x| x.1).max().unwrap();
+        let mut chunk_info: Vec<u8> = Vec::new();
+        let counts: Vec<u8> = (0..connections.len() as u32)
+            .map(|id| {
+                let count = current_messages.iter().filter(|x| x.0 == id).count() as u32;
+                (id, count)
+            })
+            .filter(|(_, count)| *count > 0)
+            .flat_map(|(id, count)| [id.to_le_bytes(), count.to_le_bytes()].concat())
+            .collect();
+        write_record(
+            &mut chunk_info,
+            &[
+                field("op", &[OP_CHUNK_INFO]),
+                field("ver", &1u32.to_le_bytes()),
+                field("chunk_pos", &chunk_position.to_le_bytes()),
+                field("start_time", &time_bytes(start_date_time)),
+                field("end_time", &time_bytes(end_date_time)),
+                field("count", &((counts.len() / 8) as u32).to_le_bytes()),
+            ],
+            &counts,
+        );
+        chunk_infos.push(chunk_info);
+    }
+
+    let index_position = buffer.len() as u64;
+    for (id, connection) in connections.iter().enumerate() {
+        write_connection_record(&mut buffer, id as u32, connection);
+    }
+    chunk_infos.iter().for_each(|x| buffer.extend_from_slice(x));
+
+    let mut bag_header: Vec<u8> = Vec::new();
+    let header_fields = [
+        field("op", &[OP_BAG_HEADER]),
+        field("index_pos", &index_position.to_le_bytes()),
+        field("conn_count", &(connections.len() as u32).to_le_bytes()),
+        field("chunk_count", &(chunk_infos.len() as u32).to_le_bytes()),
+    ];
+    let header_length: usize = header_fields.iter().map(|x| x.len()).sum();
+    let padding = vec![b' '; BAG_HEADER_LENGTH - header_length - 8];
+    write_record(&mut bag_header, &header_fields, &padding);
+    buffer[bag_header_position..bag_header_position + BAG_HEADER_LENGTH]
+        .copy_from_slice(&bag_header);
+
+    std::fs::write(file_path, buffer).expect("writing should work");
+}
+
+fn write_connection_record(buffer: &mut Vec<u8>, id: u32, connection: &Ros1TestConnection) {
+    let connection_header: Vec<u8> = [
+        field("topic", connection.topic.as_bytes()),
+        field("type", connection.message_type.as_bytes()),
+        field("md5sum", b"*"),
+        field(
+            "message_definition",
+            connection.message_definition.as_bytes(),
+        ),
+        field("latching", if connection.latching { b"1" } else { b"0" }),
+    ]
+    .concat();
+    write_record(
+        buffer,
+        &[
+            field("op", &[OP_CONNECTION]),
+            field("conn", &id.to_le_bytes()),
+            field("topic", connection.topic.as_bytes()),
+        ],
+        &connection_header,
+    );
+}
+
+fn field(name: &str, value: &[u8]) -> Vec<u8> {
+    let mut field = ((name.len() + 1 + value.len()) as u32)
+        .to_le_bytes()
+        .to_vec();
+    field.extend_from_slice(name.as_bytes());
+    field.push(b'=');
+    field.extend_from_slice(value);
+    field
+}
+
+fn write_record(buffer: &mut Vec<u8>, fields: &[Vec<u8>], data: &[u8]) {
+    let header = fields.concat();
+    buffer.extend_from_slice(&(header.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(&header);
+    buffer.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(data);
+}
+
+fn time_bytes(date_time: DateTime<Utc>) -> Vec<u8> {
+    Ros1Encoder::default().time(date_time).finish()
+}
+
+fn compress(data: &[u8], compression: &str) -> Vec<u8> {
+    match compression {
+        "none" => data.to_vec(),
+        "bz2" => {
+            let mut encoder =
+                bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+            encoder.write_all(data).expect("compressing should work");
+            encoder.finish().expect("compressing should work")
+        }
+        "lz4" => {
+            let mut encoder = lz4_flex::frame::FrameEncoder::new(Vec::new());
+            encoder.write_all(data).expect("compressing should work");
+            encoder.finish().expect("compressing should work")
+        }
+        _ => panic!("compression `{compression}` is not supported"),
+    }
+}
@@ -0,0 +1,74 @@
+//! Checks the cache of decompressed storage files, which is redirected to a test directory by the
+//! environment variable and is therefore tested in its own process.
+mod common;
+
+use common::{SyntheticBag, SyntheticChannel, date_time, imu};
+use erosbag_core::Rosbag;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Returns the paths of all files within the cache directory.
+fn cached_file_paths(cache_directory_path: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(cache_directory_path)
+        .unwrap()
+        .flat_map(|x| std::fs::read_dir(x.unwrap().path()).unwrap())
+        .map(|x| x.unwrap().path())
+        .collect()
+}
+
+#[test]
+fn decompression_cache() {
+    let cache = SyntheticBag::new("decompression_cache_directory");
+    // no other test of this process reads the environment
+    unsafe { std::env::set_var("EROSBAG_CACHE_DIR", &cache.directory_path) };
+
+    let stale_file_path = cache.directory_path.join("stale").join("bag.mcap");
+    std::fs::create_dir_all(stale_file_path.parent().unwrap()).unwrap();
+    File::create(&stale_file_path)
+        .unwrap()
+        .set_modified(SystemTime::now() - Duration::from_secs(30 * 24 * 60 * 60))
+        .unwrap();
+
+    let corrupted_bag = SyntheticBag::new("decompression_cache_corrupted");
+    std::fs::write(
+        corrupted_bag.directory_path.join("bag_0.mcap.zstd"),
+        b"no zstd frame",
+    )
+    .unwrap();
+    assert!(Rosbag::new(&corrupted_bag.directory_path).is_err());
+    assert!(!stale_file_path.exists());
+    assert!(cached_file_paths(&cache.directory_path).is_empty());
+
+    let bag = SyntheticBag::new("decompression_cache_bag");
+    bag.write_file(
+        "bag_0",
+        &[SyntheticChannel::new(
+            "/imu",
+            [0, 100].map(|x| (date_time(x), imu(date_time(x)))),
+        )],
+    );
+    let file_path = bag.directory_path.join("bag_0.mcap");
+    let compressed_data =
+        zstd::stream::encode_all(std::fs::read(&file_path).unwrap().as_slice(), 0).unwrap();
+    std::fs::write(bag.directory_path.join("bag_0.mcap.zstd"), compressed_data).unwrap();
+    std::fs::remove_file(file_path).unwrap();
+
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+    assert_eq!(rosbag.get_end_date_time().unwrap(), Some(date_time(100)));
+    let cached_file_paths = cached_file_paths(&cache.directory_path);
+    assert_eq!(cached_file_paths.len(), 1);
+    let cache_key = cached_file_paths[0]
+        .parent()
+        .unwrap()
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .into_owned();
+    assert_eq!(cache_key.len(), 64);
+    assert!(cache_key.chars().all(|x| x.is_ascii_hexdigit()));
+    assert_eq!(
+        cached_file_paths[0].file_name().unwrap().to_string_lossy(),
+        "bag_0.mcap"
+    );
+}
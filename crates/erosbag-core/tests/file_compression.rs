@@ -0,0 +1,134 @@
+//! Opens and converts bags, whose storage files are compressed as a whole with zstd as rosbag2
+//! does with `compression_mode: FILE`.
+mod common;
+
+use common::{SyntheticBag, SyntheticChannel, date_time, imu};
+use erosbag_core::ros_messages::sensor_msgs;
+use erosbag_core::storage::{
+    BagMetadata, StorageConversionOptions, StorageFormat, convert_storage,
+};
+use erosbag_core::{Error, Rosbag};
+use std::collections::HashSet;
+use std::path::Path;
+
+const IMU_TOPIC: &str = "/imu";
+
+/// Creates a bag of two MCAP files with 10 and 2 IMU messages and its metadata file.
+fn create_bag(name: &str) -> SyntheticBag {
+    let synthetic_bag = SyntheticBag::new(&format!("{name}_synthetic"));
+    synthetic_bag.write_file(
+        "bag_0",
+        &[SyntheticChannel::new(
+            IMU_TOPIC,
+            (0..10).map(|i| (date_time(i * 100), imu(date_time(i * 100)))),
+        )],
+    );
+    synthetic_bag.write_file(
+        "bag_1",
+        &[SyntheticChannel::new(
+            IMU_TOPIC,
+            [1000, 1100].map(|x| (date_time(x), imu(date_time(x)))),
+        )],
+    );
+
+    let bag = SyntheticBag::new(name);
+    convert_storage(
+        &synthetic_bag.directory_path,
+        &bag.directory_path,
+        &StorageConversionOptions::new(StorageFormat::Mcap),
+    )
+    .unwrap();
+    bag
+}
+
+/// Compresses the storage files of a bag and updates its metadata file like rosbag2 does.
+fn compress_files(directory_path: &Path, compression_format: &str) {
+    let mut metadata = BagMetadata::from_directory(directory_path)
+        .unwrap()
+        .unwrap();
+    let information = &mut metadata.rosbag2_bagfile_information;
+    for current_relative_file_path in &mut information.relative_file_paths {
+        let file_path = directory_path.join(current_relative_file_path.as_str());
+        let data = std::fs::read(&file_path).unwrap();
+        let compressed_data = zstd::stream::encode_all(data.as_slice(), 0).unwrap();
+        current_relative_file_path.push_str(".zstd");
+        std::fs::write(
+            directory_path.join(current_relative_file_path.as_str()),
+            compressed_data,
+        )
+        .unwrap();
+        std::fs::remove_file(file_path).unwrap();
+    }
+    for current_file in &mut information.files {
+        current_file.path.push_str(".zstd");
+    }
+    information.compression_format = compression_format.to_string();
+    information.compression_mode = "FILE".to_string();
+    metadata.write_to_directory(directory_path).unwrap();
+}
+
+#[test]
+fn open_compressed_mcap_files() {
+    let bag = create_bag("file_compression_open");
+    compress_files(&bag.directory_path, "zstd");
+
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+    assert_eq!(
+        rosbag.get_file_ids(),
+        HashSet::from(["bag_0".into(), "bag_1".into()])
+    );
+    let messages = rosbag
+        .messages::<sensor_msgs::Imu>(&None, &None, &None)
+        .unwrap();
+    assert_eq!(messages.len(), 12);
+    assert_eq!(messages[11].log_date_time, date_time(1100));
+
+    // the decompressed files are reused from the cache
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+    assert_eq!(rosbag.get_end_date_time().unwrap(), Some(date_time(1100)));
+}
+
+#[test]
+fn convert_compressed_sqlite3_files() {
+    let bag = create_bag("file_compression_convert");
+    let sqlite3_bag = SyntheticBag::new("file_compression_convert_db3");
+    let mcap_bag = SyntheticBag::new("file_compression_convert_mcap");
+    convert_storage(
+        &bag.directory_path,
+        &sqlite3_bag.directory_path,
+        &StorageConversionOptions::new(StorageFormat::Sqlite3),
+    )
+    .unwrap();
+    compress_files(&sqlite3_bag.directory_path, "zstd");
+
+    let metadata = convert_storage(
+        &sqlite3_bag.directory_path,
+        &mcap_bag.directory_path,
+        &StorageConversionOptions::new(StorageFormat::Mcap),
+    )
+    .unwrap();
+    let information = &metadata.rosbag2_bagfile_information;
+    assert_eq!(
+        information.relative_file_paths,
+        ["bag_0.mcap", "bag_1.mcap"]
+    );
+    assert_eq!(information.files[1].path, "bag_1.mcap");
+    assert_eq!(information.compression_format, "");
+    assert_eq!(information.compression_mode, "");
+    assert_eq!(information.message_count, 12);
+
+    let rosbag = Rosbag::new(&mcap_bag.directory_path).unwrap();
+    let messages = rosbag
+        .messages::<sensor_msgs::Imu>(&None, &None, &None)
+        .unwrap();
+    assert_eq!(messages.len(), 12);
+}
+
+#[test]
+fn unsupported_file_compression() {
+    let bag = create_bag("file_compression_unsupported");
+    compress_files(&bag.directory_path, "lz4");
+
+    let result = Rosbag::new(&bag.directory_path);
+    assert!(matches!(result, Err(Error::UnsupportedFileCompression(x)) if x == "lz4"));
+}
@@ -0,0 +1,281 @@
+#!/usr/bin/env python3
+"""Writes the reference CDR payloads of the supported message types.
+
+Requires a sourced ROS2 installation providing rclpy and the message packages:
+
+    source /opt/ros/<distro>/setup.bash
+    python3 generate_fixtures.py
+
+The values must match the expectations in `tests/cdr_round_trip.rs`.
+"""
+
+import struct
+from pathlib import Path
+
+from builtin_interfaces.msg import Duration, Time
+from geometry_msgs.msg import (
+    Point,
+    Pose,
+    PoseStamped,
+    PoseWithCovariance,
+    PoseWithCovarianceStamped,
+    Quaternion,
+    Transform,
+    TransformStamped,
+    Twist,
+    TwistStamped,
+    TwistWithCovariance,
+    Vector3,
+)
+from nav_msgs.msg import Odometry, Path as NavPath
+from rclpy.serialization import serialize_message
+from sensor_msgs.msg import (
+    CameraInfo,
+    Image,
+    Imu,
+    LaserEcho,
+    LaserScan,
+    MultiEchoLaserScan,
+    NavSatFix,
+    NavSatStatus,
+    PointCloud2,
+    PointField,
+    RegionOfInterest,
+)
+from std_msgs.msg import ColorRGBA, Header
+from tf2_msgs.msg import TFMessage
+from visualization_msgs.msg import Marker, MarkerArray
+
+FIXTURE_DIRECTORY = Path(__file__).parent
+
+
+def header(frame_id="base_link", nanosec=123456789):
+    return Header(stamp=Time(sec=1700000000, nanosec=nanosec), frame_id=frame_id)
+
+
+def pose(x, y, z, qz=0.0, qw=1.0):
+    return Pose(
+        position=Point(x=x, y=y, z=z),
+        orientation=Quaternion(x=0.0, y=0.0, z=qz, w=qw),
+    )
+
+
+def diagonal_covariance(size, value):
+    return [value if i % (size + 1) == 0 else 0.0 for i in range(size * size)]
+
+
+def imu():
+    return Imu(
+        header=header("imu_link"),
+        orientation=Quaternion(x=0.0, y=0.0, z=0.6, w=0.8),
+        orientation_covariance=diagonal_covariance(3, 0.01),
+        angular_velocity=Vector3(x=0.1, y=-0.2, z=0.3),
+        angular_velocity_covariance=diagonal_covariance(3, 0.02),
+        linear_acceleration=Vector3(x=0.5, y=0.0, z=9.81),
+        linear_acceleration_covariance=diagonal_covariance(3, 0.04),
+    )
+
+
+def nav_sat_fix():
+    return NavSatFix(
+        header=header("gnss_link"),
+        status=NavSatStatus(status=NavSatStatus.STATUS_FIX, service=NavSatStatus.SERVICE_GPS),
+        latitude=48.1486,
+        longitude=11.568,
+        altitude=519.5,
+        position_covariance=diagonal_covariance(3, 0.25),
+        position_covariance_type=NavSatFix.COVARIANCE_TYPE_DIAGONAL_KNOWN,
+    )
+
+
+def point_cloud_2():
+    points = [(1.0, 2.0, 3.0, 10.0), (4.0, 5.0, 6.0, 20.0), (7.0, 8.0, 9.0, 30.0)]
+    return PointCloud2(
+        header=header("lidar"),
+        height=1,
+        width=len(points),
+        fields=[
+            PointField(name=name, offset=4 * i, datatype=PointField.FLOAT32, count=1)
+            for i, name in enumerate(["x", "y", "z", "intensity"])
+        ],
+        is_bigendian=False,
+        point_step=16,
+        row_step=16 * len(points),
+        data=b"".join(struct.pack("<4f", *x) for x in points),
+        is_dense=True,
+    )
+
+
+def image():
+    return Image(
+        header=header("camera"),
+        height=2,
+        width=2,
+        encoding="rgb8",
+        is_bigendian=0,
+        step=6,
+        data=bytes(range(12)),
+    )
+
+
+def camera_info():
+    return CameraInfo(
+        header=header("camera"),
+        height=480,
+        width=640,
+        distortion_model="plumb_bob",
+        d=[0.1, -0.2, 0.001, 0.002, 0.0],
+        k=[500.0, 0.0, 320.0, 0.0, 500.0, 240.0, 0.0, 0.0, 1.0],
+        r=[1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0],
+        p=[500.0, 0.0, 320.0, 0.0, 0.0, 500.0, 240.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+        binning_x=0,
+        binning_y=0,
+        roi=RegionOfInterest(x_offset=0, y_offset=0, height=0, width=0, do_rectify=False),
+    )
+
+
+def laser_scan():
+    return LaserScan(
+        header=header("laser"),
+        angle_min=-1.5,
+        angle_max=1.5,
+        angle_increment=0.75,
+        time_increment=0.0,
+        scan_time=0.125,
+        range_min=0.25,
+        range_max=30.0,
+        ranges=[1.0, 2.0, 3.0, 4.0, 5.0],
+        intensities=[10.0, 20.0, 30.0, 40.0, 50.0],
+    )
+
+
+def multi_echo_laser_scan():
+    return MultiEchoLaserScan(
+        header=header("laser"),
+        angle_min=-0.5,
+        angle_max=0.5,
+        angle_increment=1.0,
+        time_increment=0.0,
+        scan_time=0.125,
+        range_min=0.25,
+        range_max=30.0,
+        ranges=[LaserEcho(echoes=[1.0, 1.5]), LaserEcho(echoes=[2.0])],
+        intensities=[LaserEcho(echoes=[10.0, 5.0]), LaserEcho(echoes=[20.0])],
+    )
+
+
+def pose_stamped():
+    return PoseStamped(header=header("map"), pose=pose(1.0, 2.0, 3.0))
+
+
+def pose_with_covariance():
+    return PoseWithCovariance(pose=pose(1.0, 2.0, 0.0, 0.6, 0.8), covariance=diagonal_covariance(6, 0.1))
+
+
+def twist_with_covariance():
+    return TwistWithCovariance(
+        twist=Twist(linear=Vector3(x=1.0, y=0.0, z=0.0), angular=Vector3(x=0.0, y=0.0, z=0.5)),
+        covariance=diagonal_covariance(6, 0.2),
+    )
+
+
+def pose_with_covariance_stamped():
+    return PoseWithCovarianceStamped(header=header("map"), pose=pose_with_covariance())
+
+
+def twist_stamped():
+    return TwistStamped(header=header("base_link"), twist=twist_with_covariance().twist)
+
+
+def tf_message():
+    return TFMessage(
+        transforms=[
+            TransformStamped(
+                header=header("map"),
+                child_frame_id="base_link",
+                transform=Transform(
+                    translation=Vector3(x=1.0, y=2.0, z=0.0),
+                    rotation=Quaternion(x=0.0, y=0.0, z=0.0, w=1.0),
+                ),
+            ),
+            TransformStamped(
+                header=header("base_link"),
+                child_frame_id="lidar",
+                transform=Transform(
+                    translation=Vector3(x=0.5, y=0.0, z=1.5),
+                    rotation=Quaternion(x=0.0, y=0.0, z=0.6, w=0.8),
+                ),
+            ),
+        ]
+    )
+
+
+def odometry():
+    return Odometry(
+        header=header("odom"),
+        child_frame_id="base_link",
+        pose=pose_with_covariance(),
+        twist=twist_with_covariance(),
+    )
+
+
+def path():
+    return NavPath(
+        header=header("map"),
+        poses=[
+            PoseStamped(header=header("map", 0), pose=pose(0.0, 0.0, 0.0)),
+            PoseStamped(header=header("map", 500000000), pose=pose(1.0, 0.5, 0.0, 0.6, 0.8)),
+        ],
+    )
+
+
+def marker(marker_id=7, action=Marker.ADD):
+    return Marker(
+        header=header("map"),
+        ns="test",
+        id=marker_id,
+        type=Marker.SPHERE,
+        action=action,
+        pose=pose(1.0, 2.0, 3.0),
+        scale=Vector3(x=1.0, y=1.0, z=1.0),
+        color=ColorRGBA(r=1.0, g=0.0, b=0.0, a=1.0),
+        lifetime=Duration(sec=2, nanosec=500000000),
+        frame_locked=True,
+        points=[Point(x=0.0, y=0.0, z=0.0), Point(x=1.0, y=1.0, z=1.0)],
+        colors=[ColorRGBA(r=0.0, g=1.0, b=0.0, a=1.0)],
+        text="hello",
+    )
+
+
+def marker_array():
+    return MarkerArray(markers=[marker(), marker(8, Marker.DELETE)])
+
+
+FIXTURES = {
+    "sensor_msgs__msg__Imu": imu,
+    "sensor_msgs__msg__NavSatFix": nav_sat_fix,
+    "sensor_msgs__msg__PointCloud2": point_cloud_2,
+    "sensor_msgs__msg__Image": image,
+    "sensor_msgs__msg__CameraInfo": camera_info,
+    "sensor_msgs__msg__LaserScan": laser_scan,
+    "sensor_msgs__msg__MultiEchoLaserScan": multi_echo_laser_scan,
+    "geometry_msgs__msg__PoseStamped": pose_stamped,
+    "geometry_msgs__msg__PoseWithCovarianceStamped": pose_with_covariance_stamped,
+    "geometry_msgs__msg__TwistStamped": twist_stamped,
+    "tf2_msgs__msg__TFMessage": tf_message,
+    "nav_msgs__msg__Odometry": odometry,
+    "nav_msgs__msg__Path": path,
+    "visualization_msgs__msg__Marker": marker,
+    "visualization_msgs__msg__MarkerArray": marker_array,
+}
+
+
+def main():
+    for name, create in FIXTURES.items():
+        file_path = FIXTURE_DIRECTORY / f"{name}.cdr"
+        file_path.write_bytes(serialize_message(create()))
+        print(f"Wrote {file_path}")
+
+
+if __name__ == "__main__":
+    main()
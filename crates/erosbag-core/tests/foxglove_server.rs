@@ -0,0 +1,338 @@
+//! Plays back a synthetic bag with the Foxglove WebSocket server to a client on a local socket,
+//! which subscribes to channels and controls the playback.
+mod common;
+
+use common::{SyntheticBag, SyntheticChannel, date_time, imu, point_cloud};
+use erosbag_core::foxglove::protocol::{
+    ClientMessage, MESSAGE_DATA_OPCODE, PLAYBACK_STATE_OPCODE, PlaybackCommand,
+    PlaybackControlRequest, PlaybackState, PlaybackStatus, ServerMessage, StatusLevel,
+    Subscription, TIME_OPCODE,
+};
+use erosbag_core::foxglove::{FoxgloveServer, FoxgloveServerOptions, SUBPROTOCOL};
+use erosbag_core::ros_messages::{RosMessage, sensor_msgs};
+use erosbag_core::{Error, Rosbag};
+use std::net::{TcpListener, TcpStream};
+use tungstenite::client::IntoClientRequest;
+use tungstenite::http::HeaderValue;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+const IMU_TOPIC: &str = "/imu";
+const LIDAR_TOPIC: &str = "/lidar";
+
+/// Creates a bag with 10 IMU messages every 100ms from 0ms and 4 point clouds every 200ms from
+/// 50ms.
+fn create_bag(name: &str) -> SyntheticBag {
+    let bag = SyntheticBag::new(name);
+    bag.write_file(
+        "bag_0",
+        &[
+            SyntheticChannel::new(
+                IMU_TOPIC,
+                (0..10).map(|i| (date_time(i * 100), imu(date_time(i * 100)))),
+            ),
+            SyntheticChannel::new(
+                LIDAR_TOPIC,
+                [50, 250, 450, 650].map(|x| (date_time(x), point_cloud("lidar", date_time(x), 3))),
+            ),
+        ],
+    );
+    bag
+}
+
+fn nanoseconds(offset_milliseconds: i64) -> u64 {
+    date_time(offset_milliseconds)
+        .timestamp_nanos_opt()
+        .unwrap() as u64
+}
+
+/// Client of the Foxglove WebSocket protocol.
+struct Client {
+    websocket: WebSocket<MaybeTlsStream<TcpStream>>,
+}
+
+/// Message received by the client.
+#[derive(Debug)]
+enum Received {
+    Text(ServerMessage),
+    MessageData {
+        subscription_id: u32,
+        log_time: u64,
+        data: Vec<u8>,
+    },
+    Time(u64),
+    PlaybackState(PlaybackState),
+}
+
+impl Client {
+    fn connect(address: std::net::SocketAddr) -> Self {
+        let mut request = format!("ws://{address}").into_client_request().unwrap();
+        request.headers_mut().insert(
+            "Sec-WebSocket-Protocol",
+            HeaderValue::from_static(SUBPROTOCOL),
+        );
+        let (websocket, response) = tungstenite::connect(request).unwrap();
+        assert_eq!(response.headers()["Sec-WebSocket-Protocol"], SUBPROTOCOL);
+        Self { websocket }
+    }
+
+    fn send(&mut self, message: &ClientMessage) {
+        let text = serde_json::to_string(message).unwrap();
+        self.websocket.send(Message::Text(text.into())).unwrap();
+    }
+
+    fn send_playback_control_request(&mut self, request: &PlaybackControlRequest) {
+        self.websocket
+            .send(Message::Binary(request.encode().into()))
+            .unwrap();
+    }
+
+    /// Returns the next playback state, skipping the playback times.
+    fn receive_playback_state(&mut self) -> PlaybackState {
+        loop {
+            match self.receive() {
+                Received::PlaybackState(state) => return state,
+                Received::Time(_) => {}
+                x => panic!("unexpected message {x:?}"),
+            }
+        }
+    }
+
+    fn receive(&mut self) -> Received {
+        loop {
+            match self.websocket.read().unwrap() {
+                Message::Text(text) => {
+                    return Received::Text(serde_json::from_str(text.as_str()).unwrap());
+                }
+                Message::Binary(data) => {
+                    return match data[0] {
+                        MESSAGE_DATA_OPCODE => Received::MessageData {
+                            subscription_id: u32::from_le_bytes(data[1..5].try_into().unwrap()),
+                            log_time: u64::from_le_bytes(data[5..13].try_into().unwrap()),
+                            data: data[13..].to_vec(),
+                        },
+                        TIME_OPCODE => {
+                            Received::Time(u64::from_le_bytes(data[1..9].try_into().unwrap()))
+                        }
+                        PLAYBACK_STATE_OPCODE => {
+                            Received::PlaybackState(PlaybackState::decode(&data).unwrap())
+                        }
+                        opcode => panic!("unexpected opcode {opcode}"),
+                    };
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns the subscription ids, log times and data of the messages until the end of the
+    /// bag is reached, which is announced by the ended playback state.
+    fn receive_until_end(&mut self) -> Vec<(u32, u64, Vec<u8>)> {
+        let mut messages = Vec::new();
+        loop {
+            match self.receive() {
+                Received::MessageData {
+                    subscription_id,
+                    log_time,
+                    data,
+                } => messages.push((subscription_id, log_time, data)),
+                Received::PlaybackState(state) if state.status == PlaybackStatus::Playing => {}
+                Received::PlaybackState(state) => {
+                    assert_eq!(state.status, PlaybackStatus::Ended);
+                    assert_eq!(state.request_id, None);
+                    let Received::Text(ServerMessage::Status { level, message }) = self.receive()
+                    else {
+                        panic!("expected status at the end of the bag");
+                    };
+                    assert_eq!(level, StatusLevel::Info, "{message}");
+                    return messages;
+                }
+                Received::Text(x) => panic!("unexpected message {x:?}"),
+                Received::Time(_) => {}
+            }
+        }
+    }
+
+    /// Closes the connection, so that the server finishes serving it.
+    fn close(mut self) {
+        self.websocket.close(None).unwrap();
+        while self.websocket.read().is_ok() {}
+    }
+}
+
+/// Serves one connection of the client in a separate thread.
+fn with_server(rosbag: &Rosbag, options: FoxgloveServerOptions, client: impl FnOnce(Client)) {
+    let server = FoxgloveServer::new(rosbag, options).unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    std::thread::scope(|scope| {
+        let server_thread = scope.spawn(|| {
+            let (stream, _) = listener.accept().unwrap();
+            server.serve_connection(stream)
+        });
+        client(Client::connect(address));
+        server_thread.join().unwrap().unwrap();
+    });
+}
+
+#[test]
+fn advertise_and_stream_subscribed_channels() {
+    let bag = create_bag("foxglove_stream");
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    with_server(
+        &rosbag,
+        FoxgloveServerOptions::new().with_rate(100.0),
+        |mut client| {
+            let Received::Text(ServerMessage::ServerInfo {
+                capabilities,
+                data_start_time,
+                data_end_time,
+                ..
+            }) = client.receive()
+            else {
+                panic!("expected server info");
+            };
+            assert_eq!(
+                capabilities,
+                vec!["time".to_string(), "playbackControl".to_string()]
+            );
+            assert_eq!(data_start_time, Some(nanoseconds(0)));
+            assert_eq!(data_end_time, Some(nanoseconds(900)));
+
+            let Received::Text(ServerMessage::Advertise { channels }) = client.receive() else {
+                panic!("expected advertisement");
+            };
+            assert_eq!(channels.len(), 2);
+            let imu_channel = channels.iter().find(|x| x.topic == IMU_TOPIC).unwrap();
+            assert_eq!(imu_channel.encoding, "cdr");
+            assert_eq!(imu_channel.schema_name, sensor_msgs::Imu::SCHEMA_NAME);
+            assert_eq!(imu_channel.schema_encoding, "ros2msg");
+            assert_eq!(imu_channel.schema, sensor_msgs::Imu::DEFINITION);
+
+            client.send(&ClientMessage::Subscribe {
+                subscriptions: vec![Subscription {
+                    id: 7,
+                    channel_id: imu_channel.id,
+                }],
+            });
+            let messages = client.receive_until_end();
+            assert_eq!(messages.len(), 10);
+            for (i, (subscription_id, log_time, data)) in messages.into_iter().enumerate() {
+                let offset_milliseconds = i as i64 * 100;
+                assert_eq!(subscription_id, 7);
+                assert_eq!(log_time, nanoseconds(offset_milliseconds));
+                assert_eq!(
+                    sensor_msgs::Imu::deserialize_cdr(&data).unwrap(),
+                    imu(date_time(offset_milliseconds))
+                );
+            }
+            client.close();
+        },
+    );
+}
+
+#[test]
+fn seek_and_play_paused_playback() {
+    let bag = create_bag("foxglove_seek");
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    with_server(
+        &rosbag,
+        FoxgloveServerOptions::new().with_paused(true),
+        |mut client| {
+            client.receive();
+            let Received::Text(ServerMessage::Advertise { channels }) = client.receive() else {
+                panic!("expected advertisement");
+            };
+            client.send(&ClientMessage::Subscribe {
+                subscriptions: channels
+                    .iter()
+                    .map(|x| Subscription {
+                        id: x.id + 100,
+                        channel_id: x.id,
+                    })
+                    .collect(),
+            });
+            client.send(&ClientMessage::Subscribe {
+                subscriptions: vec![Subscription {
+                    id: 1,
+                    channel_id: 42,
+                }],
+            });
+            let Received::Text(ServerMessage::Status { level, .. }) = client.receive() else {
+                panic!("expected status of unknown channel");
+            };
+            assert_eq!(level, StatusLevel::Warning);
+
+            client.send_playback_control_request(&PlaybackControlRequest {
+                playback_command: PlaybackCommand::Pause,
+                playback_speed: 1.0,
+                seek_time: Some(nanoseconds(400)),
+                request_id: "seek".to_string(),
+            });
+            let state = client.receive_playback_state();
+            assert_eq!(
+                state,
+                PlaybackState {
+                    status: PlaybackStatus::Paused,
+                    current_time: nanoseconds(400),
+                    playback_speed: 1.0,
+                    did_seek: true,
+                    request_id: Some("seek".to_string()),
+                }
+            );
+            let Received::Time(time) = client.receive() else {
+                panic!("expected time after seeking");
+            };
+            assert_eq!(time, nanoseconds(400));
+
+            client.send_playback_control_request(&PlaybackControlRequest {
+                playback_command: PlaybackCommand::Play,
+                playback_speed: 100.0,
+                seek_time: None,
+                request_id: "play".to_string(),
+            });
+            let state = client.receive_playback_state();
+            assert_eq!(state.status, PlaybackStatus::Playing);
+            assert_eq!(state.playback_speed, 100.0);
+            assert!(!state.did_seek);
+            assert_eq!(state.request_id, Some("play".to_string()));
+            let log_times: Vec<u64> = client
+                .receive_until_end()
+                .into_iter()
+                .map(|x| x.1)
+                .collect();
+            assert_eq!(
+                log_times,
+                [400, 450, 500, 600, 650, 700, 800, 900].map(nanoseconds)
+            );
+            client.close();
+        },
+    );
+}
+
+#[test]
+fn invalid_playback_rate() {
+    let bag = create_bag("foxglove_invalid_rate");
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    let result = FoxgloveServer::new(&rosbag, FoxgloveServerOptions::new().with_rate(0.0));
+    assert!(matches!(result, Err(Error::InvalidPlaybackRate(x)) if x == 0.0));
+}
+
+#[test]
+fn playback_control_request_frames() {
+    let request = PlaybackControlRequest {
+        playback_command: PlaybackCommand::Play,
+        playback_speed: 2.0,
+        seek_time: Some(nanoseconds(300)),
+        request_id: "request".to_string(),
+    };
+    let frame = request.encode();
+    assert_eq!(frame.len(), 1 + 1 + 4 + 1 + 8 + 4 + 7);
+    assert_eq!(PlaybackControlRequest::decode(&frame), Ok(request));
+
+    assert!(PlaybackControlRequest::decode(&frame[..frame.len() - 1]).is_err());
+    assert!(PlaybackControlRequest::decode(&[PLAYBACK_STATE_OPCODE]).is_err());
+}
@@ -0,0 +1,231 @@
+//! Reads an MCAP file from the different random-access sources and compares the overviews and
+//! messages with the memory-mapped file. The HTTP source is read from a local stand-in server,
+//! which answers range requests.
+mod common;
+
+use common::{SyntheticBag, SyntheticChannel, date_time, imu};
+use erosbag_core::dto::{McapFileOverview, McapMessagePage};
+use erosbag_core::ros_messages::sensor_msgs;
+use erosbag_core::{
+    BytesSource, ChunkId, Error, HttpSource, McapFile, McapSource, ReaderSource, Rosbag,
+};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const IMU_TOPIC: &str = "/imu";
+
+/// Creates a bag with one file of 20 IMU messages and returns the path of the file.
+fn create_file(bag: &SyntheticBag) -> PathBuf {
+    bag.write_file(
+        "bag_0",
+        &[SyntheticChannel::new(
+            IMU_TOPIC,
+            (0..20).map(|i| (date_time(i * 100), imu(date_time(i * 100)))),
+        )],
+    );
+    bag.directory_path.join("bag_0.mcap")
+}
+
+/// Returns the overview and the messages of all chunks.
+fn read_file<S: McapSource>(mcap_file: &McapFile<S>) -> (McapFileOverview, McapMessagePage) {
+    let overview = mcap_file.get_overview().unwrap();
+    let mut chunk_ids: Vec<ChunkId> = overview.chunks.keys().copied().collect();
+    chunk_ids.sort();
+    let message_page = mcap_file.read_chunks_with_ids(&chunk_ids, &None).unwrap();
+    (overview, message_page)
+}
+
+/// Minimal HTTP server, which serves one file and counts the bytes of the served bodies.
+struct HttpStandIn {
+    url: String,
+    served_bytes: Arc<AtomicU64>,
+}
+
+impl HttpStandIn {
+    fn new(data: Vec<u8>, accepts_ranges: bool) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/bag_0.mcap", listener.local_addr().unwrap());
+        let served_bytes = Arc::new(AtomicU64::new(0));
+        let counter = served_bytes.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { continue };
+                Self::respond(stream, &data, accepts_ranges, &counter);
+            }
+        });
+
+        Self { url, served_bytes }
+    }
+
+    fn respond(mut stream: TcpStream, data: &[u8], accepts_ranges: bool, counter: &AtomicU64) {
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+        let mut range: Option<(usize, usize)> = None;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line.trim().is_empty() {
+                break;
+            }
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            if name.eq_ignore_ascii_case("range") {
+                let (start, end) = value
+                    .trim()
+                    .trim_start_matches("bytes=")
+                    .split_once('-')
+                    .unwrap();
+                range = Some((start.parse().unwrap(), end.parse().unwrap()));
+            }
+        }
+
+        let accept_ranges = if accepts_ranges { "bytes" } else { "none" };
+        let (status, content_range, body) = match range {
+            Some((start, end)) if accepts_ranges => (
+                "206 Partial Content",
+                format!("Content-Range: bytes {start}-{end}/{}\r\n", data.len()),
+                &data[start..=end],
+            ),
+            _ => ("200 OK", String::new(), data),
+        };
+        let is_head = request_line.starts_with("HEAD");
+        let header = format!(
+            "HTTP/1.1 {status}\r\nContent-Length: {}\r\nAccept-Ranges: {accept_ranges}\r\n{content_range}Connection: close\r\n\r\n",
+            body.len()
+        );
+        stream.write_all(header.as_bytes()).unwrap();
+        if !is_head {
+            stream.write_all(body).unwrap();
+            counter.fetch_add(body.len() as u64, Ordering::SeqCst);
+        }
+        stream.flush().unwrap();
+    }
+}
+
+#[test]
+fn sources_read_the_same_messages() {
+    let bag = SyntheticBag::new("mcap_source_same_messages");
+    let file_path = create_file(&bag);
+    let data = std::fs::read(&file_path).unwrap();
+
+    let mmap_file = McapFile::new("bag_0".into(), &file_path).unwrap();
+    let (expected_overview, expected_message_page) = read_file(&mmap_file);
+    // one chunk per message after the chunk of the schemas and channels
+    assert_eq!(expected_overview.chunks.len(), 21);
+    assert_eq!(
+        expected_message_page
+            .get_messages::<sensor_msgs::Imu>()
+            .unwrap()[&IMU_TOPIC.into()]
+            .len(),
+        20
+    );
+
+    let bytes_file = McapFile::from_source("bag_0".into(), BytesSource::new(data.clone()));
+    assert_eq!(
+        read_file(&bytes_file),
+        (expected_overview.clone(), expected_message_page.clone())
+    );
+
+    let reader_file =
+        McapFile::from_source("bag_0".into(), ReaderSource::from_file(&file_path).unwrap());
+    assert_eq!(
+        read_file(&reader_file),
+        (expected_overview.clone(), expected_message_page.clone())
+    );
+
+    let http_stand_in = HttpStandIn::new(data, true);
+    let http_file =
+        McapFile::from_source("bag_0".into(), HttpSource::new(&http_stand_in.url).unwrap());
+    assert_eq!(
+        read_file(&http_file),
+        (expected_overview, expected_message_page)
+    );
+    assert_eq!(http_file.get_start_date_time().unwrap(), Some(date_time(0)));
+}
+
+#[test]
+fn http_source_reads_only_requested_ranges() {
+    let bag = SyntheticBag::new("mcap_source_http_ranges");
+    let data = std::fs::read(create_file(&bag)).unwrap();
+    let file_length = data.len() as u64;
+    let http_stand_in = HttpStandIn::new(data, true);
+
+    let http_file =
+        McapFile::from_source("bag_0".into(), HttpSource::new(&http_stand_in.url).unwrap());
+    assert_eq!(http_file.source().len().unwrap(), file_length);
+    let overview = http_file.get_overview().unwrap();
+    let summary_bytes = http_stand_in.served_bytes.load(Ordering::SeqCst);
+    assert!(summary_bytes < file_length);
+
+    // the summary is read once, so that reading a chunk only fetches the chunk
+    http_file
+        .read_chunks_with_ids(&[ChunkId::from(5)], &None)
+        .unwrap();
+    assert_eq!(overview.chunks.len(), 21);
+    let chunk_bytes = http_stand_in.served_bytes.load(Ordering::SeqCst) - summary_bytes;
+    assert!(chunk_bytes < file_length / 10);
+}
+
+#[test]
+fn invalid_sources() {
+    let bag = SyntheticBag::new("mcap_source_invalid");
+    let result = McapFile::new("missing".into(), bag.directory_path.join("missing.mcap"));
+    assert!(matches!(result, Err(Error::StdIoError(_))));
+
+    let invalid_file = McapFile::from_source("invalid".into(), BytesSource::new(vec![0u8; 64]));
+    assert!(invalid_file.get_overview().is_err());
+
+    let source = BytesSource::new(vec![0u8; 64]);
+    assert!(matches!(
+        source.read_range(60, 8),
+        Err(Error::InvalidRange(60, 8))
+    ));
+
+    let data = std::fs::read(create_file(&bag)).unwrap();
+    let http_stand_in = HttpStandIn::new(data, false);
+    let result = HttpSource::new(&http_stand_in.url);
+    assert!(matches!(
+        result,
+        Err(Error::HttpRangeRequestsNotSupported(_))
+    ));
+}
+
+#[test]
+fn bags_from_sources_answer_queries() {
+    let bag = SyntheticBag::new("mcap_source_bag_queries");
+    let file_path = create_file(&bag);
+    let data = std::fs::read(&file_path).unwrap();
+
+    let expected = Rosbag::new(&bag.directory_path).unwrap();
+    let bytes_bag = Rosbag::from_sources([("bag_0".into(), BytesSource::new(data.clone()))]);
+    let http_stand_in = HttpStandIn::new(data, true);
+    let http_bag =
+        Rosbag::from_sources([("bag_0".into(), HttpSource::new(&http_stand_in.url).unwrap())]);
+
+    let window = (Some(date_time(500)), Some(date_time(1000)));
+    let expected_messages = expected
+        .messages::<sensor_msgs::Imu>(&None, &window.0, &window.1)
+        .unwrap();
+    assert_eq!(expected_messages.len(), 5);
+    assert_eq!(
+        bytes_bag
+            .messages::<sensor_msgs::Imu>(&None, &window.0, &window.1)
+            .unwrap(),
+        expected_messages
+    );
+    assert_eq!(
+        http_bag
+            .messages::<sensor_msgs::Imu>(&None, &window.0, &window.1)
+            .unwrap(),
+        expected_messages
+    );
+    assert_eq!(
+        http_bag.get_overview().unwrap(),
+        expected.get_overview().unwrap()
+    );
+}
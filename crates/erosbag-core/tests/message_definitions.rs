@@ -0,0 +1,127 @@
+//! Checks that the definitions composed from the `.msg` files list the fields of the message
+//! structs in the order, in which they are encoded.
+//!
+//! The decoded reference CDR payloads serve as samples, so that the nested messages of the
+//! sequences are covered as well.
+mod common;
+
+use common::read_cdr_fixture;
+use erosbag_core::ros_messages::{
+    RosMessage, geometry_msgs, nav_msgs, sensor_msgs, tf2_msgs, visualization_msgs,
+};
+use serde::Serialize;
+use serde_yaml::Value;
+use std::collections::HashMap;
+
+/// Field of a definition with its type resolved to the full name of a nested message, if any.
+struct Field {
+    name: String,
+    message_name: Option<String>,
+}
+
+/// Splits the definition into the fields of the top-level message and of each nested message.
+fn parse_definition(schema_name: &str, definition: &str) -> HashMap<String, Vec<Field>> {
+    let top_level_name = schema_name.replace("/msg/", "/");
+    let mut sections: HashMap<String, Vec<Field>> = HashMap::new();
+    let mut current_name = top_level_name.clone();
+    for line in definition.lines() {
+        if line.starts_with("====") {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix("MSG: ") {
+            current_name = name.to_string();
+            continue;
+        }
+        let (field_type, name) = line
+            .split_once(' ')
+            .unwrap_or_else(|| panic!("line '{line}' of {schema_name} should have a type"));
+        let base_type = field_type.split('[').next().unwrap();
+        let message_name = base_type
+            .chars()
+            .next()
+            .is_some_and(|x| x.is_uppercase() || base_type.contains('/'))
+            .then(|| {
+                if base_type.contains('/') {
+                    base_type.to_string()
+                } else {
+                    let package = current_name.split('/').next().unwrap();
+                    format!("{package}/{base_type}")
+                }
+            });
+        sections
+            .entry(current_name.clone())
+            .or_default()
+            .push(Field {
+                name: name.to_string(),
+                message_name,
+            });
+    }
+    sections
+}
+
+/// Compares the field names of the section with the keys of the serialized message and descends
+/// into the nested messages.
+fn check_fields(sections: &HashMap<String, Vec<Field>>, message_name: &str, value: &Value) {
+    let fields = sections
+        .get(message_name)
+        .unwrap_or_else(|| panic!("definition of {message_name} should be appended"));
+    let mapping = value
+        .as_mapping()
+        .unwrap_or_else(|| panic!("{message_name} should serialize to a mapping"));
+    assert_eq!(
+        mapping
+            .keys()
+            .map(|x| x.as_str().unwrap())
+            .collect::<Vec<_>>(),
+        fields.iter().map(|x| x.name.as_str()).collect::<Vec<_>>(),
+        "fields of {message_name}"
+    );
+
+    for field in fields {
+        let Some(nested_message_name) = &field.message_name else {
+            continue;
+        };
+        match &mapping[field.name.as_str()] {
+            Value::Sequence(values) => values
+                .iter()
+                .for_each(|x| check_fields(sections, nested_message_name, x)),
+            value => check_fields(sections, nested_message_name, value),
+        }
+    }
+}
+
+fn check_definition<T: RosMessage + Serialize>() {
+    let message =
+        T::deserialize_cdr(&read_cdr_fixture(T::SCHEMA_NAME)).expect("decoding should work");
+    let value = serde_yaml::to_value(&message).expect("serializing should work");
+
+    let sections = parse_definition(T::SCHEMA_NAME, T::DEFINITION);
+    check_fields(&sections, &T::SCHEMA_NAME.replace("/msg/", "/"), &value);
+}
+
+#[test]
+fn sensor_messages_match_their_definitions() {
+    check_definition::<sensor_msgs::Imu>();
+    check_definition::<sensor_msgs::NavSatFix>();
+    check_definition::<sensor_msgs::PointCloud2>();
+    check_definition::<sensor_msgs::Image>();
+    check_definition::<sensor_msgs::CameraInfo>();
+    check_definition::<sensor_msgs::LaserScan>();
+    check_definition::<sensor_msgs::MultiEchoLaserScan>();
+}
+
+#[test]
+fn geometry_and_navigation_messages_match_their_definitions() {
+    check_definition::<geometry_msgs::PoseStamped>();
+    check_definition::<geometry_msgs::PoseWithCovarianceStamped>();
+    check_definition::<geometry_msgs::TwistStamped>();
+    check_definition::<tf2_msgs::TFMessage>();
+    check_definition::<nav_msgs::Odometry>();
+    check_definition::<nav_msgs::Path>();
+}
+
+#[test]
+fn visualization_messages_match_their_definitions() {
+    check_definition::<visualization_msgs::Marker>();
+    check_definition::<visualization_msgs::MarkerArray>();
+}
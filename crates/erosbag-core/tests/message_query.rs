@@ -0,0 +1,64 @@
+//! Queries the flattened messages of a synthetic bag.
+mod common;
+
+use common::{SyntheticBag, SyntheticChannel, date_time, imu, point_cloud};
+use erosbag_core::Rosbag;
+use erosbag_core::table::MessageQuery;
+
+/// Creates a bag with 10 IMU messages and 5 point clouds every 100ms.
+fn create_bag(name: &str) -> SyntheticBag {
+    let bag = SyntheticBag::new(name);
+    bag.write_file(
+        "bag_0",
+        &[
+            SyntheticChannel::new(
+                "/imu",
+                (0..10).map(|i| (date_time(i * 100), imu(date_time(i * 100)))),
+            ),
+            SyntheticChannel::new(
+                "/lidar",
+                (0..5).map(|i| {
+                    (
+                        date_time(i * 100),
+                        point_cloud("lidar", date_time(i * 100), 2),
+                    )
+                }),
+            ),
+        ],
+    );
+    bag
+}
+
+#[test]
+fn sql_without_channel_pattern_skips_channels_lacking_columns() {
+    let bag = create_bag("sql_without_channel_pattern_skips_channels_lacking_columns");
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    let query =
+        MessageQuery::new().with_sql(r#"SELECT log_time, "linear_acceleration.z" FROM messages"#);
+    let results = rosbag.query(&query).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results.get(&"/imu".into()).unwrap().height(), 10);
+}
+
+#[test]
+fn sql_with_channel_pattern_fails_on_missing_columns() {
+    let bag = create_bag("sql_with_channel_pattern_fails_on_missing_columns");
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    let query = MessageQuery::new()
+        .with_channel_topic_pattern("/lidar")
+        .with_sql(r#"SELECT log_time, "linear_acceleration.z" FROM messages"#);
+    let result = rosbag.query(&query);
+    assert!(result.is_err());
+}
+
+#[test]
+fn invalid_sql_fails() {
+    let bag = create_bag("invalid_sql_fails");
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    let query = MessageQuery::new().with_sql("SELEKT * FROM messages");
+    let result = rosbag.query(&query);
+    assert!(result.is_err());
+}
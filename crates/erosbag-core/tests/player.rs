@@ -0,0 +1,186 @@
+//! Replays a synthetic bag with the player to callbacks and channels.
+mod common;
+
+use chrono::Duration;
+use common::{SyntheticBag, SyntheticChannel, date_time, imu, point_cloud};
+use erosbag_core::playback::{PlaybackEvent, Player, PlayerOptions, RawMessage};
+use erosbag_core::ros_messages::{RosMessage, rosgraph_msgs, sensor_msgs};
+use erosbag_core::{ChannelTopic, Error, Rosbag};
+use std::collections::HashSet;
+use std::ops::ControlFlow;
+use std::sync::mpsc;
+use std::time::Instant;
+
+const IMU_TOPIC: &str = "/imu";
+const LIDAR_TOPIC: &str = "/lidar";
+
+/// Creates a bag with 10 IMU messages every 100ms from 0ms and 4 point clouds every 200ms from
+/// 50ms.
+fn create_bag(name: &str) -> SyntheticBag {
+    let bag = SyntheticBag::new(name);
+    bag.write_file(
+        "bag_0",
+        &[
+            SyntheticChannel::new(
+                IMU_TOPIC,
+                (0..10).map(|i| (date_time(i * 100), imu(date_time(i * 100)))),
+            ),
+            SyntheticChannel::new(
+                LIDAR_TOPIC,
+                [50, 250, 450, 650].map(|x| (date_time(x), point_cloud("lidar", date_time(x), 3))),
+            ),
+        ],
+    );
+    bag
+}
+
+#[test]
+fn play_decoded_messages_with_remapping_and_clock() {
+    let bag = create_bag("player_decoded");
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+    let options = PlayerOptions::new()
+        .with_rate(10.0)
+        .with_topic_remapping(IMU_TOPIC.into(), "/sensors/imu".into())
+        .with_clock_period(Duration::milliseconds(250));
+    let mut player = Player::new(&rosbag, options).unwrap();
+    let clock = player.clock();
+    assert_eq!(clock.now(), None);
+
+    let mut events: Vec<(String, i64)> = Vec::new();
+    let start_instant = Instant::now();
+    player
+        .play(|event: PlaybackEvent<sensor_msgs::Imu>| {
+            match event {
+                PlaybackEvent::Message(x) => {
+                    assert_eq!(x.message, imu(x.log_date_time));
+                    events.push((
+                        x.channel_topic.to_string(),
+                        x.log_date_time.timestamp_millis(),
+                    ));
+                }
+                PlaybackEvent::Clock(x) => {
+                    assert_eq!(x.message, rosgraph_msgs::Clock::from(x.log_date_time));
+                    events.push((
+                        x.channel_topic.to_string(),
+                        x.log_date_time.timestamp_millis(),
+                    ));
+                }
+                PlaybackEvent::Loop => panic!("unexpected loop"),
+            }
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+
+    assert!(start_instant.elapsed() >= std::time::Duration::from_millis(90));
+    let start_milliseconds = date_time(0).timestamp_millis();
+    let expected: Vec<(String, i64)> = [
+        ("/clock", 0),
+        ("/sensors/imu", 0),
+        ("/sensors/imu", 100),
+        ("/sensors/imu", 200),
+        ("/clock", 250),
+        ("/sensors/imu", 300),
+        ("/sensors/imu", 400),
+        ("/clock", 500),
+        ("/sensors/imu", 500),
+        ("/sensors/imu", 600),
+        ("/sensors/imu", 700),
+        ("/clock", 750),
+        ("/sensors/imu", 800),
+        ("/sensors/imu", 900),
+    ]
+    .into_iter()
+    .map(|(topic, offset)| (topic.to_string(), start_milliseconds + offset))
+    .collect();
+    assert_eq!(events, expected);
+    assert_eq!(clock.now(), Some(date_time(900)));
+}
+
+#[test]
+fn loop_raw_messages_from_start_offset_into_channel() {
+    let bag = create_bag("player_loop");
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+    let options = PlayerOptions::new()
+        .with_rate(100.0)
+        .with_looping(true)
+        .with_start_offset(Duration::milliseconds(500))
+        .with_channel_topics(HashSet::from([ChannelTopic::from(IMU_TOPIC)]));
+    let mut player = Player::new(&rosbag, options).unwrap();
+
+    let (sender, receiver) = mpsc::channel::<PlaybackEvent<RawMessage>>();
+    std::thread::scope(|scope| {
+        let player_thread = scope.spawn(move || player.play(sender));
+
+        let events: Vec<PlaybackEvent<RawMessage>> = receiver.iter().take(11).collect();
+        drop(receiver);
+        player_thread.join().unwrap().unwrap();
+
+        assert_eq!(events[5], PlaybackEvent::Loop);
+        let log_times: Vec<_> = events
+            .iter()
+            .filter_map(|x| match x {
+                PlaybackEvent::Message(x) => {
+                    assert_eq!(x.channel_topic, IMU_TOPIC.into());
+                    assert_eq!(
+                        x.message.schema_name.as_deref(),
+                        Some(sensor_msgs::Imu::SCHEMA_NAME)
+                    );
+                    Some(x.log_date_time)
+                }
+                _ => None,
+            })
+            .collect();
+        let expected: Vec<_> = [500, 600, 700, 800, 900]
+            .repeat(2)
+            .into_iter()
+            .map(date_time)
+            .collect();
+        assert_eq!(log_times, expected);
+    });
+}
+
+#[test]
+fn loop_without_messages_ends() {
+    let bag = create_bag("player_empty_loop");
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    // the start offset lies past the end of the bag
+    let options = PlayerOptions::new()
+        .with_looping(true)
+        .with_start_offset(Duration::seconds(10));
+    let mut events: Vec<PlaybackEvent<RawMessage>> = Vec::new();
+    Player::new(&rosbag, options)
+        .unwrap()
+        .play(|event| {
+            events.push(event);
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert!(events.is_empty());
+
+    // no message of the bag is decoded as clock
+    let options = PlayerOptions::new().with_rate(100.0).with_looping(true);
+    let mut events: Vec<PlaybackEvent<rosgraph_msgs::Clock>> = Vec::new();
+    Player::new(&rosbag, options)
+        .unwrap()
+        .play(|event| {
+            events.push(event);
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert!(events.is_empty());
+}
+
+#[test]
+fn invalid_player_options() {
+    let bag = create_bag("player_invalid_options");
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    let result = Player::new(&rosbag, PlayerOptions::new().with_rate(-1.0));
+    assert!(matches!(result, Err(Error::InvalidPlaybackRate(x)) if x == -1.0));
+    let result = Player::new(
+        &rosbag,
+        PlayerOptions::new().with_clock_period(Duration::zero()),
+    );
+    assert!(matches!(result, Err(Error::InvalidClockPeriod(_))));
+}
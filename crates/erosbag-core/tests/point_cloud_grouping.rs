@@ -0,0 +1,112 @@
+//! Divides the point clouds of a synthetic bag into groups.
+mod common;
+
+use chrono::Duration;
+use common::{SyntheticBag, SyntheticChannel, date_time, point_cloud};
+use erosbag_core::Rosbag;
+use erosbag_core::dto::{PointCloudGroup, PointCloudGrouping};
+use std::collections::HashSet;
+
+/// Creates a bag with 10 point clouds of 2 points every 100ms on `/lidar`.
+fn create_bag(name: &str) -> SyntheticBag {
+    let bag = SyntheticBag::new(name);
+    bag.write_file(
+        "bag_0",
+        &[SyntheticChannel::new(
+            "/lidar",
+            (0..10).map(|i| {
+                (
+                    date_time(i * 100),
+                    point_cloud("lidar", date_time(i * 100), 2),
+                )
+            }),
+        )],
+    );
+    bag
+}
+
+fn get_groups(rosbag: &Rosbag, grouping: PointCloudGrouping) -> Vec<PointCloudGroup> {
+    rosbag
+        .get_point_cloud_groups(&None, &None, &None, &[], &grouping, false)
+        .unwrap()
+}
+
+/// Returns the log times in milliseconds of the messages of each group.
+fn get_message_offsets(groups: &[PointCloudGroup]) -> Vec<Vec<i64>> {
+    groups
+        .iter()
+        .map(|group| {
+            group
+                .messages
+                .iter()
+                .map(|x| (x.log_date_time - date_time(0)).num_milliseconds())
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn sliding_window() {
+    let bag = create_bag("sliding_window");
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    let groups = get_groups(
+        &rosbag,
+        PointCloudGrouping::SlidingWindow {
+            window: Duration::milliseconds(300),
+            step: Duration::milliseconds(100),
+        },
+    );
+    let message_offsets = get_message_offsets(&groups);
+    assert_eq!(message_offsets.len(), 10);
+    assert_eq!(message_offsets[0], vec![0, 100, 200]);
+    assert_eq!(message_offsets[7], vec![700, 800, 900]);
+    assert_eq!(message_offsets[9], vec![900]);
+    assert_eq!(groups[1].start_date_time, date_time(100));
+    assert_eq!(groups[1].point_cloud.size(), 3 * 2);
+
+    let file_stems: HashSet<String> = groups.iter().map(|x| x.file_stem()).collect();
+    assert_eq!(file_stems.len(), groups.len());
+}
+
+#[test]
+fn sliding_window_with_tiny_step() {
+    let bag = create_bag("sliding_window_with_tiny_step");
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    // the windows in between the messages are skipped instead of stepping through each of them
+    let groups = get_groups(
+        &rosbag,
+        PointCloudGrouping::SlidingWindow {
+            window: Duration::milliseconds(300),
+            step: Duration::nanoseconds(1),
+        },
+    );
+    let message_offsets = get_message_offsets(&groups);
+    assert_eq!(message_offsets.len(), 10);
+    assert_eq!(message_offsets[1], vec![100, 200, 300]);
+    assert_eq!(
+        groups[1].start_date_time,
+        date_time(0) + Duration::nanoseconds(1)
+    );
+    assert_eq!(message_offsets[9], vec![900]);
+}
+
+#[test]
+fn sliding_window_with_zero_step() {
+    let bag = create_bag("sliding_window_with_zero_step");
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    let result = rosbag.get_point_cloud_groups(
+        &None,
+        &None,
+        &None,
+        &[],
+        &PointCloudGrouping::SlidingWindow {
+            window: Duration::milliseconds(300),
+            step: Duration::zero(),
+        },
+        false,
+    );
+    assert!(result.is_err());
+}
@@ -0,0 +1,92 @@
+//! Filters the point clouds of a synthetic bag.
+mod common;
+
+use common::{SyntheticBag, SyntheticChannel, date_time, point_cloud};
+use erosbag_core::Rosbag;
+use erosbag_core::dto::PointCloudGrouping;
+use erosbag_core::filter::{PointFilter, voxel_downsample};
+
+/// Creates a bag with 4 point clouds of 5 points on the x axis every 100ms on `/lidar`.
+fn create_bag(name: &str) -> SyntheticBag {
+    let bag = SyntheticBag::new(name);
+    bag.write_file(
+        "bag_0",
+        &[SyntheticChannel::new(
+            "/lidar",
+            (0..4).map(|i| {
+                (
+                    date_time(i * 100),
+                    point_cloud("lidar", date_time(i * 100), 5),
+                )
+            }),
+        )],
+    );
+    bag
+}
+
+fn get_combined_point_cloud(rosbag: &Rosbag, filters: &[PointFilter]) -> epoint::PointCloud {
+    rosbag
+        .get_point_cloud_groups(
+            &None,
+            &None,
+            &None,
+            filters,
+            &PointCloudGrouping::Combined,
+            false,
+        )
+        .unwrap()
+        .remove(0)
+        .point_cloud
+}
+
+#[test]
+fn beam_length_and_decimation() {
+    let bag = create_bag("beam_length_and_decimation");
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    let point_cloud = get_combined_point_cloud(
+        &rosbag,
+        &[
+            PointFilter::TemporalDecimation { keep_every: 2 },
+            PointFilter::BeamLength { min: 0.0, max: 2.5 },
+        ],
+    );
+    assert_eq!(point_cloud.size(), 2 * 3);
+}
+
+#[test]
+fn invalid_filters() {
+    let bag = create_bag("invalid_filters");
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    for current_filter in [
+        PointFilter::TemporalDecimation { keep_every: 0 },
+        PointFilter::BeamLength { min: 2.0, max: 1.0 },
+    ] {
+        let result = rosbag.get_point_cloud_groups(
+            &None,
+            &None,
+            &None,
+            &[current_filter],
+            &PointCloudGrouping::Combined,
+            false,
+        );
+        assert!(result.is_err());
+    }
+}
+
+#[test]
+fn voxel_downsample_of_merged_point_cloud() {
+    let bag = create_bag("voxel_downsample_of_merged_point_cloud");
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    // the scans cover the same points, which share their voxels after merging
+    let mut point_cloud = get_combined_point_cloud(&rosbag, &[]);
+    assert_eq!(point_cloud.size(), 4 * 5);
+    voxel_downsample(&mut point_cloud, 0.5).unwrap();
+    assert_eq!(point_cloud.size(), 5);
+    voxel_downsample(&mut point_cloud, 2.0).unwrap();
+    assert_eq!(point_cloud.size(), 3);
+
+    assert!(voxel_downsample(&mut point_cloud, 0.0).is_err());
+}
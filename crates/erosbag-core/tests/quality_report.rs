@@ -0,0 +1,302 @@
+//! Checks the data quality of a synthetic bag with dropped messages, a late message, duplicate
+//! header stamps, inconsistent point clouds and images and undecodable messages.
+mod common;
+
+use chrono::Duration;
+use common::{SyntheticBag, SyntheticChannel, date_time, header, imu, point_cloud};
+use erosbag_core::dto::{ChannelQuality, QualityIssue, QualityReport};
+use erosbag_core::ros_messages::{RosMessage, sensor_msgs, std_msgs};
+use erosbag_core::{ChannelTopic, Rosbag};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+const IMU_TOPIC: &str = "/imu";
+const LIDAR_TOPIC: &str = "/lidar";
+const CAMERA_TOPIC: &str = "/camera";
+
+/// Message written with the schema of an IMU, whose data ends after the header.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+struct TruncatedImu {
+    header: std_msgs::Header,
+}
+
+impl RosMessage for TruncatedImu {
+    const SCHEMA_NAME: &'static str = sensor_msgs::Imu::SCHEMA_NAME;
+    const DEFINITION: &'static str = sensor_msgs::Imu::DEFINITION;
+}
+
+/// Creates a mono image, whose data length is given by its step and height unless provided.
+fn image(
+    offset_milliseconds: i64,
+    width: u32,
+    height: u32,
+    data_length: Option<usize>,
+) -> sensor_msgs::Image {
+    sensor_msgs::Image {
+        header: header("camera", date_time(offset_milliseconds)),
+        height,
+        width,
+        encoding: "mono8".to_string(),
+        is_bigendian: false,
+        step: width,
+        data: vec![0; data_length.unwrap_or((width * height) as usize)],
+    }
+}
+
+/// Creates a bag, whose IMU messages every 100ms are stamped 10ms before their log time.
+///
+/// The IMU messages at 400ms and 500ms are dropped, the message at 700ms repeats the stamp of
+/// the message at 600ms and the message at 850ms is stored in a second file after the message
+/// at 900ms. The name of the second file precedes the name of the first file, as rosbag2 numbers
+/// the files of a split bag without padding.
+fn create_bag(name: &str) -> SyntheticBag {
+    let bag = SyntheticBag::new(name);
+    let imu_stamps = [
+        (0, -10),
+        (100, 90),
+        (200, 190),
+        (300, 290),
+        (600, 590),
+        (700, 590),
+    ]
+    .into_iter()
+    .chain([(800, 790), (900, 890)]);
+    let mut truncated_point_cloud = point_cloud("lidar", date_time(250), 3);
+    truncated_point_cloud.data.truncate(20);
+    bag.write_file(
+        "bag_9",
+        &[
+            SyntheticChannel::new(
+                IMU_TOPIC,
+                imu_stamps.map(|(log, stamp)| (date_time(log), imu(date_time(stamp)))),
+            ),
+            SyntheticChannel::new(
+                LIDAR_TOPIC,
+                [
+                    (date_time(50), point_cloud("lidar", date_time(50), 3)),
+                    (date_time(250), truncated_point_cloud),
+                    (date_time(450), point_cloud("lidar", date_time(450), 0)),
+                    (date_time(650), point_cloud("lidar", date_time(650), 3)),
+                ],
+            ),
+            SyntheticChannel::new(
+                CAMERA_TOPIC,
+                [
+                    (date_time(100), image(100, 4, 2, None)),
+                    (date_time(300), image(300, 4, 2, None)),
+                    (date_time(500), image(500, 8, 4, None)),
+                    (date_time(700), image(700, 8, 4, Some(16))),
+                ],
+            ),
+        ],
+    );
+    bag.write_file(
+        "bag_10",
+        &[SyntheticChannel::new(
+            IMU_TOPIC,
+            [(date_time(850), imu(date_time(840)))],
+        )],
+    );
+    bag
+}
+
+fn channel<'a>(report: &'a QualityReport, channel_topic: &str) -> &'a ChannelQuality {
+    report
+        .channels
+        .iter()
+        .find(|x| x.channel_topic == channel_topic.into())
+        .unwrap()
+}
+
+fn issues_of<'a>(report: &'a QualityReport, channel_topic: &str) -> Vec<&'a QualityIssue> {
+    let channel_topic = ChannelTopic::from(channel_topic);
+    report
+        .issues
+        .iter()
+        .filter(|x| match x {
+            QualityIssue::Gap {
+                channel_topic: c, ..
+            }
+            | QualityIssue::OutOfOrderLogTime {
+                channel_topic: c, ..
+            }
+            | QualityIssue::DuplicateHeaderStamp {
+                channel_topic: c, ..
+            }
+            | QualityIssue::EmptyMessage {
+                channel_topic: c, ..
+            }
+            | QualityIssue::InconsistentDataLength {
+                channel_topic: c, ..
+            }
+            | QualityIssue::ImageSizeChange {
+                channel_topic: c, ..
+            }
+            | QualityIssue::UndecodableMessage {
+                channel_topic: c, ..
+            } => *c == channel_topic,
+        })
+        .collect()
+}
+
+#[test]
+fn rates_latencies_and_timing_issues() {
+    let bag = create_bag("quality_timing");
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    let report = rosbag
+        .quality_report(&None, &None, &None, Duration::milliseconds(250))
+        .unwrap();
+    assert!(!report.is_consistent());
+    assert_eq!(report.channels.len(), 3);
+
+    let imu_quality = channel(&report, IMU_TOPIC);
+    assert_eq!(
+        imu_quality.message_type.as_deref(),
+        Some("sensor_msgs/msg/Imu")
+    );
+    assert_eq!(imu_quality.message_count, 9);
+    assert_eq!(imu_quality.start_date_time, Some(date_time(0)));
+    assert_eq!(imu_quality.end_date_time, Some(date_time(900)));
+    assert!((imu_quality.expected_rate.unwrap() - 10.0).abs() < 1e-9);
+    assert!((imu_quality.actual_rate.unwrap() - 8.0 / 0.9).abs() < 1e-9);
+    assert_eq!(imu_quality.estimated_dropped_messages, 2);
+    assert_eq!(imu_quality.gap_count, 1);
+    assert_eq!(imu_quality.out_of_order_count, 1);
+    assert_eq!(imu_quality.duplicate_header_stamp_count, 1);
+
+    let latency = imu_quality.latency_statistics.unwrap();
+    assert_eq!(latency.count, 9);
+    assert!((latency.median - 0.01).abs() < 1e-9);
+    assert!((latency.max - 0.11).abs() < 1e-9);
+
+    assert_eq!(
+        issues_of(&report, IMU_TOPIC),
+        vec![
+            &QualityIssue::OutOfOrderLogTime {
+                channel_topic: IMU_TOPIC.into(),
+                previous_date_time: date_time(900),
+                date_time: date_time(850),
+            },
+            &QualityIssue::Gap {
+                channel_topic: IMU_TOPIC.into(),
+                start_date_time: date_time(300),
+                end_date_time: date_time(600),
+            },
+            &QualityIssue::DuplicateHeaderStamp {
+                channel_topic: IMU_TOPIC.into(),
+                log_date_time: date_time(700),
+                stamp: date_time(590),
+            },
+        ]
+    );
+}
+
+#[test]
+fn empty_messages_and_inconsistent_sizes() {
+    let bag = create_bag("quality_sizes");
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    let channel_topics = HashSet::from([LIDAR_TOPIC.into(), CAMERA_TOPIC.into()]);
+    let report = rosbag
+        .quality_report(&None, &None, &Some(channel_topics), Duration::seconds(1))
+        .unwrap();
+    assert_eq!(report.channels.len(), 2);
+
+    assert_eq!(channel(&report, LIDAR_TOPIC).empty_message_count, 1);
+    assert_eq!(channel(&report, LIDAR_TOPIC).inconsistent_size_count, 1);
+    assert_eq!(
+        issues_of(&report, LIDAR_TOPIC),
+        vec![
+            &QualityIssue::InconsistentDataLength {
+                channel_topic: LIDAR_TOPIC.into(),
+                log_date_time: date_time(250),
+                expected_length: 48,
+                actual_length: 20,
+            },
+            &QualityIssue::EmptyMessage {
+                channel_topic: LIDAR_TOPIC.into(),
+                log_date_time: date_time(450),
+            },
+        ]
+    );
+
+    assert_eq!(channel(&report, CAMERA_TOPIC).inconsistent_size_count, 2);
+    assert_eq!(
+        issues_of(&report, CAMERA_TOPIC),
+        vec![
+            &QualityIssue::ImageSizeChange {
+                channel_topic: CAMERA_TOPIC.into(),
+                log_date_time: date_time(500),
+                previous_width: 4,
+                previous_height: 2,
+                width: 8,
+                height: 4,
+            },
+            &QualityIssue::InconsistentDataLength {
+                channel_topic: CAMERA_TOPIC.into(),
+                log_date_time: date_time(700),
+                expected_length: 32,
+                actual_length: 16,
+            },
+        ]
+    );
+}
+
+#[test]
+fn time_window_and_json() {
+    let bag = create_bag("quality_time_window");
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    let report = rosbag
+        .quality_report(
+            &Some(date_time(0)),
+            &Some(date_time(350)),
+            &Some(HashSet::from([IMU_TOPIC.into()])),
+            Duration::milliseconds(250),
+        )
+        .unwrap();
+    assert!(report.is_consistent());
+    assert_eq!(channel(&report, IMU_TOPIC).message_count, 4);
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["channels"][0]["channel_topic"], IMU_TOPIC);
+    assert_eq!(json["channels"][0]["message_count"], 4);
+    assert_eq!(json["issues"], serde_json::json!([]));
+}
+
+#[test]
+fn undecodable_messages() {
+    let bag = SyntheticBag::new("quality_undecodable");
+    bag.write_file(
+        "bag_0",
+        &[SyntheticChannel::new(
+            IMU_TOPIC,
+            [0, 100].map(|x| {
+                (
+                    date_time(x),
+                    TruncatedImu {
+                        header: header("imu_link", date_time(x)),
+                    },
+                )
+            }),
+        )],
+    );
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    let report = rosbag
+        .quality_report(&None, &None, &None, Duration::seconds(1))
+        .unwrap();
+    assert!(!report.is_consistent());
+    let imu_quality = channel(&report, IMU_TOPIC);
+    assert_eq!(imu_quality.message_count, 2);
+    assert_eq!(imu_quality.undecodable_message_count, 2);
+    assert_eq!(imu_quality.latency_statistics, None);
+    assert!(matches!(
+        issues_of(&report, IMU_TOPIC)[..],
+        [
+            QualityIssue::UndecodableMessage { log_date_time: a, .. },
+            QualityIssue::UndecodableMessage { log_date_time: b, .. },
+        ] if *a == date_time(0) && *b == date_time(100)
+    ));
+}
@@ -0,0 +1,401 @@
+//! Converts synthetic ROS1 bags and checks the messages read from the converted MCAP files.
+//!
+//! The supported message types are written with the values of the CDR fixtures, so that their
+//! conversion is compared against the reference payloads.
+mod common;
+
+use common::ros1::{Ros1Encoder, Ros1TestConnection, write_ros1_bag};
+use common::{SyntheticBag, date_time, read_cdr_fixture, start_date_time};
+use erosbag_core::ros_messages::{
+    RosMessage, builtin_msgs, sensor_msgs, std_msgs, visualization_msgs,
+};
+use erosbag_core::ros1::{Ros1Bag, get_ros2_schema_name};
+use erosbag_core::{Error, McapSource, Rosbag};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt::Debug;
+
+const ROS1_IMU: &str = "\
+Header header
+geometry_msgs/Quaternion orientation
+float64[9] orientation_covariance # Row major about x, y, z axes
+geometry_msgs/Vector3 angular_velocity
+float64[9] angular_velocity_covariance
+geometry_msgs/Vector3 linear_acceleration
+float64[9] linear_acceleration_covariance
+================================================================================
+MSG: std_msgs/Header
+uint32 seq
+time stamp
+string frame_id
+================================================================================
+MSG: geometry_msgs/Quaternion
+float64 x
+float64 y
+float64 z
+float64 w
+================================================================================
+MSG: geometry_msgs/Vector3
+float64 x
+float64 y
+float64 z
+";
+
+const ROS1_CAMERA_INFO: &str = "\
+Header header
+uint32 height
+uint32 width
+string distortion_model
+float64[] D
+float64[9] K
+float64[9] R
+float64[12] P
+uint32 binning_x
+uint32 binning_y
+RegionOfInterest roi
+================================================================================
+MSG: std_msgs/Header
+uint32 seq
+time stamp
+string frame_id
+================================================================================
+MSG: sensor_msgs/RegionOfInterest
+uint32 x_offset
+uint32 y_offset
+uint32 height
+uint32 width
+bool do_rectify
+";
+
+const ROS1_MARKER: &str = "\
+uint8 ARROW=0
+uint8 SPHERE=2
+uint8 ADD=0
+Header header
+string ns
+int32 id
+int32 type
+int32 action
+geometry_msgs/Pose pose
+geometry_msgs/Vector3 scale
+std_msgs/ColorRGBA color
+duration lifetime
+bool frame_locked
+geometry_msgs/Point[] points
+std_msgs/ColorRGBA[] colors
+string text
+string mesh_resource
+bool mesh_use_embedded_materials
+================================================================================
+MSG: std_msgs/Header
+uint32 seq
+time stamp
+string frame_id
+================================================================================
+MSG: geometry_msgs/Pose
+Point position
+Quaternion orientation
+================================================================================
+MSG: geometry_msgs/Point
+float64 x
+float64 y
+float64 z
+================================================================================
+MSG: geometry_msgs/Quaternion
+float64 x
+float64 y
+float64 z
+float64 w
+================================================================================
+MSG: geometry_msgs/Vector3
+float64 x
+float64 y
+float64 z
+================================================================================
+MSG: std_msgs/ColorRGBA
+float32 r
+float32 g
+float32 b
+float32 a
+";
+
+const ROS1_STATUS: &str = "\
+string LABEL=a # constants are not serialized
+Header header
+time last_update
+duration timeout
+string[] names
+uint8[4] flags
+================================================================================
+MSG: std_msgs/Header
+uint32 seq
+time stamp
+string frame_id
+";
+
+/// Custom message, whose ROS1 definition is converted with the ROS2 `std_msgs/Header`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Status {
+    header: std_msgs::Header,
+    last_update: builtin_msgs::Time,
+    timeout: builtin_msgs::Duration,
+    names: Vec<String>,
+    flags: [u8; 4],
+}
+
+impl RosMessage for Status {
+    const SCHEMA_NAME: &'static str = "my_msgs/msg/Status";
+    const DEFINITION: &'static str = "";
+}
+
+fn header_date_time() -> chrono::DateTime<chrono::Utc> {
+    start_date_time() + chrono::Duration::nanoseconds(123456789)
+}
+
+fn diagonal(size: usize, value: f64) -> Vec<f64> {
+    (0..size * size)
+        .map(|i| if i % (size + 1) == 0 { value } else { 0.0 })
+        .collect()
+}
+
+/// Encodes the IMU message of the CDR fixture.
+fn imu() -> Vec<u8> {
+    Ros1Encoder::default()
+        .header(42, header_date_time(), "imu_link")
+        .f64s(&[0.0, 0.0, 0.6, 0.8])
+        .f64s(&diagonal(3, 0.01))
+        .f64s(&[0.1, -0.2, 0.3])
+        .f64s(&diagonal(3, 0.02))
+        .f64s(&[0.5, 0.0, 9.81])
+        .f64s(&diagonal(3, 0.04))
+        .finish()
+}
+
+/// Encodes the camera info message of the CDR fixture.
+fn camera_info() -> Vec<u8> {
+    Ros1Encoder::default()
+        .header(0, header_date_time(), "camera")
+        .u32(480)
+        .u32(640)
+        .string("plumb_bob")
+        .u32(5)
+        .f64s(&[0.1, -0.2, 0.001, 0.002, 0.0])
+        .f64s(&[500.0, 0.0, 320.0, 0.0, 500.0, 240.0, 0.0, 0.0, 1.0])
+        .f64s(&[1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0])
+        .f64s(&[
+            500.0, 0.0, 320.0, 0.0, 0.0, 500.0, 240.0, 0.0, 0.0, 0.0, 1.0, 0.0,
+        ])
+        .u32(0)
+        .u32(0)
+        .u32(0)
+        .u32(0)
+        .u32(0)
+        .u32(0)
+        .bool(false)
+        .finish()
+}
+
+/// Encodes the marker message of the CDR fixture, which lacks the fields added in ROS2.
+fn marker() -> Vec<u8> {
+    Ros1Encoder::default()
+        .header(7, header_date_time(), "map")
+        .string("test")
+        .i32(7)
+        .i32(2)
+        .i32(0)
+        .f64s(&[1.0, 2.0, 3.0, 0.0, 0.0, 0.0, 1.0])
+        .f64s(&[1.0, 1.0, 1.0])
+        .f32(1.0)
+        .f32(0.0)
+        .f32(0.0)
+        .f32(1.0)
+        .i32(2)
+        .i32(500000000)
+        .bool(true)
+        .u32(2)
+        .f64s(&[0.0, 0.0, 0.0, 1.0, 1.0, 1.0])
+        .u32(1)
+        .f32(0.0)
+        .f32(1.0)
+        .f32(0.0)
+        .f32(1.0)
+        .string("hello")
+        .string("")
+        .bool(false)
+        .finish()
+}
+
+fn status() -> Vec<u8> {
+    Ros1Encoder::default()
+        .header(1, start_date_time(), "base_link")
+        .time(date_time(250))
+        .i32(-1)
+        .i32(250000000)
+        .u32(2)
+        .string("left")
+        .string("right")
+        .u8(1)
+        .u8(2)
+        .u8(3)
+        .u8(255)
+        .finish()
+}
+
+fn connections() -> [Ros1TestConnection; 4] {
+    [
+        Ros1TestConnection {
+            topic: "/imu",
+            message_type: "sensor_msgs/Imu",
+            message_definition: ROS1_IMU,
+            latching: false,
+        },
+        Ros1TestConnection {
+            topic: "/camera_info",
+            message_type: "sensor_msgs/CameraInfo",
+            message_definition: ROS1_CAMERA_INFO,
+            latching: false,
+        },
+        Ros1TestConnection {
+            topic: "/marker",
+            message_type: "visualization_msgs/Marker",
+            message_definition: ROS1_MARKER,
+            latching: true,
+        },
+        Ros1TestConnection {
+            topic: "/status",
+            message_type: "my_msgs/Status",
+            message_definition: ROS1_STATUS,
+            latching: false,
+        },
+    ]
+}
+
+/// Creates a ROS1 bag with an IMU message every 100ms from 0ms and a camera info, marker and
+/// status message at 50ms.
+fn create_ros1_bag(bag: &SyntheticBag, compression: &str) -> std::path::PathBuf {
+    let mut messages: Vec<(u32, chrono::DateTime<chrono::Utc>, Vec<u8>)> =
+        (0..5).map(|i| (0, date_time(i * 100), imu())).collect();
+    messages.insert(1, (1, date_time(50), camera_info()));
+    messages.insert(2, (2, date_time(50), marker()));
+    messages.insert(3, (3, date_time(50), status()));
+
+    let file_path = bag.directory_path.join("drive.bag");
+    write_ros1_bag(&file_path, &connections(), &messages, compression, 3);
+    file_path
+}
+
+/// Checks that the converted message equals the decoded CDR fixture.
+fn assert_fixture_message<T: RosMessage + PartialEq + Debug>(rosbag: &Rosbag<impl McapSource>) {
+    let messages = rosbag.messages::<T>(&None, &None, &None).unwrap();
+    let expected = T::deserialize_cdr(&read_cdr_fixture(T::SCHEMA_NAME)).unwrap();
+    assert_eq!(
+        messages.first().map(|x| &x.message),
+        Some(&expected),
+        "message of {}",
+        T::SCHEMA_NAME
+    );
+}
+
+#[test]
+fn read_ros1_bag() {
+    for compression in ["none", "bz2", "lz4"] {
+        let bag = SyntheticBag::new(&format!("read_ros1_bag_{compression}"));
+        let ros1_bag = Ros1Bag::new(create_ros1_bag(&bag, compression)).unwrap();
+
+        assert_eq!(ros1_bag.connections().len(), 4);
+        assert_eq!(ros1_bag.connections()[&2].topic, "/marker");
+        assert!(ros1_bag.connections()[&2].latching);
+        assert_eq!(ros1_bag.chunk_infos().len(), 3);
+        assert_eq!(ros1_bag.get_message_count(), 8);
+        assert_eq!(ros1_bag.get_start_date_time(), Some(start_date_time()));
+        assert_eq!(ros1_bag.get_end_date_time(), Some(date_time(400)));
+
+        let messages = ros1_bag
+            .messages(&Some(date_time(50)), &Some(date_time(300)))
+            .unwrap();
+        assert_eq!(messages.len(), 5, "messages with compression {compression}");
+        assert_eq!(messages[4].log_date_time, date_time(200));
+        assert_eq!(messages[4].data, imu());
+    }
+}
+
+#[test]
+fn convert_supported_messages() {
+    let bag = SyntheticBag::new("convert_supported_messages");
+    let ros1_bag_path = create_ros1_bag(&bag, "lz4");
+    let rosbag = Rosbag::from_ros1_bag(&ros1_bag_path).unwrap();
+    assert_eq!(rosbag.get_file_ids(), HashSet::from(["drive".into()]));
+    // the bag is read in memory without writing files
+    assert_eq!(std::fs::read_dir(&bag.directory_path).unwrap().count(), 1);
+
+    let messages = rosbag
+        .messages::<sensor_msgs::Imu>(&None, &None, &None)
+        .unwrap();
+    assert_eq!(messages.len(), 5);
+    assert_eq!(messages[1].log_date_time, date_time(100));
+    assert_fixture_message::<sensor_msgs::Imu>(&rosbag);
+    assert_fixture_message::<sensor_msgs::CameraInfo>(&rosbag);
+    assert_fixture_message::<visualization_msgs::Marker>(&rosbag);
+}
+
+#[test]
+fn convert_custom_message() {
+    let bag = SyntheticBag::new("convert_custom_message");
+    let ros1_bag_path = create_ros1_bag(&bag, "none");
+    let directory_path = bag.directory_path.join("ros2");
+    std::fs::create_dir_all(&directory_path).unwrap();
+    Ros1Bag::new(&ros1_bag_path)
+        .unwrap()
+        .convert(directory_path.join("drive.mcap"))
+        .unwrap();
+    let rosbag = Rosbag::new(&directory_path).unwrap();
+
+    let messages = rosbag.messages::<Status>(&None, &None, &None).unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(
+        messages[0].message,
+        Status {
+            header: std_msgs::Header {
+                stamp: builtin_msgs::Time {
+                    sec: 1700000000,
+                    nanosec: 0,
+                },
+                frame_id: "base_link".to_string(),
+            },
+            last_update: builtin_msgs::Time {
+                sec: 1700000000,
+                nanosec: 250000000,
+            },
+            timeout: builtin_msgs::Duration {
+                sec: -1,
+                nanosec: 250000000,
+            },
+            names: vec!["left".to_string(), "right".to_string()],
+            flags: [1, 2, 3, 255],
+        }
+    );
+}
+
+#[test]
+fn ros2_schema_names() {
+    assert_eq!(
+        get_ros2_schema_name("sensor_msgs/PointCloud2"),
+        "sensor_msgs/msg/PointCloud2"
+    );
+    assert_eq!(
+        get_ros2_schema_name("tf/tfMessage"),
+        "tf2_msgs/msg/TFMessage"
+    );
+}
+
+#[test]
+fn invalid_ros1_bag() {
+    let bag = SyntheticBag::new("invalid_ros1_bag");
+    let file_path = bag.directory_path.join("invalid.bag");
+    std::fs::write(&file_path, b"#ROSBAG V1.2\n").unwrap();
+
+    assert!(matches!(
+        Ros1Bag::new(&file_path),
+        Err(Error::InvalidRos1Bag(_))
+    ));
+}
@@ -0,0 +1,430 @@
+//! Converts synthetic bags between the MCAP and SQLite3 storage formats and compares the messages
+//! and channels of the converted files with the original files.
+mod common;
+
+use common::{SyntheticBag, SyntheticChannel, date_time, imu, point_cloud, start_date_time};
+use erosbag_core::ros_messages::{RosMessage, sensor_msgs};
+use erosbag_core::storage::metadata::METADATA_FILE_NAME;
+use erosbag_core::storage::{
+    BagMetadata, McapCompression, StorageConversionOptions, StorageFormat, convert_storage,
+};
+use erosbag_core::topics::qos_profile::QualityOfServiceProfile;
+use erosbag_core::{Error, Rosbag};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const IMU_TOPIC: &str = "/imu";
+const LIDAR_TOPIC: &str = "/lidar";
+const IMU_TYPE_HASH: &str =
+    "RIHS01_7d9a00ff131080897a5ec7e26e315954b8eae3353c3f995c55faf71574000b5b";
+
+const METADATA: &str = "\
+rosbag2_bagfile_information:
+  version: 9
+  storage_identifier: mcap
+  duration:
+    nanoseconds: 1100000000
+  starting_time:
+    nanoseconds_since_epoch: 1700000000000000000
+  message_count: 16
+  topics_with_message_count:
+    - topic_metadata:
+        name: /imu
+        type: sensor_msgs/msg/Imu
+        serialization_format: cdr
+        offered_qos_profiles: \"\"
+        type_description_hash: RIHS01_7d9a00ff131080897a5ec7e26e315954b8eae3353c3f995c55faf71574000b5b
+      message_count: 12
+    - topic_metadata:
+        name: /lidar
+        type: sensor_msgs/msg/PointCloud2
+        serialization_format: cdr
+        offered_qos_profiles: \"\"
+        type_description_hash: \"\"
+      message_count: 4
+  compression_format: \"\"
+  compression_mode: \"\"
+  relative_file_paths:
+    - bag_0.mcap
+    - bag_1.mcap
+  files:
+    - path: bag_0.mcap
+      starting_time:
+        nanoseconds_since_epoch: 1700000000000000000
+      duration:
+        nanoseconds: 900000000
+      message_count: 14
+    - path: bag_1.mcap
+      starting_time:
+        nanoseconds_since_epoch: 1700000001000000000
+      duration:
+        nanoseconds: 100000000
+      message_count: 2
+  custom_data:
+    vehicle: test
+  ros_distro: jazzy
+";
+
+fn offered_qos_profiles() -> String {
+    serde_yaml::to_string(&vec![QualityOfServiceProfile::new_for_tf_topic()]).unwrap()
+}
+
+/// Creates a bag of two files:
+///
+/// - `bag_0`: 10 IMU messages every 100ms from 0ms with QoS profiles and type hash, and 4 point
+///   clouds every 200ms from 50ms on `/lidar`
+/// - `bag_1`: 2 IMU messages at 1000ms and 1100ms
+fn create_bag(name: &str) -> SyntheticBag {
+    let bag = SyntheticBag::new(name);
+    let imu_channel = |offsets: &[i64]| {
+        SyntheticChannel::new(
+            IMU_TOPIC,
+            offsets.iter().map(|x| (date_time(*x), imu(date_time(*x)))),
+        )
+        .with_metadata("offered_qos_profiles", &offered_qos_profiles())
+        .with_metadata("topic_type_hash", IMU_TYPE_HASH)
+    };
+    bag.write_file(
+        "bag_0",
+        &[
+            imu_channel(&(0..10).map(|i| i * 100).collect::<Vec<_>>()),
+            SyntheticChannel::new(
+                LIDAR_TOPIC,
+                [50, 250, 450, 650].map(|x| (date_time(x), point_cloud("lidar", date_time(x), 3))),
+            ),
+        ],
+    );
+    bag.write_file("bag_1", &[imu_channel(&[1000, 1100])]);
+    bag
+}
+
+/// Returns the topic, log time and data of the messages of an MCAP file in the stored order.
+fn read_mcap_messages(file_path: impl AsRef<Path>) -> Vec<(String, u64, Vec<u8>)> {
+    let data = std::fs::read(file_path).unwrap();
+    mcap::MessageStream::new(&data)
+        .unwrap()
+        .map(|x| {
+            let message = x.unwrap();
+            (
+                message.channel.topic.clone(),
+                message.log_time,
+                message.data.to_vec(),
+            )
+        })
+        .collect()
+}
+
+/// Returns the schema name, schema encoding, schema data and metadata of the channels per topic.
+#[allow(clippy::type_complexity)]
+fn read_mcap_channels(
+    file_path: impl AsRef<Path>,
+) -> BTreeMap<String, (String, String, Vec<u8>, BTreeMap<String, String>)> {
+    let data = std::fs::read(file_path).unwrap();
+    let summary = mcap::Summary::read(&data).unwrap().unwrap();
+    summary
+        .channels
+        .values()
+        .map(|x| {
+            let schema = x.schema.as_ref().unwrap();
+            (
+                x.topic.clone(),
+                (
+                    schema.name.clone(),
+                    schema.encoding.clone(),
+                    schema.data.to_vec(),
+                    x.metadata.clone(),
+                ),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn mcap_to_sqlite3_and_back() {
+    let bag = create_bag("storage_mcap_to_sqlite3");
+    let sqlite3_bag = SyntheticBag::new("storage_mcap_to_sqlite3_db3");
+    let mcap_bag = SyntheticBag::new("storage_mcap_to_sqlite3_mcap");
+
+    let metadata = convert_storage(
+        &bag.directory_path,
+        &sqlite3_bag.directory_path,
+        &StorageConversionOptions::new(StorageFormat::Sqlite3),
+    )
+    .unwrap();
+    let information = &metadata.rosbag2_bagfile_information;
+    assert_eq!(information.storage_identifier, "sqlite3");
+    assert_eq!(information.relative_file_paths, ["bag_0.db3", "bag_1.db3"]);
+    assert_eq!(information.message_count, 16);
+    assert_eq!(
+        information.starting_time.nanoseconds_since_epoch,
+        start_date_time().timestamp_nanos_opt().unwrap()
+    );
+    assert_eq!(information.duration.nanoseconds, 1_100_000_000);
+    assert_eq!(
+        information
+            .files
+            .iter()
+            .map(|x| (x.path.as_str(), x.message_count))
+            .collect::<Vec<_>>(),
+        vec![("bag_0.db3", 14), ("bag_1.db3", 2)]
+    );
+
+    let imu_topic = &information.topics_with_message_count[0];
+    assert_eq!(imu_topic.topic_metadata.name, IMU_TOPIC);
+    assert_eq!(imu_topic.topic_metadata.message_type, "sensor_msgs/msg/Imu");
+    assert_eq!(
+        imu_topic.topic_metadata.type_description_hash,
+        IMU_TYPE_HASH
+    );
+    assert_eq!(imu_topic.message_count, 12);
+    assert_eq!(
+        imu_topic
+            .topic_metadata
+            .parse_offered_qos_profiles()
+            .unwrap(),
+        vec![QualityOfServiceProfile::new_for_tf_topic()]
+    );
+    assert_eq!(
+        BagMetadata::from_directory(&sqlite3_bag.directory_path).unwrap(),
+        Some(metadata.clone())
+    );
+
+    let connection =
+        rusqlite::Connection::open(sqlite3_bag.directory_path.join("bag_0.db3")).unwrap();
+    let definition: String = connection
+        .query_row(
+            "SELECT encoded_message_definition FROM message_definitions WHERE topic_type = ?1",
+            [sensor_msgs::Imu::SCHEMA_NAME],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(definition, sensor_msgs::Imu::DEFINITION);
+    let message_count: i64 = connection
+        .query_row("SELECT COUNT(*) FROM messages", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(message_count, 14);
+
+    convert_storage(
+        &sqlite3_bag.directory_path,
+        &mcap_bag.directory_path,
+        &StorageConversionOptions::new(StorageFormat::Mcap),
+    )
+    .unwrap();
+    for current_file_name in ["bag_0.mcap", "bag_1.mcap"] {
+        let original_file_path = bag.directory_path.join(current_file_name);
+        let converted_file_path = mcap_bag.directory_path.join(current_file_name);
+        assert_eq!(
+            read_mcap_messages(&converted_file_path),
+            read_mcap_messages(&original_file_path)
+        );
+        assert_eq!(
+            read_mcap_channels(&converted_file_path),
+            read_mcap_channels(&original_file_path)
+        );
+    }
+
+    let rosbag = Rosbag::new(&mcap_bag.directory_path).unwrap();
+    let messages = rosbag
+        .messages::<sensor_msgs::Imu>(&None, &None, &None)
+        .unwrap();
+    assert_eq!(messages.len(), 12);
+    assert_eq!(messages[11].log_date_time, date_time(1100));
+}
+
+/// Returns the sequence, log time and publish time of the messages of an MCAP file.
+fn read_mcap_message_headers(file_path: impl AsRef<Path>) -> Vec<(u32, u64, u64)> {
+    let data = std::fs::read(file_path).unwrap();
+    mcap::MessageStream::new(&data)
+        .unwrap()
+        .map(|x| {
+            let message = x.unwrap();
+            (message.sequence, message.log_time, message.publish_time)
+        })
+        .collect()
+}
+
+/// Returns the metadata records of an MCAP file in the stored order.
+fn read_mcap_metadata(file_path: impl AsRef<Path>) -> Vec<mcap::records::Metadata> {
+    let data = std::fs::read(file_path).unwrap();
+    mcap::read::LinearReader::new(&data)
+        .unwrap()
+        .filter_map(|x| match x.unwrap() {
+            mcap::records::Record::Metadata(metadata) => Some(metadata),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn message_headers_and_metadata_records_round_trip() {
+    let bag = SyntheticBag::new("storage_headers");
+    let sqlite3_bag = SyntheticBag::new("storage_headers_db3");
+    let mcap_bag = SyntheticBag::new("storage_headers_mcap");
+
+    let metadata_record = mcap::records::Metadata {
+        name: "vehicle".to_string(),
+        metadata: BTreeMap::from([
+            ("name".to_string(), "test".to_string()),
+            ("sensors".to_string(), "imu".to_string()),
+        ]),
+    };
+    let file = std::fs::File::create(bag.directory_path.join("bag_0.mcap")).unwrap();
+    let mut writer = mcap::WriteOptions::new()
+        .create(std::io::BufWriter::new(file))
+        .unwrap();
+    let schema_id = writer
+        .add_schema(
+            sensor_msgs::Imu::SCHEMA_NAME,
+            "ros2msg",
+            sensor_msgs::Imu::DEFINITION.as_bytes(),
+        )
+        .unwrap();
+    let channel_id = writer
+        .add_channel(schema_id, IMU_TOPIC, "cdr", &BTreeMap::new())
+        .unwrap();
+    for (sequence, offset) in [(7, 0), (3, 100), (42, 200)] {
+        let log_time = date_time(offset).timestamp_nanos_opt().unwrap() as u64;
+        let data =
+            cdr::serialize::<_, _, cdr::CdrLe>(&imu(date_time(offset)), cdr::Infinite).unwrap();
+        writer
+            .write_to_known_channel(
+                &mcap::records::MessageHeader {
+                    channel_id,
+                    sequence,
+                    log_time,
+                    publish_time: log_time - 5_000_000,
+                },
+                &data,
+            )
+            .unwrap();
+    }
+    writer.write_metadata(&metadata_record).unwrap();
+    writer.finish().unwrap();
+
+    convert_storage(
+        &bag.directory_path,
+        &sqlite3_bag.directory_path,
+        &StorageConversionOptions::new(StorageFormat::Sqlite3),
+    )
+    .unwrap();
+    convert_storage(
+        &sqlite3_bag.directory_path,
+        &mcap_bag.directory_path,
+        &StorageConversionOptions::new(StorageFormat::Mcap),
+    )
+    .unwrap();
+
+    let original_file_path = bag.directory_path.join("bag_0.mcap");
+    let converted_file_path = mcap_bag.directory_path.join("bag_0.mcap");
+    assert_eq!(
+        read_mcap_message_headers(&converted_file_path),
+        read_mcap_message_headers(&original_file_path)
+    );
+    assert_eq!(
+        read_mcap_messages(&converted_file_path),
+        read_mcap_messages(&original_file_path)
+    );
+    assert_eq!(
+        read_mcap_metadata(&converted_file_path),
+        vec![metadata_record]
+    );
+}
+
+#[test]
+fn metadata_is_preserved() {
+    let bag = create_bag("storage_metadata");
+    std::fs::write(bag.directory_path.join(METADATA_FILE_NAME), METADATA).unwrap();
+    let sqlite3_bag = SyntheticBag::new("storage_metadata_db3");
+
+    let metadata = convert_storage(
+        &bag.directory_path,
+        &sqlite3_bag.directory_path,
+        &StorageConversionOptions::new(StorageFormat::Sqlite3),
+    )
+    .unwrap();
+
+    let mut expected_metadata = BagMetadata::from_directory(&bag.directory_path)
+        .unwrap()
+        .unwrap();
+    let information = &mut expected_metadata.rosbag2_bagfile_information;
+    information.storage_identifier = "sqlite3".to_string();
+    information.relative_file_paths = vec!["bag_0.db3".to_string(), "bag_1.db3".to_string()];
+    information.files[0].path = "bag_0.db3".to_string();
+    information.files[1].path = "bag_1.db3".to_string();
+    assert_eq!(metadata, expected_metadata);
+    assert_eq!(
+        metadata.rosbag2_bagfile_information.other["custom_data"]["vehicle"],
+        "test"
+    );
+
+    let connection =
+        rusqlite::Connection::open(sqlite3_bag.directory_path.join("bag_1.db3")).unwrap();
+    let ros_distro: String = connection
+        .query_row("SELECT ros_distro FROM schema", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(ros_distro, "jazzy");
+    let stored_metadata: String = connection
+        .query_row("SELECT metadata FROM metadata", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(
+        serde_yaml::from_str::<BagMetadata>(&stored_metadata).unwrap(),
+        metadata
+    );
+}
+
+#[test]
+fn mcap_compression_and_chunk_size() {
+    let bag = create_bag("storage_compression");
+
+    for current_compression in [
+        McapCompression::None,
+        McapCompression::Lz4,
+        McapCompression::Zstd,
+    ] {
+        let mcap_bag = SyntheticBag::new(&format!("storage_compression_{current_compression}"));
+        let options = StorageConversionOptions::new(StorageFormat::Mcap)
+            .with_compression(current_compression)
+            .with_chunk_size(1024);
+        convert_storage(&bag.directory_path, &mcap_bag.directory_path, &options).unwrap();
+
+        let converted_file_path = mcap_bag.directory_path.join("bag_0.mcap");
+        assert_eq!(
+            read_mcap_messages(&converted_file_path),
+            read_mcap_messages(bag.directory_path.join("bag_0.mcap"))
+        );
+        let summary = mcap::Summary::read(&std::fs::read(&converted_file_path).unwrap())
+            .unwrap()
+            .unwrap();
+        assert!(summary.chunk_indexes.len() > 1);
+        let expected_compression = match current_compression {
+            McapCompression::None => "",
+            _ => current_compression.as_str(),
+        };
+        assert!(
+            summary
+                .chunk_indexes
+                .iter()
+                .all(|x| x.compression == expected_compression)
+        );
+    }
+}
+
+#[test]
+fn invalid_storage_conversion() {
+    let bag = SyntheticBag::new("storage_invalid");
+    let output_bag = SyntheticBag::new("storage_invalid_output");
+    let options = StorageConversionOptions::new(StorageFormat::Sqlite3);
+
+    let result = convert_storage(
+        bag.directory_path.join("missing"),
+        &output_bag.directory_path,
+        &options,
+    );
+    assert!(matches!(result, Err(Error::RosbagPathIsNoDirectory)));
+
+    let result = convert_storage(&bag.directory_path, &output_bag.directory_path, &options);
+    assert!(matches!(result, Err(Error::ContainsNoRosbagFile)));
+
+    let result = convert_storage(&bag.directory_path, &bag.directory_path, &options);
+    assert!(matches!(result, Err(Error::SameInputAndOutputDirectory)));
+}
@@ -0,0 +1,285 @@
+//! Synchronizes the channels of synthetic bags with known header stamps by exact and approximate
+//! time.
+mod common;
+
+use chrono::Duration;
+use common::{SyntheticBag, SyntheticChannel, date_time, imu, point_cloud};
+use erosbag_core::ros_messages::sensor_msgs;
+use erosbag_core::sync::{SyncPolicy, SyncStatistics, SynchronizedGroup};
+use erosbag_core::{ChannelTopic, Error, Rosbag};
+use std::collections::BTreeMap;
+
+const IMU_TOPIC: &str = "/imu";
+const IMU_REAR_TOPIC: &str = "/imu_rear";
+const LIDAR_TOPIC: &str = "/lidar";
+
+/// Returns the groups and the final statistics of synchronizing the channels.
+fn synchronize(
+    rosbag: &Rosbag,
+    channel_topics: &[&str],
+    policy: SyncPolicy,
+) -> (Vec<SynchronizedGroup>, SyncStatistics) {
+    let mut synchronizer = rosbag
+        .synchronize(
+            channel_topics
+                .iter()
+                .map(|x| ChannelTopic::from(*x))
+                .collect(),
+            policy,
+            &None,
+            &None,
+        )
+        .unwrap();
+    let groups = synchronizer
+        .by_ref()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    (groups, synchronizer.statistics().clone())
+}
+
+fn counts(entries: &[(&str, usize)]) -> BTreeMap<ChannelTopic, usize> {
+    entries
+        .iter()
+        .map(|(topic, count)| (ChannelTopic::from(*topic), *count))
+        .collect()
+}
+
+/// Returns the header stamps of the group in milliseconds after the start.
+fn stamps(group: &SynchronizedGroup) -> Vec<i64> {
+    group
+        .messages
+        .iter()
+        .map(|x| {
+            let stamp = x.message.header().unwrap().stamp;
+            (chrono::DateTime::from(stamp) - date_time(0)).num_milliseconds()
+        })
+        .collect()
+}
+
+#[test]
+fn exact_time_matches_identical_stamps() {
+    let bag = SyntheticBag::new("sync_exact_time");
+    bag.write_file(
+        "bag_0",
+        &[
+            SyntheticChannel::new(
+                IMU_TOPIC,
+                (0..10).map(|i| (date_time(i * 100), imu(date_time(i * 100)))),
+            ),
+            SyntheticChannel::new(
+                IMU_REAR_TOPIC,
+                (0..5).map(|i| (date_time(i * 200), imu(date_time(i * 200)))),
+            ),
+        ],
+    );
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    let (groups, statistics) = synchronize(
+        &rosbag,
+        &[IMU_REAR_TOPIC, IMU_TOPIC],
+        SyncPolicy::ExactTime { queue_size: 10 },
+    );
+
+    assert_eq!(
+        groups.iter().map(stamps).collect::<Vec<_>>(),
+        (0..5).map(|i| vec![i * 200, i * 200]).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        groups[1].messages[0].channel_topic,
+        ChannelTopic::from(IMU_REAR_TOPIC)
+    );
+    assert_eq!(groups[1].start_date_time(), Some(date_time(200)));
+    assert_eq!(
+        statistics,
+        SyncStatistics {
+            matched_groups: 5,
+            received_messages: counts(&[(IMU_TOPIC, 10), (IMU_REAR_TOPIC, 5)]),
+            unmatched_messages: counts(&[(IMU_TOPIC, 5)]),
+        }
+    );
+}
+
+#[test]
+fn exact_time_drops_stamps_exceeding_the_queue() {
+    // the messages of the rear IMU are logged after all messages of the front IMU
+    let bag = SyntheticBag::new("sync_exact_time_queue");
+    bag.write_file(
+        "bag_0",
+        &[
+            SyntheticChannel::new(
+                IMU_TOPIC,
+                (0..3).map(|i| (date_time(i * 100), imu(date_time(i * 100)))),
+            ),
+            SyntheticChannel::new(
+                IMU_REAR_TOPIC,
+                (0..3).map(|i| (date_time(250 + i * 10), imu(date_time(i * 100)))),
+            ),
+        ],
+    );
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    let (groups, _) = synchronize(
+        &rosbag,
+        &[IMU_TOPIC, IMU_REAR_TOPIC],
+        SyncPolicy::ExactTime { queue_size: 3 },
+    );
+    assert_eq!(groups.len(), 3);
+
+    let (groups, statistics) = synchronize(
+        &rosbag,
+        &[IMU_TOPIC, IMU_REAR_TOPIC],
+        SyncPolicy::ExactTime { queue_size: 1 },
+    );
+    assert_eq!(
+        groups.iter().map(stamps).collect::<Vec<_>>(),
+        vec![vec![200, 200]]
+    );
+    assert_eq!(
+        statistics.unmatched_messages,
+        counts(&[(IMU_TOPIC, 2), (IMU_REAR_TOPIC, 2)])
+    );
+}
+
+#[test]
+fn approximate_time_matches_stamps_within_the_slop() {
+    let bag = SyntheticBag::new("sync_approximate_time");
+    bag.write_file(
+        "bag_0",
+        &[
+            SyntheticChannel::new(
+                IMU_TOPIC,
+                (0..10).map(|i| (date_time(i * 100), imu(date_time(i * 100)))),
+            ),
+            SyntheticChannel::new(
+                LIDAR_TOPIC,
+                (0..5).map(|i| {
+                    let date_time = date_time(10 + i * 200);
+                    (date_time, point_cloud("lidar", date_time, 3))
+                }),
+            ),
+        ],
+    );
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    let (groups, statistics) = synchronize(
+        &rosbag,
+        &[LIDAR_TOPIC, IMU_TOPIC],
+        SyncPolicy::ApproximateTime {
+            slop: Duration::milliseconds(20),
+            queue_size: 10,
+        },
+    );
+    assert_eq!(
+        groups.iter().map(stamps).collect::<Vec<_>>(),
+        (0..5)
+            .map(|i| vec![10 + i * 200, i * 200])
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        groups[0].end_date_time().unwrap() - groups[0].start_date_time().unwrap(),
+        Duration::milliseconds(10)
+    );
+    assert_eq!(
+        statistics,
+        SyncStatistics {
+            matched_groups: 5,
+            received_messages: counts(&[(IMU_TOPIC, 10), (LIDAR_TOPIC, 5)]),
+            unmatched_messages: counts(&[(IMU_TOPIC, 5)]),
+        }
+    );
+
+    // no pair of messages lies within a slop below the offset of the point clouds
+    let (groups, statistics) = synchronize(
+        &rosbag,
+        &[LIDAR_TOPIC, IMU_TOPIC],
+        SyncPolicy::ApproximateTime {
+            slop: Duration::milliseconds(5),
+            queue_size: 10,
+        },
+    );
+    assert!(groups.is_empty());
+    assert_eq!(statistics.matched_groups, 0);
+    assert_eq!(
+        statistics.unmatched_messages,
+        counts(&[(IMU_TOPIC, 10), (LIDAR_TOPIC, 5)])
+    );
+}
+
+#[test]
+fn typed_messages_of_other_schemas_are_skipped() {
+    let bag = SyntheticBag::new("sync_typed_messages");
+    bag.write_file(
+        "bag_0",
+        &[
+            SyntheticChannel::new(
+                IMU_TOPIC,
+                (0..3).map(|i| (date_time(i * 100), imu(date_time(i * 100)))),
+            ),
+            SyntheticChannel::new(
+                IMU_REAR_TOPIC,
+                (0..3).map(|i| (date_time(i * 100), imu(date_time(i * 100)))),
+            ),
+            SyntheticChannel::new(
+                LIDAR_TOPIC,
+                (0..3).map(|i| {
+                    let date_time = date_time(i * 100);
+                    (date_time, point_cloud("lidar", date_time, 3))
+                }),
+            ),
+        ],
+    );
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    let groups: Vec<SynchronizedGroup<sensor_msgs::Imu>> = rosbag
+        .synchronize(
+            vec![IMU_TOPIC.into(), IMU_REAR_TOPIC.into()],
+            SyncPolicy::ExactTime { queue_size: 10 },
+            &None,
+            &None,
+        )
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(groups.len(), 3);
+    assert_eq!(groups[2].start_date_time(), Some(date_time(200)));
+
+    // the point clouds are not decoded as IMU messages, so that no group is complete
+    let mut synchronizer = rosbag
+        .synchronize::<sensor_msgs::Imu>(
+            vec![IMU_TOPIC.into(), LIDAR_TOPIC.into()],
+            SyncPolicy::ExactTime { queue_size: 10 },
+            &None,
+            &None,
+        )
+        .unwrap();
+    assert_eq!(synchronizer.by_ref().count(), 0);
+    assert_eq!(
+        synchronizer.statistics().received_messages,
+        counts(&[(IMU_TOPIC, 3)])
+    );
+}
+
+#[test]
+fn empty_queue_is_rejected() {
+    let bag = SyntheticBag::new("sync_empty_queue");
+    bag.write_file(
+        "bag_0",
+        &[SyntheticChannel::new(
+            IMU_TOPIC,
+            [(date_time(0), imu(date_time(0)))],
+        )],
+    );
+    let rosbag = Rosbag::new(&bag.directory_path).unwrap();
+
+    for policy in [
+        SyncPolicy::ExactTime { queue_size: 0 },
+        SyncPolicy::ApproximateTime {
+            slop: Duration::milliseconds(20),
+            queue_size: 0,
+        },
+    ] {
+        let result =
+            rosbag.synchronize::<sensor_msgs::Imu>(vec![IMU_TOPIC.into()], policy, &None, &None);
+        assert!(matches!(result, Err(Error::InvalidSyncPolicy(_))));
+    }
+}
@@ -0,0 +1,313 @@
+use crate::Error;
+use chrono::{DateTime, Utc};
+use ecoord::{FrameId, TransformId, TransformTree};
+use erosbag_core::dto::McapMessageMeta;
+use erosbag_core::ros_messages::{RosMessageType, sensor_msgs};
+use erosbag_core::sync::get_closest;
+use erosbag_core::{ChannelTopic, RosPointDataColumnType, Rosbag};
+use nalgebra::{Isometry3, Point3};
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tracing::{info, warn};
+
+/// Image and camera info channels of a calibrated camera.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CameraChannels {
+    pub image_channel_topic: ChannelTopic,
+    pub camera_info_channel_topic: ChannelTopic,
+}
+
+impl CameraChannels {
+    pub fn new(image_channel_topic: ChannelTopic, camera_info_channel_topic: ChannelTopic) -> Self {
+        Self {
+            image_channel_topic,
+            camera_info_channel_topic,
+        }
+    }
+}
+
+/// Returns the point clouds of optionally selected channels for a time window between
+/// start_date_time (inclusive) and end_date_time (exclusive), where each point is colored from
+/// the temporally closest image of the cameras.
+///
+/// Points are projected with the camera info's intrinsics and plumb bob distortion, while the
+/// extrinsics are taken from the transform tree. Points behind a camera or occluded by closer
+/// points of the same message are not colored by this camera. If multiple cameras see a point,
+/// the closest camera is used. Images with an unsupported encoding are skipped with a warning.
+///
+/// The occlusion test only compares points projected to the same pixel, so that a sparse
+/// foreground surface does not hide the background points projected between its points, which
+/// therefore take the foreground's color. The index of the camera is written to the
+/// [`RosPointDataColumnType::RosCameraId`] column starting at 1, while uncolored points get 0.
+#[allow(clippy::too_many_arguments)]
+pub fn colorize_point_clouds(
+    rosbag: &Rosbag,
+    start_date_time: &Option<DateTime<Utc>>,
+    end_date_time: &Option<DateTime<Utc>>,
+    point_cloud_channel_topics: &Option<HashSet<ChannelTopic>>,
+    cameras: &[CameraChannels],
+    transform_tree: &TransformTree,
+    max_time_difference: chrono::Duration,
+    occlusion_tolerance: f64,
+) -> Result<epoint::PointCloud, Error> {
+    let point_cloud_channel_topics: HashSet<ChannelTopic> = match point_cloud_channel_topics {
+        Some(channel_topics) => channel_topics.clone(),
+        None => rosbag
+            .get_overview()?
+            .get_channel_topics_of_message_type(RosMessageType::SensorMessagesPointCloud2),
+    };
+    let camera_channel_topics = cameras.iter().flat_map(|x| {
+        [
+            x.image_channel_topic.clone(),
+            x.camera_info_channel_topic.clone(),
+        ]
+    });
+    let channel_topics: HashSet<ChannelTopic> = point_cloud_channel_topics
+        .iter()
+        .cloned()
+        .chain(camera_channel_topics)
+        .collect();
+    let page = rosbag.get_message_page(start_date_time, end_date_time, &channel_topics)?;
+
+    let camera_models: Vec<CameraModel> = cameras
+        .iter()
+        .map(|x| CameraModel::new(&page, x))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let mut point_cloud_messages: Vec<McapMessageMeta<sensor_msgs::PointCloud2>> = Vec::new();
+    for current_channel_topic in &point_cloud_channel_topics {
+        point_cloud_messages.extend(
+            page.get_messages_of_channel::<sensor_msgs::PointCloud2>(current_channel_topic)?
+                .into_iter()
+                .flatten()
+                .filter(|x| {
+                    start_date_time.is_none_or(|start| start <= x.log_date_time)
+                        && end_date_time.is_none_or(|end| x.log_date_time < end)
+                }),
+        );
+    }
+    info!(
+        "Colorizing {} point cloud messages with {} cameras",
+        point_cloud_messages.len(),
+        camera_models.len()
+    );
+
+    let point_clouds: Vec<epoint::PointCloud> = point_cloud_messages
+        .into_par_iter()
+        .map(|x| {
+            colorize_point_cloud(
+                &x.message,
+                &camera_models,
+                transform_tree,
+                max_time_difference,
+                occlusion_tolerance,
+            )
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let mut point_cloud = epoint::transform::merge(point_clouds)?;
+    point_cloud.transform_tree = transform_tree.clone();
+    Ok(point_cloud)
+}
+
+fn colorize_point_cloud(
+    message: &sensor_msgs::PointCloud2,
+    camera_models: &[CameraModel],
+    transform_tree: &TransformTree,
+    max_time_difference: chrono::Duration,
+    occlusion_tolerance: f64,
+) -> Result<epoint::PointCloud, Error> {
+    let date_time: DateTime<Utc> = message.header.stamp.into();
+    let point_frame_id: FrameId = message.header.frame_id.clone().into();
+    let points = message.get_points();
+
+    // per point: (camera index, depth, rgb)
+    let mut best_matches: Vec<Option<(usize, f64, [u8; 3])>> = vec![None; points.len()];
+    for (camera_index, camera_model) in camera_models.iter().enumerate() {
+        let Some((image_date_time, image)) = camera_model.get_closest_image(date_time) else {
+            continue;
+        };
+        if (image_date_time - date_time).abs() > max_time_difference {
+            continue;
+        }
+        let Some(camera_info) = camera_model.get_closest_camera_info(image_date_time) else {
+            continue;
+        };
+
+        let transform_id =
+            TransformId::new(image.header.frame_id.clone().into(), point_frame_id.clone());
+        let isometry: Isometry3<f64> =
+            match transform_tree.get_transform_at_time(&transform_id, date_time) {
+                Ok(transform) => transform.isometry(),
+                Err(error) => {
+                    warn!(
+                        "No transform available from {} to {}: {error}",
+                        transform_id.child_frame_id, transform_id.parent_frame_id
+                    );
+                    continue;
+                }
+            };
+        let rgb_values = match image.get_rgb_values() {
+            Ok(rgb_values) => rgb_values,
+            Err(error) => {
+                warn!(
+                    "Skipping image of {} at {image_date_time}: {error}",
+                    image.header.frame_id
+                );
+                continue;
+            }
+        };
+
+        let projections: Vec<Option<(u32, u32, f64)>> = points
+            .iter()
+            .map(|p| project(&isometry.transform_point(p), camera_info))
+            .collect();
+        let visible = get_visible(&projections, occlusion_tolerance);
+
+        for (point_index, projection) in projections.iter().enumerate() {
+            let Some((u, v, depth)) = projection else {
+                continue;
+            };
+            if !visible[point_index] {
+                continue;
+            }
+            if best_matches[point_index].is_some_and(|(_, best_depth, _)| best_depth <= *depth) {
+                continue;
+            }
+
+            if *u >= image.width || *v >= image.height {
+                continue;
+            }
+            let rgb = rgb_values[(*v * image.width + *u) as usize];
+            best_matches[point_index] = Some((camera_index, *depth, rgb));
+        }
+    }
+
+    let color: Vec<palette::Srgb<u16>> = best_matches
+        .iter()
+        .map(|x| {
+            let [r, g, b] = x.map(|(_, _, rgb)| rgb).unwrap_or_default();
+            palette::Srgb::new(r as u16 * 257, g as u16 * 257, b as u16 * 257)
+        })
+        .collect();
+    let camera_id: Vec<u32> = best_matches
+        .iter()
+        .map(|x| x.map(|(i, _, _)| i as u32 + 1).unwrap_or(0))
+        .collect();
+
+    let mut point_cloud = message.to_point_cloud(Some(color));
+    point_cloud
+        .point_data
+        .add_u32_column(RosPointDataColumnType::RosCameraId.as_str(), camera_id)?;
+    Ok(point_cloud)
+}
+
+/// Projects a point given in the camera's optical frame into the image and returns the pixel
+/// coordinates together with the depth.
+fn project(point: &Point3<f64>, camera_info: &sensor_msgs::CameraInfo) -> Option<(u32, u32, f64)> {
+    if point.z <= f64::EPSILON {
+        return None;
+    }
+
+    let x = point.x / point.z;
+    let y = point.y / point.z;
+    let (x, y) = distort(x, y, camera_info);
+
+    let u = camera_info.fx() * x + camera_info.cx();
+    let v = camera_info.fy() * y + camera_info.cy();
+    if u < 0.0 || v < 0.0 || u >= camera_info.width as f64 || v >= camera_info.height as f64 {
+        return None;
+    }
+
+    Some((u as u32, v as u32, point.z))
+}
+
+/// Applies the plumb bob distortion model to normalized image coordinates.
+fn distort(x: f64, y: f64, camera_info: &sensor_msgs::CameraInfo) -> (f64, f64) {
+    if camera_info.distortion_model != "plumb_bob"
+        && camera_info.distortion_model != "rational_polynomial"
+    {
+        return (x, y);
+    }
+    let d = |i: usize| camera_info.d.get(i).copied().unwrap_or_default();
+    let (k1, k2, p1, p2, k3) = (d(0), d(1), d(2), d(3), d(4));
+    let (k4, k5, k6) = (d(5), d(6), d(7));
+
+    let r2 = x * x + y * y;
+    let r4 = r2 * r2;
+    let r6 = r4 * r2;
+    let radial = (1.0 + k1 * r2 + k2 * r4 + k3 * r6) / (1.0 + k4 * r2 + k5 * r4 + k6 * r6);
+
+    let distorted_x = x * radial + 2.0 * p1 * x * y + p2 * (r2 + 2.0 * x * x);
+    let distorted_y = y * radial + p1 * (r2 + 2.0 * y * y) + 2.0 * p2 * x * y;
+    (distorted_x, distorted_y)
+}
+
+/// Returns for each projected point, whether it is the closest one at its pixel within the
+/// occlusion tolerance.
+fn get_visible(projections: &[Option<(u32, u32, f64)>], occlusion_tolerance: f64) -> Vec<bool> {
+    let mut min_depth_per_pixel: HashMap<(u32, u32), f64> = HashMap::new();
+    for (u, v, depth) in projections.iter().flatten() {
+        let min_depth = min_depth_per_pixel.entry((*u, *v)).or_insert(*depth);
+        *min_depth = min_depth.min(*depth);
+    }
+
+    projections
+        .iter()
+        .map(|x| {
+            x.is_some_and(|(u, v, depth)| {
+                depth <= min_depth_per_pixel[&(u, v)] + occlusion_tolerance
+            })
+        })
+        .collect()
+}
+
+struct CameraModel {
+    images: BTreeMap<DateTime<Utc>, sensor_msgs::Image>,
+    camera_infos: BTreeMap<DateTime<Utc>, sensor_msgs::CameraInfo>,
+}
+
+impl CameraModel {
+    fn new(
+        page: &erosbag_core::dto::McapMessagePage,
+        camera: &CameraChannels,
+    ) -> Result<Self, Error> {
+        let images = page
+            .get_messages_of_channel::<sensor_msgs::Image>(&camera.image_channel_topic)?
+            .ok_or(erosbag_core::Error::ChannelDoesNotHold(
+                camera.image_channel_topic.clone(),
+                RosMessageType::SensorMessagesImage,
+            ))?
+            .into_iter()
+            .map(|x| (x.message.header.stamp.into(), x.message))
+            .collect();
+        let camera_infos = page
+            .get_messages_of_channel::<sensor_msgs::CameraInfo>(&camera.camera_info_channel_topic)?
+            .ok_or(erosbag_core::Error::ChannelDoesNotHold(
+                camera.camera_info_channel_topic.clone(),
+                RosMessageType::SensorMessagesCameraInfo,
+            ))?
+            .into_iter()
+            .map(|x| (x.message.header.stamp.into(), x.message))
+            .collect();
+
+        Ok(Self {
+            images,
+            camera_infos,
+        })
+    }
+
+    fn get_closest_image(
+        &self,
+        date_time: DateTime<Utc>,
+    ) -> Option<(DateTime<Utc>, &sensor_msgs::Image)> {
+        get_closest(&self.images, date_time)
+    }
+
+    fn get_closest_camera_info(
+        &self,
+        date_time: DateTime<Utc>,
+    ) -> Option<&sensor_msgs::CameraInfo> {
+        get_closest(&self.camera_infos, date_time).map(|x| x.1)
+    }
+}
@@ -6,4 +6,20 @@ pub enum Error {
     RosbagError(#[from] erosbag_core::Error),
     #[error(transparent)]
     EcoordError(#[from] ecoord::Error),
+    #[error(transparent)]
+    EpointError(#[from] epoint::Error),
+    #[error(transparent)]
+    EpointTransformError(#[from] epoint::transform::Error),
+
+    #[error(transparent)]
+    StdIoError(#[from] std::io::Error),
+    #[error(transparent)]
+    ImageError(#[from] image::ImageError),
+
+    #[error("no transforms or poses available for frame `{0}`")]
+    NoPosesForFrame(ecoord::FrameId),
+    #[error("sampling interval must be positive, got `{0}`")]
+    InvalidSamplingInterval(chrono::Duration),
+    #[error("image data does not match the dimensions of `{0}x{1}` pixels")]
+    InvalidImageDimensions(u32, u32),
 }
@@ -0,0 +1,569 @@
+use crate::Error;
+use crate::colorize::CameraChannels;
+use chrono::{DateTime, Utc};
+use ecoord::{FrameId, TransformId, TransformTree};
+use erosbag_core::dto::{McapMessageMeta, McapMessagePage};
+use erosbag_core::ros_messages::{RosMessageType, sensor_msgs};
+use erosbag_core::sync::get_closest;
+use erosbag_core::{ChannelTopic, Rosbag};
+use image::RgbImage;
+use nalgebra::{Isometry3, UnitQuaternion};
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use tracing::{info, warn};
+
+const VELODYNE_DIRECTORY_NAME: &str = "velodyne_points";
+const OXTS_DIRECTORY_NAME: &str = "oxts";
+const DATA_DIRECTORY_NAME: &str = "data";
+const TIMESTAMPS_FILE_NAME: &str = "timestamps.txt";
+const CALIB_CAM_TO_CAM_FILE_NAME: &str = "calib_cam_to_cam.txt";
+const CALIB_VELO_TO_CAM_FILE_NAME: &str = "calib_velo_to_cam.txt";
+const CALIB_IMU_TO_VELO_FILE_NAME: &str = "calib_imu_to_velo.txt";
+
+/// Field names of the KITTI oxts files.
+const OXTS_FIELD_NAMES: [&str; 30] = [
+    "lat",
+    "lon",
+    "alt",
+    "roll",
+    "pitch",
+    "yaw",
+    "vn",
+    "ve",
+    "vf",
+    "vl",
+    "vu",
+    "ax",
+    "ay",
+    "az",
+    "af",
+    "al",
+    "au",
+    "wx",
+    "wy",
+    "wz",
+    "wf",
+    "wl",
+    "wu",
+    "pos_accuracy",
+    "vel_accuracy",
+    "navstat",
+    "numsats",
+    "posmode",
+    "velmode",
+    "orimode",
+];
+
+/// Channels of the sensors exported to a KITTI raw dataset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KittiChannels {
+    /// `PointCloud2` channel of the lidar, which defines the frames of the dataset.
+    pub lidar_channel_topic: ChannelTopic,
+    /// Cameras in the order of the `image_XX` directories.
+    pub cameras: Vec<CameraChannels>,
+    pub nav_sat_fix_channel_topic: Option<ChannelTopic>,
+    pub imu_channel_topic: Option<ChannelTopic>,
+    /// Channels providing the transforms for the calibration files, where all channels of type
+    /// `tf2_msgs/msg/TFMessage` are used, if none are provided.
+    pub transform_channel_topics: Option<HashSet<ChannelTopic>>,
+}
+
+/// Exports the messages for a time window between start_date_time (inclusive) and end_date_time
+/// (exclusive) as KITTI raw dataset and returns the number of exported frames.
+///
+/// Each lidar scan is a frame, to which the temporally closest image of each camera and the
+/// closest `NavSatFix` and `Imu` messages are matched. Frames without a match within
+/// max_time_difference for any of the channels are skipped. The calibration files are derived
+/// from the camera infos and the transforms at the stamp of the first frame, where static
+/// transforms are also taken from before the time window.
+///
+/// The oxts roll, pitch and yaw are taken from the `Imu` orientation, if it is provided, while
+/// the velocities are written as zero.
+pub fn export_kitti_raw(
+    rosbag: &Rosbag,
+    start_date_time: &Option<DateTime<Utc>>,
+    end_date_time: &Option<DateTime<Utc>>,
+    channels: &KittiChannels,
+    max_time_difference: chrono::Duration,
+    output_directory_path: impl AsRef<Path>,
+) -> Result<usize, Error> {
+    let output_directory_path = output_directory_path.as_ref();
+    let channel_topics: HashSet<ChannelTopic> = [channels.lidar_channel_topic.clone()]
+        .into_iter()
+        .chain(channels.cameras.iter().flat_map(|x| {
+            [
+                x.image_channel_topic.clone(),
+                x.camera_info_channel_topic.clone(),
+            ]
+        }))
+        .chain(channels.nav_sat_fix_channel_topic.clone())
+        .chain(channels.imu_channel_topic.clone())
+        .collect();
+    let page = rosbag.get_message_page(start_date_time, end_date_time, &channel_topics)?;
+
+    let messages = KittiMessages::new(&page, channels)?;
+    let frames = match_frames(
+        &messages,
+        start_date_time,
+        end_date_time,
+        max_time_difference,
+    )?;
+    let Some(first_frame) = frames.first() else {
+        warn!("No frames with matches for all channels found");
+        return Ok(0);
+    };
+    info!("Exporting {} frames", frames.len());
+
+    let transform_tree = rosbag.get_transforms(
+        start_date_time,
+        end_date_time,
+        &channels.transform_channel_topics,
+    )?;
+    write_calibration(
+        &page,
+        channels,
+        first_frame,
+        &transform_tree,
+        output_directory_path,
+    )?;
+
+    let velodyne_directory_path = output_directory_path.join(VELODYNE_DIRECTORY_NAME);
+    fs::create_dir_all(velodyne_directory_path.join(DATA_DIRECTORY_NAME))?;
+    write_timestamps(
+        frames.iter().map(|x| x.lidar_date_time),
+        velodyne_directory_path.join(TIMESTAMPS_FILE_NAME),
+    )?;
+    frames
+        .par_iter()
+        .enumerate()
+        .try_for_each(|(index, frame)| {
+            write_velodyne_points(
+                frame.lidar,
+                velodyne_directory_path
+                    .join(DATA_DIRECTORY_NAME)
+                    .join(format!("{index:010}.bin")),
+            )
+        })?;
+
+    for camera_index in 0..channels.cameras.len() {
+        let image_directory_path = output_directory_path.join(format!("image_{camera_index:02}"));
+        fs::create_dir_all(image_directory_path.join(DATA_DIRECTORY_NAME))?;
+        write_timestamps(
+            frames.iter().map(|x| x.images[camera_index].0),
+            image_directory_path.join(TIMESTAMPS_FILE_NAME),
+        )?;
+        frames
+            .par_iter()
+            .enumerate()
+            .try_for_each(|(index, frame)| {
+                write_image(
+                    frame.images[camera_index].1,
+                    image_directory_path
+                        .join(DATA_DIRECTORY_NAME)
+                        .join(format!("{index:010}.png")),
+                )
+            })?;
+    }
+
+    if channels.nav_sat_fix_channel_topic.is_some() || channels.imu_channel_topic.is_some() {
+        let oxts_directory_path = output_directory_path.join(OXTS_DIRECTORY_NAME);
+        fs::create_dir_all(oxts_directory_path.join(DATA_DIRECTORY_NAME))?;
+        fs::write(
+            oxts_directory_path.join("dataformat.txt"),
+            OXTS_FIELD_NAMES.join("\n") + "\n",
+        )?;
+        write_timestamps(
+            frames.iter().map(|x| x.lidar_date_time),
+            oxts_directory_path.join(TIMESTAMPS_FILE_NAME),
+        )?;
+        for (index, frame) in frames.iter().enumerate() {
+            write_oxts(
+                frame.nav_sat_fix,
+                frame.imu,
+                oxts_directory_path
+                    .join(DATA_DIRECTORY_NAME)
+                    .join(format!("{index:010}.txt")),
+            )?;
+        }
+    }
+
+    Ok(frames.len())
+}
+
+struct Frame<'a> {
+    lidar_date_time: DateTime<Utc>,
+    lidar: &'a sensor_msgs::PointCloud2,
+    images: Vec<(DateTime<Utc>, &'a sensor_msgs::Image)>,
+    nav_sat_fix: Option<&'a sensor_msgs::NavSatFix>,
+    imu: Option<&'a sensor_msgs::Imu>,
+}
+
+/// Decoded messages of the channels of the KITTI raw dataset.
+struct KittiMessages {
+    lidar: Vec<McapMessageMeta<sensor_msgs::PointCloud2>>,
+    images: Vec<Vec<McapMessageMeta<sensor_msgs::Image>>>,
+    nav_sat_fixes: Option<Vec<McapMessageMeta<sensor_msgs::NavSatFix>>>,
+    imus: Option<Vec<McapMessageMeta<sensor_msgs::Imu>>>,
+}
+
+impl KittiMessages {
+    fn new(page: &McapMessagePage, channels: &KittiChannels) -> Result<Self, Error> {
+        let lidar = page
+            .get_messages_of_channel::<sensor_msgs::PointCloud2>(&channels.lidar_channel_topic)?
+            .ok_or(erosbag_core::Error::ChannelDoesNotHold(
+                channels.lidar_channel_topic.clone(),
+                RosMessageType::SensorMessagesPointCloud2,
+            ))?;
+        let images = channels
+            .cameras
+            .iter()
+            .map(|camera| {
+                page.get_messages_of_channel::<sensor_msgs::Image>(&camera.image_channel_topic)?
+                    .ok_or(erosbag_core::Error::ChannelDoesNotHold(
+                        camera.image_channel_topic.clone(),
+                        RosMessageType::SensorMessagesImage,
+                    ))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let nav_sat_fixes = channels
+            .nav_sat_fix_channel_topic
+            .as_ref()
+            .map(|x| page.get_messages_of_channel::<sensor_msgs::NavSatFix>(x))
+            .transpose()?
+            .map(Option::unwrap_or_default);
+        let imus = channels
+            .imu_channel_topic
+            .as_ref()
+            .map(|x| page.get_messages_of_channel::<sensor_msgs::Imu>(x))
+            .transpose()?
+            .map(Option::unwrap_or_default);
+
+        Ok(Self {
+            lidar,
+            images,
+            nav_sat_fixes,
+            imus,
+        })
+    }
+}
+
+fn match_frames<'a>(
+    messages: &'a KittiMessages,
+    start_date_time: &Option<DateTime<Utc>>,
+    end_date_time: &Option<DateTime<Utc>>,
+    max_time_difference: chrono::Duration,
+) -> Result<Vec<Frame<'a>>, Error> {
+    let lidar_messages: BTreeMap<DateTime<Utc>, &sensor_msgs::PointCloud2> = messages
+        .lidar
+        .iter()
+        .filter(|x| {
+            start_date_time.is_none_or(|start| start <= x.log_date_time)
+                && end_date_time.is_none_or(|end| x.log_date_time < end)
+        })
+        .map(|x| (x.message.header.stamp.into(), &x.message))
+        .collect();
+    let image_messages: Vec<BTreeMap<DateTime<Utc>, &sensor_msgs::Image>> = messages
+        .images
+        .iter()
+        .map(|x| {
+            x.iter()
+                .map(|x| (x.message.header.stamp.into(), &x.message))
+                .collect()
+        })
+        .collect();
+    let nav_sat_fix_messages: Option<BTreeMap<DateTime<Utc>, &sensor_msgs::NavSatFix>> =
+        messages.nav_sat_fixes.as_ref().map(|x| {
+            x.iter()
+                .map(|x| (x.message.header.stamp.into(), &x.message))
+                .collect()
+        });
+    let imu_messages: Option<BTreeMap<DateTime<Utc>, &sensor_msgs::Imu>> =
+        messages.imus.as_ref().map(|x| {
+            x.iter()
+                .map(|x| (x.message.header.stamp.into(), &x.message))
+                .collect()
+        });
+
+    let mut frames: Vec<Frame> = Vec::new();
+    for (lidar_date_time, lidar) in lidar_messages {
+        let images: Option<Vec<(DateTime<Utc>, &sensor_msgs::Image)>> = image_messages
+            .iter()
+            .map(|x| {
+                filter_within(
+                    get_closest(x, lidar_date_time).map(|(t, x)| (t, *x)),
+                    lidar_date_time,
+                    max_time_difference,
+                )
+            })
+            .collect();
+        let nav_sat_fix = nav_sat_fix_messages.as_ref().map(|x| {
+            filter_within(
+                get_closest(x, lidar_date_time).map(|(t, x)| (t, *x)),
+                lidar_date_time,
+                max_time_difference,
+            )
+        });
+        let imu = imu_messages.as_ref().map(|x| {
+            filter_within(
+                get_closest(x, lidar_date_time).map(|(t, x)| (t, *x)),
+                lidar_date_time,
+                max_time_difference,
+            )
+        });
+
+        let Some(images) = images else {
+            warn!("Skipping lidar scan at {lidar_date_time} without matching images");
+            continue;
+        };
+        if matches!(nav_sat_fix, Some(None)) || matches!(imu, Some(None)) {
+            warn!("Skipping lidar scan at {lidar_date_time} without matching oxts messages");
+            continue;
+        }
+
+        frames.push(Frame {
+            lidar_date_time,
+            lidar,
+            images,
+            nav_sat_fix: nav_sat_fix.flatten().map(|x| x.1),
+            imu: imu.flatten().map(|x| x.1),
+        });
+    }
+
+    Ok(frames)
+}
+
+fn filter_within<T>(
+    candidate: Option<(DateTime<Utc>, T)>,
+    date_time: DateTime<Utc>,
+    max_time_difference: chrono::Duration,
+) -> Option<(DateTime<Utc>, T)> {
+    candidate.filter(|(t, _)| (*t - date_time).abs() <= max_time_difference)
+}
+
+fn write_calibration(
+    page: &McapMessagePage,
+    channels: &KittiChannels,
+    first_frame: &Frame,
+    transform_tree: &TransformTree,
+    output_directory_path: &Path,
+) -> Result<(), Error> {
+    fs::create_dir_all(output_directory_path)?;
+    let date_time = first_frame.lidar_date_time;
+    let lidar_frame_id: FrameId = first_frame.lidar.header.frame_id.clone().into();
+    let camera_frame_ids: Vec<FrameId> = first_frame
+        .images
+        .iter()
+        .map(|(_, x)| x.header.frame_id.clone().into())
+        .collect();
+
+    if let Some(reference_camera_frame_id) = camera_frame_ids.first() {
+        let mut writer = BufWriter::new(File::create(
+            output_directory_path.join(CALIB_CAM_TO_CAM_FILE_NAME),
+        )?);
+        writeln!(writer, "calib_time: {}", format_date_time(date_time))?;
+        writeln!(writer, "corner_dist: 0.000000e+00")?;
+
+        for (camera_index, camera) in channels.cameras.iter().enumerate() {
+            let camera_info = page
+                .get_messages_of_channel::<sensor_msgs::CameraInfo>(
+                    &camera.camera_info_channel_topic,
+                )?
+                .and_then(|x| {
+                    x.into_iter()
+                        .min_by_key(|m| (m.log_date_time, m.message_id))
+                })
+                .map(|x| x.message)
+                .ok_or(erosbag_core::Error::ChannelDoesNotHold(
+                    camera.camera_info_channel_topic.clone(),
+                    RosMessageType::SensorMessagesCameraInfo,
+                ))?;
+            let extrinsics = transform_tree
+                .get_transform_at_time(
+                    &TransformId::new(
+                        camera_frame_ids[camera_index].clone(),
+                        reference_camera_frame_id.clone(),
+                    ),
+                    date_time,
+                )?
+                .isometry();
+            let distortion: Vec<f64> = (0..5)
+                .map(|i| camera_info.d.get(i).copied().unwrap_or_default())
+                .collect();
+            let size = [camera_info.width as f64, camera_info.height as f64];
+
+            writeln!(writer, "S_{camera_index:02}: {}", format_values(&size))?;
+            writeln!(
+                writer,
+                "K_{camera_index:02}: {}",
+                format_values(&camera_info.k)
+            )?;
+            writeln!(
+                writer,
+                "D_{camera_index:02}: {}",
+                format_values(&distortion)
+            )?;
+            write_isometry(&mut writer, &format!("_{camera_index:02}"), &extrinsics)?;
+            writeln!(writer, "S_rect_{camera_index:02}: {}", format_values(&size))?;
+            writeln!(
+                writer,
+                "R_rect_{camera_index:02}: {}",
+                format_values(&camera_info.r)
+            )?;
+            writeln!(
+                writer,
+                "P_rect_{camera_index:02}: {}",
+                format_values(&camera_info.p)
+            )?;
+        }
+
+        let velo_to_cam = transform_tree
+            .get_transform_at_time(
+                &TransformId::new(reference_camera_frame_id.clone(), lidar_frame_id.clone()),
+                date_time,
+            )?
+            .isometry();
+        let mut writer = BufWriter::new(File::create(
+            output_directory_path.join(CALIB_VELO_TO_CAM_FILE_NAME),
+        )?);
+        writeln!(writer, "calib_time: {}", format_date_time(date_time))?;
+        write_isometry(&mut writer, "", &velo_to_cam)?;
+        writeln!(writer, "delta_f: 0.000000e+00 0.000000e+00")?;
+        writeln!(writer, "delta_c: 0.000000e+00 0.000000e+00")?;
+    }
+
+    if let Some(imu) = first_frame.imu {
+        let imu_frame_id: FrameId = imu.header.frame_id.clone().into();
+        let imu_to_velo = transform_tree
+            .get_transform_at_time(&TransformId::new(lidar_frame_id, imu_frame_id), date_time)?
+            .isometry();
+        let mut writer = BufWriter::new(File::create(
+            output_directory_path.join(CALIB_IMU_TO_VELO_FILE_NAME),
+        )?);
+        writeln!(writer, "calib_time: {}", format_date_time(date_time))?;
+        write_isometry(&mut writer, "", &imu_to_velo)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the rotation and translation mapping points into the target frame as `R` and `T`.
+fn write_isometry(
+    writer: &mut impl Write,
+    suffix: &str,
+    isometry: &Isometry3<f64>,
+) -> Result<(), Error> {
+    let rotation = isometry.rotation.to_rotation_matrix();
+    let rotation_values: Vec<f64> = (0..3)
+        .flat_map(|row| (0..3).map(move |column| (row, column)))
+        .map(|(row, column)| rotation[(row, column)])
+        .collect();
+    let translation = isometry.translation.vector;
+
+    writeln!(writer, "R{suffix}: {}", format_values(&rotation_values))?;
+    writeln!(
+        writer,
+        "T{suffix}: {}",
+        format_values(&[translation.x, translation.y, translation.z])
+    )?;
+    Ok(())
+}
+
+fn write_timestamps(
+    date_times: impl Iterator<Item = DateTime<Utc>>,
+    path: impl AsRef<Path>,
+) -> Result<(), Error> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for current_date_time in date_times {
+        writeln!(writer, "{}", format_date_time(current_date_time))?;
+    }
+    Ok(())
+}
+
+/// Writes the points as little-endian `f32` values of x, y, z and intensity, where invalid points
+/// with non-finite coordinates are skipped.
+fn write_velodyne_points(
+    message: &sensor_msgs::PointCloud2,
+    path: impl AsRef<Path>,
+) -> Result<(), Error> {
+    let points = message.get_points();
+    let intensities: Vec<f32> = if message.fields.iter().any(|x| x.name == "intensity") {
+        message.get_field_as_f32("intensity")
+    } else {
+        vec![0.0; points.len()]
+    };
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    for (point, intensity) in points.iter().zip(intensities) {
+        if !(point.x.is_finite() && point.y.is_finite() && point.z.is_finite()) {
+            continue;
+        }
+        for value in [point.x as f32, point.y as f32, point.z as f32, intensity] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+fn write_image(message: &sensor_msgs::Image, path: impl AsRef<Path>) -> Result<(), Error> {
+    let rgb_values: Vec<u8> = message.get_rgb_values()?.into_iter().flatten().collect();
+    let image = RgbImage::from_raw(message.width, message.height, rgb_values)
+        .ok_or(Error::InvalidImageDimensions(message.width, message.height))?;
+    image.save(path)?;
+    Ok(())
+}
+
+fn write_oxts(
+    nav_sat_fix: Option<&sensor_msgs::NavSatFix>,
+    imu: Option<&sensor_msgs::Imu>,
+    path: impl AsRef<Path>,
+) -> Result<(), Error> {
+    let mut values = [0.0; 30];
+    if let Some(nav_sat_fix) = nav_sat_fix {
+        values[0] = nav_sat_fix.latitude;
+        values[1] = nav_sat_fix.longitude;
+        values[2] = nav_sat_fix.altitude;
+        values[23] =
+            (nav_sat_fix.position_covariance[0] + nav_sat_fix.position_covariance[4]).sqrt();
+    }
+    if let Some(imu) = imu {
+        let a = &imu.linear_acceleration;
+        let w = &imu.angular_velocity;
+        // a covariance of -1 marks an unknown orientation
+        if imu.orientation_covariance[0] >= 0.0 {
+            let (roll, pitch, yaw) = UnitQuaternion::from(imu.orientation).euler_angles();
+            values[3..6].copy_from_slice(&[roll, pitch, yaw]);
+        }
+        values[11..17].copy_from_slice(&[a.x, a.y, a.z, a.x, a.y, a.z]);
+        values[17..23].copy_from_slice(&[w.x, w.y, w.z, w.x, w.y, w.z]);
+    }
+
+    let line: Vec<String> = values
+        .iter()
+        .enumerate()
+        .map(|(i, x)| {
+            if i >= 25 {
+                (*x as i64).to_string()
+            } else {
+                x.to_string()
+            }
+        })
+        .collect();
+    fs::write(path, line.join(" ") + "\n")?;
+    Ok(())
+}
+
+fn format_date_time(date_time: DateTime<Utc>) -> String {
+    date_time.format("%Y-%m-%d %H:%M:%S%.9f").to_string()
+}
+
+fn format_values(values: &[f64]) -> String {
+    values
+        .iter()
+        .map(|x| format!("{x:.6e}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -1,4 +1,31 @@
+mod colorize;
 mod error;
+mod kitti;
+mod trajectory;
 
 #[doc(inline)]
 pub use error::Error;
+
+#[doc(inline)]
+pub use colorize::CameraChannels;
+
+#[doc(inline)]
+pub use colorize::colorize_point_clouds;
+
+#[doc(inline)]
+pub use kitti::KittiChannels;
+
+#[doc(inline)]
+pub use kitti::export_kitti_raw;
+
+#[doc(inline)]
+pub use trajectory::Trajectory;
+
+#[doc(inline)]
+pub use trajectory::TrajectoryFormat;
+
+#[doc(inline)]
+pub use trajectory::TrajectorySource;
+
+#[doc(inline)]
+pub use trajectory::get_trajectory;
@@ -0,0 +1,246 @@
+use crate::Error;
+use chrono::{DateTime, Utc};
+use ecoord::{FrameId, TransformId, TransformTree};
+use erosbag_core::ros_messages::RosMessageType;
+use erosbag_core::{ChannelTopic, Rosbag};
+use nalgebra::Isometry3;
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+const TRAJECTORY_FORMAT_TUM_STR: &str = "tum";
+const TRAJECTORY_FORMAT_KITTI_STR: &str = "kitti";
+const TRAJECTORY_FORMAT_EUROC_STR: &str = "euroc";
+
+/// File format for writing trajectories.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum TrajectoryFormat {
+    /// One line `timestamp tx ty tz qx qy qz qw` per pose with the timestamp in seconds.
+    Tum,
+    /// One line with the row-major 3x4 transformation matrix per pose without timestamps.
+    Kitti,
+    /// CSV file with the timestamp in nanoseconds, the position and the quaternion as `w x y z`.
+    Euroc,
+}
+
+impl TrajectoryFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrajectoryFormat::Tum => TRAJECTORY_FORMAT_TUM_STR,
+            TrajectoryFormat::Kitti => TRAJECTORY_FORMAT_KITTI_STR,
+            TrajectoryFormat::Euroc => TRAJECTORY_FORMAT_EUROC_STR,
+        }
+    }
+}
+
+impl fmt::Display for TrajectoryFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for TrajectoryFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            TRAJECTORY_FORMAT_TUM_STR => Ok(TrajectoryFormat::Tum),
+            TRAJECTORY_FORMAT_KITTI_STR => Ok(TrajectoryFormat::Kitti),
+            TRAJECTORY_FORMAT_EUROC_STR => Ok(TrajectoryFormat::Euroc),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Channels providing the poses of a trajectory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrajectorySource {
+    /// Transforms of tf channels, where all channels of type `tf2_msgs/msg/TFMessage` are used,
+    /// if none are provided.
+    Transforms(Option<HashSet<ChannelTopic>>),
+    /// Poses of a `PoseStamped`, `PoseWithCovarianceStamped`, `Odometry` or `Path` channel.
+    Poses(ChannelTopic),
+}
+
+/// Sequence of timed poses of a child frame expressed in a parent frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trajectory {
+    pub parent_frame_id: FrameId,
+    pub child_frame_id: FrameId,
+    pub poses: Vec<(DateTime<Utc>, Isometry3<f64>)>,
+}
+
+impl Trajectory {
+    /// Returns the trajectory with all poses expressed relative to the first pose.
+    pub fn relative_to_first(&self) -> Self {
+        let Some((_, first_pose)) = self.poses.first() else {
+            return self.clone();
+        };
+        let first_pose_inverse = first_pose.inverse();
+
+        Self {
+            parent_frame_id: self.parent_frame_id.clone(),
+            child_frame_id: self.child_frame_id.clone(),
+            poses: self
+                .poses
+                .iter()
+                .map(|(t, x)| (*t, first_pose_inverse * x))
+                .collect(),
+        }
+    }
+
+    /// Writes the trajectory in the provided format.
+    pub fn write(&self, format: TrajectoryFormat, mut writer: impl Write) -> Result<(), Error> {
+        if format == TrajectoryFormat::Euroc {
+            writeln!(
+                writer,
+                "#timestamp [ns],p_RS_R_x [m],p_RS_R_y [m],p_RS_R_z [m],q_RS_w [],q_RS_x [],q_RS_y [],q_RS_z []"
+            )?;
+        }
+
+        for (date_time, pose) in &self.poses {
+            let t = pose.translation.vector;
+            let q = pose.rotation.quaternion();
+
+            match format {
+                TrajectoryFormat::Tum => {
+                    let seconds = date_time.timestamp() as f64
+                        + date_time.timestamp_subsec_nanos() as f64 * 1e-9;
+                    writeln!(
+                        writer,
+                        "{:.9} {} {} {} {} {} {} {}",
+                        seconds, t.x, t.y, t.z, q.i, q.j, q.k, q.w
+                    )?;
+                }
+                TrajectoryFormat::Kitti => {
+                    let m = pose.to_homogeneous();
+                    let values: Vec<String> = (0..3)
+                        .flat_map(|row| (0..4).map(move |column| (row, column)))
+                        .map(|(row, column)| m[(row, column)].to_string())
+                        .collect();
+                    writeln!(writer, "{}", values.join(" "))?;
+                }
+                TrajectoryFormat::Euroc => {
+                    writeln!(
+                        writer,
+                        "{},{},{},{},{},{},{},{}",
+                        date_time.timestamp_nanos_opt().unwrap_or_default(),
+                        t.x,
+                        t.y,
+                        t.z,
+                        q.w,
+                        q.i,
+                        q.j,
+                        q.k
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the trajectory of the child frame in the parent frame for a time window between
+/// start_date_time (inclusive) and end_date_time (exclusive).
+///
+/// Without a sampling interval, the trajectory is sampled at the stamps of the transforms or
+/// poses describing the child frame. Otherwise, it is sampled at a fixed rate between the first
+/// and the last of these stamps, where the sampling interval must be positive. Static transforms
+/// are also taken from before the time window.
+pub fn get_trajectory(
+    rosbag: &Rosbag,
+    start_date_time: &Option<DateTime<Utc>>,
+    end_date_time: &Option<DateTime<Utc>>,
+    source: &TrajectorySource,
+    parent_frame_id: FrameId,
+    child_frame_id: FrameId,
+    sampling_interval: Option<chrono::Duration>,
+) -> Result<Trajectory, Error> {
+    if let Some(interval) = sampling_interval
+        && interval <= chrono::Duration::zero()
+    {
+        return Err(Error::InvalidSamplingInterval(interval));
+    }
+
+    let (transform_tree, native_stamps): (TransformTree, BTreeSet<DateTime<Utc>>) = match source {
+        TrajectorySource::Transforms(channel_topics) => {
+            let transform_tree =
+                rosbag.get_transforms(start_date_time, end_date_time, channel_topics)?;
+
+            let channel_topics: HashSet<ChannelTopic> = match channel_topics {
+                Some(channel_topics) => channel_topics.clone(),
+                None => rosbag
+                    .get_overview()?
+                    .get_channel_topics_of_message_type(RosMessageType::Tf2MessagesTFMessage),
+            };
+            let page = rosbag.get_message_page(start_date_time, end_date_time, &channel_topics)?;
+            let native_stamps = page
+                .get_all_timed_transforms()?
+                .into_iter()
+                .filter(|(id, _)| id.child_frame_id == child_frame_id)
+                .flat_map(|(_, transforms)| transforms.into_iter().map(|x| x.timestamp))
+                .collect();
+
+            (transform_tree, native_stamps)
+        }
+        TrajectorySource::Poses(channel_topic) => {
+            let page = rosbag.get_message_page(
+                start_date_time,
+                end_date_time,
+                &HashSet::from([channel_topic.clone()]),
+            )?;
+            let native_stamps = page
+                .get_pose_timed_transforms(channel_topic)?
+                .into_values()
+                .flat_map(|transforms| transforms.into_iter().map(|x| x.timestamp))
+                .collect();
+            let transform_edges =
+                page.get_pose_transform_edges(channel_topic, child_frame_id.clone())?;
+
+            (
+                TransformTree::new(transform_edges, Vec::new())?,
+                native_stamps,
+            )
+        }
+    };
+
+    let native_stamps: Vec<DateTime<Utc>> = native_stamps
+        .into_iter()
+        .filter(|x| {
+            start_date_time.is_none_or(|start| start <= *x)
+                && end_date_time.is_none_or(|end| *x < end)
+        })
+        .collect();
+    let (Some(first_stamp), Some(last_stamp)) = (native_stamps.first(), native_stamps.last())
+    else {
+        return Err(Error::NoPosesForFrame(child_frame_id));
+    };
+
+    let stamps: Vec<DateTime<Utc>> = match sampling_interval {
+        Some(interval) => std::iter::successors(Some(*first_stamp), |x| Some(*x + interval))
+            .take_while(|x| x <= last_stamp)
+            .collect(),
+        None => native_stamps.clone(),
+    };
+
+    let transform_id = TransformId::new(parent_frame_id.clone(), child_frame_id.clone());
+    let poses: Vec<(DateTime<Utc>, Isometry3<f64>)> = stamps
+        .into_iter()
+        .map(|t| {
+            Ok((
+                t,
+                transform_tree
+                    .get_transform_at_time(&transform_id, t)?
+                    .isometry(),
+            ))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(Trajectory {
+        parent_frame_id,
+        child_frame_id,
+        poses,
+    })
+}
@@ -7,8 +7,10 @@
 ///! # Data structure
 ///!
 pub use erosbag_core::{
-    ChannelId, ChannelTopic, ChunkId, Error, FileName, McapFile, MessageId, RosPointDataColumnType,
-    Rosbag, TopicId, dto, ros_messages, topics,
+    BytesSource, ChannelId, ChannelTopic, ChunkId, Error, FileName, HttpSource, MCAP_EXTENSION,
+    McapFile, McapSource, MessageId, MmapSource, ROS1_BAG_EXTENSION, ReaderSource,
+    RosPointDataColumnType, Rosbag, SQLITE3_EXTENSION, TopicId, ZSTD_EXTENSION, dto, filter,
+    foxglove, playback, ros_messages, ros1, serde_big_array, storage, sync, table, topics,
 };
 
 pub use erosbag_transform as transform;